@@ -17,9 +17,21 @@ pub use trigger::*;
 pub mod pin;
 pub use pin::*;
 
+pub mod i2c;
+
 pub mod pcf8574;
 pub use pcf8574::Pcf8574;
 
+pub mod mcp23017;
+pub use mcp23017::Mcp23017;
+
+pub mod adc;
+pub use adc::Adc;
+pub mod pca9685;
+pub use pca9685::Pca9685;
+
+pub mod remote;
+
 // path attribute necessary due to non-ascii module name (at least for now)
 #[path = "anschluss/anschlüsse.rs"]
 pub mod anschlüsse;
@@ -29,11 +41,16 @@ use anschlüsse::SyncError;
 pub mod serde;
 pub use self::serde::*;
 
+pub mod zuordnung;
+
+pub mod verkabelung;
+
 /// Ein Anschluss
 #[derive(Debug)]
 pub enum Anschluss {
     Pin(Pin),
     Pcf8574Port(pcf8574::Port),
+    Mcp23017Port(mcp23017::Port),
 }
 
 impl From<Pin> for Anschluss {
@@ -46,37 +63,21 @@ impl From<pcf8574::Port> for Anschluss {
         Anschluss::Pcf8574Port(port)
     }
 }
-
-fn write_level(f: &mut Formatter<'_>, level: &Level) -> fmt::Result {
-    match level {
-        Level::Low => write!(f, "L"),
-        Level::High => write!(f, "H"),
-    }
-}
-fn write_variante(f: &mut Formatter<'_>, variante: &pcf8574::Variante) -> fmt::Result {
-    match variante {
-        pcf8574::Variante::Normal => write!(f, " "),
-        pcf8574::Variante::A => write!(f, "A"),
+impl From<mcp23017::Port> for Anschluss {
+    fn from(port: mcp23017::Port) -> Self {
+        Anschluss::Mcp23017Port(port)
     }
 }
-fn write_adresse(
-    f: &mut Formatter<'_>,
-    (a0, a1, a2, variante): &(Level, Level, Level, pcf8574::Variante),
-) -> fmt::Result {
-    write_level(f, a0)?;
-    write_level(f, a1)?;
-    write_level(f, a2)?;
-    write_variante(f, variante)
-}
 
 impl Display for Anschluss {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
             Anschluss::Pin(pin) => write!(f, "Pin({})", pin.pin()),
             Anschluss::Pcf8574Port(port) => {
-                write!(f, "Pcf8574Port(")?;
-                write_adresse(f, port.adresse())?;
-                write!(f, "-{})", port.port())
+                write!(f, "Pcf8574Port({}-{})", port.adresse(), port.port())
+            }
+            Anschluss::Mcp23017Port(port) => {
+                write!(f, "Mcp23017Port({}-{})", port.adresse(), port.port())
             }
         }
     }
@@ -92,6 +93,10 @@ impl Anschluss {
             Anschluss::Pcf8574Port(port) => {
                 OutputAnschluss::Pcf8574Port { port: port.into_output(gesperrt_level)?, polarität }
             }
+            Anschluss::Mcp23017Port(port) => OutputAnschluss::Mcp23017Port {
+                port: port.into_output(gesperrt_level)?,
+                polarität,
+            },
         })
     }
 
@@ -99,6 +104,7 @@ impl Anschluss {
         Ok(match self {
             Anschluss::Pin(pin) => InputAnschluss::Pin(pin.into_input()),
             Anschluss::Pcf8574Port(port) => InputAnschluss::Pcf8574Port(port.into_input()?),
+            Anschluss::Mcp23017Port(port) => InputAnschluss::Mcp23017Port(port.into_input(false)?),
         })
     }
 }
@@ -108,6 +114,7 @@ impl Anschluss {
 pub enum OutputAnschluss {
     Pin { pin: output::Pin, polarität: Polarität },
     Pcf8574Port { port: pcf8574::OutputPort, polarität: Polarität },
+    Mcp23017Port { port: mcp23017::OutputPort, polarität: Polarität },
 }
 
 impl Display for OutputAnschluss {
@@ -117,9 +124,10 @@ impl Display for OutputAnschluss {
                 write!(f, "Pin({}, {})", pin.pin(), polarität)
             }
             OutputAnschluss::Pcf8574Port { port, polarität } => {
-                write!(f, "Pcf8574Port(")?;
-                write_adresse(f, port.adresse())?;
-                write!(f, "-{}, {})", port.port(), polarität)
+                write!(f, "Pcf8574Port({}-{}, {})", port.adresse(), port.port(), polarität)
+            }
+            OutputAnschluss::Mcp23017Port { port, polarität } => {
+                write!(f, "Mcp23017Port({}-{}, {})", port.adresse(), port.port(), polarität)
             }
         }
     }
@@ -134,6 +142,9 @@ impl OutputAnschluss {
             OutputAnschluss::Pcf8574Port { port, polarität } => {
                 port.write(fließend.with_polarity(*polarität))?
             }
+            OutputAnschluss::Mcp23017Port { port, polarität } => {
+                port.write(fließend.with_polarity(*polarität))?
+            }
         })
     }
 
@@ -147,6 +158,10 @@ impl OutputAnschluss {
                 Polarität::Normal => port.is_set_high()?,
                 Polarität::Invertiert => port.is_set_low()?,
             },
+            OutputAnschluss::Mcp23017Port { port, polarität } => match polarität {
+                Polarität::Normal => port.is_set_high()?,
+                Polarität::Invertiert => port.is_set_low()?,
+            },
         })
     }
 
@@ -160,6 +175,10 @@ impl OutputAnschluss {
                 Polarität::Normal => port.is_set_low()?,
                 Polarität::Invertiert => port.is_set_high()?,
             },
+            OutputAnschluss::Mcp23017Port { port, polarität } => match polarität {
+                Polarität::Normal => port.is_set_low()?,
+                Polarität::Invertiert => port.is_set_high()?,
+            },
         })
     }
 
@@ -167,6 +186,7 @@ impl OutputAnschluss {
         Ok(match self {
             OutputAnschluss::Pin { pin, .. } => pin.toggle()?,
             OutputAnschluss::Pcf8574Port { port, .. } => port.toggle()?,
+            OutputAnschluss::Mcp23017Port { port, .. } => port.toggle()?,
         })
     }
 }
@@ -179,6 +199,8 @@ pub enum OutputSave {
         polarität: Polarität,
     },
     Pcf8574Port {
+        #[serde(deserialize_with = "i2c::Bus::lese")]
+        bus: i2c::Bus,
         a0: Level,
         a1: Level,
         a2: Level,
@@ -186,6 +208,15 @@ pub enum OutputSave {
         port: u8,
         polarität: Polarität,
     },
+    Mcp23017Port {
+        #[serde(deserialize_with = "i2c::Bus::lese")]
+        bus: i2c::Bus,
+        a0: Level,
+        a1: Level,
+        a2: Level,
+        port: u8,
+        polarität: Polarität,
+    },
 }
 impl ToSave for OutputAnschluss {
     type Save = OutputSave;
@@ -196,14 +227,25 @@ impl ToSave for OutputAnschluss {
                 OutputSave::Pin { pin: pin.pin(), polarität: *polarität }
             }
             OutputAnschluss::Pcf8574Port { port, polarität } => {
-                let (a0, a1, a2, variante) = port.adresse();
-                let port = port.port();
+                let pcf8574::Adresse { bus, a0, a1, a2, variante } = *port.adresse();
                 OutputSave::Pcf8574Port {
-                    a0: *a0,
-                    a1: *a1,
-                    a2: *a2,
-                    variante: *variante,
-                    port: port.into(),
+                    bus,
+                    a0,
+                    a1,
+                    a2,
+                    variante,
+                    port: port.port().into(),
+                    polarität: *polarität,
+                }
+            }
+            OutputAnschluss::Mcp23017Port { port, polarität } => {
+                let mcp23017::Adresse { bus, a0, a1, a2 } = *port.adresse();
+                OutputSave::Mcp23017Port {
+                    bus,
+                    a0,
+                    a1,
+                    a2,
+                    port: port.port(),
                     polarität: *polarität,
                 }
             }
@@ -216,9 +258,13 @@ impl Reserviere<OutputAnschluss> for OutputSave {
             OutputSave::Pin { pin, polarität } => {
                 (Anschluss::from(anschlüsse.reserviere_pin(pin)?), polarität)
             }
-            OutputSave::Pcf8574Port { a0, a1, a2, variante, port, polarität } => {
-                let port = u3::new(port);
-                (anschlüsse.reserviere_pcf8574_port(a0, a1, a2, variante, port)?.into(), polarität)
+            OutputSave::Pcf8574Port { bus, a0, a1, a2, variante, port, polarität } => {
+                let adresse = pcf8574::Adresse { bus, a0, a1, a2, variante };
+                (anschlüsse.reserviere_pcf8574_port(adresse, u3::new(port))?.into(), polarität)
+            }
+            OutputSave::Mcp23017Port { bus, a0, a1, a2, port, polarität } => {
+                let adresse = mcp23017::Adresse { bus, a0, a1, a2 };
+                (anschlüsse.reserviere_mcp23017_port(adresse, port)?.into(), polarität)
             }
         };
         anschluss.into_output(polarität)
@@ -230,6 +276,7 @@ impl Reserviere<OutputAnschluss> for OutputSave {
 pub enum InputAnschluss {
     Pin(input::Pin),
     Pcf8574Port(pcf8574::InputPort),
+    Mcp23017Port(mcp23017::InputPort),
 }
 
 impl Display for InputAnschluss {
@@ -237,9 +284,10 @@ impl Display for InputAnschluss {
         match self {
             InputAnschluss::Pin(pin) => write!(f, "Pin({})", pin.pin()),
             InputAnschluss::Pcf8574Port(port) => {
-                write!(f, "Pcf8574Port(")?;
-                write_adresse(f, port.adresse())?;
-                write!(f, "-{})", port.port())
+                write!(f, "Pcf8574Port({}-{})", port.adresse(), port.port())
+            }
+            InputAnschluss::Mcp23017Port(port) => {
+                write!(f, "Mcp23017Port({}-{})", port.adresse(), port.port())
             }
         }
     }
@@ -254,6 +302,7 @@ macro_rules! match_method {
             Ok(match self {
                 InputAnschluss::Pin(pin) => pin.$method($($($arg),+)?)?,
                 InputAnschluss::Pcf8574Port(port) => port.$method($($($arg),+)?)?,
+                InputAnschluss::Mcp23017Port(port) => port.$method($($($arg),+)?)?,
             })
         }
     };
@@ -268,12 +317,14 @@ impl InputAnschluss {
 }
 
 /// Serealisierbare Informationen eines InputAnschlusses.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum InputSave {
     Pin {
         pin: u8,
     },
     Pcf8574Port {
+        #[serde(deserialize_with = "i2c::Bus::lese")]
+        bus: i2c::Bus,
         a0: Level,
         a1: Level,
         a2: Level,
@@ -281,6 +332,16 @@ pub enum InputSave {
         port: u8,
         interrupt: Option<u8>,
     },
+    Mcp23017Port {
+        #[serde(deserialize_with = "i2c::Bus::lese")]
+        bus: i2c::Bus,
+        a0: Level,
+        a1: Level,
+        a2: Level,
+        port: u8,
+        pull_up: bool,
+        interrupt: Option<u8>,
+    },
 }
 impl ToSave for InputAnschluss {
     type Save = InputSave;
@@ -289,16 +350,27 @@ impl ToSave for InputAnschluss {
         match self {
             InputAnschluss::Pin(pin) => InputSave::Pin { pin: pin.pin() },
             InputAnschluss::Pcf8574Port(port) => {
-                let (a0, a1, a2, variante) = port.adresse();
-                let interrupt = port.interrupt_pin().unwrap_or(None);
-                let port = port.port();
+                let pcf8574::Adresse { bus, a0, a1, a2, variante } = *port.adresse();
                 InputSave::Pcf8574Port {
-                    a0: *a0,
-                    a1: *a1,
-                    a2: *a2,
-                    variante: *variante,
-                    port: port.into(),
-                    interrupt,
+                    bus,
+                    a0,
+                    a1,
+                    a2,
+                    variante,
+                    port: port.port().into(),
+                    interrupt: port.interrupt_pin().unwrap_or(None),
+                }
+            }
+            InputAnschluss::Mcp23017Port(port) => {
+                let mcp23017::Adresse { bus, a0, a1, a2 } = *port.adresse();
+                InputSave::Mcp23017Port {
+                    bus,
+                    a0,
+                    a1,
+                    a2,
+                    port: port.port(),
+                    pull_up: port.pull_up().unwrap_or(false),
+                    interrupt: port.interrupt_pin().unwrap_or(None),
                 }
             }
         }
@@ -310,9 +382,9 @@ impl Reserviere<InputAnschluss> for InputSave {
             InputSave::Pin { pin } => {
                 InputAnschluss::Pin(anschlüsse.reserviere_pin(pin)?.into_input())
             }
-            InputSave::Pcf8574Port { a0, a1, a2, variante, port, interrupt } => {
-                let port =
-                    anschlüsse.reserviere_pcf8574_port(a0, a1, a2, variante, u3::new(port))?;
+            InputSave::Pcf8574Port { bus, a0, a1, a2, variante, port, interrupt } => {
+                let adresse = pcf8574::Adresse { bus, a0, a1, a2, variante };
+                let port = anschlüsse.reserviere_pcf8574_port(adresse, u3::new(port))?;
                 let mut input_port = port.into_input()?;
                 if input_port.interrupt_pin()? != interrupt {
                     if let Some(pin) = interrupt {
@@ -322,6 +394,18 @@ impl Reserviere<InputAnschluss> for InputSave {
                 }
                 InputAnschluss::Pcf8574Port(input_port)
             }
+            InputSave::Mcp23017Port { bus, a0, a1, a2, port, pull_up, interrupt } => {
+                let adresse = mcp23017::Adresse { bus, a0, a1, a2 };
+                let port = anschlüsse.reserviere_mcp23017_port(adresse, port)?;
+                let mut input_port = port.into_input(pull_up)?;
+                if input_port.interrupt_pin()? != interrupt {
+                    if let Some(pin) = interrupt {
+                        let interrupt = anschlüsse.reserviere_pin(pin)?.into_input();
+                        let _ = input_port.set_interrupt_pin(interrupt);
+                    }
+                }
+                InputAnschluss::Mcp23017Port(input_port)
+            }
         })
     }
 }
@@ -332,6 +416,9 @@ pub enum Error {
     Output(output::Error),
     Input(input::Error),
     Pcf8574(pcf8574::Error),
+    Mcp23017(mcp23017::Error),
+    Adc(adc::Error),
+    Pca9685(pca9685::Error),
 }
 impl From<SyncError> for Error {
     fn from(error: SyncError) -> Self {
@@ -358,3 +445,18 @@ impl From<pcf8574::Error> for Error {
         Error::Pcf8574(error)
     }
 }
+impl From<mcp23017::Error> for Error {
+    fn from(error: mcp23017::Error) -> Self {
+        Error::Mcp23017(error)
+    }
+}
+impl From<adc::Error> for Error {
+    fn from(error: adc::Error) -> Self {
+        Error::Adc(error)
+    }
+}
+impl From<pca9685::Error> for Error {
+    fn from(error: pca9685::Error) -> Self {
+        Error::Pca9685(error)
+    }
+}
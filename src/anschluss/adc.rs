@@ -0,0 +1,255 @@
+//! Analog-Digital-Wandler (Ads1115, Pcf8591), gesteuert über I2C.
+//!
+//! Ohne Hardware-Backend werden die Messwerte simuliert,
+//! sie können über `Kanal::setze_simulierten_wert` festgelegt werden.
+
+use std::fmt::{self, Display, Formatter};
+use std::sync::{mpsc::Sender, Arc, Mutex, PoisonError};
+#[cfg(raspi)]
+use std::{thread::sleep, time::Duration};
+
+use log::debug;
+#[cfg(raspi)]
+use rppal::i2c;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    anschlüsse::Anschlüsse,
+    i2c::{Bus, Zugriff},
+    Reserviere, ToSave,
+};
+
+/// Anzahl der (single-ended) Kanäle eines Adc.
+pub const ANZAHL_KANÄLE: u8 = 4;
+
+/// Basis-I2C-Adresse (7-bit), beide unterstützten Varianten beginnen bei 0x48.
+const BASIS_ADRESSE: u8 = 0x48;
+
+/// Unterstützte Adc-Varianten.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Variante {
+    /// 16 bit, 4 Adressen (Adress-Pin an GND, VDD, SDA, SCL).
+    Ads1115,
+    /// 8 bit, 8 Adressen (A0-A2).
+    Pcf8591,
+}
+
+impl Variante {
+    /// Anzahl der über Adress-Pins einstellbaren Adressen.
+    pub fn anzahl_adressen(&self) -> u8 {
+        match self {
+            Variante::Ads1115 => 4,
+            Variante::Pcf8591 => 8,
+        }
+    }
+
+    /// Messwert bei anliegender Versorgungsspannung (3.3V).
+    pub fn maximal_wert(&self) -> u16 {
+        match self {
+            // Messbereich ±4.096V, 3.3V / 4.096V * 32767
+            Variante::Ads1115 => 26400,
+            Variante::Pcf8591 => u8::MAX.into(),
+        }
+    }
+}
+
+impl Display for Variante {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Variante::Ads1115 => write!(f, "Ads1115"),
+            Variante::Pcf8591 => write!(f, "Pcf8591"),
+        }
+    }
+}
+
+/// Adresse eines Adc.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Adresse {
+    #[serde(deserialize_with = "Bus::lese")]
+    pub bus: Bus,
+    pub variante: Variante,
+    /// Über die Adress-Pins eingestellter Offset zur Basis-Adresse.
+    pub offset: u8,
+}
+
+impl Adresse {
+    /// 7-bit I2C-Adresse ohne R/W-Bit.
+    pub fn i2c_adresse(&self) -> u8 {
+        BASIS_ADRESSE + self.offset
+    }
+}
+
+impl From<Adresse> for super::i2c::Adresse {
+    fn from(adresse: Adresse) -> Self {
+        super::i2c::Adresse { bus: adresse.bus, adresse: adresse.i2c_adresse() }
+    }
+}
+
+impl Display for Adresse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.bus.präfix(f)?;
+        write!(f, "{}({:#04x})", self.variante, self.i2c_adresse())
+    }
+}
+
+/// Ein Adc, gesteuert über I2C.
+#[derive(Debug)]
+pub struct Adc {
+    #[cfg_attr(not(raspi), allow(dead_code))]
+    adresse: Adresse,
+    #[cfg_attr(not(raspi), allow(dead_code))]
+    i2c: Zugriff,
+    simulierte_werte: [u16; ANZAHL_KANÄLE as usize],
+}
+
+impl Adc {
+    pub(super) fn neu(adresse: Adresse, i2c: Zugriff) -> Self {
+        Adc { adresse, i2c, simulierte_werte: [0; ANZAHL_KANÄLE as usize] }
+    }
+
+    /// Lese den aktuellen Messwert eines Kanals (0 - `Variante::maximal_wert`).
+    fn lese(&mut self, kanal: u8) -> Result<u16, Error> {
+        #[cfg(raspi)]
+        if let Some(i2c) = self.i2c.i2c() {
+            let mut i2c_channel = i2c.lock()?;
+            i2c_channel.set_slave_address(self.adresse.i2c_adresse().into())?;
+            return match self.adresse.variante {
+                Variante::Ads1115 => {
+                    // Single-shot Messung, AINx gegen GND, ±4.096V, 128SPS, Comparator aus
+                    let config: u16 = 0x8000
+                        | ((0b100 | u16::from(kanal)) << 12)
+                        | 0x0200
+                        | 0x0100
+                        | 0x0080
+                        | 0x0003;
+                    i2c_channel.block_write(0x01, &config.to_be_bytes())?;
+                    // Konvertierung dauert bei 128SPS knapp 8ms
+                    sleep(Duration::from_millis(9));
+                    let mut buf = [0; 2];
+                    i2c_channel.write_read(&[0x00], &mut buf)?;
+                    Ok(i16::from_be_bytes(buf).max(0) as u16)
+                },
+                Variante::Pcf8591 => {
+                    // Kontroll-Byte: kein auto-increment, analoger Ausgang aus
+                    i2c_channel.write(&[kanal])?;
+                    // Das erste gelesene Byte ist die vorherige Messung
+                    let mut buf = [0; 2];
+                    let bytes_read = i2c_channel.read(&mut buf)?;
+                    if bytes_read != 2 {
+                        debug!("bytes_read = {} != 2", bytes_read)
+                    }
+                    Ok(buf[1].into())
+                },
+            };
+        }
+        debug!("{:?}.lese({})", self, kanal);
+        Ok(self.simulierte_werte[usize::from(kanal)])
+    }
+}
+
+/// Ein Kanal eines Adc.
+#[derive(Debug)]
+pub struct Kanal {
+    adc: Arc<Mutex<Adc>>,
+    adresse: Adresse,
+    kanal: u8,
+    sender: Sender<(super::i2c::Adresse, u8)>,
+}
+
+impl PartialEq for Kanal {
+    fn eq(&self, other: &Self) -> bool {
+        self.adresse == other.adresse && self.kanal == other.kanal
+    }
+}
+impl Eq for Kanal {}
+
+impl Drop for Kanal {
+    fn drop(&mut self) {
+        let Kanal { adresse, kanal, sender, .. } = self;
+        debug!("dropped adc {} kanal {}", adresse, kanal);
+        if let Err(err) = sender.send(((*adresse).into(), *kanal)) {
+            debug!("send error while dropping: {}", err)
+        }
+    }
+}
+
+impl Display for Kanal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.adresse, self.kanal)
+    }
+}
+
+impl Kanal {
+    pub(super) fn neu(
+        adc: Arc<Mutex<Adc>>,
+        adresse: Adresse,
+        kanal: u8,
+        sender: Sender<(super::i2c::Adresse, u8)>,
+    ) -> Self {
+        Kanal { adc, adresse, kanal, sender }
+    }
+
+    #[inline]
+    pub fn adresse(&self) -> &Adresse {
+        &self.adresse
+    }
+
+    #[inline]
+    pub fn kanal(&self) -> u8 {
+        self.kanal
+    }
+
+    /// Lese den aktuellen Messwert, normiert auf [0, 1].
+    pub fn lese(&self) -> Result<f64, Error> {
+        let wert = self.adc.lock()?.lese(self.kanal)?;
+        let maximal_wert = self.adresse.variante.maximal_wert();
+        Ok(f64::from(wert.min(maximal_wert)) / f64::from(maximal_wert))
+    }
+
+    /// Lege den Messwert fest, der ohne Hardware-Backend gelesen wird.
+    pub fn setze_simulierten_wert(&self, wert: u16) -> Result<(), Error> {
+        let adc = &mut *self.adc.lock()?;
+        adc.simulierte_werte[usize::from(self.kanal)] =
+            wert.min(self.adresse.variante.maximal_wert());
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    #[cfg(raspi)]
+    I2c(i2c::Error),
+    PoisonError,
+    UngültigeAdresse(Adresse),
+    UngültigerKanal(u8),
+}
+#[cfg(raspi)]
+impl From<i2c::Error> for Error {
+    fn from(error: i2c::Error) -> Self {
+        Error::I2c(error)
+    }
+}
+impl<T> From<PoisonError<T>> for Error {
+    fn from(_: PoisonError<T>) -> Self {
+        Error::PoisonError
+    }
+}
+
+/// Serealisierbare Informationen eines Adc-Kanals.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Save {
+    pub adresse: Adresse,
+    pub kanal: u8,
+}
+impl ToSave for Kanal {
+    type Save = Save;
+
+    fn to_save(&self) -> Save {
+        Save { adresse: self.adresse, kanal: self.kanal }
+    }
+}
+impl Reserviere<Kanal> for Save {
+    fn reserviere(self, anschlüsse: &mut Anschlüsse) -> Result<Kanal, super::Error> {
+        anschlüsse.reserviere_adc_kanal(self.adresse, self.kanal).map_err(Into::into)
+    }
+}
@@ -1,162 +1,358 @@
 //! Singleton für Zugriffsrechte auf Anschlüsse.
 
-#[cfg(not(raspi))]
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::Path;
+use std::str::FromStr;
 use std::sync::{
     mpsc::{channel, Receiver, Sender},
-    Arc, Mutex, PoisonError,
+    Arc, Mutex, MutexGuard, PoisonError,
 };
-use std::thread;
 
 use cfg_if::cfg_if;
 use log::{debug, error};
 use num_x::u3;
-use once_cell::sync::Lazy;
-use paste::paste;
+use once_cell::sync::{Lazy, OnceCell};
 
-use super::level::Level;
-use super::pcf8574::{self, Pcf8574, Port};
+use super::adc::{self, Adc};
+use super::i2c;
+use super::mcp23017::{self, Mcp23017};
+use super::pca9685::{self, Pca9685};
+use super::pcf8574::{self, Pcf8574};
 use super::pin::Pin;
+use super::remote::{self, Verbindung};
+use super::zuordnung;
 
-/// originally taken from: https://www.ecorax.net/macro-bunker-1/
-/// adjusted to 4 arguments
-macro_rules! matrix {
-    ( $inner_macro:ident [$($k:ident),+] $ls:tt $ms:tt $ns:tt $(: $value:ident)?) => {
-        matrix! { $inner_macro $($k $ls $ms $ns)+ $(: $value)?}
-    };
-    ( $inner_macro:ident $($k:ident [$($l:tt),+] $ms:tt $ns:tt)+ $(: $value:ident)?) => {
-        matrix! { $inner_macro $($( $k $l $ms $ns )+)+ $(: $value)? }
-    };
-    ( $inner_macro:ident $($k:ident $l:ident [$($m:tt),+] $ns:tt)+ $(: $value:ident)?) => {
-        matrix! { $inner_macro $($( $k $l $m $ns )+)+ $(: $value)? }
-    };
-    ( $inner_macro:ident $($k:ident $l:ident $m:ident [$($n:tt),+])+ $(: $value:ident)?) => {
-         $inner_macro! { $($($k $l $m $n),+),+ $(: $value)? }
-    };
+/// Anzahl der Gpio-Pins (BCM-Nummerierung).
+const ANZAHL_GPIO_PINS: u8 = 28;
+
+/// Backend für den Zugriff auf Gpio-Pins und I2C-Busse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// Zugriff über `/dev/gpiomem` und `/dev/i2c-*`.
+    Hardware,
+    /// Gpio-Zugriff über das character device `/dev/gpiochip<nummer>`, I2C über `/dev/i2c-*`.
+    ///
+    /// Notwendig für neuere Boards (z.B. Raspberry Pi 5), wird nicht automatisch erkannt.
+    Cdev(u8),
+    /// Zugriff über einen entfernten Raspberry Pi, auf dem ein [Server](remote::Server) läuft.
+    ///
+    /// Unterstützt werden Gpio-Pins (Input, Output, Pwm) und Pcf8574.
+    Remote(SocketAddr),
+    /// Keine Hardware angeschlossen, Zugriffe werden nur protokolliert.
+    Stub,
 }
-macro_rules! anschlüsse_data {
-    {$($k:ident $l:ident $m:ident $n:ident),*} => {
-        paste! {
-            #[doc="Singleton für Zugriff auf raspberry pi Anschlüsse."]
-            #[derive(Debug)]
-            struct AnschlüsseData {
-                #[cfg(raspi)]
-                gpio: rppal::gpio::Gpio,
-                #[cfg(raspi)]
-                i2c: Arc<Mutex<rppal::i2c::I2c>>,
-                #[cfg(not(raspi))]
-                ausgegebene_pins: HashSet<u8>,
-                #[cfg(not(raspi))]
-                pin_rückgabe: Sender<u8>,
-                $(
-                    [<$k $l $m $n>]: Arc<Mutex<Pcf8574>>,
-                    [<$k $l $m $n 0>]: Option<pcf8574::Port>,
-                    [<$k $l $m $n 1>]: Option<pcf8574::Port>,
-                    [<$k $l $m $n 2>]: Option<pcf8574::Port>,
-                    [<$k $l $m $n 3>]: Option<pcf8574::Port>,
-                    [<$k $l $m $n 4>]: Option<pcf8574::Port>,
-                    [<$k $l $m $n 5>]: Option<pcf8574::Port>,
-                    [<$k $l $m $n 6>]: Option<pcf8574::Port>,
-                    [<$k $l $m $n 7>]: Option<pcf8574::Port>,
-                )*
-            }
+
+impl Backend {
+    /// Verwende die Hardware, sofern `/dev/gpiomem` und mindestens ein `/dev/i2c-*` existieren.
+    pub fn erkenne() -> Backend {
+        let i2c_vorhanden = || match std::fs::read_dir("/dev") {
+            Ok(dir) => dir
+                .filter_map(Result::ok)
+                .any(|eintrag| eintrag.file_name().to_string_lossy().starts_with("i2c-")),
+            Err(_) => false,
+        };
+        if cfg!(raspi) && Path::new("/dev/gpiomem").exists() && i2c_vorhanden() {
+            Backend::Hardware
+        } else {
+            Backend::Stub
+        }
+    }
+}
+
+impl Display for Backend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::Hardware => write!(f, "hardware"),
+            Backend::Cdev(chip) => write!(f, "cdev:{}", chip),
+            Backend::Remote(adresse) => write!(f, "remote:{}", adresse),
+            Backend::Stub => write!(f, "stub"),
         }
-    };
+    }
 }
-macro_rules! level {
-    (l) => {
-        Level::Low
-    };
-    (h) => {
-        Level::High
-    };
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hardware" => Ok(Backend::Hardware),
+            "stub" => Ok(Backend::Stub),
+            "cdev" => Ok(Backend::Cdev(0)),
+            _ => {
+                if let Some(Ok(chip)) = s.strip_prefix("cdev:").map(str::parse) {
+                    return Ok(Backend::Cdev(chip));
+                }
+                // Adresse mit oder ohne Port, Hostnamen werden direkt aufgelöst
+                let adresse = s.strip_prefix("remote:").ok_or_else(|| s.to_string())?;
+                adresse
+                    .to_socket_addrs()
+                    .or_else(|_| (adresse, remote::STANDARD_PORT).to_socket_addrs())
+                    .ok()
+                    .and_then(|mut adressen| adressen.next())
+                    .map(Backend::Remote)
+                    .ok_or_else(|| s.to_string())
+            },
+        }
+    }
 }
-macro_rules! variante {
-    (n) => {
-        pcf8574::Variante::Normal
-    };
-    (a) => {
-        pcf8574::Variante::A
-    };
+
+/// Ein verwendeter I2C-Baustein.
+#[derive(Debug, Clone)]
+enum Baustein {
+    Pcf8574(pcf8574::Variante, Arc<Mutex<Pcf8574>>),
+    Mcp23017(Arc<Mutex<Mcp23017>>),
+    Adc(adc::Variante, Arc<Mutex<Adc>>),
+    Pca9685(Arc<Mutex<Pca9685>>),
 }
-macro_rules! llln_to_hhha {
-    ($inner_macro:ident $(: $value:ident)?) => {
-        matrix! {$inner_macro  [l,h] [l,h] [l,h] [n,a] $(: $value)?}
-    };
+
+impl Baustein {
+    fn art(&self) -> i2c::Art {
+        match self {
+            Baustein::Pcf8574(variante, _) => i2c::Art::Pcf8574(*variante),
+            Baustein::Mcp23017(_) => i2c::Art::Mcp23017,
+            Baustein::Adc(variante, _) => i2c::Art::Adc(*variante),
+            Baustein::Pca9685(_) => i2c::Art::Pca9685,
+        }
+    }
+}
+
+/// Verwender eines reservierten Anschlusses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Besitzer {
+    Weiche(String),
+    Kontakt(String),
+    Streckenabschnitt(String),
+    Geschwindigkeit(String),
+    Taster(String),
+    Drehregler(String),
+    Nothalt,
+}
+
+impl Display for Besitzer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Besitzer::Weiche(name) => write!(f, "Weiche {}", name),
+            Besitzer::Kontakt(name) => write!(f, "Kontakt {}", name),
+            Besitzer::Streckenabschnitt(name) => write!(f, "Streckenabschnitt {}", name),
+            Besitzer::Geschwindigkeit(name) => write!(f, "Geschwindigkeit {}", name),
+            Besitzer::Taster(name) => write!(f, "Taster {}", name),
+            Besitzer::Drehregler(name) => write!(f, "Drehregler {}", name),
+            Besitzer::Nothalt => write!(f, "Nothalt-Taster"),
+        }
+    }
+}
+
+/// Kennung eines reservierten Anschlusses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Kennung {
+    Pin(u8),
+    I2c(i2c::Adresse, u8),
+}
+
+/// Singleton für Zugriff auf raspberry pi Anschlüsse.
+#[derive(Debug)]
+struct AnschlüsseData {
+    /// Nur mit Hardware-Backend vorhanden.
+    #[cfg(raspi)]
+    gpio: Option<rppal::gpio::Gpio>,
+    /// Nur mit Cdev-Backend vorhanden.
+    #[cfg(raspi)]
+    gpio_chip: Option<gpio_cdev::Chip>,
+    /// Nur mit Remote-Backend vorhanden.
+    remote: Option<Verbindung>,
+    #[cfg(raspi)]
+    i2c_busse: HashMap<i2c::Bus, i2c::Zugriff>,
+    /// Mit Cdev-, Remote- oder Stub-Backend ausgegebene Pins.
+    ausgegebene_pins: HashSet<u8>,
+    pin_rückgabe: Sender<u8>,
+    pin_rückgabe_empfänger: Receiver<u8>,
+    /// Alle bisher verwendeten I2C-Bausteine.
+    bausteine: HashMap<i2c::Adresse, Baustein>,
+    /// Aktuell reservierte Ports (bzw. Kanäle) von I2C-Bausteinen.
+    ausgegebene_i2c_ports: HashSet<(i2c::Adresse, u8)>,
+    i2c_rückgabe: Sender<(i2c::Adresse, u8)>,
+    i2c_rückgabe_empfänger: Receiver<(i2c::Adresse, u8)>,
+    /// Besitzer reservierter Anschlüsse, sofern bekannt.
+    besitzer: HashMap<Kennung, Besitzer>,
+    /// Besitzer neu reservierter Anschlüsse (siehe [Anschlüsse::mit_besitzer]).
+    aktueller_besitzer: Option<Besitzer>,
 }
 
-llln_to_hhha! { anschlüsse_data }
 impl AnschlüsseData {
-    /// Gebe den Pcf8574 an Anschlüsse zurück, so dass er von anderen verwendet werden kann.
+    /// Daten ohne Hardware-Zugriff und ohne reservierte Anschlüsse.
+    fn leer() -> Self {
+        let (pin_rückgabe, pin_rückgabe_empfänger) = channel();
+        let (i2c_rückgabe, i2c_rückgabe_empfänger) = channel();
+        AnschlüsseData {
+            #[cfg(raspi)]
+            gpio: None,
+            #[cfg(raspi)]
+            gpio_chip: None,
+            remote: None,
+            #[cfg(raspi)]
+            i2c_busse: HashMap::new(),
+            ausgegebene_pins: HashSet::new(),
+            pin_rückgabe,
+            pin_rückgabe_empfänger,
+            bausteine: HashMap::new(),
+            ausgegebene_i2c_ports: HashSet::new(),
+            i2c_rückgabe,
+            i2c_rückgabe_empfänger,
+            besitzer: HashMap::new(),
+            aktueller_besitzer: None,
+        }
+    }
+
+    /// Gebe alle seit dem letzten Aufruf gedroppten Anschlüsse wieder frei.
     ///
-    /// Wird vom Drop-handler ausgeführt, hier ist es explizit.
-    fn rückgabe(&mut self, port: pcf8574::Port) -> Result<(), SyncError> {
-        macro_rules! match_pcf8574 {
-            {$($k:ident $l:ident $m:ident $n:ident),*} => {
-                paste! {
-                    match port.adresse() {
-                        $(
-                            (level!($k), level!($l), level!($m), variante!($n)) => {
-                                debug!("rückgabe pcf8574 {:?}-{:?}-{:?}-{:?}-{:?}"
-                                    , level!($k), level!($l), level!($m), variante!($n), port.port());
-                                match u8::from(port.port()) {
-                                    0 => self.[<$k $l $m $n 0>] = Some(port),
-                                    1 => self.[<$k $l $m $n 1>] = Some(port),
-                                    2 => self.[<$k $l $m $n 2>] = Some(port),
-                                    3 => self.[<$k $l $m $n 3>] = Some(port),
-                                    4 => self.[<$k $l $m $n 4>] = Some(port),
-                                    5 => self.[<$k $l $m $n 5>] = Some(port),
-                                    6 => self.[<$k $l $m $n 6>] = Some(port),
-                                    7 => self.[<$k $l $m $n 7>] = Some(port),
-                                    _ => error!("Port > 7!"),
-                                }
-                            }
-                            //TODO port über macro-expansion
-                        ),*
-                    }
-                    Ok(())
+    /// Die Rückgabe erfolgt synchron beim nächsten Zugriff,
+    /// ein gedroppter Anschluss kann also direkt danach wieder reserviert werden.
+    fn verarbeite_rückgaben(&mut self) {
+        while let Ok(pin) = self.pin_rückgabe_empfänger.try_recv() {
+            debug!("rückgabe pin {}", pin);
+            self.ausgegebene_pins.remove(&pin);
+            self.besitzer.remove(&Kennung::Pin(pin));
+        }
+        while let Ok((adresse, port)) = self.i2c_rückgabe_empfänger.try_recv() {
+            debug!("rückgabe i2c {} port {}", adresse, port);
+            self.ausgegebene_i2c_ports.remove(&(adresse, port));
+            self.besitzer.remove(&Kennung::I2c(adresse, port));
+        }
+    }
+
+    /// Vermerke den aktuellen Besitzer für einen gerade reservierten Anschluss.
+    fn vermerke_besitzer(&mut self, kennung: Kennung) {
+        match &self.aktueller_besitzer {
+            Some(besitzer) => {
+                self.besitzer.insert(kennung, besitzer.clone());
+            },
+            None => {
+                self.besitzer.remove(&kennung);
+            },
+        }
+    }
+
+    /// Aktuell reservierte Gpio-Pins, inklusive der durch I2C belegten Gpio 2, 3.
+    fn reservierte_pins(&self) -> Vec<u8> {
+        (0..ANZAHL_GPIO_PINS)
+            .filter(|pin| {
+                if let 2 | 3 = pin {
+                    return true;
+                }
+                #[cfg(raspi)]
+                if let Some(gpio) = &self.gpio {
+                    // ein nicht reservierter Pin wird beim drop sofort wieder freigegeben
+                    return gpio.get(*pin).is_err();
                 }
+                self.ausgegebene_pins.contains(pin)
+            })
+            .collect()
+    }
+
+    /// Reserviere den Pin über das gewählte Backend.
+    fn reserviere_pin(&mut self, pin: u8) -> Result<Pin, Error> {
+        #[cfg(raspi)]
+        if let Some(gpio) = &self.gpio {
+            return Ok(Pin::hardware(gpio.get(pin)?));
+        }
+        if !self.ausgegebene_pins.insert(pin) {
+            // TODO besserer Fehler (welcher Pin wurde angefragt)
+            return Err(Error::Sync(SyncError::InVerwendung));
+        }
+        #[cfg(raspi)]
+        if let Some(chip) = &mut self.gpio_chip {
+            return match chip.get_line(pin.into()) {
+                Ok(line) => Ok(Pin::cdev(line, pin, self.pin_rückgabe.clone())),
+                Err(error) => {
+                    self.ausgegebene_pins.remove(&pin);
+                    Err(error.into())
+                },
             };
         }
-        llln_to_hhha! {match_pcf8574}
+        if let Some(verbindung) = &self.remote {
+            return Ok(Pin::remote(verbindung.clone(), pin, self.pin_rückgabe.clone()));
+        }
+        Ok(Pin::stub(pin, self.pin_rückgabe.clone()))
     }
 
-    /// Reserviere den spezifizierten Pcf8574 zur exklusiven Nutzung.
-    fn reserviere_pcf8574_port(
+    /// Register-Zugriffe (Adc, Pca9685) werden vom [remote::Server] nicht unterstützt.
+    fn nur_lokal(&self, art: i2c::Art) -> Result<(), Error> {
+        if self.remote.is_some() {
+            return Err(Error::NichtRemote(art));
+        }
+        Ok(())
+    }
+
+    /// Zugriff auf den spezifizierten I2C-Bus, wird bei der ersten Verwendung geöffnet.
+    fn i2c_zugriff(&mut self, bus: i2c::Bus) -> Result<i2c::Zugriff, Error> {
+        #[cfg(raspi)]
+        if self.gpio.is_some() || self.gpio_chip.is_some() {
+            if let Some(zugriff) = self.i2c_busse.get(&bus) {
+                return Ok(zugriff.clone());
+            }
+            let zugriff = i2c::Zugriff::hardware(rppal::i2c::I2c::with_bus(bus.0)?);
+            self.i2c_busse.insert(bus, zugriff.clone());
+            return Ok(zugriff);
+        }
+        if let Some(verbindung) = &self.remote {
+            return Ok(i2c::Zugriff::remote(verbindung.clone()));
+        }
+        debug!("i2c_zugriff({})", bus);
+        Ok(i2c::Zugriff::stub())
+    }
+
+    /// Reserviere einen Port des I2C-Bausteins an der spezifizierten Adresse.
+    ///
+    /// Existiert an der Adresse bereits ein passender Baustein (`als` gibt Some zurück),
+    /// wird dieser verwendet. Ansonsten wird er über `erzeuge` neu erstellt,
+    /// sofern kein Port eines anderen Bausteins mit der gleichen Adresse reserviert ist.
+    fn reserviere_i2c_port<T>(
         &mut self,
-        a0: Level,
-        a1: Level,
-        a2: Level,
-        variante: pcf8574::Variante,
-        port: u3,
-    ) -> Option<pcf8574::Port> {
-        // gebe aktuellen Wert zurück und speichere stattdessen None
-        macro_rules! reserviere_pcf8574 {
-            {$($k:ident $l:ident $m:ident $n:ident),*} => {
-                paste! {
-                    match (a0, a1,a2, variante) {
-                        $(
-                            (level!($k),level!($l),level!($m),variante!($n)) => {
-                                debug!("reserviere pcf8574 {:?}-{:?}-{:?}-{:?}-{}"
-                                        , level!($k), level!($l), level!($m), variante!($n), port);
-                                match u8::from(port) {
-                                    0 => std::mem::replace(&mut self.[<$k $l $m $n 0>], None),
-                                    1 => std::mem::replace(&mut self.[<$k $l $m $n 1>], None),
-                                    2 => std::mem::replace(&mut self.[<$k $l $m $n 2>], None),
-                                    3 => std::mem::replace(&mut self.[<$k $l $m $n 3>], None),
-                                    4 => std::mem::replace(&mut self.[<$k $l $m $n 4>], None),
-                                    5 => std::mem::replace(&mut self.[<$k $l $m $n 5>], None),
-                                    6 => std::mem::replace(&mut self.[<$k $l $m $n 6>], None),
-                                    7 => std::mem::replace(&mut self.[<$k $l $m $n 7>], None),
-                                    _ => None,
-                                }
-                            }
-                        ),*
-                    }
-                }
-            };
+        adresse: i2c::Adresse,
+        port: u8,
+        als: impl Fn(&Baustein) -> Option<Arc<Mutex<T>>>,
+        erzeuge: impl FnOnce(i2c::Zugriff) -> Baustein,
+    ) -> Result<Arc<Mutex<T>>, Error> {
+        if self.ausgegebene_i2c_ports.contains(&(adresse, port)) {
+            return Err(Error::Sync(SyncError::InVerwendung));
         }
-        llln_to_hhha! {reserviere_pcf8574}
+        let baustein = match self.bausteine.get(&adresse).and_then(&als) {
+            Some(baustein) => baustein,
+            None => {
+                if self.ausgegebene_i2c_ports.iter().any(|(belegt, _port)| belegt == &adresse) {
+                    debug!("I2C-Adresse {} durch anderen Baustein belegt", adresse);
+                    return Err(Error::Sync(SyncError::InVerwendung));
+                }
+                let neu = erzeuge(self.i2c_zugriff(adresse.bus)?);
+                let baustein = als(&neu).ok_or(Error::Sync(SyncError::InVerwendung))?;
+                self.bausteine.insert(adresse, neu);
+                baustein
+            },
+        };
+        self.ausgegebene_i2c_ports.insert((adresse, port));
+        self.vermerke_besitzer(Kennung::I2c(adresse, port));
+        Ok(baustein)
+    }
+
+    /// Belegung aller bisher verwendeten I2C-Bausteine, sortiert nach Adresse.
+    fn i2c_belegung(&self) -> Vec<i2c::Belegung> {
+        let mut belegung: Vec<_> = self
+            .bausteine
+            .iter()
+            .map(|(adresse, baustein)| {
+                let mut reserviert: Vec<_> = self
+                    .ausgegebene_i2c_ports
+                    .iter()
+                    .filter_map(|(belegt, port)| if belegt == adresse { Some(*port) } else { None })
+                    .collect();
+                reserviert.sort_unstable();
+                i2c::Belegung { adresse: *adresse, art: baustein.art(), reserviert }
+            })
+            .collect();
+        belegung.sort_by_key(|i2c::Belegung { adresse, .. }| *adresse);
+        belegung
     }
 }
 
@@ -186,264 +382,445 @@ impl Anschlüsse {
                 let data =
                     std::mem::replace(anschlüsse, Err(Error::Sync(SyncError::InVerwendung)))?;
                 Ok(Anschlüsse(Some(data)))
-            }
+            },
             Err(_er) => Err(Error::Sync(SyncError::PoisonError)),
         }
     }
 
-    fn listen_restore_messages(
-        sender: Sender<(pcf8574::Nachricht, u3)>,
-        receiver: Receiver<(pcf8574::Nachricht, u3)>,
-        inner: AnschlüsseInternal,
-    ) {
-        loop {
-            match receiver.recv() {
-                Ok((nachricht, port)) => match inner.lock() {
-                    Ok(mut guard) => {
-                        let anschlüsse = &mut *guard;
-                        macro_rules! port_value {
-                            ($a0:ident $a1:ident $a2:ident $var:ident $port:expr) => {
-                                paste! {
-                                    Port::neu(
-                                        anschlüsse.[<$a0 $a1 $a2 $var>].clone(),
-                                        (level!{$a0}, level!{$a1}, level!{$a2}, variante!{$var}),
-                                        $port,
-                                        sender.clone()
-                                    )
-                                }
-                            };
-                        }
-                        macro_rules! match_nachricht {
-                            {$($k:ident $l:ident $m:ident $n:ident),*: $value:ident} => {
-                                paste! {
-                                    match nachricht {
-                                        $(
-                                            (level!($k),level!($l),level!($m),variante!($n)) => {
-                                                $value! {$k $l $m $n port}
-                                            }
-                                        )*
-                                    }
-                                }
-                            }
-                        }
-                        let port = llln_to_hhha! {match_nachricht: port_value};
-                        if let Err(err) = anschlüsse.rückgabe(port) {
-                            error!("Error bei rückgabe: {:?}", err);
-                            break;
-                        }
-                    }
-                    Err(err) => {
-                        error!("Anschlüsse-static poisoned: {}", err);
-                        break;
-                    }
-                },
-                Err(err) => {
-                    error!("Kanal für Pcf8574-Rückgabe geschlossen: {}", err);
-                    break;
-                }
+    /// Lege das verwendete Backend fest.
+    ///
+    /// Muss vor dem ersten Aufruf von [Anschlüsse::neu] erfolgen,
+    /// ansonsten wird das Backend über [Backend::erkenne] bestimmt.
+    pub fn wähle_backend(backend: Backend) -> Result<(), Error> {
+        if !matches!(backend, Backend::Stub | Backend::Remote(_)) && !cfg!(raspi) {
+            return Err(Error::KeinRaspberryPi);
+        }
+        if BACKEND.set(backend).is_err() {
+            let aktuell = Anschlüsse::backend();
+            if aktuell != backend {
+                return Err(Error::BackendFestgelegt(aktuell));
             }
         }
+        Ok(())
     }
 
-    #[cfg(not(raspi))]
-    fn listen_pin_restore_messages(receiver: Receiver<u8>, inner: AnschlüsseInternal) {
-        loop {
-            match receiver.recv() {
-                Ok(pin) => match inner.lock() {
-                    Ok(mut guard) => {
-                        let anschlüsse = &mut *guard;
-                        debug!("rückgabe pin {}", pin);
-                        anschlüsse.ausgegebene_pins.remove(&pin);
-                    }
-                    Err(err) => {
-                        error!("Anschlüsse-static poisoned: {}", err);
-                        break;
-                    }
-                },
-                Err(err) => {
-                    error!("Kanal für Pin-Rückgabe geschlossen: {}", err);
-                    break;
-                }
-            }
-        }
+    /// Das verwendete Backend.
+    pub fn backend() -> Backend {
+        *BACKEND.get_or_init(Backend::erkenne)
     }
 
     fn erstelle_static() -> AnschlüsseStatic {
-        #[cfg(not(raspi))]
-        let (pin_sender, pin_receiver) = channel();
-        macro_rules! make_anschlüsse {
-            {$($a0:ident $a1:ident $a2:ident $var:ident),*} => {{
-                #[cfg(raspi)]
-                let i2c = match rppal::i2c::I2c::new() {
-                    Ok(i2c) => Arc::new(Mutex::new(i2c)),
-                    Err(error) => return Arc::new(Mutex::new(Err(error.into()))),
-                };
-                #[cfg(raspi)]
-                let gpio = match rppal::gpio::Gpio::new() {
-                    Ok(gpio) => gpio,
-                    Err(error) => return Arc::new(Mutex::new(Err(error.into()))),
-                };
-                paste! {
-                    Ok(AnschlüsseData {
-                        #[cfg(raspi)]
-                        gpio,
-                        #[cfg(raspi)]
-                        i2c: i2c.clone(),
-                        #[cfg(not(raspi))]
-                        ausgegebene_pins: HashSet::new(),
-                        #[cfg(not(raspi))]
-                        pin_rückgabe: pin_sender,
-                        $(
-                            [<$a0 $a1 $a2 $var>]: Arc::new(Mutex::new(Pcf8574::neu(
-                                level!($a0),
-                                level!($a1),
-                                level!($a2),
-                                variante!($var),
-                                #[cfg(raspi)]
-                                i2c.clone(),
-                            ))),
-                            [<$a0 $a1 $a2 $var 0>]: None,
-                            [<$a0 $a1 $a2 $var 1>]: None,
-                            [<$a0 $a1 $a2 $var 2>]: None,
-                            [<$a0 $a1 $a2 $var 3>]: None,
-                            [<$a0 $a1 $a2 $var 4>]: None,
-                            [<$a0 $a1 $a2 $var 5>]: None,
-                            [<$a0 $a1 $a2 $var 6>]: None,
-                            [<$a0 $a1 $a2 $var 7>]: None,
-                        )*
-                    })
-                }
-            }};
-        }
-        let inner = (llln_to_hhha! {make_anschlüsse}).map(|anschlüsse| {
-            let inner = Arc::new(Mutex::new(anschlüsse));
-            let inner_clone = inner.clone();
-
-            let (sender, receiver) = channel();
-            let sender_clone = sender.clone();
-
-            // erzeuge Thread der Rückgaben behandelt
-            thread::spawn(move || {
-                Anschlüsse::listen_restore_messages(sender_clone, receiver, inner_clone)
-            });
-
-            #[cfg(not(raspi))]
-            {
-                let inner_clone = inner.clone();
-
-                // erzeuge Thread der Pin-Rückgaben behandelt
-                thread::spawn(move || {
-                    Anschlüsse::listen_pin_restore_messages(pin_receiver, inner_clone)
-                });
-            }
-
-            // Eigener Block um borrow-lifetime von inner zu beschränken
-            {
-                let anschlüsse =
-                    &mut *inner.lock().expect("Anschlüsse poisoned vor Initialisierung");
-                macro_rules! port_value {
-                    ($a0:ident $a1:ident $a2:ident $var:ident $port:expr) => {
-                        paste! {
-                            Some(
-                                Port::neu(
-                                    anschlüsse.[<$a0 $a1 $a2 $var>].clone(),
-                                    (level!{$a0}, level!{$a1}, level!{$a2}, variante!{$var}),
-                                    u3::new($port),
-                                    sender.clone()
-                                )
-                            )
-                        }
-                    };
-                }
-                macro_rules! init_anschlüsse {
-                    {$($k:ident $l:ident $m:ident $n:ident),*: $value:ident} => {
-                        paste! {
-                            $(
-                                anschlüsse.[<$k $l $m $n 0>] = $value!($k $l $m $n 0);
-                                anschlüsse.[<$k $l $m $n 1>] = $value!($k $l $m $n 1);
-                                anschlüsse.[<$k $l $m $n 2>] = $value!($k $l $m $n 2);
-                                anschlüsse.[<$k $l $m $n 3>] = $value!($k $l $m $n 3);
-                                anschlüsse.[<$k $l $m $n 4>] = $value!($k $l $m $n 4);
-                                anschlüsse.[<$k $l $m $n 5>] = $value!($k $l $m $n 5);
-                                anschlüsse.[<$k $l $m $n 6>] = $value!($k $l $m $n 6);
-                                anschlüsse.[<$k $l $m $n 7>] = $value!($k $l $m $n 7);
-                            )*
-                        }
-                    };
-                }
-                llln_to_hhha! {init_anschlüsse: port_value}
-            }
-
-            inner
-        });
-
-        Arc::new(Mutex::new(inner))
+        debug!("Backend: {}", Anschlüsse::backend());
+        #[cfg(raspi)]
+        let (gpio, gpio_chip) = match Anschlüsse::backend() {
+            Backend::Hardware => match rppal::gpio::Gpio::new() {
+                Ok(gpio) => (Some(gpio), None),
+                Err(error) => return Arc::new(Mutex::new(Err(error.into()))),
+            },
+            Backend::Cdev(chip) => match gpio_cdev::Chip::new(format!("/dev/gpiochip{}", chip)) {
+                Ok(gpio_chip) => (None, Some(gpio_chip)),
+                Err(error) => return Arc::new(Mutex::new(Err(error.into()))),
+            },
+            Backend::Remote(_) | Backend::Stub => (None, None),
+        };
+        let remote = match Anschlüsse::backend() {
+            Backend::Remote(adresse) => match Verbindung::neu(adresse) {
+                Ok(verbindung) => Some(verbindung),
+                Err(error) => return Arc::new(Mutex::new(Err(error.into()))),
+            },
+            _ => None,
+        };
+        Arc::new(Mutex::new(Ok(Arc::new(Mutex::new(AnschlüsseData {
+            #[cfg(raspi)]
+            gpio,
+            #[cfg(raspi)]
+            gpio_chip,
+            remote,
+            ..AnschlüsseData::leer()
+        })))))
     }
 
-    /// Gebe den Pcf8574 an Anschlüsse zurück, so dass er von anderen verwendet werden kann.
-    ///
-    /// Der Drop-Handler von Pcf8574 (und dem letzten Pcf8574Port) hat die selbe Auswirkung.
-    /// Diese Methode ist explizit (keine Wartezeit, kann dafür blockieren).
-    pub fn rückgabe(&mut self, port: pcf8574::Port) -> Result<(), SyncError> {
-        if let Some(arc) = &self.0 {
-            arc.lock()?.rückgabe(port)
-        } else {
-            Err(SyncError::InVerwendung)
-        }
+    /// Zugriff auf die Daten, nachdem alle gedroppten Anschlüsse freigegeben wurden.
+    fn data(&self) -> Result<MutexGuard<'_, AnschlüsseData>, Error> {
+        let arc = self.0.as_ref().ok_or(SyncError::WertDropped)?;
+        let mut anschlüsse = arc.lock()?;
+        anschlüsse.verarbeite_rückgaben();
+        Ok(anschlüsse)
     }
 
     /// Reserviere den spezifizierten Pin zur exklusiven Nutzung.
-    /// Rückgabe über den Drop-Handler (nur bei Raspi).
+    /// Rückgabe über den Drop-Handler (nur mit Cdev-, Remote- oder Stub-Backend).
     pub fn reserviere_pin(&mut self, pin: u8) -> Result<Pin, Error> {
         debug!("reserviere pin {}", pin);
         if let 2 | 3 = pin {
             // Gpio 2,3 nicht verfügbar (durch I2C belegt)
             return Err(Error::Sync(SyncError::InVerwendung));
         }
-        if let Some(arc) = self.0.as_ref() {
-            #[cfg_attr(raspi, allow(unused_mut))]
-            let mut anschlüsse = arc.lock()?;
-            cfg_if! {
-                if #[cfg(raspi)] {
-                    Ok(Pin::neu(anschlüsse.gpio.get(pin)?))
-                } else {
-                    if anschlüsse.ausgegebene_pins.insert(pin) {
-                        Ok(Pin::neu(pin, anschlüsse.pin_rückgabe.clone()))
-                    } else {
-                        // TODO besserer Fehler (welcher Pin wurde angefragt)
-                        Err(Error::Sync(SyncError::InVerwendung))
-                    }
-                }
-            }
+        if self.0.is_some() {
+            let mut anschlüsse = self.data()?;
+            let pin = anschlüsse.reserviere_pin(pin)?;
+            anschlüsse.vermerke_besitzer(Kennung::Pin(pin.pin()));
+            Ok(pin)
         } else {
             Err(Error::Sync(SyncError::InVerwendung))
         }
     }
 
     /// Reserviere den spezifizierten Pcf8574-Port zur exklusiven Nutzung.
+    /// Rückgabe über den Drop-Handler.
     pub fn reserviere_pcf8574_port(
         &mut self,
-        a0: Level,
-        a1: Level,
-        a2: Level,
-        variante: pcf8574::Variante,
+        adresse: pcf8574::Adresse,
         port: u3,
-    ) -> Result<pcf8574::Port, SyncError> {
-        self.0.as_mut().ok_or(SyncError::WertDropped).and_then(|arc| {
-            // TODO besserer Fehler (welche Port wurde angefragt)
-            arc.lock().map_err(SyncError::from).and_then(|mut guard| {
-                guard
-                    .reserviere_pcf8574_port(a0, a1, a2, variante, port)
-                    .ok_or(SyncError::InVerwendung)
+    ) -> Result<pcf8574::Port, Error> {
+        debug!("reserviere pcf8574 {} port {}", adresse, port);
+        let anschlüsse = &mut *self.data()?;
+        let pcf8574 = anschlüsse.reserviere_i2c_port(
+            adresse.into(),
+            port.into(),
+            |baustein| match baustein {
+                Baustein::Pcf8574(_variante, pcf8574) => Some(pcf8574.clone()),
+                _ => None,
+            },
+            |i2c| {
+                Baustein::Pcf8574(
+                    adresse.variante,
+                    Arc::new(Mutex::new(Pcf8574::neu(adresse, i2c))),
+                )
+            },
+        )?;
+        Ok(pcf8574::Port::neu(pcf8574, adresse, port, anschlüsse.i2c_rückgabe.clone()))
+    }
+
+    /// Reserviere den spezifizierten Mcp23017-Port zur exklusiven Nutzung.
+    /// Rückgabe über den Drop-Handler.
+    ///
+    /// Ein Mcp23017 belegt die gleiche I2C-Adresse wie ein Pcf8574 (Variante Normal)
+    /// mit gleichen Adress-Bits, beide können nicht gleichzeitig verwendet werden.
+    pub fn reserviere_mcp23017_port(
+        &mut self,
+        adresse: mcp23017::Adresse,
+        port: u8,
+    ) -> Result<mcp23017::Port, Error> {
+        debug!("reserviere mcp23017 {} port {}", adresse, port);
+        if port >= mcp23017::ANZAHL_PORTS {
+            return Err(Error::Mcp23017(mcp23017::Error::UngültigerPort(port)));
+        }
+        let anschlüsse = &mut *self.data()?;
+        let mcp23017 = anschlüsse.reserviere_i2c_port(
+            adresse.into(),
+            port,
+            |baustein| match baustein {
+                Baustein::Mcp23017(mcp23017) => Some(mcp23017.clone()),
+                _ => None,
+            },
+            |i2c| Baustein::Mcp23017(Arc::new(Mutex::new(Mcp23017::neu(adresse, i2c)))),
+        )?;
+        Ok(mcp23017::Port::neu(mcp23017, adresse, port, anschlüsse.i2c_rückgabe.clone()))
+    }
+
+    /// Reserviere den spezifizierten Kanal eines Adc zur exklusiven Nutzung.
+    /// Rückgabe über den Drop-Handler.
+    ///
+    /// Über einen entfernten Raspberry Pi schlägt die Reservierung mit [Error::NichtRemote] fehl.
+    pub fn reserviere_adc_kanal(
+        &mut self,
+        adresse: adc::Adresse,
+        kanal: u8,
+    ) -> Result<adc::Kanal, Error> {
+        debug!("reserviere adc {} kanal {}", adresse, kanal);
+        if adresse.offset >= adresse.variante.anzahl_adressen() {
+            return Err(Error::Adc(adc::Error::UngültigeAdresse(adresse)));
+        }
+        if kanal >= adc::ANZAHL_KANÄLE {
+            return Err(Error::Adc(adc::Error::UngültigerKanal(kanal)));
+        }
+        let anschlüsse = &mut *self.data()?;
+        anschlüsse.nur_lokal(i2c::Art::Adc(adresse.variante))?;
+        let adc = anschlüsse.reserviere_i2c_port(
+            adresse.into(),
+            kanal,
+            |baustein| match baustein {
+                Baustein::Adc(variante, adc) if variante == &adresse.variante => Some(adc.clone()),
+                _ => None,
+            },
+            |i2c| Baustein::Adc(adresse.variante, Arc::new(Mutex::new(Adc::neu(adresse, i2c)))),
+        )?;
+        Ok(adc::Kanal::neu(adc, adresse, kanal, anschlüsse.i2c_rückgabe.clone()))
+    }
+
+    /// Reserviere den spezifizierten Kanal eines Pca9685 zur exklusiven Nutzung.
+    /// Rückgabe über den Drop-Handler.
+    ///
+    /// Eine `frequenz` wird für alle Kanäle des Pca9685 eingestellt.
+    /// Sie muss zur bisherigen Frequenz passen, sofern bereits ein anderer Kanal reserviert ist.
+    /// Über einen entfernten Raspberry Pi schlägt die Reservierung mit [Error::NichtRemote] fehl.
+    pub fn reserviere_pca9685_kanal(
+        &mut self,
+        adresse: pca9685::Adresse,
+        kanal: u8,
+        frequenz: Option<f64>,
+    ) -> Result<pca9685::Kanal, Error> {
+        debug!("reserviere pca9685 {} kanal {}", adresse, kanal);
+        if adresse.offset >= pca9685::ANZAHL_ADRESSEN {
+            return Err(Error::Pca9685(pca9685::Error::UngültigeAdresse(adresse)));
+        }
+        if kanal >= pca9685::ANZAHL_KANÄLE {
+            return Err(Error::Pca9685(pca9685::Error::UngültigerKanal(kanal)));
+        }
+        let anschlüsse = &mut *self.data()?;
+        anschlüsse.nur_lokal(i2c::Art::Pca9685)?;
+        let i2c_adresse = i2c::Adresse::from(adresse);
+        let andere_reserviert =
+            anschlüsse.ausgegebene_i2c_ports.iter().any(|(belegt, _port)| belegt == &i2c_adresse);
+        let pca9685 = anschlüsse.reserviere_i2c_port(
+            i2c_adresse,
+            kanal,
+            |baustein| match baustein {
+                Baustein::Pca9685(pca9685) => Some(pca9685.clone()),
+                _ => None,
+            },
+            |i2c| Baustein::Pca9685(Arc::new(Mutex::new(Pca9685::neu(adresse, i2c)))),
+        )?;
+        let kanal = pca9685::Kanal::neu(pca9685, adresse, kanal, anschlüsse.i2c_rückgabe.clone());
+        if let Some(frequenz) = frequenz {
+            // bei einem Fehler wird der Kanal über den Drop-Handler zurückgegeben
+            kanal.stelle_frequenz(frequenz, andere_reserviert).map_err(Error::Pca9685)?;
+        }
+        Ok(kanal)
+    }
+
+    /// Zugriff auf einen I2C-Bus, ohne einen Baustein zu reservieren (für den [remote::Server]).
+    pub(super) fn i2c_zugriff(&mut self, bus: i2c::Bus) -> Result<i2c::Zugriff, Error> {
+        self.data()?.i2c_zugriff(bus)
+    }
+
+    /// Belegung aller bisher verwendeten I2C-Bausteine (reservierte und freie Ports),
+    /// sortiert nach Bus und Adresse.
+    pub fn i2c_belegung(&self) -> Result<Vec<i2c::Belegung>, Error> {
+        let anschlüsse = &*self.data()?;
+        Ok(anschlüsse.i2c_belegung())
+    }
+
+    /// Setze den callback, der nach einem erneuten Verbindungsaufbau zum entfernten Raspberry Pi
+    /// aufgerufen wird (nur mit Remote-Backend).
+    ///
+    /// Die Ausgänge bleiben bis zum Aufruf von [ausgänge_freigeben](Anschlüsse::ausgänge_freigeben)
+    /// im sicheren Zustand.
+    pub fn bei_wiederverbindung(
+        &mut self,
+        callback: impl FnMut() + Send + 'static,
+    ) -> Result<(), Error> {
+        if let Some(verbindung) = &self.data()?.remote {
+            verbindung.bei_wiederverbindung(callback)
+        }
+        Ok(())
+    }
+
+    /// Gebe die nach einem Verbindungsabbruch gesperrten Ausgänge wieder frei
+    /// (nur mit Remote-Backend).
+    pub fn ausgänge_freigeben(&mut self) -> Result<(), Error> {
+        let verbindung = self.data()?.remote.clone();
+        if let Some(verbindung) = verbindung {
+            verbindung.ausgänge_freigeben()?
+        }
+        Ok(())
+    }
+
+    /// Aktuell reservierte Gpio-Pins, inklusive der durch I2C belegten Gpio 2, 3.
+    pub fn reservierte_pins(&self) -> Result<Vec<u8>, Error> {
+        let anschlüsse = &*self.data()?;
+        Ok(anschlüsse.reservierte_pins())
+    }
+
+    /// Führe `reserviere` aus und vermerke `besitzer` für alle dabei reservierten Anschlüsse.
+    pub fn mit_besitzer<T>(
+        &mut self,
+        besitzer: Besitzer,
+        reserviere: impl FnOnce(&mut Anschlüsse) -> T,
+    ) -> T {
+        let vorheriger = self.setze_aktuellen_besitzer(Some(besitzer));
+        let ergebnis = reserviere(self);
+        let _ = self.setze_aktuellen_besitzer(vorheriger);
+        ergebnis
+    }
+
+    fn setze_aktuellen_besitzer(&mut self, besitzer: Option<Besitzer>) -> Option<Besitzer> {
+        match self.0.as_ref().map(|arc| arc.lock()) {
+            Some(Ok(mut anschlüsse)) => {
+                std::mem::replace(&mut anschlüsse.aktueller_besitzer, besitzer)
+            },
+            Some(Err(err)) => {
+                error!("Anschlüsse-static poisoned: {}", err);
+                None
+            },
+            None => None,
+        }
+    }
+
+    /// Momentaufnahme aller reservierten Anschlüsse und ihrer Besitzer.
+    pub fn reservierungen(&self) -> Result<Reservierungen, Error> {
+        let anschlüsse = &*self.data()?;
+        let besitzer = |kennung| anschlüsse.besitzer.get(&kennung).cloned();
+        let pins = anschlüsse
+            .reservierte_pins()
+            .into_iter()
+            .map(|pin| (pin, besitzer(Kennung::Pin(pin))))
+            .collect();
+        let i2c_ports = anschlüsse
+            .ausgegebene_i2c_ports
+            .iter()
+            .map(|&(adresse, port)| ((adresse, port), besitzer(Kennung::I2c(adresse, port))))
+            .collect();
+        let i2c_bausteine = anschlüsse
+            .bausteine
+            .iter()
+            .filter(|(adresse, _baustein)| {
+                anschlüsse.ausgegebene_i2c_ports.iter().any(|(belegt, _port)| belegt == *adresse)
             })
+            .map(|(adresse, baustein)| (*adresse, baustein.art()))
+            .collect();
+        Ok(Reservierungen { pins, i2c_ports, i2c_bausteine })
+    }
+
+    /// Suche alle antwortenden Adressen eines I2C-Busses.
+    ///
+    /// Jede Adresse wird durch Lesen eines Bytes (ohne Register-Auswahl) getestet.
+    #[cfg_attr(not(raspi), allow(unused_variables))]
+    pub fn scanne_i2c_bus(&mut self, bus: i2c::Bus) -> Result<Vec<i2c::Antwort>, Error> {
+        debug!("scanne {}", bus);
+        let (zugriff, verwendet) = {
+            let anschlüsse = &mut *self.data()?;
+            let verwendet: HashMap<u8, i2c::Art> = anschlüsse
+                .bausteine
+                .iter()
+                .filter(|(adresse, _baustein)| adresse.bus == bus)
+                .map(|(adresse, baustein)| (adresse.adresse, baustein.art()))
+                .collect();
+            (anschlüsse.i2c_zugriff(bus)?, verwendet)
+        };
+        #[cfg(raspi)]
+        if let Some(i2c) = zugriff.i2c() {
+            let mut i2c_channel = i2c.lock()?;
+            let mut antworten = Vec::new();
+            for adresse in i2c::SCAN_ADRESSEN {
+                i2c_channel.set_slave_address(adresse.into())?;
+                let mut buffer = [0];
+                if let Ok(1) = i2c_channel.read(&mut buffer) {
+                    antworten.push(i2c::Antwort {
+                        adresse: i2c::Adresse { bus, adresse },
+                        gelesen: buffer[0],
+                        kandidaten: i2c::Art::kandidaten(adresse),
+                        verwendet: verwendet.get(&adresse).copied(),
+                    })
+                }
+            }
+            return Ok(antworten);
+        }
+        Err(Error::KeinRaspberryPi)
+    }
+}
+
+/// Verfügbarkeit eines Anschlusses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verfügbarkeit {
+    Frei,
+    /// Reserviert, mit Besitzer sofern bekannt.
+    Reserviert(Option<Besitzer>),
+    /// Die I2C-Adresse wird bereits von einem anderen Baustein verwendet.
+    AndererBaustein(i2c::Art),
+}
+
+impl Display for Verfügbarkeit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Verfügbarkeit::Frei => write!(f, "Frei"),
+            Verfügbarkeit::Reserviert(Some(besitzer)) => write!(f, "Reserviert von {}", besitzer),
+            Verfügbarkeit::Reserviert(None) => write!(f, "Reserviert"),
+            Verfügbarkeit::AndererBaustein(art) => write!(f, "Adresse durch {} belegt", art),
+        }
+    }
+}
+
+/// Momentaufnahme der reservierten Anschlüsse, erzeugt über [Anschlüsse::reservierungen].
+#[derive(Debug, Clone, Default)]
+pub struct Reservierungen {
+    pins: HashMap<u8, Option<Besitzer>>,
+    i2c_ports: HashMap<(i2c::Adresse, u8), Option<Besitzer>>,
+    /// Bausteine mit mindestens einem reservierten Port.
+    i2c_bausteine: HashMap<i2c::Adresse, i2c::Art>,
+}
+
+impl Reservierungen {
+    /// Verfügbarkeit eines Gpio-Pins.
+    pub fn pin(&self, pin: u8) -> Verfügbarkeit {
+        match self.pins.get(&pin) {
+            Some(besitzer) => Verfügbarkeit::Reserviert(besitzer.clone()),
+            None => Verfügbarkeit::Frei,
+        }
+    }
+
+    /// Verfügbarkeit eines Ports (bzw. Kanals) des Bausteins `art` an der I2C-Adresse.
+    pub fn i2c_port(&self, adresse: i2c::Adresse, art: i2c::Art, port: u8) -> Verfügbarkeit {
+        match (self.i2c_ports.get(&(adresse, port)), self.i2c_bausteine.get(&adresse)) {
+            (_, Some(verwendet)) if verwendet != &art => {
+                Verfügbarkeit::AndererBaustein(*verwendet)
+            },
+            (Some(besitzer), _) => Verfügbarkeit::Reserviert(besitzer.clone()),
+            (None, _) => Verfügbarkeit::Frei,
+        }
+    }
+
+    /// Verfügbarkeit des Anschlusses `kennung`.
+    pub fn anschluss(&self, kennung: zuordnung::Kennung) -> Verfügbarkeit {
+        match kennung {
+            zuordnung::Kennung::Pin(pin) => self.pin(pin),
+            zuordnung::Kennung::Pcf8574Port(adresse, port) => {
+                self.i2c_port(adresse.into(), i2c::Art::Pcf8574(adresse.variante), port)
+            },
+            zuordnung::Kennung::Mcp23017Port(adresse, port) => {
+                self.i2c_port(adresse.into(), i2c::Art::Mcp23017, port)
+            },
+            zuordnung::Kennung::Pca9685Kanal(adresse, kanal) => {
+                self.i2c_port(adresse.into(), i2c::Art::Pca9685, kanal)
+            },
+        }
+    }
+
+    /// Nächster freier Gpio-Pin ab `pin` (aufsteigend, danach von vorne).
+    pub fn nächster_freier_pin(&self, pin: u8) -> Option<u8> {
+        nächster_freier(pin, ANZAHL_GPIO_PINS, |pin| self.pin(pin) == Verfügbarkeit::Frei)
+    }
+
+    /// Nächster freier Port des Bausteins ab `port` (aufsteigend, danach von vorne).
+    pub fn nächster_freier_i2c_port(
+        &self,
+        adresse: i2c::Adresse,
+        art: i2c::Art,
+        port: u8,
+    ) -> Option<u8> {
+        nächster_freier(port, art.anzahl_ports(), |port| {
+            self.i2c_port(adresse, art, port) == Verfügbarkeit::Frei
         })
     }
 }
 
+fn nächster_freier(start: u8, anzahl: u8, ist_frei: impl Fn(u8) -> bool) -> Option<u8> {
+    (start..anzahl).chain(0..start.min(anzahl)).find(|wert| ist_frei(*wert))
+}
+
 type AnschlüsseInternal = Arc<Mutex<AnschlüsseData>>;
 type AnschlüsseResult = Result<AnschlüsseInternal, Error>;
 type AnschlüsseStatic = Arc<Mutex<AnschlüsseResult>>;
 static ANSCHLÜSSE: Lazy<AnschlüsseStatic> = Lazy::new(Anschlüsse::erstelle_static);
+static BACKEND: OnceCell<Backend> = OnceCell::new();
+
+/// Tests verwenden das gleiche Singleton und dürfen daher nicht parallel laufen.
+#[cfg(test)]
+pub(crate) fn test_sperre() -> MutexGuard<'static, ()> {
+    static SPERRE: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+    SPERRE.lock().unwrap_or_else(PoisonError::into_inner)
+}
 
 #[derive(Debug)]
 pub enum Error {
@@ -453,7 +830,18 @@ pub enum Error {
     I2c(rppal::i2c::Error),
     #[cfg(raspi)]
     Pwm(rppal::pwm::Error),
+    #[cfg(raspi)]
+    Cdev(gpio_cdev::errors::Error),
+    KeinRaspberryPi,
+    /// Das Backend wurde bereits (anders) festgelegt.
+    BackendFestgelegt(Backend),
     Sync(SyncError),
+    Mcp23017(mcp23017::Error),
+    Adc(adc::Error),
+    Pca9685(pca9685::Error),
+    Remote(remote::Error),
+    /// Der Baustein kann nicht über einen entfernten Raspberry Pi verwendet werden.
+    NichtRemote(i2c::Art),
 }
 impl From<SyncError> for Error {
     fn from(error: SyncError) -> Self {
@@ -465,6 +853,11 @@ impl<T> From<PoisonError<T>> for Error {
         SyncError::from(error).into()
     }
 }
+impl From<remote::Error> for Error {
+    fn from(error: remote::Error) -> Self {
+        Error::Remote(error)
+    }
+}
 cfg_if! {
     if #[cfg(raspi)] {
         impl From<rppal::gpio::Error> for Error {
@@ -482,6 +875,11 @@ cfg_if! {
                 Error::Pwm(error)
             }
         }
+        impl From<gpio_cdev::errors::Error> for Error {
+            fn from(error: gpio_cdev::errors::Error) -> Self {
+                Error::Cdev(error)
+            }
+        }
     }
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -1,13 +1,21 @@
 //! unittests für das anschluss-Modul
 
-use std::thread::sleep;
-use std::time::Duration;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+#[cfg(raspi)]
+use std::{fs, sync::mpsc::channel, time::Duration};
 
 use num_x::u3;
 use simple_logger::SimpleLogger;
 
-use super::{Anschlüsse, SyncError};
-use crate::anschluss::{level::Level, pcf8574};
+#[cfg(raspi)]
+use super::Backend;
+use super::{
+    test_sperre, Anschlüsse, AnschlüsseData, Besitzer, Error, SyncError, Verbindung, Verfügbarkeit,
+};
+#[cfg(raspi)]
+use crate::anschluss::trigger::Trigger;
+use crate::anschluss::{adc, i2c, level::Level, mcp23017, pca9685, pcf8574, zuordnung::Kennung};
 
 #[test]
 fn drop_semantics() {
@@ -16,92 +24,231 @@ fn drop_semantics() {
         .with_module_level("zugkontrolle", log::LevelFilter::Debug)
         .init()
         .expect("failed to initialize error logging");
+    let _sperre = test_sperre();
 
+    let llln = pcf8574::Adresse {
+        bus: i2c::Bus::STANDARD,
+        a0: Level::Low,
+        a1: Level::Low,
+        a2: Level::Low,
+        variante: pcf8574::Variante::Normal,
+    };
     let mut anschlüsse = Anschlüsse::neu().expect("1.ter Aufruf von neu.");
     Anschlüsse::neu().expect_err("2.ter Aufruf von neu.");
-    let llln = anschlüsse
-        .reserviere_pcf8574_port(
-            Level::Low,
-            Level::Low,
-            Level::Low,
-            pcf8574::Variante::Normal,
-            u3::new(0),
-        )
-        .expect("1. Aufruf von llln.");
-    assert_eq!(llln.adresse(), &(Level::Low, Level::Low, Level::Low, pcf8574::Variante::Normal));
-    assert_eq!(llln.port(), u3::new(0));
-    assert_eq!(
-        anschlüsse.reserviere_pcf8574_port(
-            Level::Low,
-            Level::Low,
-            Level::Low,
-            pcf8574::Variante::Normal,
-            u3::new(0)
+    let llln0 = anschlüsse.reserviere_pcf8574_port(llln, u3::new(0)).expect("1. Aufruf von llln.");
+    assert_eq!(llln0.adresse(), &llln);
+    assert_eq!(llln0.port(), u3::new(0));
+    assert!(
+        matches!(
+            anschlüsse.reserviere_pcf8574_port(llln, u3::new(0)),
+            Err(Error::Sync(SyncError::InVerwendung))
         ),
-        Err(SyncError::InVerwendung),
         "2. Aufruf von llln."
     );
-    drop(llln);
-    // Warte etwas, damit der restore-thread genug Zeit hat.
-    sleep(Duration::from_secs(1));
-    let llln = anschlüsse
-        .reserviere_pcf8574_port(
-            Level::Low,
-            Level::Low,
-            Level::Low,
-            pcf8574::Variante::Normal,
-            u3::new(0),
-        )
-        .expect("Aufruf von llln nach drop.");
+    drop(llln0);
+    let llln0 =
+        anschlüsse.reserviere_pcf8574_port(llln, u3::new(0)).expect("Aufruf von llln nach drop.");
     drop(anschlüsse);
 
     // jetzt sollte Anschlüsse wieder verfügbar sein
     let mut anschlüsse = Anschlüsse::neu().expect("Aufruf von neu nach drop.");
-    assert_eq!(
-        anschlüsse.reserviere_pcf8574_port(
-            Level::Low,
-            Level::Low,
-            Level::Low,
-            pcf8574::Variante::Normal,
-            u3::new(0)
+    assert!(
+        matches!(
+            anschlüsse.reserviere_pcf8574_port(llln, u3::new(0)),
+            Err(Error::Sync(SyncError::InVerwendung))
         ),
-        Err(SyncError::InVerwendung),
         "Aufruf von llln mit vorherigem Ergebnis in scope."
     );
-    drop(llln);
-    // Warte etwas, damit der restore-thread genug Zeit hat.
-    sleep(Duration::from_secs(1));
-    let llln0 = anschlüsse
-        .reserviere_pcf8574_port(
-            Level::Low,
-            Level::Low,
-            Level::Low,
-            pcf8574::Variante::Normal,
-            u3::new(0),
-        )
-        .expect("Aufruf von llln nach drop.");
+    drop(llln0);
+    let llln0 =
+        anschlüsse.reserviere_pcf8574_port(llln, u3::new(0)).expect("Aufruf von llln nach drop.");
     let llln1 = anschlüsse
-        .reserviere_pcf8574_port(
-            Level::Low,
-            Level::Low,
-            Level::Low,
-            pcf8574::Variante::Normal,
-            u3::new(1),
-        )
+        .reserviere_pcf8574_port(llln, u3::new(1))
         .expect("Aufruf von llln nach drop, alternativer port.");
+    // gleiche Adress-Bits an einem anderen Bus
+    let llln_bus3 = pcf8574::Adresse { bus: i2c::Bus(3), ..llln };
+    let llln_bus3_0 = anschlüsse
+        .reserviere_pcf8574_port(llln_bus3, u3::new(0))
+        .expect("Aufruf von llln an Bus 3.");
+    let belegung = anschlüsse.i2c_belegung().expect("Belegung der I2C-Bausteine.");
+    assert_eq!(
+        belegung
+            .iter()
+            .map(|i2c::Belegung { adresse, reserviert, .. }| (*adresse, reserviert.clone()))
+            .collect::<Vec<_>>(),
+        vec![(llln.into(), vec![0, 1]), (llln_bus3.into(), vec![0])],
+        "Belegung mit zwei Bussen."
+    );
+    assert_eq!(belegung[0].frei().collect::<Vec<_>>(), vec![2, 3, 4, 5, 6, 7]);
     drop(llln0);
     drop(llln1);
-    // Warte etwas, damit der restore-thread genug Zeit hat.
-    sleep(Duration::from_secs(1));
-    let llln = anschlüsse
-        .reserviere_pcf8574_port(
-            Level::Low,
-            Level::Low,
-            Level::Low,
-            pcf8574::Variante::Normal,
-            u3::new(0),
-        )
-        .expect("Aufruf von llln nach drop.");
-    drop(llln);
+    drop(llln_bus3_0);
+    let llln0 =
+        anschlüsse.reserviere_pcf8574_port(llln, u3::new(0)).expect("Aufruf von llln nach drop.");
+    drop(llln0);
+
+    // Mcp23017 verwendet den gleichen Adressbereich wie Pcf8574 (Variante Normal)
+    let lll = mcp23017::Adresse {
+        bus: i2c::Bus::STANDARD,
+        a0: Level::Low,
+        a1: Level::Low,
+        a2: Level::Low,
+    };
+    let mcp23017_port =
+        anschlüsse.reserviere_mcp23017_port(lll, 15).expect("Aufruf von Mcp23017 lll.");
+    anschlüsse.reserviere_mcp23017_port(lll, 16).expect_err("Aufruf von Mcp23017 mit Port 16.");
+    assert!(
+        matches!(
+            anschlüsse.reserviere_pcf8574_port(llln, u3::new(0)),
+            Err(Error::Sync(SyncError::InVerwendung))
+        ),
+        "Aufruf von llln bei verwendetem Mcp23017 lll."
+    );
+    drop(mcp23017_port);
+    let llln0 = anschlüsse
+        .reserviere_pcf8574_port(llln, u3::new(0))
+        .expect("Aufruf von llln nach drop von Mcp23017 lll.");
+    anschlüsse
+        .reserviere_mcp23017_port(lll, 0)
+        .expect_err("Aufruf von Mcp23017 lll bei verwendetem llln.");
+    drop(llln0);
+
+    let pca9685 = pca9685::Adresse { bus: i2c::Bus::STANDARD, offset: 0 };
+    let kanal =
+        anschlüsse.reserviere_pca9685_kanal(pca9685, 15, None).expect("Aufruf von Pca9685.");
+    anschlüsse
+        .reserviere_pca9685_kanal(pca9685, 15, None)
+        .expect_err("Aufruf von Pca9685 bei verwendetem Kanal.");
+    anschlüsse
+        .reserviere_pca9685_kanal(pca9685, 16, None)
+        .expect_err("Aufruf von Pca9685 mit Kanal 16.");
+    drop(kanal);
+    let kanal = anschlüsse
+        .reserviere_pca9685_kanal(pca9685, 15, Some(50.))
+        .expect("Aufruf von Pca9685 nach drop.");
+    anschlüsse
+        .reserviere_pca9685_kanal(pca9685, 14, Some(100.))
+        .expect_err("Aufruf von Pca9685 mit anderer Frequenz.");
+    anschlüsse
+        .reserviere_pca9685_kanal(pca9685, 14, Some(50.))
+        .expect("Aufruf von Pca9685 nach anderer Frequenz.");
+    drop(kanal);
+
+    // Reservierungen mit Besitzer
+    let weiche = Besitzer::Weiche("W".to_string());
+    let pin4 = anschlüsse
+        .mit_besitzer(weiche.clone(), |anschlüsse| anschlüsse.reserviere_pin(4))
+        .expect("Aufruf von Pin 4 für Weiche.");
+    let pin5 = anschlüsse.reserviere_pin(5).expect("Aufruf von Pin 5 ohne Besitzer.");
+    let llln0 = anschlüsse
+        .mit_besitzer(Besitzer::Nothalt, |anschlüsse| {
+            anschlüsse.reserviere_pcf8574_port(llln, u3::new(0))
+        })
+        .expect("Aufruf von llln für Nothalt.");
+    let reservierungen = anschlüsse.reservierungen().expect("Reservierungen.");
+    assert_eq!(reservierungen.pin(4), Verfügbarkeit::Reserviert(Some(weiche)));
+    assert_eq!(reservierungen.pin(5), Verfügbarkeit::Reserviert(None));
+    assert_eq!(reservierungen.pin(6), Verfügbarkeit::Frei);
+    assert_eq!(reservierungen.nächster_freier_pin(4), Some(6));
+    let llln_i2c = i2c::Adresse::from(llln);
+    let pcf8574 = i2c::Art::Pcf8574(pcf8574::Variante::Normal);
+    assert_eq!(
+        reservierungen.i2c_port(llln_i2c, pcf8574, 0),
+        Verfügbarkeit::Reserviert(Some(Besitzer::Nothalt))
+    );
+    assert_eq!(reservierungen.i2c_port(llln_i2c, pcf8574, 1), Verfügbarkeit::Frei);
+    assert_eq!(reservierungen.nächster_freier_i2c_port(llln_i2c, pcf8574, 0), Some(1));
+    assert_eq!(
+        reservierungen.i2c_port(llln_i2c, i2c::Art::Mcp23017, 1),
+        Verfügbarkeit::AndererBaustein(pcf8574)
+    );
+    assert_eq!(
+        reservierungen.anschluss(Kennung::Pcf8574Port(llln, 0)),
+        Verfügbarkeit::Reserviert(Some(Besitzer::Nothalt))
+    );
+    assert_eq!(reservierungen.anschluss(Kennung::Pin(6)), Verfügbarkeit::Frei);
+    drop(pin4);
+    drop(pin5);
+    drop(llln0);
+    let reservierungen = anschlüsse.reservierungen().expect("Reservierungen nach drop.");
+    assert_eq!(reservierungen.pin(4), Verfügbarkeit::Frei);
+    assert_eq!(reservierungen.i2c_port(llln_i2c, pcf8574, 0), Verfügbarkeit::Frei);
     drop(anschlüsse);
 }
+
+#[test]
+fn remote_ohne_register_zugriff() {
+    let _sperre = test_sperre();
+    // Der Verbindungsaufbau benötigt nur einen offenen Port, keinen laufenden Server.
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Öffnen eines Ports.");
+    let verbindung = Verbindung::neu(listener.local_addr().expect("Adresse des Ports."))
+        .expect("Verbindung zum Port.");
+    let mut anschlüsse = Anschlüsse(Some(Arc::new(Mutex::new(AnschlüsseData {
+        remote: Some(verbindung),
+        ..AnschlüsseData::leer()
+    }))));
+
+    let adc = adc::Adresse { bus: i2c::Bus::STANDARD, variante: adc::Variante::Ads1115, offset: 0 };
+    assert!(
+        matches!(
+            anschlüsse.reserviere_adc_kanal(adc, 0),
+            Err(Error::NichtRemote(i2c::Art::Adc(adc::Variante::Ads1115)))
+        ),
+        "Adc über Remote reserviert."
+    );
+    let pca9685 = pca9685::Adresse { bus: i2c::Bus::STANDARD, offset: 0 };
+    assert!(
+        matches!(
+            anschlüsse.reserviere_pca9685_kanal(pca9685, 0, Some(50.)),
+            Err(Error::NichtRemote(i2c::Art::Pca9685))
+        ),
+        "Pca9685 über Remote reserviert."
+    );
+    let reservierungen = anschlüsse.reservierungen().expect("Reservierungen.");
+    assert_eq!(
+        reservierungen.i2c_port(adc.into(), i2c::Art::Adc(adc::Variante::Ads1115), 0),
+        Verfügbarkeit::Frei
+    );
+}
+
+/// Benötigt das Kernel-Modul gpio-mockup (`modprobe gpio-mockup gpio_mockup_ranges=-1,28`)
+/// und Schreibrechte für debugfs.
+///
+/// Ausführen mit `ZUGKONTROLLE_GPIOCHIP=<nummer> cargo test -- --ignored cdev`.
+#[cfg(raspi)]
+#[test]
+#[ignore]
+fn cdev_backend() {
+    let chip: u8 = std::env::var("ZUGKONTROLLE_GPIOCHIP")
+        .expect("ZUGKONTROLLE_GPIOCHIP nicht gesetzt.")
+        .parse()
+        .expect("ZUGKONTROLLE_GPIOCHIP ist keine Zahl.");
+    let _sperre = test_sperre();
+    let debugfs = |pin: u8| format!("/sys/kernel/debug/gpio-mockup/gpiochip{}/{}", chip, pin);
+    Anschlüsse::wähle_backend(Backend::Cdev(chip)).expect("Wahl des Cdev-Backends.");
+    let mut anschlüsse = Anschlüsse::neu().expect("Aufruf von neu.");
+
+    let mut output =
+        anschlüsse.reserviere_pin(4).expect("Aufruf von Pin 4.").into_output(Level::Low);
+    output.write(Level::High).expect("Schreiben von Pin 4.");
+    assert_eq!(fs::read_to_string(debugfs(4)).expect("Lesen von debugfs.").trim(), "1");
+    anschlüsse.reserviere_pin(4).expect_err("2. Aufruf von Pin 4.");
+
+    let mut input = anschlüsse.reserviere_pin(5).expect("Aufruf von Pin 5.").into_input();
+    assert_eq!(input.read().expect("Lesen von Pin 5."), Level::Low);
+    let (sender, empfänger) = channel();
+    input
+        .set_async_interrupt(Trigger::Both, move |level| {
+            let _ = sender.send(level);
+        })
+        .expect("Interrupt für Pin 5.");
+    fs::write(debugfs(5), "1").expect("Schreiben von debugfs.");
+    assert_eq!(empfänger.recv_timeout(Duration::from_secs(1)), Ok(Level::High));
+    input.clear_async_interrupt().expect("Entfernen des Interrupts für Pin 5.");
+    assert_eq!(input.read().expect("Lesen von Pin 5 nach Interrupt."), Level::High);
+
+    drop(output);
+    drop(input);
+    anschlüsse.reserviere_pin(4).expect("Aufruf von Pin 4 nach drop.");
+    anschlüsse.reserviere_pin(5).expect("Aufruf von Pin 5 nach drop.");
+}
@@ -0,0 +1,254 @@
+//! I2C-Busse und Adressen der daran angeschlossenen Bausteine.
+
+use std::fmt::{self, Display, Formatter};
+use std::ops::RangeInclusive;
+#[cfg(raspi)]
+use std::sync::{Arc, Mutex};
+
+use cfg_if::cfg_if;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::{adc, level::Level, mcp23017, pca9685, pcf8574, remote::Verbindung};
+use crate::speicherformat::{neu_seit, Version};
+
+/// Geteilter Zugriff auf einen I2C-Bus, ohne Hardware-Backend nur ein Stub.
+#[derive(Debug, Clone)]
+pub(super) struct Zugriff {
+    #[cfg(raspi)]
+    hardware: Option<Arc<Mutex<rppal::i2c::I2c>>>,
+    /// Nur Pcf8574 werden über einen entfernten Raspberry Pi unterstützt.
+    remote: Option<Verbindung>,
+}
+
+impl Zugriff {
+    pub(super) fn stub() -> Self {
+        Zugriff {
+            #[cfg(raspi)]
+            hardware: None,
+            remote: None,
+        }
+    }
+
+    #[cfg(raspi)]
+    pub(super) fn hardware(i2c: rppal::i2c::I2c) -> Self {
+        Zugriff { hardware: Some(Arc::new(Mutex::new(i2c))), remote: None }
+    }
+
+    pub(super) fn remote(verbindung: Verbindung) -> Self {
+        Zugriff {
+            #[cfg(raspi)]
+            hardware: None,
+            remote: Some(verbindung),
+        }
+    }
+
+    /// Wird der I2C-Bus über das Hardware-Backend (oder einen entfernten Raspberry Pi) angesprochen?
+    pub(super) fn ist_hardware(&self) -> bool {
+        cfg_if! {
+            if #[cfg(raspi)] {
+                self.hardware.is_some() || self.remote.is_some()
+            } else {
+                self.remote.is_some()
+            }
+        }
+    }
+
+    /// Die Verbindung, sofern ein entfernter Raspberry Pi verwendet wird.
+    pub(super) fn verbindung(&self) -> Option<&Verbindung> {
+        self.remote.as_ref()
+    }
+
+    /// Der I2C-Bus, sofern das Hardware-Backend verwendet wird.
+    #[cfg(raspi)]
+    pub(super) fn i2c(&self) -> Option<&Arc<Mutex<rppal::i2c::I2c>>> {
+        self.hardware.as_ref()
+    }
+}
+
+/// Nummer eines I2C-Busses (`/dev/i2c-<nummer>`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Bus(pub u8);
+
+impl Bus {
+    /// Standard-Bus des Raspberry Pi (Gpio 2, 3).
+    pub const STANDARD: Bus = Bus(1);
+    /// Obergrenze für die Eingabe einer Busnummer.
+    pub const MAXIMUM: u8 = 31;
+
+    /// Alle im System vorhandenen I2C-Busse (`/dev/i2c-*`), aufsteigend sortiert.
+    pub fn vorhandene() -> Vec<Bus> {
+        let mut busse: Vec<_> = std::fs::read_dir("/dev")
+            .into_iter()
+            .flatten()
+            .filter_map(|eintrag| {
+                let name = eintrag.ok()?.file_name();
+                name.to_str()?.strip_prefix("i2c-")?.parse().ok().map(Bus)
+            })
+            .collect();
+        busse.sort_unstable();
+        busse
+    }
+
+    /// Lese den Bus eines gespeicherten Bausteins,
+    /// ältere Speicherstände verwenden immer den Standard-Bus.
+    pub(crate) fn lese<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Bus, D::Error> {
+        neu_seit(Version::I2cBus, deserializer, || Bus::STANDARD)
+    }
+
+    /// Schreibe `<bus>:` vor eine Adresse, sofern nicht der Standard-Bus verwendet wird.
+    pub(super) fn präfix(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self != &Bus::STANDARD {
+            write!(f, "{}:", self)?;
+        }
+        Ok(())
+    }
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        Bus::STANDARD
+    }
+}
+
+impl Display for Bus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "i2c-{}", self.0)
+    }
+}
+
+/// Eindeutige Adresse eines I2C-Bausteins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Adresse {
+    pub bus: Bus,
+    /// 7-bit I2C-Adresse ohne R/W-Bit.
+    pub adresse: u8,
+}
+
+impl Display for Adresse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{:#04x}", self.bus, self.adresse)
+    }
+}
+
+/// Art eines I2C-Bausteins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Art {
+    Pcf8574(pcf8574::Variante),
+    Mcp23017,
+    Adc(adc::Variante),
+    Pca9685,
+}
+
+impl Art {
+    /// Alle unterstützten Bausteine.
+    pub const ALLE: [Art; 6] = [
+        Art::Pcf8574(pcf8574::Variante::Normal),
+        Art::Pcf8574(pcf8574::Variante::A),
+        Art::Mcp23017,
+        Art::Adc(adc::Variante::Ads1115),
+        Art::Adc(adc::Variante::Pcf8591),
+        Art::Pca9685,
+    ];
+
+    /// Anzahl der Ports bzw. Kanäle.
+    pub fn anzahl_ports(&self) -> u8 {
+        match self {
+            Art::Pcf8574(_) => 8,
+            Art::Mcp23017 => mcp23017::ANZAHL_PORTS,
+            Art::Adc(_) => adc::ANZAHL_KANÄLE,
+            Art::Pca9685 => pca9685::ANZAHL_KANÄLE,
+        }
+    }
+
+    /// Über die Adress-Pins einstellbare I2C-Adressen.
+    pub fn adressen(&self) -> RangeInclusive<u8> {
+        let bus = Bus::STANDARD;
+        let (a0, a1, a2) = (Level::Low, Level::Low, Level::Low);
+        let (basis, anzahl) = match *self {
+            Art::Pcf8574(variante) => {
+                (pcf8574::Adresse { bus, a0, a1, a2, variante }.i2c_adresse(), 8)
+            }
+            Art::Mcp23017 => (mcp23017::Adresse { bus, a0, a1, a2 }.i2c_adresse(), 8),
+            Art::Adc(variante) => (
+                adc::Adresse { bus, variante, offset: 0 }.i2c_adresse(),
+                variante.anzahl_adressen(),
+            ),
+            Art::Pca9685 => {
+                (pca9685::Adresse { bus, offset: 0 }.i2c_adresse(), pca9685::ANZAHL_ADRESSEN)
+            }
+        };
+        basis..=(basis + anzahl - 1)
+    }
+
+    /// Alle Bausteine, die unter der I2C-Adresse erreichbar sein können.
+    pub fn kandidaten(adresse: u8) -> Vec<Art> {
+        Art::ALLE.iter().filter(|art| art.adressen().contains(&adresse)).copied().collect()
+    }
+}
+
+impl Display for Art {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Art::Pcf8574(pcf8574::Variante::Normal) => write!(f, "Pcf8574"),
+            Art::Pcf8574(pcf8574::Variante::A) => write!(f, "Pcf8574A"),
+            Art::Mcp23017 => write!(f, "Mcp23017"),
+            Art::Adc(variante) => write!(f, "{}", variante),
+            Art::Pca9685 => write!(f, "Pca9685"),
+        }
+    }
+}
+
+/// Belegung eines verwendeten I2C-Bausteins.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Belegung {
+    pub adresse: Adresse,
+    pub art: Art,
+    /// Aktuell reservierte Ports bzw. Kanäle (aufsteigend sortiert).
+    pub reserviert: Vec<u8>,
+}
+
+impl Belegung {
+    /// Aktuell freie Ports bzw. Kanäle.
+    pub fn frei(&self) -> impl Iterator<Item = u8> + '_ {
+        (0..self.art.anzahl_ports()).filter(move |port| !self.reserviert.contains(port))
+    }
+}
+
+/// Gültige 7-bit Adressen beim Scannen eines I2C-Busses (ohne reservierte Adressen).
+pub const SCAN_ADRESSEN: RangeInclusive<u8> = 0x03..=0x77;
+
+/// Antwort eines Bausteins beim Scannen eines I2C-Busses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Antwort {
+    pub adresse: Adresse,
+    /// Ohne Register-Auswahl gelesenes Byte.
+    pub gelesen: u8,
+    /// Bausteine, die unter der Adresse erreichbar sein können.
+    pub kandidaten: Vec<Art>,
+    /// Baustein, der unter der Adresse verwendet wird.
+    pub verwendet: Option<Art>,
+}
+
+impl Antwort {
+    /// Zustand der Ports, falls es sich um einen Pcf8574 handeln kann.
+    ///
+    /// Ein Pcf8574 antwortet beim Lesen direkt mit dem Zustand seiner Ports (Bit n = Port n).
+    pub fn pcf8574_ports(&self) -> Option<[Level; 8]> {
+        let ist_pcf8574 = |art: &Art| matches!(art, Art::Pcf8574(_));
+        let möglich = match &self.verwendet {
+            Some(art) => ist_pcf8574(art),
+            None => self.kandidaten.iter().any(ist_pcf8574),
+        };
+        if möglich {
+            let mut ports = [Level::Low; 8];
+            for (port, level) in ports.iter_mut().enumerate() {
+                if self.gelesen & (1 << port) != 0 {
+                    *level = Level::High;
+                }
+            }
+            Some(ports)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,624 @@
+//! Mcp23017, gesteuert über I2C.
+//!
+//! Die 16 Ports sind in zwei Bänke aufgeteilt (A: Port 0-7, B: Port 8-15),
+//! jede Bank hat einen eigenen Interrupt-Ausgang (INTA, INTB).
+//! Im Gegensatz zum Pcf8574 hat jeder Port eine feste Richtung (Input/Output)
+//! und kann einen internen Pull-up-Widerstand aktivieren.
+//!
+//! Alle Methoden in diesem Modul können an einem Mutex blocken (exklusiver I2C-Zugriff).
+//! Der Zugriff auf diese Mutex ist auf dieses Modul beschränkt,
+//! so dass es zu keinen Deadlocks kommen sollte.
+
+use std::fmt::{self, Debug, Display, Formatter};
+use std::sync::{mpsc::Sender, Arc, Mutex, PoisonError};
+
+use log::{debug, error};
+#[cfg(raspi)]
+use rppal::{gpio, i2c};
+use serde::{Deserialize, Serialize};
+
+use super::pin::input;
+use super::remote;
+use super::{
+    i2c::{Bus, Zugriff},
+    level::Level,
+    trigger::Trigger,
+};
+
+/// Anzahl der Ports eines Mcp23017.
+pub const ANZAHL_PORTS: u8 = 16;
+
+/// Register-Adressen (IOCON.BANK = 0, Register von Bank A und B folgen direkt aufeinander).
+#[cfg(raspi)]
+mod register {
+    pub const IODIRA: u8 = 0x00;
+    pub const GPINTENA: u8 = 0x04;
+    pub const GPPUA: u8 = 0x0C;
+    pub const GPIOA: u8 = 0x12;
+    pub const OLATA: u8 = 0x14;
+}
+
+/// Bus und Adress-Bits (a0, a1, a2) eines Mcp23017.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Adresse {
+    pub bus: Bus,
+    pub a0: Level,
+    pub a1: Level,
+    pub a2: Level,
+}
+
+impl Adresse {
+    /// 7-bit I2C-Adresse ohne R/W-Bit.
+    ///
+    /// Entspricht der Adresse eines Pcf8574 (Variante Normal) mit gleichen Adress-Bits.
+    pub fn i2c_adresse(&self) -> u8 {
+        let mut adresse = 0x20;
+        if let Level::High = self.a0 {
+            adresse += 0b001;
+        }
+        if let Level::High = self.a1 {
+            adresse += 0b010;
+        }
+        if let Level::High = self.a2 {
+            adresse += 0b100;
+        }
+        adresse
+    }
+}
+
+impl From<Adresse> for super::i2c::Adresse {
+    fn from(adresse: Adresse) -> Self {
+        super::i2c::Adresse { bus: adresse.bus, adresse: adresse.i2c_adresse() }
+    }
+}
+
+impl Display for Adresse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.bus.präfix(f)?;
+        for level in [self.a0, self.a1, self.a2].iter() {
+            match level {
+                Level::Low => write!(f, "L")?,
+                Level::High => write!(f, "H")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Bank eines Mcp23017, jede Bank hat einen eigenen Interrupt-Ausgang.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Bank {
+    /// Port 0-7, Interrupt-Ausgang INTA.
+    A,
+    /// Port 8-15, Interrupt-Ausgang INTB.
+    B,
+}
+
+impl Bank {
+    /// Bank, zu der ein Port gehört.
+    pub fn von_port(port: u8) -> Bank {
+        if port < 8 {
+            Bank::A
+        } else {
+            Bank::B
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Bank::A => 0,
+            Bank::B => 1,
+        }
+    }
+
+    fn ports(&self) -> std::ops::Range<usize> {
+        match self {
+            Bank::A => 0..8,
+            Bank::B => 8..16,
+        }
+    }
+}
+
+impl Display for Bank {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Bank::A => write!(f, "INTA"),
+            Bank::B => write!(f, "INTB"),
+        }
+    }
+}
+
+enum Modus {
+    Input {
+        trigger: Trigger,
+        pull_up: bool,
+        callback: Option<Box<dyn FnMut(Level) + Send + 'static>>,
+    },
+    High,
+    Low,
+}
+impl Default for Modus {
+    fn default() -> Self {
+        Modus::Input { trigger: Trigger::Disabled, pull_up: false, callback: None }
+    }
+}
+impl From<Level> for Modus {
+    fn from(level: Level) -> Self {
+        match level {
+            Level::High => Modus::High,
+            Level::Low => Modus::Low,
+        }
+    }
+}
+impl Debug for Modus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Modus::Input { trigger, pull_up, callback } => write!(
+                f,
+                "Input {{trigger: {:?}, pull_up: {}, callback: {}}}",
+                trigger,
+                pull_up,
+                if callback.is_some() { "Some(_)" } else { "None" }
+            ),
+            Modus::High => write!(f, "High"),
+            Modus::Low => write!(f, "Low"),
+        }
+    }
+}
+/// Gleichheit unabhängig vom callback.
+impl PartialEq for Modus {
+    fn eq(&self, other: &Modus) -> bool {
+        match (self, other) {
+            (Modus::Input { pull_up: p0, .. }, Modus::Input { pull_up: p1, .. }) => p0 == p1,
+            (Modus::High, Modus::High) => true,
+            (Modus::Low, Modus::Low) => true,
+            _ => false,
+        }
+    }
+}
+impl Eq for Modus {}
+
+/// Wird `trigger` durch einen Wechsel von `last` zu `current` ausgelöst?
+fn ausgelöst(trigger: Trigger, last: Level, current: Level) -> bool {
+    match (last, current) {
+        (Level::High, Level::Low) => matches!(trigger, Trigger::Both | Trigger::FallingEdge),
+        (Level::Low, Level::High) => matches!(trigger, Trigger::Both | Trigger::RisingEdge),
+        _ => false,
+    }
+}
+
+/// Ein Mcp23017, gesteuert über I2C.
+#[derive(Debug)]
+pub struct Mcp23017 {
+    adresse: Adresse,
+    ports: [Modus; ANZAHL_PORTS as usize],
+    interrupts: [Option<input::Pin>; 2],
+    i2c: Zugriff,
+}
+
+impl Mcp23017 {
+    pub(super) fn neu(adresse: Adresse, i2c: Zugriff) -> Self {
+        Mcp23017 { adresse, ports: Default::default(), interrupts: Default::default(), i2c }
+    }
+
+    /// Assoziiere den an INTA/INTB angeschlossenen InterruptPin.
+    /// Rückgabewert ist ein evtl. vorher konfigurierter InterruptPin.
+    /// Interrupt-Callbacks von Ports werden nicht zurückgesetzt!
+    fn set_interrupt_pin(
+        arc: &mut Arc<Mutex<Self>>,
+        bank: Bank,
+        mut interrupt: input::Pin,
+    ) -> Result<Option<input::Pin>, Error> {
+        let mut previous = {
+            let mcp23017 = &mut *arc.lock()?;
+            let mut last = mcp23017.read()?;
+            let arc_clone = arc.clone();
+            // INTA/INTB sind active-low, lesen von GPIO setzt den Interrupt zurück.
+            interrupt.set_async_interrupt(Trigger::FallingEdge, move |_level| {
+                match arc_clone.lock() {
+                    Ok(mut guard) => {
+                        let mcp23017 = &mut *guard;
+                        match mcp23017.read() {
+                            Ok(current) => {
+                                for i in bank.ports() {
+                                    if let (
+                                        Modus::Input { trigger, callback: Some(callback), .. },
+                                        Some(current),
+                                        Some(last),
+                                    ) = (&mut mcp23017.ports[i], current[i], last[i])
+                                    {
+                                        if ausgelöst(*trigger, last, current) {
+                                            callback(current);
+                                        }
+                                    }
+                                    last[i] = current[i];
+                                }
+                            }
+                            Err(error) => {
+                                error!(
+                                    "Error while reading Mcp23017 reacting to interrupt: {:?}",
+                                    error
+                                );
+                            }
+                        }
+                    }
+                    Err(_error) => {
+                        error!("Poison error on mcp23017-Mutex while reacting to interrupt!")
+                    }
+                }
+            })?;
+            let previous =
+                std::mem::replace(&mut mcp23017.interrupts[bank.index()], Some(interrupt));
+            mcp23017.schreibe_konfiguration()?;
+            previous
+        };
+        // clear interrupt on previous pin.
+        previous.as_mut().map(input::Pin::clear_async_interrupt);
+        Ok(previous)
+    }
+
+    /// Lese von einem Mcp23017.
+    /// Nur als Input konfigurierte Ports werden als Some-Wert zurückgegeben.
+    ///
+    /// Lesen setzt die Interrupt-Ausgänge zurück.
+    fn read(&self) -> Result<[Option<Level>; ANZAHL_PORTS as usize], Error> {
+        #[cfg(raspi)]
+        if let Some(i2c) = self.i2c.i2c() {
+            let mut i2c_channel = i2c.lock()?;
+            i2c_channel.set_slave_address(self.adresse.i2c_adresse().into())?;
+            let mut buf = [0; 2];
+            i2c_channel.write_read(&[register::GPIOA], &mut buf)?;
+            let werte = u16::from_le_bytes(buf);
+            let mut result = [None; ANZAHL_PORTS as usize];
+            for (port, modus) in self.ports.iter().enumerate() {
+                result[port] = if let Modus::Input { .. } = modus {
+                    Some(if (werte & (1 << port)) > 0 { Level::High } else { Level::Low })
+                } else {
+                    None
+                };
+            }
+            return Ok(result);
+        }
+        debug!("{:?}.read()", self);
+        Err(Error::KeinRaspberryPi)
+    }
+
+    /// Schreibe Richtung, Pull-ups, aktivierte Interrupts und Output-Werte aller Ports.
+    ///
+    /// Über einen entfernten Raspberry Pi schlägt die Konfiguration mit
+    /// [Error::KeinRaspberryPi] fehl, ohne Hardware (Stub) wird sie nur vermerkt.
+    fn schreibe_konfiguration(&self) -> Result<(), Error> {
+        #[cfg(raspi)]
+        if let Some(i2c) = self.i2c.i2c() {
+            let mut iodir: u16 = 0;
+            let mut gppu: u16 = 0;
+            let mut gpinten: u16 = 0;
+            let mut olat: u16 = 0;
+            for (port, modus) in self.ports.iter().enumerate() {
+                let bit = 1 << port;
+                match modus {
+                    Modus::Input { trigger, pull_up, callback } => {
+                        iodir |= bit;
+                        if *pull_up {
+                            gppu |= bit;
+                        }
+                        let bank = Bank::von_port(port as u8);
+                        if callback.is_some()
+                            && trigger != &Trigger::Disabled
+                            && self.interrupts[bank.index()].is_some()
+                        {
+                            gpinten |= bit;
+                        }
+                    }
+                    Modus::High => olat |= bit,
+                    Modus::Low => {}
+                }
+            }
+            let mut i2c_channel = i2c.lock()?;
+            i2c_channel.set_slave_address(self.adresse.i2c_adresse().into())?;
+            // Output-Werte vor der Richtung schreiben, um kurze Fehl-Pegel zu vermeiden
+            i2c_channel.block_write(register::OLATA, &olat.to_le_bytes())?;
+            i2c_channel.block_write(register::GPPUA, &gppu.to_le_bytes())?;
+            i2c_channel.block_write(register::IODIRA, &iodir.to_le_bytes())?;
+            i2c_channel.block_write(register::GPINTENA, &gpinten.to_le_bytes())?;
+            return Ok(());
+        }
+        if self.i2c.verbindung().is_some() {
+            // Register-Zugriffe werden vom remote::Server nicht unterstützt
+            return Err(Error::KeinRaspberryPi);
+        }
+        debug!("{:?}.schreibe_konfiguration()", self);
+        Ok(())
+    }
+
+    /// Setze den Modus eines Ports und übertrage die Konfiguration.
+    fn setze_modus(&mut self, port: u8, modus: Modus) -> Result<(), Error> {
+        self.ports[usize::from(port)] = modus;
+        self.schreibe_konfiguration()
+    }
+
+    /// Konvertiere einen Port als Input, ein evtl. vorher gesetzter Pull-up bleibt erhalten.
+    fn port_as_input<C: FnMut(Level) + Send + 'static>(
+        &mut self,
+        port: u8,
+        trigger: Trigger,
+        callback: Option<C>,
+    ) -> Result<(), Error> {
+        let pull_up = match &self.ports[usize::from(port)] {
+            Modus::Input { pull_up, .. } => *pull_up,
+            Modus::High | Modus::Low => false,
+        };
+        // type annotations need, so extra let binding required
+        let callback: Option<Box<dyn FnMut(Level) + Send + 'static>> = match callback {
+            Some(c) => Some(Box::new(c)),
+            None => None,
+        };
+        self.setze_modus(port, Modus::Input { trigger, pull_up, callback })
+    }
+
+    /// Schreibe auf einen Port des Mcp23017.
+    /// Der Port wird automatisch als Output gesetzt.
+    fn write_port(&mut self, port: u8, level: Level) -> Result<(), Error> {
+        if self.i2c.ist_hardware() {
+            self.setze_modus(port, level.into())
+        } else {
+            self.ports[usize::from(port)] = level.into();
+            debug!("{:?}.write_port({}, {:?})", self, port, level);
+            Err(Error::KeinRaspberryPi)
+        }
+    }
+}
+impl PartialEq for Mcp23017 {
+    fn eq(&self, other: &Self) -> bool {
+        self.adresse == other.adresse
+    }
+}
+impl Eq for Mcp23017 {}
+
+/// Ein Port eines Mcp23017.
+#[derive(Debug)]
+pub struct Port {
+    mcp23017: Arc<Mutex<Mcp23017>>,
+    adresse: Adresse,
+    port: u8,
+    sender: Sender<(super::i2c::Adresse, u8)>,
+}
+impl PartialEq for Port {
+    fn eq(&self, other: &Self) -> bool {
+        self.port == other.port && self.adresse == other.adresse
+    }
+}
+impl Eq for Port {}
+impl Drop for Port {
+    fn drop(&mut self) {
+        let Port { adresse, port, sender, .. } = self;
+        debug!("dropped mcp23017 {} {}", adresse, port);
+        // Schicke Werte als Tupel, um keine Probleme mit dem Drop-Handler zu bekommen.
+        if let Err(err) = sender.send(((*adresse).into(), *port)) {
+            debug!("send error while dropping: {}", err)
+        }
+    }
+}
+impl Port {
+    pub(super) fn neu(
+        mcp23017: Arc<Mutex<Mcp23017>>,
+        adresse: Adresse,
+        port: u8,
+        sender: Sender<(super::i2c::Adresse, u8)>,
+    ) -> Self {
+        Port { mcp23017, adresse, port, sender }
+    }
+
+    #[inline]
+    pub fn adresse(&self) -> &Adresse {
+        &self.adresse
+    }
+
+    #[inline]
+    pub fn port(&self) -> u8 {
+        self.port
+    }
+
+    #[inline]
+    pub fn bank(&self) -> Bank {
+        Bank::von_port(self.port)
+    }
+
+    /// Konfiguriere den Port für Output.
+    pub fn into_output(self, level: Level) -> Result<OutputPort, Error> {
+        {
+            let mcp23017 = &mut *self.mcp23017.lock()?;
+            mcp23017.setze_modus(self.port, level.into())?;
+        }
+        Ok(OutputPort(self))
+    }
+
+    /// Konfiguriere den Port für Input, optional mit internem Pull-up-Widerstand.
+    pub fn into_input(self, pull_up: bool) -> Result<InputPort, Error> {
+        {
+            let mcp23017 = &mut *self.mcp23017.lock()?;
+            mcp23017.setze_modus(
+                self.port,
+                Modus::Input { trigger: Trigger::Disabled, pull_up, callback: None },
+            )?;
+        }
+        Ok(InputPort(self))
+    }
+}
+
+// Ein Port eines Mcp23017, konfiguriert für Output.
+#[derive(Debug)]
+pub struct OutputPort(Port);
+
+impl OutputPort {
+    #[inline]
+    pub fn adresse(&self) -> &Adresse {
+        self.0.adresse()
+    }
+
+    #[inline]
+    pub fn port(&self) -> u8 {
+        self.0.port()
+    }
+
+    pub fn write(&mut self, level: Level) -> Result<(), Error> {
+        let mcp23017 = &mut *self.0.mcp23017.lock()?;
+        mcp23017.write_port(self.0.port, level)
+    }
+
+    pub fn is_set_high(&mut self) -> Result<bool, Error> {
+        let mcp23017 = &mut *self.0.mcp23017.lock()?;
+        Ok(mcp23017.ports[usize::from(self.port())] == Modus::High)
+    }
+
+    pub fn is_set_low(&mut self) -> Result<bool, Error> {
+        let mcp23017 = &mut *self.0.mcp23017.lock()?;
+        Ok(mcp23017.ports[usize::from(self.port())] == Modus::Low)
+    }
+
+    pub fn toggle(&mut self) -> Result<(), Error> {
+        let level = {
+            let mcp23017 = &*self.0.mcp23017.lock()?;
+            match &mcp23017.ports[usize::from(self.port())] {
+                Modus::High => Level::Low,
+                Modus::Low => Level::High,
+                Modus::Input { .. } => {
+                    error!("Output port configured as input: {:?}", self);
+                    Level::Low
+                }
+            }
+        };
+        self.write(level)
+    }
+}
+
+// Ein Port eines Mcp23017, konfiguriert für Input.
+#[derive(Debug)]
+pub struct InputPort(Port);
+
+impl InputPort {
+    #[inline]
+    pub fn adresse(&self) -> &Adresse {
+        self.0.adresse()
+    }
+
+    #[inline]
+    pub fn port(&self) -> u8 {
+        self.0.port()
+    }
+
+    /// Ist der interne Pull-up-Widerstand aktiviert?
+    pub fn pull_up(&self) -> Result<bool, Error> {
+        let mcp23017 = &*self.0.mcp23017.lock()?;
+        Ok(match &mcp23017.ports[usize::from(self.port())] {
+            Modus::Input { pull_up, .. } => *pull_up,
+            Modus::High | Modus::Low => false,
+        })
+    }
+
+    pub fn read(&self) -> Result<Level, Error> {
+        let values = {
+            let mcp23017 = &*self.0.mcp23017.lock()?;
+            mcp23017.read()?
+        };
+        if let Some(value) = values[usize::from(self.0.port)] {
+            Ok(value)
+        } else {
+            error!("{:?} war nicht als input konfiguriert!", self);
+            // war nicht als Input konfiguriert -> erneut konfigurieren und neu versuchen
+            {
+                let mcp23017 = &mut *self.0.mcp23017.lock()?;
+                mcp23017.port_as_input::<fn(Level)>(self.0.port, Trigger::Disabled, None)?;
+            }
+            self.read()
+        }
+    }
+
+    /// Aktuell konfigurierter Interrupt Pin der Bank dieses Ports.
+    pub(super) fn interrupt_pin(&self) -> Result<Option<u8>, Error> {
+        let mcp23017 = &*self.0.mcp23017.lock()?;
+        Ok(mcp23017.interrupts[self.0.bank().index()].as_ref().map(input::Pin::pin))
+    }
+
+    /// Assoziiere den angeschlossenen InterruptPin für die Bank dieses Ports.
+    /// Rückgabewert ist ein evtl. vorher konfigurierter InterruptPin.
+    /// Interrupt-Callbacks werden nicht zurückgesetzt!
+    pub fn set_interrupt_pin(
+        &mut self,
+        interrupt: input::Pin,
+    ) -> Result<Option<input::Pin>, Error> {
+        let bank = self.0.bank();
+        Mcp23017::set_interrupt_pin(&mut self.0.mcp23017, bank, interrupt)
+    }
+
+    /// Configures an asynchronous interrupt trigger, which executes the callback on a separate
+    /// thread when the interrupt is triggered.
+    ///
+    /// The callback closure or function pointer is called with a single Level argument.
+    ///
+    /// Any previously configured (a)synchronous interrupt triggers for this port are cleared when
+    /// set_async_interrupt is called, or when InputPort goes out of scope.
+    #[inline]
+    pub fn set_async_interrupt(
+        &mut self,
+        trigger: Trigger,
+        callback: impl FnMut(Level) + Send + 'static,
+    ) -> Result<(), Error> {
+        let port = self.port();
+        let mcp23017 = &mut *self.0.mcp23017.lock()?;
+        mcp23017.port_as_input(port, trigger, Some(callback))
+    }
+
+    /// Removes a previously configured asynchronous interrupt trigger.
+    #[inline]
+    pub fn clear_async_interrupt(&mut self) -> Result<(), Error> {
+        let port = self.port();
+        let mcp23017 = &mut *self.0.mcp23017.lock()?;
+        mcp23017.port_as_input::<fn(Level)>(port, Trigger::Disabled, None)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    #[cfg(raspi)]
+    I2c(i2c::Error),
+    #[cfg(raspi)]
+    Gpio(gpio::Error),
+    #[cfg(raspi)]
+    Cdev(gpio_cdev::errors::Error),
+    #[cfg(raspi)]
+    WiderstandNichtUnterstützt(u8),
+    /// Mcp23017 werden über einen entfernten Raspberry Pi nicht unterstützt,
+    /// ein Interrupt-Pin kann aber trotzdem ein Remote-Pin sein.
+    Remote(remote::Error),
+    KeinRaspberryPi,
+    PoisonError,
+    UngültigerPort(u8),
+}
+#[cfg(raspi)]
+impl From<i2c::Error> for Error {
+    fn from(error: i2c::Error) -> Self {
+        Error::I2c(error)
+    }
+}
+impl From<input::Error> for Error {
+    fn from(error: input::Error) -> Self {
+        match error {
+            #[cfg(raspi)]
+            input::Error::Gpio(err) => Error::Gpio(err),
+            #[cfg(raspi)]
+            input::Error::Cdev(err) => Error::Cdev(err),
+            #[cfg(raspi)]
+            input::Error::WiderstandNichtUnterstützt(pin) => {
+                Error::WiderstandNichtUnterstützt(pin)
+            },
+            input::Error::Remote(err) => Error::Remote(err),
+            input::Error::KeinRaspberryPi => Error::KeinRaspberryPi,
+        }
+    }
+}
+impl<T> From<PoisonError<T>> for Error {
+    fn from(_: PoisonError<T>) -> Self {
+        Error::PoisonError
+    }
+}
@@ -0,0 +1,362 @@
+//! Pwm-Treiber Pca9685 (16 Kanäle, 12 bit), gesteuert über I2C.
+//!
+//! Alle Kanäle eines Pca9685 verwenden die gleiche Frequenz.
+//! Sie wird beim Reservieren eines Kanals eingestellt
+//! und kann nur geändert werden, solange kein anderer Kanal des Pca9685 reserviert ist.
+
+use std::fmt::{self, Display, Formatter};
+use std::sync::{mpsc::Sender, Arc, Mutex, PoisonError};
+#[cfg(raspi)]
+use std::{thread::sleep, time::Duration};
+
+use log::debug;
+#[cfg(raspi)]
+use rppal::i2c;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::{
+    anschlüsse::Anschlüsse,
+    i2c::{Bus, Zugriff},
+    Reserviere, ToSave,
+};
+use crate::speicherformat::{neu_seit, Version};
+
+/// Anzahl der Pwm-Kanäle eines Pca9685.
+pub const ANZAHL_KANÄLE: u8 = 16;
+
+/// Anzahl der über die Adress-Pins (A0-A5) einstellbaren Adressen.
+pub const ANZAHL_ADRESSEN: u8 = 64;
+
+/// Basis-I2C-Adresse (7-bit).
+const BASIS_ADRESSE: u8 = 0x40;
+
+/// Frequenz des internen Oszillators (in Herz).
+const OSZILLATOR_FREQUENZ: f64 = 25_000_000.;
+
+/// Schritte pro Periode (12 bit).
+const AUFLÖSUNG: u16 = 4096;
+
+#[cfg(raspi)]
+mod register {
+    pub const MODE1: u8 = 0x00;
+    /// Die Register eines Kanals (ON_L, ON_H, OFF_L, OFF_H) folgen ab hier im Abstand von 4.
+    pub const LED0_ON_L: u8 = 0x06;
+    pub const PRE_SCALE: u8 = 0xFE;
+
+    pub const RESTART: u8 = 0x80;
+    pub const AUTO_INCREMENT: u8 = 0x20;
+    pub const SLEEP: u8 = 0x10;
+    /// Bit 4 in ON_H/OFF_H schaltet den Kanal dauerhaft an/aus.
+    pub const VOLL: u16 = 0x1000;
+}
+
+/// Adresse eines Pca9685.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Adresse {
+    #[serde(deserialize_with = "Bus::lese")]
+    pub bus: Bus,
+    /// Über die Adress-Pins eingestellter Offset zur Basis-Adresse.
+    pub offset: u8,
+}
+
+impl Adresse {
+    /// 7-bit I2C-Adresse ohne R/W-Bit.
+    pub fn i2c_adresse(&self) -> u8 {
+        BASIS_ADRESSE + self.offset
+    }
+}
+
+impl From<Adresse> for super::i2c::Adresse {
+    fn from(adresse: Adresse) -> Self {
+        super::i2c::Adresse { bus: adresse.bus, adresse: adresse.i2c_adresse() }
+    }
+}
+
+impl Display for Adresse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.bus.präfix(f)?;
+        write!(f, "Pca9685({:#04x})", self.i2c_adresse())
+    }
+}
+
+/// Prescale-Wert für die gewünschte Frequenz, sofern sie einstellbar ist (ca. 24Hz - 1526Hz).
+fn prescale(frequenz: f64) -> Option<u8> {
+    let prescale = (OSZILLATOR_FREQUENZ / (f64::from(AUFLÖSUNG) * frequenz)).round() - 1.;
+    if prescale >= 3. && prescale <= f64::from(u8::MAX) {
+        Some(prescale as u8)
+    } else {
+        None
+    }
+}
+
+/// Prüfe, ob `frequenz` zur eingestellten Frequenz `aktuell` passt.
+///
+/// Frequenzen mit gleichem Prescale-Wert lassen sich nicht unterscheiden.
+fn prüfe_frequenz(frequenz: f64, aktuell: Option<f64>) -> Result<u8, Error> {
+    let wert = prescale(frequenz).ok_or(Error::UngültigeFrequenz(frequenz))?;
+    match aktuell {
+        Some(aktuell) if prescale(aktuell) != Some(wert) => {
+            Err(Error::AndereFrequenz { frequenz, aktuell })
+        },
+        _ => Ok(wert),
+    }
+}
+
+/// Ein Pca9685, gesteuert über I2C.
+#[derive(Debug)]
+pub struct Pca9685 {
+    #[cfg_attr(not(raspi), allow(dead_code))]
+    adresse: Adresse,
+    /// Eingestellte Frequenz, gilt für alle Kanäle.
+    frequenz: Option<f64>,
+    /// Zuletzt in den Baustein geschriebener Prescale-Wert.
+    prescale: Option<u8>,
+    aktive_kanäle: [bool; ANZAHL_KANÄLE as usize],
+    #[cfg_attr(not(raspi), allow(dead_code))]
+    i2c: Zugriff,
+}
+
+impl Pca9685 {
+    pub(super) fn neu(adresse: Adresse, i2c: Zugriff) -> Self {
+        Pca9685 {
+            adresse,
+            frequenz: None,
+            prescale: None,
+            aktive_kanäle: [false; ANZAHL_KANÄLE as usize],
+            i2c,
+        }
+    }
+
+    /// Aktuell eingestellte Frequenz.
+    pub fn frequenz(&self) -> Option<f64> {
+        self.frequenz
+    }
+
+    /// Stelle die Frequenz aller Kanäle ein.
+    /// Eine neue Frequenz wird nur übernommen, wenn kein anderer Kanal reserviert ist.
+    fn stelle_frequenz(&mut self, frequenz: f64, andere_reserviert: bool) -> Result<(), Error> {
+        let aktuell = if andere_reserviert { self.frequenz } else { None };
+        let _ = prüfe_frequenz(frequenz, aktuell)?;
+        self.frequenz = Some(frequenz);
+        Ok(())
+    }
+
+    /// Aktiviere den Pwm-Puls eines Kanals.
+    /// Die Frequenz muss zur eingestellten Frequenz passen.
+    fn setze_puls(&mut self, kanal: u8, frequenz: f64, duty_cycle: f64) -> Result<(), Error> {
+        let prescale = prüfe_frequenz(frequenz, self.frequenz)?;
+        let _ = self.frequenz.get_or_insert(frequenz);
+        if self.prescale != Some(prescale) {
+            self.schreibe_prescale(prescale)?;
+            self.prescale = Some(prescale);
+        }
+        let aus = (duty_cycle.max(0.).min(1.) * f64::from(AUFLÖSUNG)).round() as u16;
+        self.schreibe_kanal(kanal, aus)?;
+        self.aktive_kanäle[usize::from(kanal)] = true;
+        Ok(())
+    }
+
+    /// Deaktiviere den Pwm-Puls eines Kanals (dauerhaft aus).
+    fn deaktiviere(&mut self, kanal: u8) -> Result<(), Error> {
+        self.schreibe_kanal(kanal, 0)?;
+        self.aktive_kanäle[usize::from(kanal)] = false;
+        Ok(())
+    }
+
+    fn schreibe_prescale(&mut self, prescale: u8) -> Result<(), Error> {
+        #[cfg(raspi)]
+        if let Some(i2c) = self.i2c.i2c() {
+            let mut i2c_channel = i2c.lock()?;
+            i2c_channel.set_slave_address(self.adresse.i2c_adresse().into())?;
+            // Prescale kann nur im Sleep-Modus geändert werden
+            i2c_channel
+                .smbus_write_byte(register::MODE1, register::AUTO_INCREMENT | register::SLEEP)?;
+            i2c_channel.smbus_write_byte(register::PRE_SCALE, prescale)?;
+            i2c_channel.smbus_write_byte(register::MODE1, register::AUTO_INCREMENT)?;
+            // Oszillator braucht bis zu 500µs zum Stabilisieren
+            sleep(Duration::from_micros(500));
+            i2c_channel
+                .smbus_write_byte(register::MODE1, register::AUTO_INCREMENT | register::RESTART)?;
+            return Ok(());
+        }
+        debug!("{:?}.schreibe_prescale({})", self, prescale);
+        Err(Error::KeinRaspberryPi)
+    }
+
+    /// Der Kanal ist für `aus` von `AUFLÖSUNG` Schritten einer Periode an.
+    fn schreibe_kanal(&mut self, kanal: u8, aus: u16) -> Result<(), Error> {
+        #[cfg(raspi)]
+        if let Some(i2c) = self.i2c.i2c() {
+            let (on, off) = if aus >= AUFLÖSUNG {
+                (register::VOLL, 0)
+            } else if aus == 0 {
+                (0, register::VOLL)
+            } else {
+                (0, aus)
+            };
+            let [on_l, on_h] = on.to_le_bytes();
+            let [off_l, off_h] = off.to_le_bytes();
+            let mut i2c_channel = i2c.lock()?;
+            i2c_channel.set_slave_address(self.adresse.i2c_adresse().into())?;
+            i2c_channel
+                .block_write(register::LED0_ON_L + 4 * kanal, &[on_l, on_h, off_l, off_h])?;
+            return Ok(());
+        }
+        debug!("{:?}.schreibe_kanal({}, {})", self, kanal, aus);
+        Err(Error::KeinRaspberryPi)
+    }
+}
+
+/// Ein Kanal eines Pca9685.
+#[derive(Debug)]
+pub struct Kanal {
+    pca9685: Arc<Mutex<Pca9685>>,
+    adresse: Adresse,
+    kanal: u8,
+    sender: Sender<(super::i2c::Adresse, u8)>,
+}
+
+impl PartialEq for Kanal {
+    fn eq(&self, other: &Self) -> bool {
+        self.adresse == other.adresse && self.kanal == other.kanal
+    }
+}
+impl Eq for Kanal {}
+
+impl Drop for Kanal {
+    fn drop(&mut self) {
+        let Kanal { pca9685, adresse, kanal, sender } = self;
+        debug!("dropped pca9685 {} kanal {}", adresse, kanal);
+        // Kanal ausschalten, bevor er zurückgegeben wird
+        let ergebnis = match pca9685.lock() {
+            Ok(mut pca9685) => pca9685.deaktiviere(*kanal),
+            Err(poison_error) => poison_error.into_inner().deaktiviere(*kanal),
+        };
+        if let Err(err) = ergebnis {
+            debug!("error while disabling dropped pca9685 kanal: {:?}", err)
+        }
+        if let Err(err) = sender.send(((*adresse).into(), *kanal)) {
+            debug!("send error while dropping: {}", err)
+        }
+    }
+}
+
+impl Display for Kanal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.adresse, self.kanal)
+    }
+}
+
+impl Kanal {
+    pub(super) fn neu(
+        pca9685: Arc<Mutex<Pca9685>>,
+        adresse: Adresse,
+        kanal: u8,
+        sender: Sender<(super::i2c::Adresse, u8)>,
+    ) -> Self {
+        Kanal { pca9685, adresse, kanal, sender }
+    }
+
+    #[inline]
+    pub fn adresse(&self) -> &Adresse {
+        &self.adresse
+    }
+
+    #[inline]
+    pub fn kanal(&self) -> u8 {
+        self.kanal
+    }
+
+    /// Frequenz (in Herz) aller Kanäle des Pca9685.
+    pub fn frequenz(&self) -> Option<f64> {
+        match self.pca9685.lock() {
+            Ok(pca9685) => pca9685.frequenz(),
+            Err(poison_error) => poison_error.into_inner().frequenz(),
+        }
+    }
+
+    pub(super) fn stelle_frequenz(
+        &self,
+        frequenz: f64,
+        andere_reserviert: bool,
+    ) -> Result<(), Error> {
+        self.pca9685.lock()?.stelle_frequenz(frequenz, andere_reserviert)
+    }
+
+    /// Aktiviere den Pwm-Puls mit Frequenz (in Herz) und Duty-cycle (\[0,1\]).
+    pub fn setze_puls(&self, frequenz: f64, duty_cycle: f64) -> Result<(), Error> {
+        self.pca9685.lock()?.setze_puls(self.kanal, frequenz, duty_cycle)
+    }
+
+    /// Deaktiviere den Pwm-Puls.
+    pub fn deaktiviere(&self) -> Result<(), Error> {
+        self.pca9685.lock()?.deaktiviere(self.kanal)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    #[cfg(raspi)]
+    I2c(i2c::Error),
+    KeinRaspberryPi,
+    PoisonError,
+    UngültigeAdresse(Adresse),
+    UngültigerKanal(u8),
+    /// Frequenz ist außerhalb des einstellbaren Bereichs.
+    UngültigeFrequenz(f64),
+    /// Die Frequenz passt nicht zur Frequenz des Pca9685.
+    AndereFrequenz {
+        frequenz: f64,
+        aktuell: f64,
+    },
+}
+#[cfg(raspi)]
+impl From<i2c::Error> for Error {
+    fn from(error: i2c::Error) -> Self {
+        Error::I2c(error)
+    }
+}
+impl<T> From<PoisonError<T>> for Error {
+    fn from(_: PoisonError<T>) -> Self {
+        Error::PoisonError
+    }
+}
+
+/// Serealisierbare Informationen eines Pca9685-Kanals.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Save {
+    pub adresse: Adresse,
+    pub kanal: u8,
+    /// Frequenz (in Herz) aller Kanäle des Pca9685.
+    ///
+    /// Ohne Frequenz (z.B. in älteren Speicherständen) wird die Frequenz des Pwm-Signals übernommen.
+    #[serde(deserialize_with = "Save::lese_frequenz")]
+    pub frequenz: Option<f64>,
+}
+impl Save {
+    fn lese_frequenz<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+        neu_seit(Version::Pca9685Frequenz, deserializer, || None)
+    }
+
+    /// Prüfe, ob ein Pwm-Signal mit `frequenz` (in Herz) erzeugt werden kann.
+    ///
+    /// Ohne eigene Frequenz wird `frequenz` übernommen.
+    pub fn mit_frequenz(self, frequenz: f64) -> Result<Save, Error> {
+        let _ = prüfe_frequenz(frequenz, self.frequenz)?;
+        Ok(Save { frequenz: self.frequenz.or(Some(frequenz)), ..self })
+    }
+}
+impl ToSave for Kanal {
+    type Save = Save;
+
+    fn to_save(&self) -> Save {
+        Save { adresse: self.adresse, kanal: self.kanal, frequenz: self.frequenz() }
+    }
+}
+impl Reserviere<Kanal> for Save {
+    fn reserviere(self, anschlüsse: &mut Anschlüsse) -> Result<Kanal, super::Error> {
+        anschlüsse
+            .reserviere_pca9685_kanal(self.adresse, self.kanal, self.frequenz)
+            .map_err(Into::into)
+    }
+}
@@ -4,21 +4,23 @@
 //! Der Zugriff auf diese Mutex ist auf dieses Modul beschränkt,
 //! so dass es zu keinen Deadlocks kommen sollte.
 
-use std::fmt::Debug;
+use std::fmt::{self, Debug, Display, Formatter};
 use std::sync::{mpsc::Sender, Arc, Mutex, PoisonError};
 
-use cfg_if::cfg_if;
 use log::debug;
 use log::error;
 use num_x::u3;
 #[cfg(raspi)]
-use num_x::u7;
-#[cfg(raspi)]
 use rppal::{gpio, i2c};
 use serde::{Deserialize, Serialize};
 
 use super::pin::input;
-use super::{level::Level, trigger::Trigger};
+use super::remote;
+use super::{
+    i2c::{Bus, Zugriff},
+    level::Level,
+    trigger::Trigger,
+};
 
 pub(super) enum Modus {
     Input { trigger: Trigger, callback: Option<Box<dyn FnMut(Level) + Send + 'static>> },
@@ -60,34 +62,71 @@ impl PartialEq for Modus {
 }
 impl Eq for Modus {}
 
+/// Bus, Adress-Bits (a0, a1, a2) und Variante eines Pcf8574.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Adresse {
+    pub bus: Bus,
+    pub a0: Level,
+    pub a1: Level,
+    pub a2: Level,
+    pub variante: Variante,
+}
+
+impl Adresse {
+    /// 7-bit I2C-Adresse ohne R/W-Bit.
+    pub fn i2c_adresse(&self) -> u8 {
+        let mut adresse = match self.variante {
+            Variante::Normal => 0x20,
+            Variante::A => 0x38,
+        };
+        if let Level::High = self.a0 {
+            adresse += 0b001;
+        }
+        if let Level::High = self.a1 {
+            adresse += 0b010;
+        }
+        if let Level::High = self.a2 {
+            adresse += 0b100;
+        }
+        adresse
+    }
+}
+
+impl From<Adresse> for super::i2c::Adresse {
+    fn from(adresse: Adresse) -> Self {
+        super::i2c::Adresse { bus: adresse.bus, adresse: adresse.i2c_adresse() }
+    }
+}
+
+impl Display for Adresse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        self.bus.präfix(f)?;
+        for level in [self.a0, self.a1, self.a2].iter() {
+            match level {
+                Level::Low => write!(f, "L")?,
+                Level::High => write!(f, "H")?,
+            }
+        }
+        match self.variante {
+            Variante::Normal => write!(f, " "),
+            Variante::A => write!(f, "A"),
+        }
+    }
+}
+
 /// Ein Pcf8574, gesteuert über I2C.
 #[derive(Debug)]
 pub struct Pcf8574 {
-    a0: Level,
-    a1: Level,
-    a2: Level,
-    variante: Variante,
+    adresse: Adresse,
     ports: [Modus; 8],
     interrupt: Option<input::Pin>,
-    #[cfg(raspi)]
-    i2c: Arc<Mutex<i2c::I2c>>,
+    i2c: Zugriff,
 }
 
-pub(super) type Nachricht = (Level, Level, Level, Variante);
-
 impl Pcf8574 {
-    pub(super) fn neu(
-        a0: Level,
-        a1: Level,
-        a2: Level,
-        variante: Variante,
-        #[cfg(raspi)] i2c: Arc<Mutex<i2c::I2c>>,
-    ) -> Self {
+    pub(super) fn neu(adresse: Adresse, i2c: Zugriff) -> Self {
         Pcf8574 {
-            a0,
-            a1,
-            a2,
-            variante,
+            adresse,
             ports: [
                 Modus::Input { trigger: Trigger::Disabled, callback: None },
                 Modus::Input { trigger: Trigger::Disabled, callback: None },
@@ -99,7 +138,6 @@ impl Pcf8574 {
                 Modus::Input { trigger: Trigger::Disabled, callback: None },
             ],
             interrupt: None,
-            #[cfg(raspi)]
             i2c,
         }
     }
@@ -169,67 +207,57 @@ impl Pcf8574 {
         Ok(previous)
     }
 
-    /*
-    /// Adress-Bits (a0, a1, a2) und Variante eines Pcf8574.
-    #[inline]
-    fn adresse(&self) -> (Level, Level, Level, Variante) {
-        (self.a0, self.a1, self.a2, self.variante)
-    }
-    */
-
-    #[cfg(raspi)]
-    /// 7-bit i2c-Adresse ohne R/W-Bit
-    fn i2c_adresse(&self) -> u7 {
-        let Pcf8574 { a0, a1, a2, variante, .. } = self;
-        let mut adresse = u7::new(match variante {
-            Variante::Normal => 0x20,
-            Variante::A => 0x38,
-        });
-        if let Level::High = a0 {
-            adresse = adresse + u7::new(0b001);
-        }
-        if let Level::High = a1 {
-            adresse = adresse + u7::new(0b010);
-        }
-        if let Level::High = a2 {
-            adresse = adresse + u7::new(0b100);
-        }
-        adresse
-    }
-
     /// Lese von einem Pcf8574.
     /// Nur als Input konfigurierte Ports werden als Some-Wert zurückgegeben.
     ///
     /// Bei Interrupt-basiertem lesen sollten alle Port gleichzeitig gelesen werden!
     fn read(&self) -> Result<[Option<Level>; 8], Error> {
-        cfg_if! {
-            if #[cfg(raspi)] {
-                if let Ok(mut i2c_channel) = self.i2c.lock() {
-                    i2c_channel.set_slave_address(self.i2c_adresse().into())?;
-                    let mut buf = [0; 1];
-                    let bytes_read = i2c_channel.read(&mut buf)?;
-                    if bytes_read != 1 {
-                        debug!("bytes_read = {} != 1", bytes_read)
-                    }
-                    let mut result = [None; 8];
-                    for (port, modus) in self.ports.iter().enumerate() {
-                        let port_bit = 2u8.pow(port as u32) as u8;
-                        result[port] = if let Modus::Input { .. } = modus {
-                            Some(if (buf[0] & port_bit) > 0 { Level::High } else { Level::Low })
-                        } else {
-                            None
-                        };
-                    }
-                    Ok(result)
-                } else {
-                    error!("I2C-Mutex poisoned!");
-                    Err(Error::PoisonError)
-                }
-            } else {
-                debug!("{:?}.read()", self);
-                Err(Error::KeinRaspberryPi)
+        if let Some(verbindung) = self.i2c.verbindung() {
+            let wert = verbindung.i2c_lese(self.adresse.into())?;
+            return Ok(self.input_ports(wert));
+        }
+        #[cfg(raspi)]
+        if let Some(i2c) = self.i2c.i2c() {
+            let mut i2c_channel = i2c.lock().map_err(|_| {
+                error!("I2C-Mutex poisoned!");
+                Error::PoisonError
+            })?;
+            i2c_channel.set_slave_address(self.adresse.i2c_adresse().into())?;
+            let mut buf = [0; 1];
+            let bytes_read = i2c_channel.read(&mut buf)?;
+            if bytes_read != 1 {
+                debug!("bytes_read = {} != 1", bytes_read)
             }
+            return Ok(self.input_ports(buf[0]));
+        }
+        debug!("{:?}.read()", self);
+        Err(Error::KeinRaspberryPi)
+    }
+
+    /// Level der als Input konfigurierten Ports aus einem gelesenen Byte.
+    fn input_ports(&self, wert: u8) -> [Option<Level>; 8] {
+        let mut result = [None; 8];
+        for (port, modus) in self.ports.iter().enumerate() {
+            let port_bit = 2u8.pow(port as u32) as u8;
+            result[port] = if let Modus::Input { .. } = modus {
+                Some(if (wert & port_bit) > 0 { Level::High } else { Level::Low })
+            } else {
+                None
+            };
+        }
+        result
+    }
+
+    /// Zu schreibendes Byte, als Input konfigurierte Ports sind High.
+    fn output_wert(&self) -> u8 {
+        let mut wert = 0;
+        for (port, modus) in self.ports.iter().enumerate() {
+            wert |= match modus {
+                Modus::Input { .. } | Modus::High => 2u8.pow(port as u32) as u8,
+                Modus::Low => 0,
+            };
         }
+        wert
     }
 
     /// Konvertiere einen Port als Input.
@@ -239,8 +267,9 @@ impl Pcf8574 {
         trigger: Trigger,
         callback: Option<C>,
     ) -> Result<(), Error> {
-        #[cfg(raspi)]
-        self.write_port(port, Level::High)?;
+        if self.i2c.ist_hardware() {
+            self.write_port(port, Level::High)?;
+        }
         // type annotations need, so extra let binding required
         let callback: Option<Box<dyn FnMut(Level) + Send + 'static>> = match callback {
             Some(c) => Some(Box::new(c)),
@@ -254,40 +283,30 @@ impl Pcf8574 {
     /// Der Port wird automatisch als Output gesetzt.
     fn write_port(&mut self, port: u3, level: Level) -> Result<(), Error> {
         self.ports[usize::from(port)] = level.into();
-        cfg_if! {
-            if #[cfg(raspi)] {
-                if let Ok(mut i2c_channel) = self.i2c.lock() {
-                    i2c_channel.set_slave_address(self.i2c_adresse().into())?;
-                    let mut wert = 0;
-                    for (port, modus) in self.ports.iter().enumerate() {
-                        wert |= match modus {
-                            Modus::Input { .. } | Modus::High => 2u8.pow(port as u32) as u8,
-                            Modus::Low => 0,
-                        };
-                    }
-                    let buf = [wert; 1];
-                    let bytes_written = i2c_channel.write(&buf)?;
-                    if bytes_written != 1 {
-                        error!("bytes_written = {} != 1", bytes_written)
-                    }
-                    Ok(())
-                } else {
-                    error!("I2C-Mutex poisoned!");
-                    Err(Error::PoisonError)
-                }
-            } else {
-                debug!("{:?}.write_port({}, {:?})", self, port, level);
-                Err(Error::KeinRaspberryPi)
+        if let Some(verbindung) = self.i2c.verbindung() {
+            return Ok(verbindung.i2c_schreibe(self.adresse.into(), self.output_wert())?);
+        }
+        #[cfg(raspi)]
+        if let Some(i2c) = self.i2c.i2c() {
+            let mut i2c_channel = i2c.lock().map_err(|_| {
+                error!("I2C-Mutex poisoned!");
+                Error::PoisonError
+            })?;
+            i2c_channel.set_slave_address(self.adresse.i2c_adresse().into())?;
+            let buf = [self.output_wert(); 1];
+            let bytes_written = i2c_channel.write(&buf)?;
+            if bytes_written != 1 {
+                error!("bytes_written = {} != 1", bytes_written)
             }
+            return Ok(());
         }
+        debug!("{:?}.write_port({}, {:?})", self, port, level);
+        Err(Error::KeinRaspberryPi)
     }
 }
 impl PartialEq for Pcf8574 {
     fn eq(&self, other: &Self) -> bool {
-        self.a0 == other.a0
-            && self.a1 == other.a1
-            && self.a2 == other.a2
-            && self.variante == other.variante
+        self.adresse == other.adresse
     }
 }
 impl Eq for Pcf8574 {}
@@ -302,23 +321,23 @@ pub enum Variante {
 #[derive(Debug)]
 pub struct Port {
     pcf8574: Arc<Mutex<Pcf8574>>,
-    nachricht: Nachricht,
+    adresse: Adresse,
     port: u3,
-    sender: Sender<(Nachricht, u3)>,
+    sender: Sender<(super::i2c::Adresse, u8)>,
 }
 impl PartialEq for Port {
     fn eq(&self, other: &Self) -> bool {
-        self.port == other.port && self.nachricht == other.nachricht
+        self.port == other.port && self.adresse == other.adresse
     }
 }
 impl Eq for Port {}
 impl Drop for Port {
     fn drop(&mut self) {
-        let Port { port, nachricht, sender, .. } = self;
-        debug!("dropped {:?} {:?} ", nachricht, port);
+        let Port { port, adresse, sender, .. } = self;
+        debug!("dropped {:?} {:?} ", adresse, port);
         // Schicke Werte als Tupel, um keine Probleme mit dem Drop-Handler zu bekommen.
         // (Ein Klon würde bei send-Fehler eine Endlos-Schleife erzeugen)
-        if let Err(err) = sender.send((nachricht.clone(), port.clone())) {
+        if let Err(err) = sender.send(((*adresse).into(), u8::from(*port))) {
             debug!("send error while dropping: {}", err)
         }
     }
@@ -326,16 +345,16 @@ impl Drop for Port {
 impl Port {
     pub(super) fn neu(
         pcf8574: Arc<Mutex<Pcf8574>>,
-        nachricht: Nachricht,
+        adresse: Adresse,
         port: u3,
-        sender: Sender<(Nachricht, u3)>,
+        sender: Sender<(super::i2c::Adresse, u8)>,
     ) -> Self {
-        Port { pcf8574, port, nachricht, sender }
+        Port { pcf8574, port, adresse, sender }
     }
 
     #[inline]
-    pub fn adresse(&self) -> &Nachricht {
-        &self.nachricht
+    pub fn adresse(&self) -> &Adresse {
+        &self.adresse
     }
 
     #[inline]
@@ -347,12 +366,10 @@ impl Port {
     pub fn into_output(self, level: Level) -> Result<OutputPort, Error> {
         {
             let pcf8574 = &mut *self.pcf8574.lock()?;
-            cfg_if! {
-                if #[cfg(raspi)] {
-                    pcf8574.write_port(self.port, level)?;
-                } else {
-                    pcf8574.ports[usize::from(self.port)] = level.into();
-                }
+            if pcf8574.i2c.ist_hardware() {
+                pcf8574.write_port(self.port, level)?;
+            } else {
+                pcf8574.ports[usize::from(self.port)] = level.into();
             }
         }
         Ok(OutputPort(self))
@@ -374,7 +391,7 @@ pub struct OutputPort(Port);
 
 impl OutputPort {
     #[inline]
-    pub fn adresse(&self) -> &Nachricht {
+    pub fn adresse(&self) -> &Adresse {
         self.0.adresse()
     }
 
@@ -424,7 +441,7 @@ pub struct InputPort(Port);
 
 impl InputPort {
     #[inline]
-    pub fn adresse(&self) -> &Nachricht {
+    pub fn adresse(&self) -> &Adresse {
         self.0.adresse()
     }
 
@@ -502,7 +519,11 @@ pub enum Error {
     I2c(i2c::Error),
     #[cfg(raspi)]
     Gpio(gpio::Error),
-    #[cfg(not(raspi))]
+    #[cfg(raspi)]
+    Cdev(gpio_cdev::errors::Error),
+    #[cfg(raspi)]
+    WiderstandNichtUnterstützt(u8),
+    Remote(remote::Error),
     KeinRaspberryPi,
     PoisonError,
     KeinInterruptPin,
@@ -518,11 +539,22 @@ impl From<input::Error> for Error {
         match error {
             #[cfg(raspi)]
             input::Error::Gpio(err) => Error::Gpio(err),
-            #[cfg(not(raspi))]
+            #[cfg(raspi)]
+            input::Error::Cdev(err) => Error::Cdev(err),
+            #[cfg(raspi)]
+            input::Error::WiderstandNichtUnterstützt(pin) => {
+                Error::WiderstandNichtUnterstützt(pin)
+            },
+            input::Error::Remote(err) => Error::Remote(err),
             input::Error::KeinRaspberryPi => Error::KeinRaspberryPi,
         }
     }
 }
+impl From<remote::Error> for Error {
+    fn from(error: remote::Error) -> Self {
+        Error::Remote(error)
+    }
+}
 impl<T> From<PoisonError<T>> for Error {
     fn from(_: PoisonError<T>) -> Self {
         Error::PoisonError
@@ -1,51 +1,73 @@
 //! Gpio Pin in verschiedenen Konfigurationen.
 
-#[cfg(not(raspi))]
 use std::sync::mpsc::Sender;
 pub use std::time::Duration;
 
-use cfg_if::cfg_if;
 #[cfg(raspi)]
 use rppal::{self, gpio};
 
-use super::level::Level;
+use super::{
+    level::Level,
+    remote::{Verbindung, Widerstand},
+};
 
+#[cfg(raspi)]
+mod cdev;
 pub mod input;
 pub mod output;
 pub mod pwm;
 #[cfg(raspi)]
 use pwm::Pwm;
+mod remote;
 
-#[cfg(not(raspi))]
+/// Gpio-Pin ohne Hardware-Backend, wird beim drop an [Anschlüsse](super::Anschlüsse) zurückgegeben.
 #[derive(Debug)]
 struct Wrapper(u8, Sender<u8>);
 
-#[cfg(not(raspi))]
 impl PartialEq for Wrapper {
     fn eq(&self, other: &Wrapper) -> bool {
         self.0 == other.0
     }
 }
 
-#[cfg(not(raspi))]
 impl Drop for Wrapper {
     fn drop(&mut self) {
         let _ = self.1.send(self.0);
     }
 }
 
+/// Zugriff auf einen Gpio Pin über das Hardware-Backend, einen entfernten Raspberry Pi
+/// oder einen Stub.
+#[derive(Debug, PartialEq)]
+enum Zugriff {
+    #[cfg(raspi)]
+    Hardware(gpio::Pin),
+    #[cfg(raspi)]
+    Cdev(cdev::Pin),
+    Remote(remote::Pin),
+    Stub(Wrapper),
+}
+
 /// Ein Gpio Pin.
 #[derive(Debug, PartialEq)]
-pub struct Pin(#[cfg(raspi)] gpio::Pin, #[cfg(not(raspi))] Wrapper);
+pub struct Pin(Zugriff);
 impl Pin {
     #[cfg(raspi)]
-    pub(super) fn neu(pin: gpio::Pin) -> Self {
-        Pin(pin)
+    pub(super) fn hardware(pin: gpio::Pin) -> Self {
+        Pin(Zugriff::Hardware(pin))
     }
 
-    #[cfg(not(raspi))]
-    pub(super) fn neu(pin: u8, sender: Sender<u8>) -> Self {
-        Pin(Wrapper(pin, sender))
+    #[cfg(raspi)]
+    pub(super) fn cdev(line: gpio_cdev::Line, pin: u8, sender: Sender<u8>) -> Self {
+        Pin(Zugriff::Cdev(cdev::Pin::neu(line, Wrapper(pin, sender))))
+    }
+
+    pub(super) fn remote(verbindung: Verbindung, pin: u8, sender: Sender<u8>) -> Self {
+        Pin(Zugriff::Remote(remote::Pin::neu(verbindung, Wrapper(pin, sender))))
+    }
+
+    pub(super) fn stub(pin: u8, sender: Sender<u8>) -> Self {
+        Pin(Zugriff::Stub(Wrapper(pin, sender)))
     }
 
     /// Returns the GPIO pin number.
@@ -53,14 +75,13 @@ impl Pin {
     /// Pins are addressed by their BCM numbers, rather than their physical location.
     #[inline]
     pub fn pin(&self) -> u8 {
-        cfg_if! {
-            if #[cfg(raspi)] {
-                self.0.pin()
-            } else {
-                // Pins sollten nur auf einem Raspi erzeugbar sein!
-                // Liefere Standard-Wert, der in näherer Zukunft nicht von Pins erreicht wird
-                self.0.0
-            }
+        match &self.0 {
+            #[cfg(raspi)]
+            Zugriff::Hardware(pin) => pin.pin(),
+            #[cfg(raspi)]
+            Zugriff::Cdev(pin) => pin.pin(),
+            Zugriff::Remote(pin) => pin.pin(),
+            Zugriff::Stub(wrapper) => wrapper.0,
         }
     }
 
@@ -68,12 +89,14 @@ impl Pin {
     /// built-in pull-up/pull-down resistors.
     #[inline]
     pub fn into_input(self) -> input::Pin {
-        input::Pin(
+        input::Pin(match self.0 {
+            #[cfg(raspi)]
+            Zugriff::Hardware(pin) => input::Zugriff::Hardware(pin.into_input()),
             #[cfg(raspi)]
-            self.0.into_input(),
-            #[cfg(not(raspi))]
-            self.0,
-        )
+            Zugriff::Cdev(pin) => input::Zugriff::Cdev(pin.into_input(cdev::Widerstand::Keiner)),
+            Zugriff::Remote(pin) => input::Zugriff::Remote(pin.into_input(Widerstand::Keiner)),
+            Zugriff::Stub(wrapper) => input::Zugriff::Stub(wrapper),
+        })
     }
 
     /// Consumes the Pin, returns an input::Pin, sets its mode to Input, and enables the pin’s
@@ -83,12 +106,14 @@ impl Pin {
     /// to true (default).
     #[inline]
     pub fn into_input_pulldown(self) -> input::Pin {
-        input::Pin(
+        input::Pin(match self.0 {
+            #[cfg(raspi)]
+            Zugriff::Hardware(pin) => input::Zugriff::Hardware(pin.into_input_pulldown()),
             #[cfg(raspi)]
-            self.0.into_input_pulldown(),
-            #[cfg(not(raspi))]
-            self.0,
-        )
+            Zugriff::Cdev(pin) => input::Zugriff::Cdev(pin.into_input(cdev::Widerstand::PullDown)),
+            Zugriff::Remote(pin) => input::Zugriff::Remote(pin.into_input(Widerstand::PullDown)),
+            Zugriff::Stub(wrapper) => input::Zugriff::Stub(wrapper),
+        })
     }
 
     /// Consumes the Pin, returns an input::Pin, sets its mode to Input, and enables the pin’s
@@ -98,60 +123,77 @@ impl Pin {
     /// to true (default).
     #[inline]
     pub fn into_input_pullup(self) -> input::Pin {
-        input::Pin(
+        input::Pin(match self.0 {
             #[cfg(raspi)]
-            self.0.into_input_pullup(),
-            #[cfg(not(raspi))]
-            self.0,
-        )
+            Zugriff::Hardware(pin) => input::Zugriff::Hardware(pin.into_input_pullup()),
+            #[cfg(raspi)]
+            Zugriff::Cdev(pin) => input::Zugriff::Cdev(pin.into_input(cdev::Widerstand::PullUp)),
+            Zugriff::Remote(pin) => input::Zugriff::Remote(pin.into_input(Widerstand::PullUp)),
+            Zugriff::Stub(wrapper) => input::Zugriff::Stub(wrapper),
+        })
     }
 
     ///Consumes the Pin, returns an output::Pin and sets its mode to Output.
     #[inline]
     #[cfg_attr(not(raspi), allow(unused_variables))]
     pub fn into_output(self, level: Level) -> output::Pin {
-        output::Pin(
+        output::Pin(match self.0 {
             #[cfg(raspi)]
-            {
-                let mut output_pin = self.0.into_output();
+            Zugriff::Hardware(pin) => {
+                let mut output_pin = pin.into_output();
                 output_pin.write(level.into());
-                output_pin
+                output::Zugriff::Hardware(output_pin)
             },
-            #[cfg(not(raspi))]
-            self.0,
-        )
+            #[cfg(raspi)]
+            Zugriff::Cdev(pin) => output::Zugriff::Cdev(pin.into_output(level)),
+            Zugriff::Remote(pin) => output::Zugriff::Remote(pin.into_output(level)),
+            Zugriff::Stub(wrapper) => output::Zugriff::Stub(wrapper),
+        })
     }
 
     #[cfg(raspi)]
     #[inline]
-    fn pwm_channel(&self) -> Option<rppal::pwm::Channel> {
-        match self.0.pin() {
+    fn pwm_channel(pin: &gpio::Pin) -> Option<rppal::pwm::Channel> {
+        match pin.pin() {
             18 => Some(rppal::pwm::Channel::Pwm0),
             19 => Some(rppal::pwm::Channel::Pwm1),
             _ => None,
         }
     }
 
-    #[cfg_attr(not(raspi), allow(unused_variables))]
-    #[cfg_attr(not(raspi), inline)]
     pub fn into_pwm(self) -> pwm::Pin {
-        cfg_if! {
-            if #[cfg(raspi)]
-            {
-                if let Some(pwm) = self.pwm_channel().and_then(|channel| rppal::pwm::Pwm::new(channel).ok()) {
-                    let config = pwm.polarity().and_then(|polarität|
-                                    pwm.period().and_then(|period|
-                                    pwm.pulse_width().map(|pulse_width|
-                                    pwm::Config {time: pwm::Time::Period {period, pulse_width}, polarity: polarität.into()}))
-                                 ).ok();
-                    pwm::Pin {pin: Pwm::Hardware(pwm, self.0), config }
+        match self.0 {
+            #[cfg(raspi)]
+            Zugriff::Hardware(pin) => {
+                if let Some(pwm) =
+                    Pin::pwm_channel(&pin).and_then(|channel| rppal::pwm::Pwm::new(channel).ok())
+                {
+                    let config = pwm
+                        .polarity()
+                        .and_then(|polarität| {
+                            pwm.period().and_then(|period| {
+                                pwm.pulse_width().map(|pulse_width| pwm::Config {
+                                    time: pwm::Time::Period { period, pulse_width },
+                                    polarity: polarität.into(),
+                                })
+                            })
+                        })
+                        .ok();
+                    pwm::Pin { pin: Pwm::Hardware(pwm, pin), config }
                 } else {
                     // fallback software pwm
-                    pwm::Pin {pin: Pwm::Software(self.0.into_output()), config: None }
+                    pwm::Pin { pin: Pwm::Software(pin.into_output()), config: None }
                 }
-            } else {
-                pwm::Pin {pin: self.0, config:None}
-            }
+            },
+            // kein Software-Pwm über gpio-cdev
+            #[cfg(raspi)]
+            Zugriff::Cdev(pin) => {
+                pwm::Pin { pin: pwm::Pwm::Gpio(pin.into_wrapper()), config: None }
+            },
+            Zugriff::Remote(pin) => {
+                pwm::Pin { pin: pwm::Pwm::Remote(pin.into_pwm()), config: None }
+            },
+            Zugriff::Stub(wrapper) => pwm::Pin { pin: pwm::Pwm::Gpio(wrapper), config: None },
         }
     }
 }
@@ -0,0 +1,306 @@
+//! Gpio Pins über das Linux GPIO character device (`/dev/gpiochipN`).
+//!
+//! Im Gegensatz zu rppal (`/dev/gpiomem`) funktioniert das auch mit neueren Boards
+//! (z.B. Raspberry Pi 5) und kann mit den `gpio-sim`/`gpio-mockup` Kernel-Modulen getestet werden.
+//! Interne Pull-up/Pull-down-Widerstände und Pwm werden nicht unterstützt,
+//! ein Input-Pin mit gewünschtem Widerstand kann nicht angefordert werden.
+
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex, PoisonError,
+};
+use std::thread::{self, JoinHandle};
+
+use gpio_cdev::{
+    errors::Error, EventRequestFlags, EventType, Line, LineEventHandle, LineHandle,
+    LineRequestFlags,
+};
+use log::error;
+
+use super::{input, Wrapper};
+use crate::anschluss::{level::Level, trigger::Trigger};
+
+/// Name, unter dem angeforderte Pins vom Kernel angezeigt werden (z.B. bei `gpioinfo`).
+const CONSUMER: &str = "zugkontrolle";
+
+/// Maximale Wartezeit auf ein Interrupt-Event (in ms), bevor ein Beenden geprüft wird.
+const POLL_TIMEOUT: i32 = 100;
+
+fn wert(level: Level) -> u8 {
+    match level {
+        Level::Low => 0,
+        Level::High => 1,
+    }
+}
+
+fn level(wert: u8) -> Level {
+    if wert == 0 {
+        Level::Low
+    } else {
+        Level::High
+    }
+}
+
+/// Gewünschter interner Widerstand eines Input-Pins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Widerstand {
+    Keiner,
+    PullUp,
+    PullDown,
+}
+
+/// Ein reservierter, aber noch nicht angeforderter Pin.
+#[derive(Debug)]
+pub(super) struct Pin {
+    line: Line,
+    wrapper: Wrapper,
+}
+
+impl PartialEq for Pin {
+    fn eq(&self, other: &Pin) -> bool {
+        self.wrapper == other.wrapper
+    }
+}
+
+impl Pin {
+    pub(super) fn neu(line: Line, wrapper: Wrapper) -> Self {
+        Pin { line, wrapper }
+    }
+
+    pub(super) fn pin(&self) -> u8 {
+        self.wrapper.0
+    }
+
+    /// Der Pin wird erst beim ersten Lesen (oder setzen eines Interrupts) angefordert.
+    /// Mit einem internen Widerstand schlägt das Anfordern fehl.
+    pub(super) fn into_input(self, widerstand: Widerstand) -> InputPin {
+        InputPin {
+            anforderung: Anforderung::Keine,
+            line: self.line,
+            wrapper: self.wrapper,
+            widerstand,
+        }
+    }
+
+    /// Schlägt das Anfordern fehl wird es beim nächsten Schreiben erneut versucht.
+    pub(super) fn into_output(self, level: Level) -> OutputPin {
+        let mut output_pin =
+            OutputPin { handle: None, line: self.line, wrapper: self.wrapper, level };
+        if let Err(fehler) = output_pin.write(level) {
+            error!(
+                "Gpio {} konnte nicht als Output angefordert werden: {}",
+                output_pin.pin(),
+                fehler
+            )
+        }
+        output_pin
+    }
+
+    /// Gebe den Pin frei, ohne ihn an [Anschlüsse](crate::anschluss::Anschlüsse) zurückzugeben.
+    pub(super) fn into_wrapper(self) -> Wrapper {
+        self.wrapper
+    }
+}
+
+/// Ein als Output angeforderter Pin.
+#[derive(Debug)]
+pub(super) struct OutputPin {
+    // Reihenfolge wichtig: die Anforderung muss vor der Rückgabe an Anschlüsse freigegeben werden.
+    handle: Option<LineHandle>,
+    line: Line,
+    wrapper: Wrapper,
+    level: Level,
+}
+
+impl PartialEq for OutputPin {
+    fn eq(&self, other: &OutputPin) -> bool {
+        self.wrapper == other.wrapper
+    }
+}
+
+impl OutputPin {
+    pub(super) fn pin(&self) -> u8 {
+        self.wrapper.0
+    }
+
+    pub(super) fn write(&mut self, level: Level) -> Result<(), Error> {
+        match &self.handle {
+            Some(handle) => handle.set_value(wert(level))?,
+            None => {
+                self.handle =
+                    Some(self.line.request(LineRequestFlags::OUTPUT, wert(level), CONSUMER)?)
+            },
+        }
+        self.level = level;
+        Ok(())
+    }
+
+    pub(super) fn is_set_high(&self) -> bool {
+        self.level == Level::High
+    }
+
+    pub(super) fn is_set_low(&self) -> bool {
+        self.level == Level::Low
+    }
+
+    pub(super) fn toggle(&mut self) -> Result<(), Error> {
+        self.write(match self.level {
+            Level::Low => Level::High,
+            Level::High => Level::Low,
+        })
+    }
+}
+
+/// Aktuelle Anforderung eines Input-Pins, ein Pin kann nur einmal angefordert werden.
+#[derive(Debug)]
+enum Anforderung {
+    Keine,
+    Wert(LineHandle),
+    Interrupt {
+        events: Arc<Mutex<LineEventHandle>>,
+        beenden: Arc<AtomicBool>,
+        thread: JoinHandle<()>,
+    },
+}
+
+/// Ein als Input angeforderter Pin.
+#[derive(Debug)]
+pub(super) struct InputPin {
+    // Reihenfolge wichtig: die Anforderung muss vor der Rückgabe an Anschlüsse freigegeben werden.
+    anforderung: Anforderung,
+    line: Line,
+    wrapper: Wrapper,
+    widerstand: Widerstand,
+}
+
+impl PartialEq for InputPin {
+    fn eq(&self, other: &InputPin) -> bool {
+        self.wrapper == other.wrapper
+    }
+}
+
+impl Drop for InputPin {
+    fn drop(&mut self) {
+        self.beende_interrupt()
+    }
+}
+
+impl InputPin {
+    pub(super) fn pin(&self) -> u8 {
+        self.wrapper.0
+    }
+
+    pub(super) fn read(&mut self) -> Result<Level, input::Error> {
+        let wert = match &self.anforderung {
+            Anforderung::Wert(handle) => handle.get_value()?,
+            Anforderung::Interrupt { events, .. } => {
+                events.lock().unwrap_or_else(PoisonError::into_inner).get_value()?
+            },
+            Anforderung::Keine => {
+                self.prüfe_widerstand()?;
+                let handle = self.line.request(LineRequestFlags::INPUT, 0, CONSUMER)?;
+                let wert = handle.get_value()?;
+                self.anforderung = Anforderung::Wert(handle);
+                wert
+            },
+        };
+        Ok(level(wert))
+    }
+
+    /// Die Events werden in einem eigenen Thread abgefragt, der callback wird dort ausgeführt.
+    pub(super) fn set_async_interrupt(
+        &mut self,
+        trigger: Trigger,
+        callback: impl FnMut(Level) + Send + 'static,
+    ) -> Result<(), input::Error> {
+        // eine vorherige Anforderung muss zuerst freigegeben werden
+        self.beende_interrupt();
+        self.anforderung = Anforderung::Keine;
+        let event_flags = match trigger {
+            Trigger::Disabled => return Ok(()),
+            Trigger::RisingEdge => EventRequestFlags::RISING_EDGE,
+            Trigger::FallingEdge => EventRequestFlags::FALLING_EDGE,
+            Trigger::Both => EventRequestFlags::BOTH_EDGES,
+        };
+        self.prüfe_widerstand()?;
+        let events = Arc::new(Mutex::new(self.line.events(
+            LineRequestFlags::INPUT,
+            event_flags,
+            CONSUMER,
+        )?));
+        let beenden = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let pin = self.pin();
+            let events = events.clone();
+            let beenden = beenden.clone();
+            thread::spawn(move || warte_auf_events(pin, events, beenden, callback))
+        };
+        self.anforderung = Anforderung::Interrupt { events, beenden, thread };
+        Ok(())
+    }
+
+    pub(super) fn clear_async_interrupt(&mut self) -> Result<(), Error> {
+        self.beende_interrupt();
+        Ok(())
+    }
+
+    /// Ein gewünschter interner Widerstand kann mit gpio-cdev nicht gesetzt werden.
+    fn prüfe_widerstand(&self) -> Result<(), input::Error> {
+        if self.widerstand == Widerstand::Keiner {
+            Ok(())
+        } else {
+            Err(input::Error::WiderstandNichtUnterstützt(self.pin()))
+        }
+    }
+
+    /// Beende einen evtl. laufenden Interrupt-Thread und gebe die Anforderung frei.
+    fn beende_interrupt(&mut self) {
+        if let Anforderung::Interrupt { beenden, thread, .. } =
+            std::mem::replace(&mut self.anforderung, Anforderung::Keine)
+        {
+            beenden.store(true, Ordering::Relaxed);
+            if thread.join().is_err() {
+                error!("Interrupt-Thread von Gpio {} ist abgestürzt.", self.pin())
+            }
+        }
+    }
+}
+
+/// Warte auf Events, bis `beenden` gesetzt wird.
+fn warte_auf_events(
+    pin: u8,
+    events: Arc<Mutex<LineEventHandle>>,
+    beenden: Arc<AtomicBool>,
+    mut callback: impl FnMut(Level),
+) {
+    let fd = events.lock().unwrap_or_else(PoisonError::into_inner).as_raw_fd();
+    while !beenden.load(Ordering::Relaxed) {
+        let mut poll_fd = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+        // Safety: poll_fd ist für die Dauer des Aufrufs gültig,
+        // fd bleibt geöffnet solange `events` existiert.
+        let bereit = unsafe { libc::poll(&mut poll_fd, 1, POLL_TIMEOUT) };
+        if bereit == 0 {
+            continue;
+        } else if bereit < 0 {
+            let fehler = io::Error::last_os_error();
+            if fehler.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            error!("Warten auf Events von Gpio {} fehlgeschlagen: {}", pin, fehler);
+            break;
+        }
+        let event = events.lock().unwrap_or_else(PoisonError::into_inner).get_event();
+        match event {
+            Ok(event) => callback(match event.event_type() {
+                EventType::RisingEdge => Level::High,
+                EventType::FallingEdge => Level::Low,
+            }),
+            Err(fehler) => {
+                error!("Lesen eines Events von Gpio {} fehlgeschlagen: {}", pin, fehler);
+                break;
+            },
+        }
+    }
+}
@@ -1,18 +1,27 @@
 //! Gpio Pins konfiguriert für Input.
 
-use cfg_if::cfg_if;
-#[cfg(not(raspi))]
 use log::debug;
 #[cfg(raspi)]
 use rppal::gpio;
 
-#[cfg(not(raspi))]
 use super::Wrapper;
-use crate::anschluss::{level::Level, trigger::Trigger};
+use crate::anschluss::{level::Level, remote, trigger::Trigger};
+
+/// Zugriff auf einen Input-Pin über das Hardware-Backend, einen entfernten Raspberry Pi
+/// oder einen Stub.
+#[derive(Debug, PartialEq)]
+pub(super) enum Zugriff {
+    #[cfg(raspi)]
+    Hardware(gpio::InputPin),
+    #[cfg(raspi)]
+    Cdev(super::cdev::InputPin),
+    Remote(super::remote::Pin),
+    Stub(Wrapper),
+}
 
 /// Ein Gpio Pin konfiguriert für Input.
 #[derive(Debug, PartialEq)]
-pub struct Pin(#[cfg(raspi)] pub(super) gpio::InputPin, #[cfg(not(raspi))] pub(super) Wrapper);
+pub struct Pin(pub(super) Zugriff);
 
 impl Pin {
     /// Returns the GPIO pin number.
@@ -20,26 +29,26 @@ impl Pin {
     /// Pins are addressed by their BCM numbers, rather than their physical location.
     #[inline]
     pub fn pin(&self) -> u8 {
-        cfg_if! {
-            if #[cfg(raspi)] {
-                self.0.pin()
-            } else {
-                // Pins sollten nur auf einem Raspi erzeugbar sein!
-                // Liefere Standard-Wert, der in näherer Zukunft nicht von Pins erreicht wird
-                self.0.0
-            }
+        match &self.0 {
+            #[cfg(raspi)]
+            Zugriff::Hardware(pin) => pin.pin(),
+            #[cfg(raspi)]
+            Zugriff::Cdev(pin) => pin.pin(),
+            Zugriff::Remote(pin) => pin.pin(),
+            Zugriff::Stub(wrapper) => wrapper.0,
         }
     }
 
     /// Reads the pin’s logic level.
     #[inline]
     pub fn read(&mut self) -> Result<Level, Error> {
-        cfg_if! {
-            if #[cfg(raspi)] {
-                Ok(self.0.read().into())
-            } else {
-                Err(Error::KeinRaspberryPi)
-            }
+        match &mut self.0 {
+            #[cfg(raspi)]
+            Zugriff::Hardware(pin) => Ok(pin.read().into()),
+            #[cfg(raspi)]
+            Zugriff::Cdev(pin) => Ok(pin.read()?),
+            Zugriff::Remote(pin) => Ok(pin.read()?),
+            Zugriff::Stub(_) => Err(Error::KeinRaspberryPi),
         }
     }
 
@@ -64,10 +73,15 @@ impl Pin {
         trigger: Trigger,
         #[cfg_attr(not(raspi), allow(unused_mut))] mut callback: impl FnMut(Level) + Send + 'static,
     ) -> Result<(), Error> {
-        cfg_if! {
-            if #[cfg(raspi)] {
-                Ok(self.0.set_async_interrupt(trigger.into(), move |level| callback(level.into()))?)
-            } else {
+        match &mut self.0 {
+            #[cfg(raspi)]
+            Zugriff::Hardware(pin) => {
+                Ok(pin.set_async_interrupt(trigger.into(), move |level| callback(level.into()))?)
+            }
+            #[cfg(raspi)]
+            Zugriff::Cdev(pin) => Ok(pin.set_async_interrupt(trigger, callback)?),
+            Zugriff::Remote(pin) => Ok(pin.set_async_interrupt(trigger, callback)?),
+            Zugriff::Stub(_) => {
                 debug!("{:?}.set_async_interrupt({}, callback)", self, trigger);
                 Err(Error::KeinRaspberryPi)
             }
@@ -77,10 +91,13 @@ impl Pin {
     /// Removes a previously configured asynchronous interrupt trigger.
     #[inline]
     pub fn clear_async_interrupt(&mut self) -> Result<(), Error> {
-        cfg_if! {
-            if #[cfg(raspi)] {
-                Ok(self.0.clear_async_interrupt()?)
-            } else {
+        match &mut self.0 {
+            #[cfg(raspi)]
+            Zugriff::Hardware(pin) => Ok(pin.clear_async_interrupt()?),
+            #[cfg(raspi)]
+            Zugriff::Cdev(pin) => Ok(pin.clear_async_interrupt()?),
+            Zugriff::Remote(pin) => Ok(pin.clear_async_interrupt()?),
+            Zugriff::Stub(_) => {
                 debug!("{:?}.clear_async_interrupt()", self);
                 Err(Error::KeinRaspberryPi)
             }
@@ -92,7 +109,12 @@ impl Pin {
 pub enum Error {
     #[cfg(raspi)]
     Gpio(gpio::Error),
-    #[cfg(not(raspi))]
+    #[cfg(raspi)]
+    Cdev(gpio_cdev::errors::Error),
+    /// Interne Pull-up/Pull-down-Widerstände werden mit gpio-cdev nicht unterstützt.
+    #[cfg(raspi)]
+    WiderstandNichtUnterstützt(u8),
+    Remote(remote::Error),
     KeinRaspberryPi,
 }
 #[cfg(raspi)]
@@ -101,3 +123,14 @@ impl From<gpio::Error> for Error {
         Error::Gpio(error)
     }
 }
+#[cfg(raspi)]
+impl From<gpio_cdev::errors::Error> for Error {
+    fn from(error: gpio_cdev::errors::Error) -> Self {
+        Error::Cdev(error)
+    }
+}
+impl From<remote::Error> for Error {
+    fn from(error: remote::Error) -> Self {
+        Error::Remote(error)
+    }
+}
@@ -1,18 +1,27 @@
 //! Gpio Pins konfiguriert für Output.
 
-use cfg_if::cfg_if;
-#[cfg(not(raspi))]
 use log::debug;
 #[cfg(raspi)]
 use rppal::gpio;
 
-#[cfg(not(raspi))]
 use super::Wrapper;
-use crate::anschluss::level::Level;
+use crate::anschluss::{level::Level, remote};
+
+/// Zugriff auf einen Output-Pin über das Hardware-Backend, einen entfernten Raspberry Pi
+/// oder einen Stub.
+#[derive(Debug, PartialEq)]
+pub(super) enum Zugriff {
+    #[cfg(raspi)]
+    Hardware(gpio::OutputPin),
+    #[cfg(raspi)]
+    Cdev(super::cdev::OutputPin),
+    Remote(super::remote::Pin),
+    Stub(Wrapper),
+}
 
 /// Ein Gpio Pin konfiguriert für Output.
 #[derive(Debug, PartialEq)]
-pub struct Pin(#[cfg(raspi)] pub(super) gpio::OutputPin, #[cfg(not(raspi))] pub(super) Wrapper);
+pub struct Pin(pub(super) Zugriff);
 
 impl Pin {
     /// Returns the GPIO pin number.
@@ -20,26 +29,29 @@ impl Pin {
     /// Pins are addressed by their BCM numbers, rather than their physical location.
     #[inline]
     pub fn pin(&self) -> u8 {
-        cfg_if! {
-            if #[cfg(raspi)] {
-                self.0.pin()
-            } else {
-                // Pins sollten nur auf einem Raspi erzeugbar sein!
-                // Liefere Standard-Wert, der in näherer Zukunft nicht von Pins erreicht wird
-                self.0.0
-            }
+        match &self.0 {
+            #[cfg(raspi)]
+            Zugriff::Hardware(pin) => pin.pin(),
+            #[cfg(raspi)]
+            Zugriff::Cdev(pin) => pin.pin(),
+            Zugriff::Remote(pin) => pin.pin(),
+            Zugriff::Stub(wrapper) => wrapper.0,
         }
     }
 
     /// Sets the pin’s output state.
-    #[cfg_attr(not(raspi), allow(unused_variables))]
     #[inline]
     pub fn write(&mut self, level: Level) -> Result<(), Error> {
-        cfg_if! {
-            if #[cfg(raspi)] {
-                self.0.write(level.into());
+        match &mut self.0 {
+            #[cfg(raspi)]
+            Zugriff::Hardware(pin) => {
+                pin.write(level.into());
                 Ok(())
-            } else {
+            }
+            #[cfg(raspi)]
+            Zugriff::Cdev(pin) => Ok(pin.write(level)?),
+            Zugriff::Remote(pin) => Ok(pin.write(level)?),
+            Zugriff::Stub(_) => {
                 debug!("{:?}.write({:?})", self, level);
                 Err(Error::KeinRaspberryPi)
             }
@@ -49,10 +61,13 @@ impl Pin {
     /// Returns `true` if the pin's output state is set to `Level::Low`.
     #[inline]
     pub fn is_set_low(&self) -> Result<bool, Error> {
-        cfg_if! {
-            if #[cfg(raspi)] {
-                Ok(self.0.is_set_low())
-            } else {
+        match &self.0 {
+            #[cfg(raspi)]
+            Zugriff::Hardware(pin) => Ok(pin.is_set_low()),
+            #[cfg(raspi)]
+            Zugriff::Cdev(pin) => Ok(pin.is_set_low()),
+            Zugriff::Remote(pin) => Ok(pin.is_set_low()),
+            Zugriff::Stub(_) => {
                 debug!("{:?}.is_set_low()", self);
                 Err(Error::KeinRaspberryPi)
             }
@@ -62,10 +77,13 @@ impl Pin {
     /// Returns `true` if the pin's output state is set to `Level::High`.
     #[inline]
     pub fn is_set_high(&self) -> Result<bool, Error> {
-        cfg_if! {
-            if #[cfg(raspi)] {
-                Ok(self.0.is_set_high())
-            } else {
+        match &self.0 {
+            #[cfg(raspi)]
+            Zugriff::Hardware(pin) => Ok(pin.is_set_high()),
+            #[cfg(raspi)]
+            Zugriff::Cdev(pin) => Ok(pin.is_set_high()),
+            Zugriff::Remote(pin) => Ok(pin.is_set_high()),
+            Zugriff::Stub(_) => {
                 debug!("{:?}.is_set_high()", self);
                 Err(Error::KeinRaspberryPi)
             }
@@ -75,11 +93,16 @@ impl Pin {
     /// Toggles the pin’s output state between Low and High.
     #[inline]
     pub fn toggle(&mut self) -> Result<(), Error> {
-        cfg_if! {
-            if #[cfg(raspi)] {
-                self.0.toggle();
+        match &mut self.0 {
+            #[cfg(raspi)]
+            Zugriff::Hardware(pin) => {
+                pin.toggle();
                 Ok(())
-            } else {
+            }
+            #[cfg(raspi)]
+            Zugriff::Cdev(pin) => Ok(pin.toggle()?),
+            Zugriff::Remote(pin) => Ok(pin.toggle()?),
+            Zugriff::Stub(_) => {
                 debug!("{:?}.toggle()", self);
                 Err(Error::KeinRaspberryPi)
             }
@@ -92,6 +115,19 @@ impl Pin {
 
 #[derive(Debug)]
 pub enum Error {
-    #[cfg(not(raspi))]
+    #[cfg(raspi)]
+    Cdev(gpio_cdev::errors::Error),
+    Remote(remote::Error),
     KeinRaspberryPi,
 }
+#[cfg(raspi)]
+impl From<gpio_cdev::errors::Error> for Error {
+    fn from(error: gpio_cdev::errors::Error) -> Self {
+        Error::Cdev(error)
+    }
+}
+impl From<remote::Error> for Error {
+    fn from(error: remote::Error) -> Self {
+        Error::Remote(error)
+    }
+}
@@ -1,47 +1,51 @@
-//! Gpio Pins für Pwm konfiguriert.
+//! Gpio Pins oder Pca9685-Kanäle für Pwm konfiguriert.
 
+use std::fmt::{self, Display, Formatter};
 use std::time::Duration;
 
-use cfg_if::cfg_if;
-#[cfg(not(raspi))]
 use log::debug;
 #[cfg(raspi)]
 use rppal::{gpio, pwm};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[cfg(not(raspi))]
 use super::Wrapper;
-use crate::anschluss::{anschlüsse::Anschlüsse, polarity::Polarität, serde::*};
+use crate::anschluss::{anschlüsse::Anschlüsse, pca9685, polarity::Polarität, remote, serde::*};
+use crate::speicherformat::{lese_version, Version};
 
-/// Ein Gpio Pin konfiguriert für Pwm.
+/// Ein Gpio Pin oder Pca9685-Kanal konfiguriert für Pwm.
 #[derive(Debug, PartialEq)]
 pub struct Pin {
-    #[cfg(raspi)]
     pub(super) pin: Pwm,
-    #[cfg(not(raspi))]
-    pub(super) pin: Wrapper,
     pub(super) config: Option<Config>,
 }
 
-#[cfg(raspi)]
 #[derive(Debug)]
 pub(super) enum Pwm {
+    #[cfg(raspi)]
     Hardware(pwm::Pwm, gpio::Pin),
+    #[cfg(raspi)]
     Software(gpio::OutputPin),
+    Gpio(Wrapper),
+    Remote(super::remote::Pin),
+    Pca9685(pca9685::Kanal),
 }
-#[cfg(raspi)]
 impl PartialEq for Pwm {
     fn eq(&self, other: &Pwm) -> bool {
         match (self, other) {
+            #[cfg(raspi)]
             (Pwm::Hardware(_, pin0), Pwm::Hardware(_, pin1)) => pin0 == pin1,
+            #[cfg(raspi)]
             (Pwm::Software(pin0), Pwm::Software(pin1)) => pin0 == pin1,
+            (Pwm::Gpio(pin0), Pwm::Gpio(pin1)) => pin0 == pin1,
+            (Pwm::Remote(pin0), Pwm::Remote(pin1)) => pin0 == pin1,
+            (Pwm::Pca9685(kanal0), Pwm::Pca9685(kanal1)) => kanal0 == kanal1,
             _ => false,
         }
     }
 }
 
 /// Einstellung eines Pwm-Pulses.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Config {
     pub time: Time,
     pub polarity: Polarität,
@@ -70,7 +74,7 @@ impl Config {
     }
 }
 /// Zeit-Einstellung eines Pwm-Pulses.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Time {
     /// Periodendauer und Pulsweite.
     Period { period: Duration, pulse_width: Duration },
@@ -91,34 +95,46 @@ impl Time {
     }
 }
 
+impl Display for Pin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.pin {
+            #[cfg(raspi)]
+            Pwm::Hardware(_pwm, pin) => write!(f, "{}", pin.pin()),
+            #[cfg(raspi)]
+            Pwm::Software(pin) => write!(f, "{}", pin.pin()),
+            Pwm::Gpio(pin) => write!(f, "{}", pin.0),
+            Pwm::Remote(pin) => write!(f, "{}", pin.pin()),
+            Pwm::Pca9685(kanal) => write!(f, "{}", kanal),
+        }
+    }
+}
+
 impl Pin {
-    /// Returns the GPIO pin number.
+    /// Returns the GPIO pin number, if no Pca9685 is used.
     ///
     /// Pins are addressed by their BCM numbers, rather than their physical location.
-    pub fn pin(&self) -> u8 {
-        cfg_if! {
-            if #[cfg(raspi)] {
-                match &self.pin {
-                    Pwm::Hardware(_pwm, pin) => pin.pin(),
-                    Pwm::Software(pin) => pin.pin(),
-                }
-            } else {
-                self.pin.0
-            }
+    pub fn pin(&self) -> Option<u8> {
+        match &self.pin {
+            #[cfg(raspi)]
+            Pwm::Hardware(_pwm, pin) => Some(pin.pin()),
+            #[cfg(raspi)]
+            Pwm::Software(pin) => Some(pin.pin()),
+            Pwm::Gpio(pin) => Some(pin.0),
+            Pwm::Remote(pin) => Some(pin.pin()),
+            Pwm::Pca9685(_kanal) => None,
         }
     }
 
-    /// Wird Hardware-Pwm verwendet?
+    /// Wird Hardware-Pwm (Raspberry Pi oder Pca9685) verwendet?
     pub fn hardware_pwm(&self) -> bool {
-        cfg_if! {
-            if #[cfg(raspi)] {
-                match self.pin {
-                    Pwm::Hardware(_, _) => true,
-                    Pwm::Software(_) => false,
-                }
-            } else {
-                false
-            }
+        match &self.pin {
+            #[cfg(raspi)]
+            Pwm::Hardware(_, _) => true,
+            #[cfg(raspi)]
+            Pwm::Software(_) => false,
+            Pwm::Gpio(_) => false,
+            Pwm::Remote(pin) => pin.hardware_pwm(),
+            Pwm::Pca9685(_) => true,
         }
     }
 
@@ -136,8 +152,9 @@ impl Pin {
             }
             #[cfg(raspi)]
             Pwm::Software(_pin) => Ok(&self.config),
-            #[cfg(not(raspi))]
-            _pin => {
+            Pwm::Pca9685(_kanal) => Ok(&self.config),
+            Pwm::Remote(pin) => Ok(if pin.pwm_aktiv() { &self.config } else { &None }),
+            Pwm::Gpio(_pin) => {
                 debug!("{:?}.is_enabled()", self);
                 Err(Error::KeinRaspberryPi)
             }
@@ -146,6 +163,7 @@ impl Pin {
 
     /// Aktiviere den Pwm-Puls.
     pub fn enable_with_config(&mut self, config: Config) -> Result<(), Error> {
+        let neue_config = config.clone();
         match &mut self.pin {
             #[cfg(raspi)]
             Pwm::Hardware(pwm_channel, _pin) => {
@@ -166,7 +184,7 @@ impl Pin {
                         }
                     }
                 }
-                Ok(pwm_channel.enable()?)
+                pwm_channel.enable()?;
             }
             #[cfg(raspi)]
             Pwm::Software(pin) => match config.time {
@@ -174,21 +192,36 @@ impl Pin {
                     if config.polarity == Polarität::Invertiert {
                         pulse_width = period - pulse_width;
                     }
-                    Ok(pin.set_pwm(period, pulse_width)?)
+                    pin.set_pwm(period, pulse_width)?;
                 }
                 Time::Frequency { frequency, mut duty_cycle } => {
                     if config.polarity == Polarität::Invertiert {
                         duty_cycle = 1. - duty_cycle;
                     }
-                    Ok(pin.set_pwm_frequency(frequency, duty_cycle)?)
+                    pin.set_pwm_frequency(frequency, duty_cycle)?;
                 }
             },
-            #[cfg(not(raspi))]
-            _pin => {
+            Pwm::Pca9685(kanal) => {
+                let (frequenz, mut duty_cycle) = match config.time {
+                    Time::Period { period, pulse_width } => (
+                        1. / period.as_secs_f64(),
+                        pulse_width.as_secs_f64() / period.as_secs_f64(),
+                    ),
+                    Time::Frequency { frequency, duty_cycle } => (frequency, duty_cycle),
+                };
+                if config.polarity == Polarität::Invertiert {
+                    duty_cycle = 1. - duty_cycle;
+                }
+                kanal.setze_puls(frequenz, duty_cycle)?;
+            }
+            Pwm::Remote(pin) => pin.enable_with_config(config)?,
+            Pwm::Gpio(_pin) => {
                 debug!("{:?}.enable_with_config({:?})", self, config);
-                Err(Error::KeinRaspberryPi)
+                return Err(Error::KeinRaspberryPi);
             }
         }
+        self.config = Some(neue_config);
+        Ok(())
     }
 
     /// Deaktiviere den Pwm-Puls
@@ -202,17 +235,17 @@ impl Pin {
             Pwm::Software(pin) => {
                 pin.clear_pwm()?;
             }
-            #[cfg(not(raspi))]
-            _pin => {
+            Pwm::Pca9685(kanal) => {
+                kanal.deaktiviere()?;
+            }
+            Pwm::Remote(pin) => pin.disable()?,
+            Pwm::Gpio(_pin) => {
                 debug!("{:?}.disable()", self);
                 return Err(Error::KeinRaspberryPi);
             }
         }
-        #[cfg_attr(not(raspi), allow(unreachable_code))]
-        {
-            self.config = None;
-            Ok(())
-        }
+        self.config = None;
+        Ok(())
     }
 
     // TODO cfg-reexport/stub-methods
@@ -226,9 +259,10 @@ pub enum Error {
     Gpio(gpio::Error),
     #[cfg(raspi)]
     Pwm(pwm::Error),
-    #[cfg(not(raspi))]
     KeinRaspberryPi,
     InvalideConfig(Config),
+    Pca9685(pca9685::Error),
+    Remote(remote::Error),
 }
 #[cfg(raspi)]
 impl From<gpio::Error> for Error {
@@ -243,18 +277,75 @@ impl From<pwm::Error> for Error {
     }
 }
 
+impl From<pca9685::Error> for Error {
+    fn from(error: pca9685::Error) -> Self {
+        Error::Pca9685(error)
+    }
+}
+
+impl From<remote::Error> for Error {
+    fn from(error: remote::Error) -> Self {
+        Error::Remote(error)
+    }
+}
+
 /// Serealisierbare Informationen einen Pwm-Pins.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Save(pub u8);
+#[serde(remote = "Self")]
+pub enum Save {
+    Pin(u8),
+    Pca9685(pca9685::Save),
+}
+impl Serialize for Save {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Save::serialize(self, serializer)
+    }
+}
+impl<'de> Deserialize<'de> for Save {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // vor Version::Pca9685 wurde nur die Nummer des Gpio-Pins gespeichert
+        if lese_version() < Version::Pca9685 {
+            u8::deserialize(deserializer).map(Save::Pin)
+        } else {
+            Save::deserialize(deserializer)
+        }
+    }
+}
+impl Save {
+    /// Prüfe, ob ein Pwm-Signal mit `frequenz` (in Herz) erzeugt werden kann.
+    ///
+    /// Ein Pca9685-Kanal ohne eigene Frequenz übernimmt `frequenz`.
+    pub fn mit_frequenz(self, frequenz: f64) -> Result<Save, pca9685::Error> {
+        match self {
+            Save::Pin(_) => Ok(self),
+            Save::Pca9685(save) => save.mit_frequenz(frequenz).map(Save::Pca9685),
+        }
+    }
+}
 impl ToSave for Pin {
     type Save = Save;
 
     fn to_save(&self) -> Save {
-        Save(self.pin())
+        match &self.pin {
+            #[cfg(raspi)]
+            Pwm::Hardware(_pwm, pin) => Save::Pin(pin.pin()),
+            #[cfg(raspi)]
+            Pwm::Software(pin) => Save::Pin(pin.pin()),
+            Pwm::Gpio(pin) => Save::Pin(pin.0),
+            Pwm::Remote(pin) => Save::Pin(pin.pin()),
+            Pwm::Pca9685(kanal) => Save::Pca9685(kanal.to_save()),
+        }
     }
 }
 impl Reserviere<Pin> for Save {
     fn reserviere(self, anschlüsse: &mut Anschlüsse) -> Result<Pin, crate::anschluss::Error> {
-        anschlüsse.reserviere_pin(self.0).map(super::Pin::into_pwm).map_err(Into::into)
+        match self {
+            Save::Pin(pin) => {
+                anschlüsse.reserviere_pin(pin).map(super::Pin::into_pwm).map_err(Into::into)
+            }
+            Save::Pca9685(save) => {
+                Ok(Pin { pin: Pwm::Pca9685(save.reserviere(anschlüsse)?), config: None })
+            }
+        }
     }
 }
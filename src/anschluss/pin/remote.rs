@@ -0,0 +1,108 @@
+//! Gpio Pins eines entfernten Raspberry Pi, siehe [remote](crate::anschluss::remote).
+//!
+//! Die Konfiguration (Input, Output, Pwm) wird vom Server übernommen,
+//! ein Pin-Typ für alle Konfigurationen ist daher ausreichend.
+
+use super::Wrapper;
+use crate::anschluss::{
+    level::Level,
+    remote::{Error, Verbindung, Widerstand},
+    trigger::Trigger,
+};
+
+/// Ein über eine [Verbindung] reservierter Pin, wird beim drop auf dem Server freigegeben.
+#[derive(Debug)]
+pub(super) struct Pin {
+    wrapper: Wrapper,
+    verbindung: Verbindung,
+}
+
+impl PartialEq for Pin {
+    fn eq(&self, other: &Pin) -> bool {
+        self.wrapper == other.wrapper
+    }
+}
+
+impl Drop for Pin {
+    fn drop(&mut self) {
+        self.verbindung.gebe_frei(self.pin())
+    }
+}
+
+impl Pin {
+    pub(super) fn neu(verbindung: Verbindung, wrapper: Wrapper) -> Self {
+        verbindung.reserviere(wrapper.0);
+        Pin { wrapper, verbindung }
+    }
+
+    pub(super) fn pin(&self) -> u8 {
+        self.wrapper.0
+    }
+
+    pub(super) fn into_input(self, widerstand: Widerstand) -> Self {
+        self.verbindung.als_input(self.pin(), widerstand);
+        self
+    }
+
+    pub(super) fn into_output(self, level: Level) -> Self {
+        self.verbindung.als_output(self.pin(), level);
+        self
+    }
+
+    pub(super) fn into_pwm(self) -> Self {
+        self.verbindung.als_pwm(self.pin());
+        self
+    }
+
+    pub(super) fn read(&self) -> Result<Level, Error> {
+        self.verbindung.lese(self.pin())
+    }
+
+    pub(super) fn set_async_interrupt(
+        &mut self,
+        trigger: Trigger,
+        callback: impl FnMut(Level) + Send + 'static,
+    ) -> Result<(), Error> {
+        self.verbindung.setze_interrupt(self.pin(), trigger, callback)
+    }
+
+    pub(super) fn clear_async_interrupt(&mut self) -> Result<(), Error> {
+        self.verbindung.lösche_interrupt(self.pin())
+    }
+
+    pub(super) fn write(&mut self, level: Level) -> Result<(), Error> {
+        self.verbindung.schreibe(self.pin(), level)
+    }
+
+    pub(super) fn is_set_high(&self) -> bool {
+        self.verbindung.output_level(self.pin()) == Some(Level::High)
+    }
+
+    pub(super) fn is_set_low(&self) -> bool {
+        self.verbindung.output_level(self.pin()) == Some(Level::Low)
+    }
+
+    pub(super) fn toggle(&mut self) -> Result<(), Error> {
+        self.write(match self.verbindung.output_level(self.pin()) {
+            Some(Level::Low) => Level::High,
+            Some(Level::High) | None => Level::Low,
+        })
+    }
+
+    pub(super) fn hardware_pwm(&self) -> bool {
+        self.verbindung.hardware_pwm(self.pin())
+    }
+
+    /// Ist der Pwm-Puls aktiv? Nach einem Verbindungsabbruch ist das nicht mehr der Fall.
+    pub(super) fn pwm_aktiv(&self) -> bool {
+        self.verbindung.pwm_config(self.pin()).is_some()
+    }
+
+    pub(super) fn enable_with_config(&mut self, config: super::pwm::Config) -> Result<(), Error> {
+        self.verbindung.pwm_aktiviere(self.pin(), config)
+    }
+
+    pub(super) fn disable(&mut self) -> Result<(), Error> {
+        self.verbindung.pwm_deaktiviere(self.pin())
+    }
+}
@@ -0,0 +1,848 @@
+//! Zugriff auf die Anschlüsse eines entfernten Raspberry Pi über TCP.
+//!
+//! Auf dem Raspberry Pi läuft ein [Server] ohne Anzeige, der das [Anschlüsse]-Singleton besitzt.
+//! Die Anzeige verwendet das Backend [Remote](super::anschlüsse::Backend::Remote),
+//! Gpio-Pins (Input, Output, Pwm) und Pcf8574 werden dann über eine [Verbindung] gesteuert.
+//!
+//! Alle Nachrichten werden mit bincode serialisiert. Der Client sendet regelmäßig einen Ping,
+//! bleibt dieser aus gilt die Verbindung als unterbrochen. Der Server versetzt dann alle
+//! Ausgänge in einen sicheren Zustand (Gpio als Input, Pwm deaktiviert, Pcf8574 alle Ports High).
+//! Nach einem erneuten Verbindungsaufbau stellt der Client die Konfiguration der Inputs wieder her.
+//! Ausgänge bleiben aus Sicherheitsgründen im sicheren Zustand, bis sie explizit über
+//! [Verbindung::ausgänge_freigeben] freigegeben werden. Bis dahin geschriebene Werte werden
+//! nur vermerkt und erst bei der Freigabe übertragen, Pwm-Pulse von vor dem Abbruch verfallen.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::{self, Debug, Formatter},
+    io::{self, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex, PoisonError,
+    },
+    thread,
+    time::Duration,
+};
+
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    anschlüsse::Anschlüsse,
+    i2c,
+    level::Level,
+    pin::{input, output, pwm, Pin},
+    trigger::Trigger,
+};
+
+/// Standard-Port, falls keiner angegeben wurde.
+pub const STANDARD_PORT: u16 = 7353;
+/// Abstand zwischen zwei Pings des Clients.
+const PING_INTERVALL: Duration = Duration::from_secs(1);
+/// Ohne Nachricht für diese Zeit gilt die Verbindung als unterbrochen.
+const VERBINDUNG_TIMEOUT: Duration = Duration::from_secs(3);
+/// Maximale Wartezeit auf eine Antwort des Servers.
+const ANTWORT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Wartezeit vor einem erneuten Verbindungsversuch.
+const WARTEZEIT: Duration = Duration::from_secs(1);
+/// Id für Anfragen, auf deren Antwort nicht gewartet wird.
+const KEINE_ID: u64 = 0;
+
+/// Gewünschter interner Widerstand eines Input-Pins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Widerstand {
+    Keiner,
+    PullUp,
+    PullDown,
+}
+
+/// Anfrage des Clients an den Server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Anfrage {
+    Ping,
+    Reserviere(u8),
+    GebeFrei(u8),
+    Input {
+        pin: u8,
+        widerstand: Widerstand,
+    },
+    Output {
+        pin: u8,
+        level: Level,
+    },
+    /// Antwort ist [Antwort::HardwarePwm].
+    Pwm(u8),
+    Schreibe {
+        pin: u8,
+        level: Level,
+    },
+    Lese(u8),
+    /// [Trigger::Disabled] entfernt einen vorher gesetzten Interrupt.
+    Interrupt {
+        pin: u8,
+        trigger: Trigger,
+    },
+    PwmAktiviere {
+        pin: u8,
+        config: pwm::Config,
+    },
+    PwmDeaktiviere(u8),
+    /// Lese ein Byte ohne Register-Auswahl, z.B. alle Ports eines Pcf8574.
+    I2cLese(i2c::Adresse),
+    /// Schreibe ein Byte ohne Register-Auswahl, z.B. alle Ports eines Pcf8574.
+    I2cSchreibe {
+        adresse: i2c::Adresse,
+        wert: u8,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Paket {
+    id: u64,
+    anfrage: Anfrage,
+}
+
+/// Antwort des Servers auf eine Anfrage.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Antwort {
+    Ok,
+    Level(Level),
+    HardwarePwm(bool),
+    Byte(u8),
+    Fehler(String),
+}
+
+/// Nachricht vom Server an den Client.
+#[derive(Debug, Serialize, Deserialize)]
+enum Nachricht {
+    Antwort { id: u64, antwort: Antwort },
+    Interrupt { pin: u8, level: Level },
+}
+
+/// Serialisiere und sende einen Wert als Ganzes.
+fn sende<T: Serialize>(mut stream: &TcpStream, wert: &T) -> Result<(), Error> {
+    let bytes = bincode::serialize(wert)?;
+    stream.write_all(&bytes)?;
+    Ok(())
+}
+
+/// Konfiguration eines Pins, wird nach einem erneuten Verbindungsaufbau wiederhergestellt.
+#[derive(Debug, Clone, PartialEq)]
+enum Modus {
+    Reserviert,
+    Input { widerstand: Widerstand, trigger: Trigger },
+    Output(Level),
+    Pwm { hardware: bool, config: Option<pwm::Config> },
+}
+
+impl Modus {
+    /// Notwendige Anfragen, um den Pin (erneut) zu konfigurieren.
+    fn anfragen(&self, pin: u8) -> Vec<Anfrage> {
+        let mut anfragen = vec![Anfrage::Reserviere(pin)];
+        match self {
+            Modus::Reserviert => {},
+            Modus::Input { widerstand, trigger } => {
+                anfragen.push(Anfrage::Input { pin, widerstand: *widerstand });
+                if *trigger != Trigger::Disabled {
+                    anfragen.push(Anfrage::Interrupt { pin, trigger: *trigger })
+                }
+            },
+            Modus::Output(level) => anfragen.push(Anfrage::Output { pin, level: *level }),
+            Modus::Pwm { .. } => anfragen.push(Anfrage::Pwm(pin)),
+        }
+        anfragen
+    }
+}
+
+type Callback = Box<dyn FnMut(Level) + Send + 'static>;
+
+/// Zustand einer [Verbindung], geteilt zwischen allen Threads.
+#[derive(Debug)]
+struct Status {
+    /// Nur vorhanden, solange eine Verbindung besteht.
+    stream: Option<TcpStream>,
+    nächste_id: u64,
+    wartend: HashMap<u64, mpsc::Sender<Antwort>>,
+    pins: BTreeMap<u8, Modus>,
+    /// Zuletzt geschriebener Wert aller Pcf8574.
+    i2c_werte: BTreeMap<i2c::Adresse, u8>,
+    /// Nach einem erneuten Verbindungsaufbau bis zur expliziten Freigabe gesetzt.
+    ausgänge_gesperrt: bool,
+}
+
+impl Status {
+    /// Ist `modus` ein Ausgang, der aktuell im sicheren Zustand gehalten wird?
+    fn gesperrt(&self, modus: &Modus) -> bool {
+        self.ausgänge_gesperrt && matches!(modus, Modus::Output(_) | Modus::Pwm { .. })
+    }
+}
+
+/// Verbindung zu einem [Server], wird bei Unterbrechungen automatisch wiederhergestellt.
+#[derive(Clone)]
+pub struct Verbindung {
+    adresse: SocketAddr,
+    status: Arc<Mutex<Status>>,
+    callbacks: Arc<Mutex<HashMap<u8, Callback>>>,
+    /// Wird nach einem erneuten Verbindungsaufbau aufgerufen.
+    wiederverbunden: Arc<Mutex<Option<Box<dyn FnMut() + Send + 'static>>>>,
+}
+
+impl Debug for Verbindung {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Verbindung {{adresse: {}, status: {:?}}}", self.adresse, self.status)
+    }
+}
+
+impl Verbindung {
+    /// Verbinde zum Server an `adresse`.
+    ///
+    /// Nur der erste Verbindungsaufbau muss erfolgreich sein,
+    /// spätere Unterbrechungen werden im Hintergrund behandelt.
+    pub fn neu(adresse: SocketAddr) -> Result<Self, Error> {
+        let stream = TcpStream::connect_timeout(&adresse, VERBINDUNG_TIMEOUT)?;
+        let verbindung = Verbindung {
+            adresse,
+            status: Arc::new(Mutex::new(Status {
+                stream: None,
+                nächste_id: KEINE_ID + 1,
+                wartend: HashMap::new(),
+                pins: BTreeMap::new(),
+                i2c_werte: BTreeMap::new(),
+                ausgänge_gesperrt: false,
+            })),
+            callbacks: Arc::new(Mutex::new(HashMap::new())),
+            wiederverbunden: Arc::new(Mutex::new(None)),
+        };
+        verbindung.verbunden(&stream, false)?;
+        let (interrupt_sender, interrupt_empfänger) = mpsc::channel();
+        {
+            // Callbacks in eigenem Thread, damit sie selbst Anfragen stellen können.
+            let callbacks = verbindung.callbacks.clone();
+            thread::spawn(move || {
+                for (pin, level) in interrupt_empfänger {
+                    let mut callbacks = callbacks.lock().unwrap_or_else(PoisonError::into_inner);
+                    if let Some(callback) = callbacks.get_mut(&pin) {
+                        callback(level)
+                    }
+                }
+            });
+        }
+        {
+            let verbindung = verbindung.clone();
+            thread::spawn(move || {
+                let mut stream = stream;
+                loop {
+                    if let Err(fehler) = verbindung.empfange(&stream, &interrupt_sender) {
+                        warn!("Verbindung zu {} unterbrochen: {:?}", verbindung.adresse, fehler)
+                    }
+                    verbindung.getrennt();
+                    stream = loop {
+                        thread::sleep(WARTEZEIT);
+                        match TcpStream::connect_timeout(&verbindung.adresse, VERBINDUNG_TIMEOUT) {
+                            Ok(stream) => match verbindung.verbunden(&stream, true) {
+                                Ok(()) => break stream,
+                                Err(fehler) => warn!("Verbindung fehlgeschlagen: {:?}", fehler),
+                            },
+                            Err(fehler) => debug!("Verbindung fehlgeschlagen: {}", fehler),
+                        }
+                    };
+                    warn!(
+                        "Verbindung zu {} wiederhergestellt, Ausgänge bis zur Freigabe gesperrt.",
+                        verbindung.adresse
+                    );
+                    let mut wiederverbunden =
+                        verbindung.wiederverbunden.lock().unwrap_or_else(PoisonError::into_inner);
+                    if let Some(callback) = &mut *wiederverbunden {
+                        callback()
+                    }
+                }
+            });
+        }
+        {
+            let verbindung = verbindung.clone();
+            thread::spawn(move || loop {
+                thread::sleep(PING_INTERVALL);
+                let _ = verbindung.sende(Anfrage::Ping);
+            });
+        }
+        Ok(verbindung)
+    }
+
+    /// Nach einem (erneuten) Verbindungsaufbau: stelle die bekannte Konfiguration wieder her.
+    ///
+    /// Bei einem `erneuten` Verbindungsaufbau bleiben alle Ausgänge im sicheren Zustand,
+    /// bis sie über [ausgänge_freigeben](Verbindung::ausgänge_freigeben) freigegeben werden.
+    fn verbunden(&self, stream: &TcpStream, erneut: bool) -> Result<(), Error> {
+        stream.set_read_timeout(Some(VERBINDUNG_TIMEOUT))?;
+        stream.set_nodelay(true)?;
+        let status = &mut *self.status.lock()?;
+        status.ausgänge_gesperrt |= erneut;
+        for (pin, modus) in status.pins.iter_mut() {
+            if let Modus::Pwm { config, .. } = modus {
+                if config.take().is_some() {
+                    warn!("Pwm an Gpio {} nach Verbindungsabbruch deaktiviert.", pin)
+                }
+            }
+            let anfragen = if erneut && matches!(modus, Modus::Output(_)) {
+                vec![Anfrage::Reserviere(*pin)]
+            } else {
+                modus.anfragen(*pin)
+            };
+            for anfrage in anfragen {
+                sende(stream, &Paket { id: KEINE_ID, anfrage })?
+            }
+        }
+        if !status.ausgänge_gesperrt {
+            for (adresse, wert) in status.i2c_werte.iter() {
+                sende(
+                    stream,
+                    &Paket {
+                        id: KEINE_ID,
+                        anfrage: Anfrage::I2cSchreibe { adresse: *adresse, wert: *wert },
+                    },
+                )?
+            }
+        }
+        status.stream = Some(stream.try_clone()?);
+        Ok(())
+    }
+
+    /// Setze den callback, der nach einem erneuten Verbindungsaufbau aufgerufen wird.
+    ///
+    /// Die Ausgänge sind dann bis zum Aufruf von
+    /// [ausgänge_freigeben](Verbindung::ausgänge_freigeben) gesperrt.
+    pub(super) fn bei_wiederverbindung(&self, callback: impl FnMut() + Send + 'static) {
+        let mut wiederverbunden =
+            self.wiederverbunden.lock().unwrap_or_else(PoisonError::into_inner);
+        *wiederverbunden = Some(Box::new(callback))
+    }
+
+    /// Übertrage die seit dem Verbindungsabbruch vermerkten Werte aller Ausgänge.
+    pub(super) fn ausgänge_freigeben(&self) -> Result<(), Error> {
+        let (pins, i2c_werte) = {
+            let status = &*self.status.lock()?;
+            if !status.ausgänge_gesperrt {
+                return Ok(());
+            }
+            (status.pins.clone(), status.i2c_werte.clone())
+        };
+        for (pin, modus) in pins {
+            match modus {
+                Modus::Output(level) => self.anfrage_ok(Anfrage::Output { pin, level })?,
+                Modus::Pwm { config: Some(config), .. } => {
+                    self.anfrage_ok(Anfrage::PwmAktiviere { pin, config })?
+                },
+                Modus::Reserviert | Modus::Input { .. } | Modus::Pwm { config: None, .. } => {},
+            }
+        }
+        for (adresse, wert) in i2c_werte {
+            self.anfrage_ok(Anfrage::I2cSchreibe { adresse, wert })?
+        }
+        self.status.lock()?.ausgänge_gesperrt = false;
+        info!("Ausgänge von {} freigegeben.", self.adresse);
+        Ok(())
+    }
+
+    /// Nach einem Verbindungsabbruch: alle wartenden Anfragen schlagen fehl.
+    fn getrennt(&self) {
+        let status = &mut *self.status.lock().unwrap_or_else(PoisonError::into_inner);
+        status.stream = None;
+        status.wartend.clear();
+    }
+
+    /// Empfange Nachrichten des Servers, bis die Verbindung unterbrochen wird.
+    fn empfange(
+        &self,
+        stream: &TcpStream,
+        interrupts: &mpsc::Sender<(u8, Level)>,
+    ) -> Result<(), Error> {
+        let mut reader = BufReader::new(stream);
+        loop {
+            match bincode::deserialize_from(&mut reader)? {
+                Nachricht::Antwort { id: KEINE_ID, antwort: Antwort::Fehler(fehler) } => {
+                    warn!("Fehler bei einer Anfrage an {}: {}", self.adresse, fehler)
+                },
+                Nachricht::Antwort { id, antwort } => {
+                    let sender = self.status.lock()?.wartend.remove(&id);
+                    if let Some(sender) = sender {
+                        let _ = sender.send(antwort);
+                    }
+                },
+                Nachricht::Interrupt { pin, level } => {
+                    let _ = interrupts.send((pin, level));
+                },
+            }
+        }
+    }
+
+    /// Sende eine Anfrage, ohne auf die Antwort zu warten.
+    fn sende(&self, anfrage: Anfrage) -> Result<(), Error> {
+        let status = &*self.status.lock()?;
+        let stream = status.stream.as_ref().ok_or(Error::NichtVerbunden)?;
+        sende(stream, &Paket { id: KEINE_ID, anfrage })
+    }
+
+    /// Sende eine Anfrage und warte auf die Antwort.
+    fn anfrage(&self, anfrage: Anfrage) -> Result<Antwort, Error> {
+        let (sender, empfänger) = mpsc::channel();
+        {
+            let Status { stream, nächste_id, wartend, .. } = &mut *self.status.lock()?;
+            let stream = stream.as_ref().ok_or(Error::NichtVerbunden)?;
+            let id = *nächste_id;
+            *nächste_id = nächste_id.wrapping_add(1).max(KEINE_ID + 1);
+            wartend.insert(id, sender);
+            sende(stream, &Paket { id, anfrage })?;
+        }
+        match empfänger.recv_timeout(ANTWORT_TIMEOUT) {
+            Ok(Antwort::Fehler(fehler)) => Err(Error::Server(fehler)),
+            Ok(antwort) => Ok(antwort),
+            Err(RecvTimeoutError::Timeout) => Err(Error::KeineAntwort),
+            Err(RecvTimeoutError::Disconnected) => Err(Error::NichtVerbunden),
+        }
+    }
+
+    /// Wie [anfrage](Verbindung::anfrage), erwartet wird [Antwort::Ok].
+    fn anfrage_ok(&self, anfrage: Anfrage) -> Result<(), Error> {
+        match self.anfrage(anfrage)? {
+            Antwort::Ok => Ok(()),
+            antwort => Err(Error::UnerwarteteAntwort(format!("{:?}", antwort))),
+        }
+    }
+
+    /// Vermerke die Konfiguration eines Pins und übertrage sie zum Server.
+    ///
+    /// Besteht gerade keine Verbindung erfolgt die Übertragung beim nächsten Verbindungsaufbau.
+    /// Während die Ausgänge gesperrt sind wird ein Output-Pin nur reserviert.
+    fn konfiguriere(&self, pin: u8, modus: Modus) {
+        let mut anfragen = match &modus {
+            Modus::Reserviert => vec![Anfrage::Reserviere(pin)],
+            Modus::Input { widerstand, .. } => {
+                vec![Anfrage::Input { pin, widerstand: *widerstand }]
+            },
+            Modus::Output(level) => vec![Anfrage::Output { pin, level: *level }],
+            Modus::Pwm { .. } => vec![Anfrage::Pwm(pin)],
+        };
+        match self.status.lock() {
+            Ok(mut status) => {
+                if status.ausgänge_gesperrt && matches!(modus, Modus::Output(_)) {
+                    anfragen.clear()
+                }
+                status.pins.insert(pin, modus);
+            },
+            Err(_) => error!("Status der Verbindung zu {} poisoned!", self.adresse),
+        }
+        for anfrage in anfragen {
+            match self.anfrage(anfrage) {
+                Ok(Antwort::HardwarePwm(hardware)) => self.ändere(pin, |modus| {
+                    if let Modus::Pwm { hardware: bisher, .. } = modus {
+                        *bisher = hardware
+                    }
+                }),
+                Ok(_) => {},
+                Err(fehler) => error!("Gpio {} nicht konfiguriert: {:?}", pin, fehler),
+            }
+        }
+    }
+
+    fn ändere(&self, pin: u8, ändern: impl FnOnce(&mut Modus)) {
+        match self.status.lock() {
+            Ok(mut status) => {
+                if let Some(modus) = status.pins.get_mut(&pin) {
+                    ändern(modus)
+                }
+            },
+            Err(_) => error!("Status der Verbindung zu {} poisoned!", self.adresse),
+        }
+    }
+
+    fn modus(&self, pin: u8) -> Option<Modus> {
+        self.status.lock().ok().and_then(|status| status.pins.get(&pin).cloned())
+    }
+
+    /// Reserviere einen Pin auf dem Server.
+    pub(super) fn reserviere(&self, pin: u8) {
+        self.konfiguriere(pin, Modus::Reserviert)
+    }
+
+    pub(super) fn als_input(&self, pin: u8, widerstand: Widerstand) {
+        self.konfiguriere(pin, Modus::Input { widerstand, trigger: Trigger::Disabled })
+    }
+
+    pub(super) fn als_output(&self, pin: u8, level: Level) {
+        self.konfiguriere(pin, Modus::Output(level))
+    }
+
+    pub(super) fn als_pwm(&self, pin: u8) {
+        self.konfiguriere(pin, Modus::Pwm { hardware: false, config: None })
+    }
+
+    /// Gebe einen Pin auf dem Server frei.
+    pub(super) fn gebe_frei(&self, pin: u8) {
+        if let Ok(mut status) = self.status.lock() {
+            status.pins.remove(&pin);
+        }
+        if let Ok(mut callbacks) = self.callbacks.lock() {
+            callbacks.remove(&pin);
+        }
+        if let Err(fehler) = self.sende(Anfrage::GebeFrei(pin)) {
+            debug!("Gpio {} nicht freigegeben: {:?}", pin, fehler)
+        }
+    }
+
+    pub(super) fn lese(&self, pin: u8) -> Result<Level, Error> {
+        match self.anfrage(Anfrage::Lese(pin))? {
+            Antwort::Level(level) => Ok(level),
+            antwort => Err(Error::UnerwarteteAntwort(format!("{:?}", antwort))),
+        }
+    }
+
+    /// Während die Ausgänge gesperrt sind wird das Level nur vermerkt.
+    pub(super) fn schreibe(&self, pin: u8, level: Level) -> Result<(), Error> {
+        if !self.ist_gesperrt(pin)? {
+            self.anfrage_ok(Anfrage::Schreibe { pin, level })?;
+        }
+        self.ändere(pin, |modus| *modus = Modus::Output(level));
+        Ok(())
+    }
+
+    /// Wird der Ausgang `pin` aktuell im sicheren Zustand gehalten?
+    fn ist_gesperrt(&self, pin: u8) -> Result<bool, Error> {
+        let status = &*self.status.lock()?;
+        Ok(status.pins.get(&pin).map_or(false, |modus| status.gesperrt(modus)))
+    }
+
+    /// Zuletzt geschriebenes Level eines Output-Pins.
+    pub(super) fn output_level(&self, pin: u8) -> Option<Level> {
+        match self.modus(pin) {
+            Some(Modus::Output(level)) => Some(level),
+            _ => None,
+        }
+    }
+
+    /// Der callback wird in einem eigenen Thread ausgeführt.
+    pub(super) fn setze_interrupt(
+        &self,
+        pin: u8,
+        trigger: Trigger,
+        callback: impl FnMut(Level) + Send + 'static,
+    ) -> Result<(), Error> {
+        self.callbacks.lock()?.insert(pin, Box::new(callback));
+        self.übertrage_trigger(pin, trigger)
+    }
+
+    pub(super) fn lösche_interrupt(&self, pin: u8) -> Result<(), Error> {
+        self.callbacks.lock()?.remove(&pin);
+        self.übertrage_trigger(pin, Trigger::Disabled)
+    }
+
+    fn übertrage_trigger(&self, pin: u8, trigger: Trigger) -> Result<(), Error> {
+        self.ändere(pin, |modus| {
+            if let Modus::Input { trigger: bisher, .. } = modus {
+                *bisher = trigger
+            }
+        });
+        self.anfrage_ok(Anfrage::Interrupt { pin, trigger })
+    }
+
+    pub(super) fn hardware_pwm(&self, pin: u8) -> bool {
+        matches!(self.modus(pin), Some(Modus::Pwm { hardware: true, .. }))
+    }
+
+    /// Aktuelle Pwm-Konfiguration, nach einem Verbindungsabbruch immer [None].
+    pub(super) fn pwm_config(&self, pin: u8) -> Option<pwm::Config> {
+        match self.modus(pin) {
+            Some(Modus::Pwm { config, .. }) => config,
+            _ => None,
+        }
+    }
+
+    /// Während die Ausgänge gesperrt sind wird die Konfiguration nur vermerkt.
+    pub(super) fn pwm_aktiviere(&self, pin: u8, config: pwm::Config) -> Result<(), Error> {
+        if !self.ist_gesperrt(pin)? {
+            self.anfrage_ok(Anfrage::PwmAktiviere { pin, config: config.clone() })?;
+        }
+        self.ändere(pin, |modus| {
+            if let Modus::Pwm { config: bisher, .. } = modus {
+                *bisher = Some(config)
+            }
+        });
+        Ok(())
+    }
+
+    pub(super) fn pwm_deaktiviere(&self, pin: u8) -> Result<(), Error> {
+        self.ändere(pin, |modus| {
+            if let Modus::Pwm { config, .. } = modus {
+                *config = None
+            }
+        });
+        self.anfrage_ok(Anfrage::PwmDeaktiviere(pin))
+    }
+
+    pub(super) fn i2c_lese(&self, adresse: i2c::Adresse) -> Result<u8, Error> {
+        match self.anfrage(Anfrage::I2cLese(adresse))? {
+            Antwort::Byte(wert) => Ok(wert),
+            antwort => Err(Error::UnerwarteteAntwort(format!("{:?}", antwort))),
+        }
+    }
+
+    /// Während die Ausgänge gesperrt sind wird der Wert nur vermerkt.
+    pub(super) fn i2c_schreibe(&self, adresse: i2c::Adresse, wert: u8) -> Result<(), Error> {
+        let gesperrt = {
+            let status = &mut *self.status.lock()?;
+            status.i2c_werte.insert(adresse, wert);
+            status.ausgänge_gesperrt
+        };
+        if gesperrt {
+            Ok(())
+        } else {
+            self.anfrage_ok(Anfrage::I2cSchreibe { adresse, wert })
+        }
+    }
+}
+
+/// Fehlermeldung für eine [Antwort::Fehler].
+fn fehler(fehler: impl Debug) -> String {
+    format!("{:?}", fehler)
+}
+
+/// Konfiguration eines Pins auf dem Server.
+#[derive(Debug)]
+enum ServerPin {
+    Reserviert(Pin),
+    Input(input::Pin),
+    Output(output::Pin),
+    Pwm(pwm::Pin),
+}
+
+/// Server für den Zugriff auf die Anschlüsse dieses Raspberry Pi.
+///
+/// Es wird immer nur ein Client gleichzeitig bedient,
+/// weitere Verbindungen werden erst nach dessen Trennung angenommen.
+#[derive(Debug)]
+pub struct Server<'t> {
+    anschlüsse: &'t mut Anschlüsse,
+    pins: HashMap<u8, ServerPin>,
+    /// Über [Anfrage::I2cSchreibe] verwendete Adressen.
+    i2c_adressen: HashSet<i2c::Adresse>,
+}
+
+impl Server<'_> {
+    /// Warte an `adresse` auf Verbindungen und bearbeite deren Anfragen, kehrt nur bei Fehlern zurück.
+    pub fn starte(anschlüsse: &mut Anschlüsse, adresse: impl ToSocketAddrs) -> io::Result<()> {
+        let listener = TcpListener::bind(adresse)?;
+        info!("Warte auf Verbindungen an {}", listener.local_addr()?);
+        let mut server = Server { anschlüsse, pins: HashMap::new(), i2c_adressen: HashSet::new() };
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    info!("Verbunden mit {:?}", stream.peer_addr());
+                    if let Err(fehler) = server.bearbeite(&stream) {
+                        warn!("Verbindung unterbrochen: {:?}", fehler)
+                    }
+                    server.sicherer_zustand();
+                },
+                Err(fehler) => warn!("Verbindung nicht angenommen: {}", fehler),
+            }
+        }
+        Ok(())
+    }
+
+    /// Bearbeite Anfragen, bis die Verbindung unterbrochen wird.
+    fn bearbeite(&mut self, stream: &TcpStream) -> Result<(), Error> {
+        stream.set_read_timeout(Some(VERBINDUNG_TIMEOUT))?;
+        stream.set_nodelay(true)?;
+        let writer = Arc::new(Mutex::new(stream.try_clone()?));
+        let mut reader = BufReader::new(stream);
+        loop {
+            let Paket { id, anfrage } = bincode::deserialize_from(&mut reader)?;
+            let antwort = match self.beantworte(anfrage, &writer) {
+                Ok(antwort) => antwort,
+                Err(fehler) => Antwort::Fehler(fehler),
+            };
+            sende(&*writer.lock()?, &Nachricht::Antwort { id, antwort })?;
+        }
+    }
+
+    fn beantworte(
+        &mut self,
+        anfrage: Anfrage,
+        writer: &Arc<Mutex<TcpStream>>,
+    ) -> Result<Antwort, String> {
+        debug!("{:?}", anfrage);
+        let nicht_konfiguriert = |pin| format!("Gpio {} nicht passend konfiguriert", pin);
+        match anfrage {
+            Anfrage::Ping => {},
+            Anfrage::Reserviere(pin) => {
+                // eine vorherige Reservierung (vor einem Verbindungsabbruch) wird ersetzt
+                self.pins.remove(&pin);
+                let reserviert = self.anschlüsse.reserviere_pin(pin).map_err(fehler)?;
+                self.pins.insert(pin, ServerPin::Reserviert(reserviert));
+            },
+            Anfrage::GebeFrei(pin) => {
+                self.pins.remove(&pin);
+            },
+            Anfrage::Input { pin, widerstand } => match self.pins.remove(&pin) {
+                Some(ServerPin::Reserviert(reserviert)) => {
+                    let input_pin = match widerstand {
+                        Widerstand::Keiner => reserviert.into_input(),
+                        Widerstand::PullUp => reserviert.into_input_pullup(),
+                        Widerstand::PullDown => reserviert.into_input_pulldown(),
+                    };
+                    self.pins.insert(pin, ServerPin::Input(input_pin));
+                },
+                _ => return Err(nicht_konfiguriert(pin)),
+            },
+            Anfrage::Output { pin, level } => match self.pins.remove(&pin) {
+                Some(ServerPin::Reserviert(reserviert)) => {
+                    self.pins.insert(pin, ServerPin::Output(reserviert.into_output(level)));
+                },
+                _ => return Err(nicht_konfiguriert(pin)),
+            },
+            Anfrage::Pwm(pin) => match self.pins.remove(&pin) {
+                Some(ServerPin::Reserviert(reserviert)) => {
+                    let pwm_pin = reserviert.into_pwm();
+                    let hardware = pwm_pin.hardware_pwm();
+                    self.pins.insert(pin, ServerPin::Pwm(pwm_pin));
+                    return Ok(Antwort::HardwarePwm(hardware));
+                },
+                _ => return Err(nicht_konfiguriert(pin)),
+            },
+            Anfrage::Schreibe { pin, level } => match self.pins.get_mut(&pin) {
+                Some(ServerPin::Output(output_pin)) => output_pin.write(level).map_err(fehler)?,
+                _ => return Err(nicht_konfiguriert(pin)),
+            },
+            Anfrage::Lese(pin) => match self.pins.get_mut(&pin) {
+                Some(ServerPin::Input(input_pin)) => {
+                    return input_pin.read().map(Antwort::Level).map_err(fehler)
+                },
+                _ => return Err(nicht_konfiguriert(pin)),
+            },
+            Anfrage::Interrupt { pin, trigger } => match self.pins.get_mut(&pin) {
+                Some(ServerPin::Input(input_pin)) if trigger == Trigger::Disabled => {
+                    input_pin.clear_async_interrupt().map_err(fehler)?
+                },
+                Some(ServerPin::Input(input_pin)) => {
+                    let writer = writer.clone();
+                    input_pin
+                        .set_async_interrupt(trigger, move |level| {
+                            let ergebnis = writer.lock().map_err(Error::from).and_then(|stream| {
+                                sende(&stream, &Nachricht::Interrupt { pin, level })
+                            });
+                            if let Err(fehler) = ergebnis {
+                                warn!("Interrupt von Gpio {} nicht gesendet: {:?}", pin, fehler)
+                            }
+                        })
+                        .map_err(fehler)?
+                },
+                _ => return Err(nicht_konfiguriert(pin)),
+            },
+            Anfrage::PwmAktiviere { pin, config } => match self.pins.get_mut(&pin) {
+                Some(ServerPin::Pwm(pwm_pin)) => {
+                    pwm_pin.enable_with_config(config).map_err(fehler)?
+                },
+                _ => return Err(nicht_konfiguriert(pin)),
+            },
+            Anfrage::PwmDeaktiviere(pin) => match self.pins.get_mut(&pin) {
+                Some(ServerPin::Pwm(pwm_pin)) => pwm_pin.disable().map_err(fehler)?,
+                _ => return Err(nicht_konfiguriert(pin)),
+            },
+            Anfrage::I2cLese(adresse) => return self.i2c_lese(adresse).map(Antwort::Byte),
+            Anfrage::I2cSchreibe { adresse, wert } => {
+                self.i2c_schreibe(adresse, wert)?;
+                self.i2c_adressen.insert(adresse);
+            },
+        }
+        Ok(Antwort::Ok)
+    }
+
+    #[cfg_attr(not(raspi), allow(unused_variables))]
+    fn i2c_lese(&mut self, adresse: i2c::Adresse) -> Result<u8, String> {
+        let zugriff = self.anschlüsse.i2c_zugriff(adresse.bus).map_err(fehler)?;
+        #[cfg(raspi)]
+        if let Some(i2c) = zugriff.i2c() {
+            let mut i2c_channel = i2c.lock().map_err(fehler)?;
+            i2c_channel.set_slave_address(adresse.adresse.into()).map_err(fehler)?;
+            let mut buf = [0; 1];
+            let _ = i2c_channel.read(&mut buf).map_err(fehler)?;
+            return Ok(buf[0]);
+        }
+        Err(fehler(Error::KeinRaspberryPi))
+    }
+
+    #[cfg_attr(not(raspi), allow(unused_variables))]
+    fn i2c_schreibe(&mut self, adresse: i2c::Adresse, wert: u8) -> Result<(), String> {
+        let zugriff = self.anschlüsse.i2c_zugriff(adresse.bus).map_err(fehler)?;
+        #[cfg(raspi)]
+        if let Some(i2c) = zugriff.i2c() {
+            let mut i2c_channel = i2c.lock().map_err(fehler)?;
+            i2c_channel.set_slave_address(adresse.adresse.into()).map_err(fehler)?;
+            let _ = i2c_channel.write(&[wert]).map_err(fehler)?;
+            return Ok(());
+        }
+        Err(fehler(Error::KeinRaspberryPi))
+    }
+
+    /// Versetze alle Ausgänge in einen sicheren Zustand und gebe alle Pins frei.
+    ///
+    /// Pwm-Pulse werden deaktiviert, Output-Pins als Input (hochohmig) konfiguriert
+    /// und alle Ports verwendeter Pcf8574 auf High (Zustand nach dem Einschalten) gesetzt.
+    fn sicherer_zustand(&mut self) {
+        let mut outputs = Vec::new();
+        for (pin, server_pin) in self.pins.drain() {
+            match server_pin {
+                ServerPin::Pwm(mut pwm_pin) => {
+                    if let Err(fehler) = pwm_pin.disable() {
+                        error!("Pwm an Gpio {} nicht deaktiviert: {:?}", pin, fehler)
+                    }
+                },
+                ServerPin::Output(_) => outputs.push(pin),
+                ServerPin::Reserviert(_) | ServerPin::Input(_) => {},
+            }
+        }
+        for pin in outputs {
+            match self.anschlüsse.reserviere_pin(pin) {
+                Ok(reserviert) => {
+                    // das Lesen fordert den Pin auch mit dem Cdev-Backend als Input an
+                    let _ = reserviert.into_input().read();
+                },
+                Err(fehler) => error!("Gpio {} nicht als Input konfiguriert: {:?}", pin, fehler),
+            }
+        }
+        for adresse in std::mem::take(&mut self.i2c_adressen) {
+            if let Err(fehler) = self.i2c_schreibe(adresse, 0xFF) {
+                error!("Pcf8574 {} nicht zurückgesetzt: {}", adresse, fehler)
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Bincode(bincode::Error),
+    /// Aktuell besteht keine Verbindung zum Server.
+    NichtVerbunden,
+    /// Der Server hat nicht rechtzeitig geantwortet.
+    KeineAntwort,
+    UnerwarteteAntwort(String),
+    /// Fehlermeldung des Servers.
+    Server(String),
+    KeinRaspberryPi,
+    PoisonError,
+}
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+impl From<bincode::Error> for Error {
+    fn from(error: bincode::Error) -> Self {
+        Error::Bincode(error)
+    }
+}
+impl<T> From<PoisonError<T>> for Error {
+    fn from(_: PoisonError<T>) -> Self {
+        Error::PoisonError
+    }
+}
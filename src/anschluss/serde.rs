@@ -2,7 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 
-use super::{Anschlüsse, Error};
+use super::{anschlüsse::Besitzer, Anschlüsse, Error};
 
 pub trait ToSave: Sized {
     type Save: Serialize + for<'de> Deserialize<'de> + Reserviere<Self>;
@@ -10,6 +10,13 @@ pub trait ToSave: Sized {
     fn to_save(&self) -> Self::Save;
 }
 
-pub trait Reserviere<R> {
+pub trait Reserviere<R>: Sized {
     fn reserviere(self, anschlüsse: &mut Anschlüsse) -> Result<R, Error>;
+
+    /// Reserviere und vermerke `besitzer` für alle dabei reservierten Anschlüsse.
+    fn reserviere_für(
+        self, anschlüsse: &mut Anschlüsse, besitzer: Besitzer
+    ) -> Result<R, Error> {
+        anschlüsse.mit_besitzer(besitzer, |anschlüsse| self.reserviere(anschlüsse))
+    }
 }
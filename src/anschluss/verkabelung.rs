@@ -0,0 +1,409 @@
+//! Dokumentation der Verkabelung aller Anschlüsse als Markdown, CSV oder TeX (TikZ).
+
+use std::fmt::{self, Display, Formatter, Write};
+use std::path::Path;
+
+use super::{i2c, pin::pwm, zuordnung::Kennung, InputSave, OutputSave, Polarität, Trigger};
+use crate::non_empty::NonEmpty;
+
+/// Richtung eines Anschlusses aus Sicht des Raspberry Pi.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Richtung {
+    Ausgang,
+    Eingang,
+    Pwm,
+}
+
+impl Display for Richtung {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Richtung::Ausgang => "Ausgang",
+                Richtung::Eingang => "Eingang",
+                Richtung::Pwm => "Pwm",
+            }
+        )
+    }
+}
+
+/// Verwendung eines Anschlusses, z.B. Richtung "Gerade" der Weiche "W3".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verbindung {
+    pub kennung: Kennung,
+    pub richtung: Richtung,
+    pub objekt: String,
+    pub funktion: String,
+    pub polarität: Option<Polarität>,
+    pub trigger: Option<Trigger>,
+}
+
+/// Angaben der Steuerung, zu der ein Anschluss gehört.
+#[derive(Debug, Clone)]
+pub struct Beschreibung {
+    objekt: String,
+    funktion: String,
+    polarität: Option<Polarität>,
+    trigger: Option<Trigger>,
+}
+
+impl Beschreibung {
+    pub fn neu(objekt: impl Into<String>) -> Self {
+        Beschreibung {
+            objekt: objekt.into(),
+            funktion: String::new(),
+            polarität: None,
+            trigger: None,
+        }
+    }
+
+    pub fn funktion(&self, funktion: impl Into<String>) -> Self {
+        Beschreibung { funktion: funktion.into(), ..self.clone() }
+    }
+
+    /// Polarität eines Pwm-Anschlusses, Output-Anschlüsse kennen ihre Polarität selbst.
+    pub fn polarität(&self, polarität: Polarität) -> Self {
+        Beschreibung { polarität: Some(polarität), ..self.clone() }
+    }
+
+    pub fn trigger(&self, trigger: Trigger) -> Self {
+        Beschreibung { trigger: Some(trigger), ..self.clone() }
+    }
+
+    fn verbindung(&self, kennung: Kennung, richtung: Richtung) -> Verbindung {
+        Verbindung {
+            kennung,
+            richtung,
+            objekt: self.objekt.clone(),
+            funktion: self.funktion.clone(),
+            polarität: self.polarität,
+            trigger: self.trigger,
+        }
+    }
+}
+
+/// Sammle alle verwendeten Anschlüsse.
+pub trait Verkabeln {
+    fn verkabeln(&self, beschreibung: &Beschreibung, verbindungen: &mut Vec<Verbindung>);
+}
+
+impl Verkabeln for OutputSave {
+    fn verkabeln(&self, beschreibung: &Beschreibung, verbindungen: &mut Vec<Verbindung>) {
+        let polarität = match *self {
+            OutputSave::Pin { polarität, .. }
+            | OutputSave::Pcf8574Port { polarität, .. }
+            | OutputSave::Mcp23017Port { polarität, .. } => polarität,
+        };
+        verbindungen
+            .push(beschreibung.polarität(polarität).verbindung(self.kennung(), Richtung::Ausgang))
+    }
+}
+
+impl Verkabeln for InputSave {
+    fn verkabeln(&self, beschreibung: &Beschreibung, verbindungen: &mut Vec<Verbindung>) {
+        let kennung = self.kennung();
+        verbindungen.push(beschreibung.verbindung(kennung, Richtung::Eingang));
+        let interrupt = match *self {
+            InputSave::Pin { .. } => None,
+            InputSave::Pcf8574Port { interrupt, .. }
+            | InputSave::Mcp23017Port { interrupt, .. } => interrupt,
+        };
+        if let Some(pin) = interrupt {
+            // Interrupt-Pins werden von allen Ports eines Bausteins geteilt.
+            let interrupt = Beschreibung::neu(baustein(&kennung)).funktion("Interrupt");
+            verbindungen.push(
+                interrupt
+                    .trigger(Trigger::FallingEdge)
+                    .verbindung(Kennung::Pin(pin), Richtung::Eingang),
+            )
+        }
+    }
+}
+
+impl Verkabeln for pwm::Save {
+    fn verkabeln(&self, beschreibung: &Beschreibung, verbindungen: &mut Vec<Verbindung>) {
+        verbindungen.push(beschreibung.verbindung(self.kennung(), Richtung::Pwm))
+    }
+}
+
+impl<T: Verkabeln> Verkabeln for Option<T> {
+    fn verkabeln(&self, beschreibung: &Beschreibung, verbindungen: &mut Vec<Verbindung>) {
+        if let Some(t) = self {
+            t.verkabeln(beschreibung, verbindungen)
+        }
+    }
+}
+
+impl<T: Verkabeln> Verkabeln for NonEmpty<T> {
+    /// Bei mehreren Anschlüssen wird die Funktion durchnummeriert.
+    fn verkabeln(&self, beschreibung: &Beschreibung, verbindungen: &mut Vec<Verbindung>) {
+        if self.len() == 1 {
+            return self.head().verkabeln(beschreibung, verbindungen);
+        }
+        for (index, t) in self.iter().enumerate() {
+            let funktion = format!("{} {}", beschreibung.funktion, index + 1);
+            t.verkabeln(&beschreibung.funktion(funktion), verbindungen)
+        }
+    }
+}
+
+/// Bezeichnung des Bausteins, an dem sich der Anschluss befindet.
+fn baustein(kennung: &Kennung) -> String {
+    match *kennung {
+        Kennung::Pin(_) => "Raspberry Pi".to_string(),
+        Kennung::Pcf8574Port(adresse, _) => {
+            format!("{} {}", i2c::Art::Pcf8574(adresse.variante), i2c::Adresse::from(adresse))
+        },
+        Kennung::Mcp23017Port(adresse, _) => {
+            format!("{} {}", i2c::Art::Mcp23017, i2c::Adresse::from(adresse))
+        },
+        Kennung::Pca9685Kanal(adresse, _) => {
+            format!("{} {}", i2c::Art::Pca9685, i2c::Adresse::from(adresse))
+        },
+    }
+}
+
+/// Bezeichnung des Anschlusses am Baustein.
+fn anschluss(kennung: &Kennung) -> String {
+    match *kennung {
+        Kennung::Pin(pin) => format!("Gpio {}", pin),
+        Kennung::Pcf8574Port(_, port) | Kennung::Mcp23017Port(_, port) => format!("Port {}", port),
+        Kennung::Pca9685Kanal(_, kanal) => format!("Kanal {}", kanal),
+    }
+}
+
+/// Sortierung nach Baustein (Raspberry Pi zuerst) und Anschluss.
+fn sortierung(kennung: &Kennung) -> (Option<i2c::Adresse>, u8) {
+    match *kennung {
+        Kennung::Pin(pin) => (None, pin),
+        Kennung::Pcf8574Port(adresse, port) => (Some(adresse.into()), port),
+        Kennung::Mcp23017Port(adresse, port) => (Some(adresse.into()), port),
+        Kennung::Pca9685Kanal(adresse, kanal) => (Some(adresse.into()), kanal),
+    }
+}
+
+/// Ausgabeformat der Verkabelung.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Markdown,
+    Csv,
+    Tex,
+}
+
+impl Format {
+    /// Bestimme das Format anhand der Dateiendung (`.md`, `.csv`, `.tex`).
+    pub fn für_pfad(pfad: impl AsRef<Path>) -> Option<Format> {
+        let endung = pfad.as_ref().extension()?.to_str()?.to_lowercase();
+        match endung.as_str() {
+            "md" => Some(Format::Markdown),
+            "csv" => Some(Format::Csv),
+            "tex" => Some(Format::Tex),
+            _ => None,
+        }
+    }
+}
+
+const SPALTEN: [&str; 6] = ["Anschluss", "Objekt", "Funktion", "Richtung", "Polarität", "Trigger"];
+
+/// Alle Verbindungen, sortiert nach Baustein und Anschluss.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verkabelung(Vec<Verbindung>);
+
+impl Verkabelung {
+    /// Sortiere `verbindungen`, doppelte Einträge (z.B. geteilte Interrupt-Pins) werden entfernt.
+    pub fn neu(mut verbindungen: Vec<Verbindung>) -> Self {
+        verbindungen.sort_by(|a, b| {
+            sortierung(&a.kennung)
+                .cmp(&sortierung(&b.kennung))
+                .then_with(|| a.objekt.cmp(&b.objekt))
+                .then_with(|| a.funktion.cmp(&b.funktion))
+        });
+        verbindungen.dedup();
+        Verkabelung(verbindungen)
+    }
+
+    pub fn verbindungen(&self) -> &[Verbindung] {
+        &self.0
+    }
+
+    /// Mehrfach verwendete Anschlüsse, z.B. nach dem Laden mit ignorierten Konflikten.
+    pub fn mehrfach_verwendet(&self) -> Vec<Kennung> {
+        let mut mehrfach: Vec<Kennung> = self
+            .0
+            .windows(2)
+            .filter(|paar| paar[0].kennung == paar[1].kennung)
+            .map(|paar| paar[0].kennung)
+            .collect();
+        mehrfach.dedup();
+        mehrfach
+    }
+
+    /// I2C-Adressen, die von verschiedenen Bausteinen (z.B. Pcf8574 und Mcp23017) verwendet werden.
+    pub fn adressen_mehrfach_verwendet(&self) -> Vec<i2c::Adresse> {
+        let mut bausteine: Vec<(i2c::Adresse, String)> = self
+            .0
+            .iter()
+            .filter_map(|verbindung| {
+                let (adresse, _anschluss) = sortierung(&verbindung.kennung);
+                adresse.map(|adresse| (adresse, baustein(&verbindung.kennung)))
+            })
+            .collect();
+        bausteine.sort();
+        bausteine.dedup();
+        let mut mehrfach: Vec<i2c::Adresse> = bausteine
+            .windows(2)
+            .filter(|paar| paar[0].0 == paar[1].0)
+            .map(|paar| paar[0].0)
+            .collect();
+        mehrfach.dedup();
+        mehrfach
+    }
+
+    /// Verbindungen gruppiert nach Baustein.
+    fn bausteine(&self) -> Vec<(String, Vec<&Verbindung>)> {
+        let mut bausteine: Vec<(String, Vec<&Verbindung>)> = Vec::new();
+        for verbindung in self.0.iter() {
+            let name = baustein(&verbindung.kennung);
+            match bausteine.last_mut() {
+                Some((letzter, verbindungen)) if *letzter == name => verbindungen.push(verbindung),
+                _ => bausteine.push((name, vec![verbindung])),
+            }
+        }
+        bausteine
+    }
+
+    pub fn formatiert(&self, format: Format, titel: &str) -> String {
+        match format {
+            Format::Markdown => self.markdown(titel),
+            Format::Csv => self.csv(),
+            Format::Tex => self.tex(titel),
+        }
+    }
+
+    fn zeile(verbindung: &Verbindung) -> [String; 6] {
+        let optional = |wert: Option<String>| wert.unwrap_or_default();
+        [
+            anschluss(&verbindung.kennung),
+            verbindung.objekt.clone(),
+            verbindung.funktion.clone(),
+            verbindung.richtung.to_string(),
+            optional(verbindung.polarität.map(|polarität| polarität.to_string())),
+            optional(verbindung.trigger.map(|trigger| trigger.to_string())),
+        ]
+    }
+
+    fn markdown(&self, titel: &str) -> String {
+        let escape = |text: &str| text.replace('|', "\\|");
+        let mut s = String::new();
+        let _ = writeln!(s, "# Verkabelung {}", escape(titel));
+        for (baustein, verbindungen) in self.bausteine() {
+            let _ = writeln!(s, "\n## {}\n", escape(&baustein));
+            let _ = writeln!(s, "| {} |", SPALTEN.join(" | "));
+            let _ = writeln!(s, "|{}", "---|".repeat(SPALTEN.len()));
+            for verbindung in verbindungen {
+                let zeile: Vec<_> =
+                    Self::zeile(verbindung).iter().map(|text| escape(text)).collect();
+                let _ = writeln!(s, "| {} |", zeile.join(" | "));
+            }
+        }
+        s
+    }
+
+    fn csv(&self) -> String {
+        let escape = |text: &str| {
+            if text.contains(&[',', '"', '\n'][..]) {
+                format!("\"{}\"", text.replace('"', "\"\""))
+            } else {
+                text.to_string()
+            }
+        };
+        let mut s = format!("Baustein,{}\n", SPALTEN.join(","));
+        for verbindung in self.0.iter() {
+            let baustein = baustein(&verbindung.kennung);
+            let zeile: Vec<_> = Self::zeile(verbindung).iter().map(|text| escape(text)).collect();
+            let _ = writeln!(s, "{},{}", escape(&baustein), zeile.join(","));
+        }
+        s
+    }
+
+    /// Tabelle und Anschluss-Diagramm pro Baustein, im Stil der `Schaltpläne/*.tex`.
+    fn tex(&self, titel: &str) -> String {
+        let mut s = String::new();
+        let _ = write!(
+            s,
+            "% !TeX encoding = UTF-8\n\
+             % !TeX spellcheck = de_DE\n\
+             % !TeX program = lualatex\n\
+             \\documentclass{{article}}\n\n\
+             \\usepackage[margin=1.5cm]{{geometry}}\n\
+             \\usepackage{{tikz}}\n\
+             \\usetikzlibrary{{circuits.ee.IEC}}\n\n\
+             \\title{{Verkabelung {}}}\n\
+             \\date{{\\today}}\n\n\
+             \\begin{{document}}\n\
+             \t\\maketitle\n",
+            tex_escape(titel)
+        );
+        for (baustein, verbindungen) in self.bausteine() {
+            let baustein = tex_escape(&baustein);
+            let _ = writeln!(s, "\t\\section*{{{}}}", baustein);
+            let _ = writeln!(s, "\t\\begin{{tabular}}{{{}}}", "l".repeat(SPALTEN.len()));
+            let _ = writeln!(s, "\t\t{} \\\\ \\hline", SPALTEN.join(" & "));
+            for verbindung in verbindungen.iter() {
+                let zeile: Vec<_> =
+                    Self::zeile(verbindung).iter().map(|text| tex_escape(text)).collect();
+                let _ = writeln!(s, "\t\t{} \\\\", zeile.join(" & "));
+            }
+            let _ = writeln!(s, "\t\\end{{tabular}}\n");
+            let höhe = verbindungen.len() as f32 * 0.5 + 0.5;
+            let _ = writeln!(
+                s,
+                "\t\\begin{{tikzpicture}}[circuit ee IEC, every info/.style={{font=\\scriptsize}}]"
+            );
+            let _ = writeln!(
+                s,
+                "\t\t\\node (baustein) [rectangle, draw, minimum width=2.5cm, minimum height={}cm] {{{}}};",
+                höhe, baustein
+            );
+            for (index, verbindung) in verbindungen.iter().enumerate() {
+                let beschriftung = if verbindung.funktion.is_empty() {
+                    tex_escape(&verbindung.objekt)
+                } else {
+                    format!(
+                        "{} ({})",
+                        tex_escape(&verbindung.objekt),
+                        tex_escape(&verbindung.funktion)
+                    )
+                };
+                let _ = writeln!(
+                    s,
+                    "\t\t\\draw (baustein.north east) ++(0, -{}) to ++(1, 0) node [contact, info={{{}}}] {{}} to ++(.5, 0) node [anchor=west] {{{}}};",
+                    index as f32 * 0.5 + 0.5,
+                    tex_escape(&anschluss(&verbindung.kennung)),
+                    beschriftung
+                );
+            }
+            let _ = writeln!(s, "\t\\end{{tikzpicture}}\n");
+        }
+        s.push_str("\\end{document}\n");
+        s
+    }
+}
+
+fn tex_escape(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => s.push_str("\\textbackslash{}"),
+            '~' => s.push_str("\\textasciitilde{}"),
+            '^' => s.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                s.push('\\');
+                s.push(c)
+            },
+            c => s.push(c),
+        }
+    }
+    s
+}
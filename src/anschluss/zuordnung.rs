@@ -0,0 +1,334 @@
+//! Zuordnung bisheriger zu neuen Anschlüssen, z.B. nach einem Umbau der Hardware.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use super::{
+    i2c::{self, Bus},
+    mcp23017, pca9685, pcf8574,
+    pin::pwm,
+    InputSave, Level, OutputSave,
+};
+use crate::non_empty::NonEmpty;
+
+/// Eindeutige Bezeichnung eines Anschlusses, unabhängig von seiner Konfiguration.
+///
+/// Textform: `pin:17` (oder `17`), `pcf8574:0x21:3`, `mcp23017:i2c-3:0x20:12`, `pca9685:0x40:5`.
+/// Der I2C-Bus kann weggelassen werden, dann wird der Standard-Bus verwendet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kennung {
+    Pin(u8),
+    Pcf8574Port(pcf8574::Adresse, u8),
+    Mcp23017Port(mcp23017::Adresse, u8),
+    Pca9685Kanal(pca9685::Adresse, u8),
+}
+
+impl Display for Kennung {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (art, adresse, port): (_, i2c::Adresse, _) = match *self {
+            Kennung::Pin(pin) => return write!(f, "pin:{}", pin),
+            Kennung::Pcf8574Port(adresse, port) => ("pcf8574", adresse.into(), port),
+            Kennung::Mcp23017Port(adresse, port) => ("mcp23017", adresse.into(), port),
+            Kennung::Pca9685Kanal(adresse, kanal) => ("pca9685", adresse.into(), kanal),
+        };
+        write!(f, "{}:", art)?;
+        adresse.bus.präfix(f)?;
+        write!(f, "{:#04x}:{}", adresse.adresse, port)
+    }
+}
+
+fn parse_zahl(s: &str) -> Result<u8, String> {
+    let s = s.trim();
+    let ergebnis = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    ergebnis.map_err(|_| format!("Ungültige Zahl: {}", s))
+}
+
+fn parse_bus(s: &str) -> Result<Bus, String> {
+    let nummer = parse_zahl(s.trim().strip_prefix("i2c-").unwrap_or(s))?;
+    if nummer > Bus::MAXIMUM {
+        return Err(format!("Ungültiger I2C-Bus: {}", s));
+    }
+    Ok(Bus(nummer))
+}
+
+/// Adress-Bits (a0, a1, a2) zur Differenz einer I2C-Adresse zur Basis-Adresse.
+fn adress_bits(offset: u8) -> (Level, Level, Level) {
+    let level = |bit: u8| if offset & bit == 0 { Level::Low } else { Level::High };
+    (level(0b001), level(0b010), level(0b100))
+}
+
+impl FromStr for Kennung {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let teile: Vec<_> = s.trim().split(':').map(str::trim).collect();
+        let (art, bus, adresse, port) = match teile.as_slice() {
+            [pin] => return parse_zahl(pin).map(Kennung::Pin),
+            [art, pin] if ["pin", "gpio"].contains(&art.to_lowercase().as_str()) => {
+                return parse_zahl(pin).map(Kennung::Pin)
+            }
+            [art, adresse, port] => (art, Bus::STANDARD, adresse, port),
+            [art, bus, adresse, port] => (art, parse_bus(bus)?, adresse, port),
+            _ => return Err(format!("Ungültiger Anschluss: {}", s)),
+        };
+        let adresse = parse_zahl(adresse)?;
+        let port = parse_zahl(port)?;
+        let ungültig = || format!("Ungültige Adresse für {}: {:#04x}", art, adresse);
+        let (kennung, i2c_art) = match art.to_lowercase().as_str() {
+            "pcf8574" => {
+                let variante = [pcf8574::Variante::Normal, pcf8574::Variante::A]
+                    .iter()
+                    .copied()
+                    .find(|variante| i2c::Art::Pcf8574(*variante).adressen().contains(&adresse))
+                    .ok_or_else(ungültig)?;
+                let basis = *i2c::Art::Pcf8574(variante).adressen().start();
+                let (a0, a1, a2) = adress_bits(adresse - basis);
+                let adresse = pcf8574::Adresse { bus, a0, a1, a2, variante };
+                (Kennung::Pcf8574Port(adresse, port), i2c::Art::Pcf8574(variante))
+            }
+            "mcp23017" => {
+                let adressen = i2c::Art::Mcp23017.adressen();
+                if !adressen.contains(&adresse) {
+                    return Err(ungültig());
+                }
+                let (a0, a1, a2) = adress_bits(adresse - adressen.start());
+                let adresse = mcp23017::Adresse { bus, a0, a1, a2 };
+                (Kennung::Mcp23017Port(adresse, port), i2c::Art::Mcp23017)
+            }
+            "pca9685" => {
+                let adressen = i2c::Art::Pca9685.adressen();
+                if !adressen.contains(&adresse) {
+                    return Err(ungültig());
+                }
+                let adresse = pca9685::Adresse { bus, offset: adresse - adressen.start() };
+                (Kennung::Pca9685Kanal(adresse, port), i2c::Art::Pca9685)
+            }
+            _ => return Err(format!("Unbekannter Baustein: {}", art)),
+        };
+        if port >= i2c_art.anzahl_ports() {
+            return Err(format!("Ungültiger Port für {}: {}", i2c_art, port));
+        }
+        Ok(kennung)
+    }
+}
+
+impl OutputSave {
+    pub fn kennung(&self) -> Kennung {
+        match *self {
+            OutputSave::Pin { pin, .. } => Kennung::Pin(pin),
+            OutputSave::Pcf8574Port { bus, a0, a1, a2, variante, port, .. } => {
+                Kennung::Pcf8574Port(pcf8574::Adresse { bus, a0, a1, a2, variante }, port)
+            }
+            OutputSave::Mcp23017Port { bus, a0, a1, a2, port, .. } => {
+                Kennung::Mcp23017Port(mcp23017::Adresse { bus, a0, a1, a2 }, port)
+            }
+        }
+    }
+}
+
+impl InputSave {
+    pub fn kennung(&self) -> Kennung {
+        match *self {
+            InputSave::Pin { pin } => Kennung::Pin(pin),
+            InputSave::Pcf8574Port { bus, a0, a1, a2, variante, port, .. } => {
+                Kennung::Pcf8574Port(pcf8574::Adresse { bus, a0, a1, a2, variante }, port)
+            }
+            InputSave::Mcp23017Port { bus, a0, a1, a2, port, .. } => {
+                Kennung::Mcp23017Port(mcp23017::Adresse { bus, a0, a1, a2 }, port)
+            }
+        }
+    }
+}
+
+impl pwm::Save {
+    pub fn kennung(&self) -> Kennung {
+        match *self {
+            pwm::Save::Pin(pin) => Kennung::Pin(pin),
+            pwm::Save::Pca9685(pca9685::Save { adresse, kanal, .. }) => {
+                Kennung::Pca9685Kanal(adresse, kanal)
+            }
+        }
+    }
+}
+
+/// Tabelle mit neuen Anschlüssen für bisher verwendete Anschlüsse.
+///
+/// Alle Einträge werden gleichzeitig angewendet, zwei Anschlüsse können also getauscht werden.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Zuordnung(Vec<(Kennung, Kennung)>);
+
+impl Zuordnung {
+    /// Erzeuge eine Zuordnung, jeder bisherige Anschluss darf nur einmal vorkommen.
+    pub fn neu(einträge: Vec<(Kennung, Kennung)>) -> Result<Self, String> {
+        for (index, (quelle, _ziel)) in einträge.iter().enumerate() {
+            if einträge[..index].iter().any(|(andere, _ziel)| andere == quelle) {
+                return Err(format!("{} ist mehrfach zugeordnet.", quelle));
+            }
+        }
+        Ok(Zuordnung(einträge))
+    }
+
+    pub fn einträge(&self) -> &[(Kennung, Kennung)] {
+        &self.0
+    }
+
+    pub fn ist_leer(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Der neue Anschluss für `quelle`, sofern er geändert werden soll.
+    pub fn ziel(&self, quelle: &Kennung) -> Option<Kennung> {
+        self.0.iter().find(|(andere, _ziel)| andere == quelle).map(|(_quelle, ziel)| *ziel)
+    }
+
+    /// Neuer Interrupt-Pin, ein Gpio-Pin kann nur einem Gpio-Pin zugeordnet werden.
+    fn interrupt_pin(&self, pin: u8) -> Result<u8, Error> {
+        match self.ziel(&Kennung::Pin(pin)) {
+            None => Ok(pin),
+            Some(Kennung::Pin(neu)) => Ok(neu),
+            Some(ziel) => Err(Error::UngültigesZiel { quelle: Kennung::Pin(pin), ziel }),
+        }
+    }
+}
+
+impl Display for Zuordnung {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (quelle, ziel) in self.0.iter() {
+            writeln!(f, "{} = {}", quelle, ziel)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Zuordnung {
+    type Err = String;
+
+    /// Einträge der Form `<bisher> = <neu>`, getrennt durch Zeilenumbrüche oder Kommas.
+    /// Leere Einträge und Kommentare (beginnend mit `#`) werden ignoriert.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut einträge = Vec::new();
+        for zeile in s.lines() {
+            let zeile = zeile.split('#').next().unwrap_or("");
+            for eintrag in zeile.split(',').map(str::trim).filter(|eintrag| !eintrag.is_empty()) {
+                let (quelle, ziel) = eintrag
+                    .split_once('=')
+                    .ok_or_else(|| format!("Erwarte '<bisher> = <neu>': {}", eintrag))?;
+                einträge.push((quelle.parse()?, ziel.parse()?));
+            }
+        }
+        Zuordnung::neu(einträge)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Error {
+    /// Der neue Anschluss kann nicht für den bisherigen verwendet werden,
+    /// z.B. ein Pca9685-Kanal statt eines Output-Anschlusses.
+    UngültigesZiel { quelle: Kennung, ziel: Kennung },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::UngültigesZiel { quelle, ziel } => {
+                write!(f, "{} kann nicht durch {} ersetzt werden.", quelle, ziel)
+            }
+        }
+    }
+}
+
+/// Ersetze Anschlüsse gemäß einer `Zuordnung`.
+pub trait Zuordnen {
+    /// Gibt die Anzahl der geänderten Anschlüsse zurück.
+    fn zuordnen(&mut self, zuordnung: &Zuordnung) -> Result<usize, Error>;
+}
+
+impl Zuordnen for OutputSave {
+    fn zuordnen(&mut self, zuordnung: &Zuordnung) -> Result<usize, Error> {
+        let quelle = self.kennung();
+        let ziel = match zuordnung.ziel(&quelle) {
+            Some(ziel) => ziel,
+            None => return Ok(0),
+        };
+        let polarität = match *self {
+            OutputSave::Pin { polarität, .. }
+            | OutputSave::Pcf8574Port { polarität, .. }
+            | OutputSave::Mcp23017Port { polarität, .. } => polarität,
+        };
+        *self = match ziel {
+            Kennung::Pin(pin) => OutputSave::Pin { pin, polarität },
+            Kennung::Pcf8574Port(pcf8574::Adresse { bus, a0, a1, a2, variante }, port) => {
+                OutputSave::Pcf8574Port { bus, a0, a1, a2, variante, port, polarität }
+            }
+            Kennung::Mcp23017Port(mcp23017::Adresse { bus, a0, a1, a2 }, port) => {
+                OutputSave::Mcp23017Port { bus, a0, a1, a2, port, polarität }
+            }
+            Kennung::Pca9685Kanal(..) => return Err(Error::UngültigesZiel { quelle, ziel }),
+        };
+        Ok(1)
+    }
+}
+
+impl Zuordnen for InputSave {
+    fn zuordnen(&mut self, zuordnung: &Zuordnung) -> Result<usize, Error> {
+        let (interrupt, pull_up) = match *self {
+            InputSave::Pin { .. } => (None, false),
+            InputSave::Pcf8574Port { interrupt, .. } => (interrupt, false),
+            InputSave::Mcp23017Port { interrupt, pull_up, .. } => (interrupt, pull_up),
+        };
+        let neuer_interrupt = interrupt.map(|pin| zuordnung.interrupt_pin(pin)).transpose()?;
+        let mut geändert = if neuer_interrupt != interrupt { 1 } else { 0 };
+        let quelle = self.kennung();
+        let ziel = zuordnung.ziel(&quelle).unwrap_or(quelle);
+        if ziel != quelle {
+            geändert += 1;
+        }
+        let interrupt = neuer_interrupt;
+        *self = match ziel {
+            Kennung::Pin(pin) => InputSave::Pin { pin },
+            Kennung::Pcf8574Port(pcf8574::Adresse { bus, a0, a1, a2, variante }, port) => {
+                InputSave::Pcf8574Port { bus, a0, a1, a2, variante, port, interrupt }
+            }
+            Kennung::Mcp23017Port(mcp23017::Adresse { bus, a0, a1, a2 }, port) => {
+                InputSave::Mcp23017Port { bus, a0, a1, a2, port, pull_up, interrupt }
+            }
+            Kennung::Pca9685Kanal(..) => return Err(Error::UngültigesZiel { quelle, ziel }),
+        };
+        Ok(geändert)
+    }
+}
+
+impl Zuordnen for pwm::Save {
+    fn zuordnen(&mut self, zuordnung: &Zuordnung) -> Result<usize, Error> {
+        let quelle = self.kennung();
+        // die Frequenz bleibt beim Wechsel zwischen Pca9685-Kanälen erhalten
+        let frequenz = match self {
+            pwm::Save::Pin(_) => None,
+            pwm::Save::Pca9685(pca9685::Save { frequenz, .. }) => *frequenz,
+        };
+        *self = match zuordnung.ziel(&quelle) {
+            None => return Ok(0),
+            Some(Kennung::Pin(pin)) => pwm::Save::Pin(pin),
+            Some(Kennung::Pca9685Kanal(adresse, kanal)) => {
+                pwm::Save::Pca9685(pca9685::Save { adresse, kanal, frequenz })
+            }
+            Some(ziel) => return Err(Error::UngültigesZiel { quelle, ziel }),
+        };
+        Ok(1)
+    }
+}
+
+impl<T: Zuordnen> Zuordnen for Option<T> {
+    fn zuordnen(&mut self, zuordnung: &Zuordnung) -> Result<usize, Error> {
+        self.as_mut().map_or(Ok(0), |t| t.zuordnen(zuordnung))
+    }
+}
+
+impl<T: Zuordnen> Zuordnen for NonEmpty<T> {
+    fn zuordnen(&mut self, zuordnung: &Zuordnung) -> Result<usize, Error> {
+        self.iter_mut().try_fold(0, |summe, t| Ok(summe + t.zuordnen(zuordnung)?))
+    }
+}
@@ -18,23 +18,41 @@ use self::streckenabschnitt::Streckenabschnitt;
 use self::style::*;
 pub use self::typen::*;
 use crate::{
-    anschluss::{anschlüsse::Anschlüsse, OutputAnschluss, OutputSave, Reserviere, ToSave},
+    anschluss::{
+        anschlüsse::{Anschlüsse, Besitzer, Reservierungen},
+        verkabelung::Verkabeln,
+        zuordnung::{Zuordnen, Zuordnung},
+        Fließend, InputAnschluss, InputSave, Level, OutputAnschluss, OutputSave, Reserviere,
+        ToSave, Trigger,
+    },
     farbe::Farbe,
     lookup::Lookup,
-    steuerung,
+    steuerung::{self, kontakt::Kontakt},
 };
 
 pub mod anschluss;
+pub mod drehregler;
 pub mod farbwahl;
 pub mod geschwindigkeit;
 pub mod gleis;
+pub mod hardware;
+pub mod headless;
 pub mod icon;
+pub mod importieren;
+pub mod kanal;
 pub(crate) mod macros;
+pub mod mqtt;
+pub mod netzwerk;
+pub mod regel;
+pub mod skript;
+pub mod stellpult;
 pub mod streckenabschnitt;
 pub mod style;
 mod touch_canvas;
 pub mod typen;
+pub mod web;
 pub mod weiche;
+pub mod zuordnung;
 
 #[derive(zugkontrolle_derive::Debug, zugkontrolle_derive::Clone)]
 pub enum AnyGleis<Z> {
@@ -139,7 +157,10 @@ where
     StreckenabschnittFestlegen(bool),
     Speichern,
     Laden,
+    /// Lade trotz Konflikten, die betroffenen Steuerungen bleiben ohne Anschluss.
+    LadenTrotzdem,
     Pfad(String),
+    ProfilPfad(String),
     GeschwindigkeitAnzeige {
         name: geschwindigkeit::Name,
         nachricht: <Z::Leiter as LeiterAnzeige>::Message,
@@ -187,6 +208,48 @@ where
         >,
     ),
     FahrenAktion(AnyId<Z>),
+    Nothalt,
+    NothaltAufheben,
+    /// Die Verbindung zum entfernten Raspberry Pi wurde nach einem Abbruch wiederhergestellt.
+    Wiederverbunden,
+    ZeigeAuswahlStellpult,
+    HinzufügenStellpultTaster(stellpult::Name, stellpult::TasterSave),
+    LöscheStellpultTaster(stellpult::Name),
+    StellpultAktion(stellpult::Aktion),
+    ZeigeAuswahlDrehregler,
+    HinzufügenDrehregler(geschwindigkeit::Name, drehregler::DrehreglerSave),
+    LöscheDrehregler(geschwindigkeit::Name),
+    DrehreglerWert(geschwindigkeit::Name, u8),
+    ZeigeHardware,
+    HardwareScannen,
+    ZeigeZuordnung,
+    Zuordnen(Zuordnung),
+    /// Ordne trotz Konflikten zu, die betroffenen Steuerungen bleiben ohne Anschluss.
+    ZuordnenTrotzdem(Zuordnung),
+    ZeigeImportieren,
+    /// Lese die offenen Anschlusspunkte einer Datei für die Auswahl beim Importieren.
+    ImportierenLesen(String),
+    Importieren(importieren::Einstellungen),
+    /// Importiere trotz Konflikten, die betroffenen Steuerungen bleiben ohne Anschluss.
+    ImportierenTrotzdem(importieren::Einstellungen),
+    ZeigeAuswahlSkripte,
+    /// Lese den Quelltext eines Skriptes aus einer Datei.
+    HinzufügenSkript(skript::Name, String),
+    LöscheSkript(skript::Name),
+    StarteSkript(skript::Name),
+    StoppeSkript(skript::Name),
+    SkriptBeendet(skript::Name, skript::Ende),
+    ZeigeAuswahlRegeln,
+    HinzufügenRegel(regel::Name, regel::Regel),
+    LöscheRegel(regel::Name),
+    /// Der Takt einer Regel ist abgelaufen.
+    RegelTakt(regel::Name),
+    /// Eine verzögerte Aktion einer Regel ist fällig.
+    RegelAktion(regel::Name, regel::Aktion),
+    /// Der Anfahrimpuls einer Geschwindigkeit ist beendet.
+    AnfahrimpulsEnde(geschwindigkeit::Name),
+    Kontakt(steuerung::kontakt::Name, Level),
+    Netzwerk(netzwerk::Anfrage),
 }
 
 impl<Z> From<gleise::Message<Z>> for Message<Z>
@@ -250,6 +313,13 @@ where
 {
     Streckenabschnitt(streckenabschnitt::AuswahlStatus),
     Geschwindigkeit(geschwindigkeit::AuswahlStatus),
+    Stellpult(stellpult::AuswahlStatus),
+    Drehregler(drehregler::AuswahlStatus),
+    Hardware(hardware::Status),
+    Zuordnung(zuordnung::Status),
+    Importieren(importieren::Status),
+    Skripte(skript::AuswahlStatus),
+    Regeln(regel::AuswahlStatus),
     Weiche(
         weiche::Status<
             gleis::weiche::gerade::RichtungAnschlüsseSave,
@@ -294,11 +364,84 @@ where
     ),
 }
 
+impl<Z> Modal<Z>
+where
+    Z: Zugtyp,
+    <<Z as Zugtyp>::Leiter as ToSave>::Save: Debug + Clone,
+{
+    /// Aktualisiere die Hinweise auf bereits reservierte Anschlüsse.
+    fn setze_reservierungen(&mut self, reservierungen: &Reservierungen) {
+        match self {
+            Modal::Streckenabschnitt(status) => status.setze_reservierungen(reservierungen),
+            Modal::Geschwindigkeit(status) => status.setze_reservierungen(reservierungen),
+            Modal::Stellpult(status) => status.setze_reservierungen(reservierungen),
+            Modal::Drehregler(_)
+            | Modal::Hardware(_)
+            | Modal::Zuordnung(_)
+            | Modal::Importieren(_)
+            | Modal::Skripte(_)
+            | Modal::Regeln(_) => {},
+            Modal::Weiche(status, _) => {
+                status.setze_reservierungen::<gleis::weiche::gerade::Richtung>(reservierungen)
+            },
+            Modal::DreiwegeWeiche(status, _) => {
+                status.setze_reservierungen::<gleis::weiche::dreiwege::Richtung>(reservierungen)
+            },
+            Modal::KurvenWeiche(status, _) => {
+                status.setze_reservierungen::<gleis::weiche::kurve::Richtung>(reservierungen)
+            },
+        }
+    }
+}
+
+/// Aktion, die trotz Konflikten zwischen Anschlüssen ausgeführt werden kann.
+#[derive(Debug, Clone)]
+enum Trotzdem {
+    Laden,
+    Zuordnen(Zuordnung),
+    Importieren(importieren::Einstellungen),
+}
+
+impl Trotzdem {
+    fn beschriftung(&self) -> &'static str {
+        match self {
+            Trotzdem::Laden => "Trotzdem laden",
+            Trotzdem::Zuordnen(_) => "Trotzdem zuordnen",
+            Trotzdem::Importieren(_) => "Trotzdem importieren",
+        }
+    }
+
+    fn message<Z>(&self) -> Message<Z>
+    where
+        Z: Zugtyp,
+        <<Z as Zugtyp>::Leiter as ToSave>::Save: Debug + Clone,
+    {
+        match self {
+            Trotzdem::Laden => Message::LadenTrotzdem,
+            Trotzdem::Zuordnen(zuordnung) => Message::ZuordnenTrotzdem(zuordnung.clone()),
+            Trotzdem::Importieren(einstellungen) => {
+                Message::ImportierenTrotzdem(einstellungen.clone())
+            },
+        }
+    }
+}
+
+/// Konflikte beim Ersetzen des Zustands, siehe [Zugkontrolle::ersetze_zustand].
+#[derive(Debug)]
+struct ErsetzenKonflikte {
+    /// Konflikte zwischen den Anschlüssen des neuen Zustands.
+    neu: Vec<Konflikt>,
+    /// Steuerungen des bisherigen Zustands, die ihren Anschluss verloren haben.
+    verloren: Vec<Konflikt>,
+}
+
 #[derive(Debug)]
 struct MessageBox {
     titel: String,
     nachricht: String,
     button_state: iced::button::State,
+    /// Angezeigt bei Konflikten beim Laden, Zuordnen oder Importieren.
+    trotzdem: Option<(Trotzdem, iced::button::State)>,
 }
 
 pub struct Zugkontrolle<Z>
@@ -336,6 +479,37 @@ where
     laden: iced::button::State,
     pfad: iced::text_input::State,
     aktueller_pfad: String,
+    profil_pfad: iced::text_input::State,
+    /// Hardware-Profil mit den Anschlüssen, leer wenn sie zusammen mit dem Gleisplan gespeichert werden.
+    aktueller_profil_pfad: String,
+    nothalt: bool,
+    nothalt_button_state: iced::button::State,
+    /// Muss gespeichert werden, damit der Interrupt-Callback aktiv bleibt.
+    #[allow(dead_code)]
+    nothalt_taster: Option<InputAnschluss>,
+    kanal: kanal::Kanal<Message<Z>>,
+    stellpult: stellpult::Map,
+    stellpult_button_state: iced::button::State,
+    drehregler: drehregler::Map,
+    drehregler_button_state: iced::button::State,
+    hardware_button_state: iced::button::State,
+    zuordnung_button_state: iced::button::State,
+    importieren_button_state: iced::button::State,
+    skripte_aktiv: BTreeMap<skript::Name, skript::Ausführung>,
+    /// Werden nach dem nächsten erfolgreichen Laden gestartet.
+    skripte_nach_laden: Vec<skript::Name>,
+    skripte_button_state: iced::button::State,
+    regeln_button_state: iced::button::State,
+    regel_takte: BTreeMap<regel::Name, regel::Zeitgeber>,
+    regel_verzögerungen: Vec<regel::Zeitgeber>,
+    /// Nach Ablauf wird die eingestellte Geschwindigkeit wiederhergestellt.
+    anfahrimpulse: BTreeMap<geschwindigkeit::Name, regel::Zeitgeber>,
+    /// Zustand bei der letzten Auswertung der Regeln, um Änderungen zu erkennen.
+    regel_zustand: netzwerk::Zustand,
+    /// Zuletzt angewendete Zuordnung, Vorgabe für die nächste Eingabe.
+    letzte_zuordnung: Zuordnung,
+    netzwerk: Option<netzwerk::Server>,
+    mqtt: Option<mqtt::Brücke>,
     // TODO Wegstrecke, Plan
 }
 
@@ -346,12 +520,60 @@ where
     <<Z as Zugtyp>::Leiter as ToSave>::Save: Debug + Clone,
 {
     fn zeige_message_box(&mut self, titel_arg: String, nachricht_arg: String) {
-        let MessageBox { titel, nachricht, .. } = self.message_box.inner_mut();
+        let MessageBox { titel, nachricht, trotzdem, .. } = self.message_box.inner_mut();
         *titel = titel_arg;
         *nachricht = nachricht_arg;
+        *trotzdem = None;
         self.message_box.show(true)
     }
 
+    /// Zeige alle Konflikte und biete an, die Aktion trotzdem auszuführen.
+    fn zeige_konflikte(&mut self, titel: String, konflikte: ErsetzenKonflikte, trotzdem: Trotzdem) {
+        let ErsetzenKonflikte { neu, verloren } = konflikte;
+        let mut nachricht: Vec<_> = neu.iter().map(ToString::to_string).collect();
+        if verloren.is_empty() {
+            nachricht.push("Der bisherige Zustand wurde beibehalten.".to_string());
+        } else {
+            nachricht.push(
+                "Der bisherige Zustand konnte nicht vollständig wiederhergestellt werden, \
+                ohne Anschluss:"
+                    .to_string(),
+            );
+            nachricht.extend(verloren.iter().map(ToString::to_string));
+        }
+        nachricht.push(format!(
+            "Mit \"{}\" bleiben die betroffenen Steuerungen ohne Anschluss.",
+            trotzdem.beschriftung()
+        ));
+        self.zeige_message_box(titel, nachricht.join("\n"));
+        self.message_box.inner_mut().trotzdem = Some((trotzdem, iced::button::State::new()));
+    }
+
+    /// Gespeicherte Form von Geschwindigkeiten, Stellpult und Drehreglern.
+    fn steuerungen_save(
+        &self,
+    ) -> (
+        steuerung::geschwindigkeit::Map<<Z::Leiter as ToSave>::Save>,
+        stellpult::Map<InputSave>,
+        steuerung::drehregler::MapSave,
+    ) {
+        let geschwindigkeiten = self
+            .geschwindigkeiten
+            .iter()
+            .map(|(name, (geschwindigkeit, _anzeige_status))| {
+                (name.clone(), geschwindigkeit.to_save())
+            })
+            .collect();
+        let stellpult =
+            self.stellpult.iter().map(|(name, taster)| (name.clone(), taster.to_save())).collect();
+        let drehregler = self
+            .drehregler
+            .iter()
+            .map(|(name, drehregler)| (name.clone(), drehregler.to_save()))
+            .collect();
+        (geschwindigkeiten, stellpult, drehregler)
+    }
+
     /// Icon für das Fenster.
     pub fn icon() -> iced::window::Icon {
         icon::icon()
@@ -475,160 +697,1106 @@ where
             )
         }
     }
-}
 
-impl<Z> Zugkontrolle<Z>
-where
-    Z: 'static + Zugtyp + Debug + PartialEq + for<'de> Deserialize<'de>,
-    Z::Leiter: LeiterAnzeige,
-    <<Z as Zugtyp>::Leiter as ToSave>::Save: Debug + Clone,
-{
-    fn laden(&mut self) {
-        match self.gleise.laden(&mut self.anschlüsse, &self.aktueller_pfad) {
-            Ok(geschwindigkeiten) => {
-                self.geschwindigkeiten = geschwindigkeiten
-                    .into_iter()
-                    .map(|(name, geschwindigkeit)| {
-                        (name, (geschwindigkeit, Z::Leiter::anzeige_status_neu()))
-                    })
-                    .collect();
-                self.streckenabschnitt_aktuell.aktuell = None;
-            }
-            Err(err) => self.zeige_message_box(
-                format!("Fehler beim Laden von {}", self.aktueller_pfad),
-                format!("{:?}", err),
+    /// Führe die Fahren-Aktion für ein Gleis aus (Weiche stellen, Streckenabschnitt umschalten).
+    fn fahren_aktion(&mut self, any_id: AnyId<Z>) {
+        match any_id {
+            AnyId::Gerade(id) => self.streckenabschnitt_umschalten("Gerade", id),
+            AnyId::Kurve(id) => self.streckenabschnitt_umschalten("Kurve", id),
+            AnyId::Weiche(id) => self.weiche_stellen(
+                "Weiche",
+                id,
+                Gleise::steuerung_weiche,
+                |aktuelle_richtung, _letzte_richtung| {
+                    use gleis::weiche::gerade::Richtung;
+                    if aktuelle_richtung == &Richtung::Gerade {
+                        Richtung::Kurve
+                    } else {
+                        Richtung::Gerade
+                    }
+                },
+            ),
+            AnyId::DreiwegeWeiche(id) => self.weiche_stellen(
+                "DreiwegeWeiche",
+                id,
+                Gleise::steuerung_dreiwege_weiche,
+                |aktuelle_richtung, letzte_richtung| {
+                    use gleis::weiche::dreiwege::Richtung;
+                    if aktuelle_richtung == &Richtung::Gerade {
+                        if letzte_richtung == &Richtung::Links {
+                            Richtung::Rechts
+                        } else {
+                            Richtung::Links
+                        }
+                    } else {
+                        Richtung::Gerade
+                    }
+                },
+            ),
+            AnyId::KurvenWeiche(id) => self.weiche_stellen(
+                "KurvenWeiche",
+                id,
+                Gleise::steuerung_kurven_weiche,
+                |aktuelle_richtung, _letzte_richtung| {
+                    use gleis::weiche::kurve::Richtung;
+                    if aktuelle_richtung == &Richtung::Außen {
+                        Richtung::Innen
+                    } else {
+                        Richtung::Außen
+                    }
+                },
+            ),
+            AnyId::SKurvenWeiche(id) => self.weiche_stellen(
+                "SKurvenWeiche",
+                id,
+                Gleise::steuerung_s_kurven_weiche,
+                |aktuelle_richtung, _letzte_richtung| {
+                    use gleis::weiche::gerade::Richtung;
+                    if aktuelle_richtung == &Richtung::Gerade {
+                        Richtung::Kurve
+                    } else {
+                        Richtung::Gerade
+                    }
+                },
+            ),
+            AnyId::Kreuzung(id) => self.weiche_stellen(
+                "Kreuzung",
+                id,
+                Gleise::steuerung_kreuzung,
+                |aktuelle_richtung, _letzte_richtung| {
+                    use gleis::weiche::gerade::Richtung;
+                    if aktuelle_richtung == &Richtung::Gerade {
+                        Richtung::Kurve
+                    } else {
+                        Richtung::Gerade
+                    }
+                },
             ),
         }
     }
-}
 
-impl<Z> iced::Application for Zugkontrolle<Z>
-where
-    Z: 'static + Zugtyp + Debug + PartialEq + Serialize + for<'de> Deserialize<'de> + Send + Sync,
-    Z::Leiter: Debug,
-    <<Z as Zugtyp>::Leiter as ToSave>::Save: Debug + Clone + Send,
-    <<Z as Zugtyp>::Leiter as LeiterAnzeige>::Fahrtrichtung: Debug,
-{
-    type Executor = iced::executor::Default;
-    type Flags = (Anschlüsse, Option<String>, Option<Modus>);
-    type Message = Message<Z>;
+    /// Alle Aktionen, die einem Stellpult-Taster zugewiesen werden können.
+    fn stellpult_aktionen(&self) -> Vec<stellpult::Aktion> {
+        let weichen =
+            self.gleise.weichen_namen().into_iter().map(stellpult::Aktion::WeicheUmschalten);
+        let streckenabschnitte =
+            self.gleise.streckenabschnitte().map(|(name, _streckenabschnitt)| {
+                stellpult::Aktion::StreckenabschnittUmschalten(name.clone())
+            });
+        let geschwindigkeiten = self
+            .geschwindigkeiten
+            .keys()
+            .map(|name| stellpult::Aktion::GeschwindigkeitUmdrehen(name.clone()));
+        weichen.chain(streckenabschnitte).chain(geschwindigkeiten).collect()
+    }
 
-    fn new(
-        (anschlüsse, pfad_arg, modus_arg): Self::Flags
-    ) -> (Self, iced::Command<Self::Message>) {
-        let mut gleise = Gleise::neu();
-        if let Some(modus) = modus_arg {
-            gleise.moduswechsel(modus)
+    /// Reserviere und aktiviere einen Taster, bevor der bisherige Taster `bisher` freigegeben wird.
+    ///
+    /// Verwendet der neue Taster den gleichen Anschluss, werden nur Trigger und Aktion übernommen.
+    /// Bei einem Fehler wird der bisherige Taster (weiterhin aktiv) zurückgegeben.
+    fn ersetze_stellpult_taster(
+        &mut self,
+        name: &stellpult::Name,
+        bisher: Option<stellpult::Taster>,
+        taster_save: stellpult::TasterSave,
+    ) -> Result<stellpult::Taster, (Option<stellpult::Taster>, String)> {
+        match bisher {
+            Some(mut bisher) if bisher.anschluss.to_save() == taster_save.anschluss => {
+                if let Err(error) = bisher.clear_async_interrupt() {
+                    error!("Interrupt für Taster {} nicht entfernt: {:?}", name.0, error)
+                }
+                let trigger = std::mem::replace(&mut bisher.trigger, taster_save.trigger);
+                let aktion = std::mem::replace(&mut bisher.aktion, taster_save.aktion);
+                match aktiviere_stellpult_taster(&mut bisher, self.kanal.sender()) {
+                    Ok(()) => Ok(bisher),
+                    Err(error) => {
+                        bisher.trigger = trigger;
+                        bisher.aktion = aktion;
+                        if let Err(error) =
+                            aktiviere_stellpult_taster(&mut bisher, self.kanal.sender())
+                        {
+                            error!("Taster {} nicht wiederhergestellt: {:?}", name.0, error)
+                        }
+                        Err((Some(bisher), format!("Fehler beim Aktivieren: {:?}", error)))
+                    },
+                }
+            },
+            bisher => {
+                let besitzer = Besitzer::Taster(name.0.clone());
+                let reserviert = taster_save.reserviere_für(&mut self.anschlüsse, besitzer);
+                let mut taster = match reserviert {
+                    Ok(taster) => taster,
+                    Err(error) => {
+                        return Err((bisher, format!("Fehler beim Hinzufügen: {:?}", error)))
+                    },
+                };
+                if let Err(error) = aktiviere_stellpult_taster(&mut taster, self.kanal.sender()) {
+                    // der neue Taster wird beim drop freigegeben
+                    return Err((bisher, format!("Fehler beim Aktivieren: {:?}", error)));
+                }
+                if let Some(mut bisher) = bisher {
+                    if let Err(error) = bisher.clear_async_interrupt() {
+                        error!("Interrupt für Taster {} nicht entfernt: {:?}", name.0, error)
+                    }
+                }
+                Ok(taster)
+            },
         }
-        let auswahl_status = streckenabschnitt::AuswahlStatus::neu(gleise.streckenabschnitte());
-        let command = if pfad_arg.is_some() {
-            iced::Command::perform(Message::laden(), identity)
+    }
+
+    /// Stelle die Geschwindigkeit `name` entsprechend der Position (0 - `u8::MAX`) eines Drehreglers ein.
+    fn drehregler_wert(&mut self, name: geschwindigkeit::Name, position: u8) {
+        if let Some((geschwindigkeit, anzeige_status)) = self.geschwindigkeiten.get_mut(&name) {
+            let maximal_wert = u16::from(geschwindigkeit.maximal_wert());
+            let skaliert =
+                (u16::from(position) * maximal_wert + u16::from(u8::MAX / 2)) / u16::from(u8::MAX);
+            let wert = skaliert as u8;
+            if let Err(error) = anzeige_status.geschwindigkeit_einstellen(geschwindigkeit, wert) {
+                self.zeige_message_box(
+                    format!("Fehler Geschwindigkeit {}", name.0),
+                    format!("{:?}", error),
+                )
+            }
         } else {
-            iced::Command::none()
-        };
-        let zugkontrolle = Zugkontrolle {
-            anschlüsse,
-            gleise,
-            scrollable_state: iced::scrollable::State::new(),
-            geraden: Z::geraden().into_iter().map(Button::new).collect(),
-            kurven: Z::kurven().into_iter().map(Button::new).collect(),
-            weichen: Z::weichen().into_iter().map(Button::new).collect(),
-            dreiwege_weichen: Z::dreiwege_weichen().into_iter().map(Button::new).collect(),
-            kurven_weichen: Z::kurven_weichen().into_iter().map(Button::new).collect(),
-            s_kurven_weichen: Z::s_kurven_weichen().into_iter().map(Button::new).collect(),
-            kreuzungen: Z::kreuzungen().into_iter().map(Button::new).collect(),
-            geschwindigkeiten: BTreeMap::new(),
-            modal_state: iced_aw::modal::State::new(Modal::Streckenabschnitt(auswahl_status)),
-            streckenabschnitt_aktuell: streckenabschnitt::AnzeigeStatus::neu(),
-            streckenabschnitt_aktuell_festlegen: false,
-            geschwindigkeit_button_state: iced::button::State::new(),
-            message_box: iced_aw::modal::State::new(MessageBox {
-                titel: "Nicht initialisiert".to_string(),
-                nachricht: "Diese Nachricht sollte nicht sichtbar sein!".to_string(),
-                button_state: iced::button::State::new(),
-            }),
-            oben: iced::button::State::new(),
-            unten: iced::button::State::new(),
-            links: iced::button::State::new(),
-            rechts: iced::button::State::new(),
-            clockwise: iced::button::State::new(),
-            counter_clockwise: iced::button::State::new(),
-            größer: iced::button::State::new(),
-            kleiner: iced::button::State::new(),
-            speichern: iced::button::State::new(),
-            laden: iced::button::State::new(),
-            pfad: iced::text_input::State::new(),
-            aktueller_pfad: pfad_arg.unwrap_or(format!("{}.zug", Z::NAME)),
-        };
-        (zugkontrolle, command)
+            debug!("Drehregler für unbekannte Geschwindigkeit {}", name.0)
+        }
     }
 
-    fn title(&self) -> String {
-        format!("Zugkontrolle {}", version!())
+    /// Beende gestartete Anfahrimpulse nach ihrer Dauer über einen Zeitgeber,
+    /// anstatt die Anzeige währenddessen zu blockieren.
+    fn anfahrimpulse_planen(&mut self) {
+        for (name, (_geschwindigkeit, anzeige_status)) in self.geschwindigkeiten.iter_mut() {
+            if let Some(dauer) = anzeige_status.anfahrimpuls_gestartet() {
+                let sender = self.kanal.sender();
+                let impuls_name = name.clone();
+                let zeitgeber = regel::Zeitgeber::einmal(dauer, move || {
+                    if let Err(_nachricht) = sender.send(Message::AnfahrimpulsEnde(impuls_name)) {
+                        error!("Ende eines Anfahrimpulses konnte nicht gesendet werden!")
+                    }
+                });
+                let _ = self.anfahrimpulse.insert(name.clone(), zeitgeber);
+            }
+        }
     }
 
-    fn update(
-        &mut self,
-        message: Self::Message,
-        _clipboard: &mut iced::Clipboard,
-    ) -> iced::Command<Self::Message> {
-        let mut command = iced::Command::none();
+    /// Stelle nach dem Anfahrimpuls die eingestellte Geschwindigkeit `name` ein.
+    fn anfahrimpuls_beenden(&mut self, name: geschwindigkeit::Name) {
+        let _ = self.anfahrimpulse.remove(&name);
+        if let Some((geschwindigkeit, anzeige_status)) = self.geschwindigkeiten.get_mut(&name) {
+            let wert = anzeige_status.aktuelle_geschwindigkeit();
+            if let Err(error) = anzeige_status.geschwindigkeit_einstellen(geschwindigkeit, wert) {
+                self.zeige_message_box(
+                    format!("Fehler Geschwindigkeit {}", name.0),
+                    format!("{:?}", error),
+                )
+            }
+        }
+    }
 
-        match message {
-            Message::Gleis { gleis, grab_height } => {
-                let streckenabschnitt = self
-                    .streckenabschnitt_aktuell
-                    .aktuell
-                    .as_ref()
-                    .map(|(name, _farbe)| name.clone());
-                macro_rules! add_grabbed_at_mouse {
-                    ($gleis:expr) => {{
-                        self.gleise.add_grabbed_at_mouse(
-                            $gleis.to_option(),
-                            Vektor { x: Skalar(0.), y: grab_height },
-                            streckenabschnitt,
-                        );
-                    }};
+    fn stellpult_aktion(&mut self, aktion: stellpult::Aktion) {
+        match aktion {
+            stellpult::Aktion::WeicheUmschalten(name) => {
+                if let Some(any_id) = self.gleise.weiche_mit_name(&name) {
+                    self.fahren_aktion(any_id);
+                    self.gleise.erzwinge_neuzeichnen()
+                } else {
+                    self.zeige_message_box(
+                        "Stellpult".to_string(),
+                        format!("Keine Weiche mit Namen {} gefunden!", name.0),
+                    )
                 }
-                match gleis {
-                    AnyGleis::GeradeUnit(gerade) => add_grabbed_at_mouse!(gerade),
-                    AnyGleis::KurveUnit(kurve) => add_grabbed_at_mouse!(kurve),
-                    AnyGleis::WeicheUnit(weiche) => add_grabbed_at_mouse!(weiche),
-                    AnyGleis::DreiwegeWeicheUnit(dreiwege_weiche) => {
-                        add_grabbed_at_mouse!(dreiwege_weiche)
-                    }
-                    AnyGleis::KurvenWeicheUnit(kurven_weiche) => {
-                        add_grabbed_at_mouse!(kurven_weiche)
+            }
+            stellpult::Aktion::StreckenabschnittUmschalten(name) => {
+                if let Some(streckenabschnitt) = self.gleise.streckenabschnitt_mut(&name) {
+                    if let Err(error) = streckenabschnitt.strom_umschalten() {
+                        self.zeige_message_box(
+                            "Streckenabschnitt umschalten".to_string(),
+                            format!("{:?}", error),
+                        )
                     }
-                    AnyGleis::SKurvenWeicheUnit(s_kurven_weiche) => {
-                        add_grabbed_at_mouse!(s_kurven_weiche)
+                    self.gleise.erzwinge_neuzeichnen()
+                } else {
+                    self.zeige_message_box(
+                        "Stellpult".to_string(),
+                        format!("Kein Streckenabschnitt mit Namen {} gefunden!", name.0),
+                    )
+                }
+            }
+            stellpult::Aktion::GeschwindigkeitUmdrehen(name) => {
+                if let Some((geschwindigkeit, anzeige_status)) =
+                    self.geschwindigkeiten.get_mut(&name)
+                {
+                    let nachricht =
+                        <Z::Leiter as LeiterAnzeige>::umdrehen_nachricht(anzeige_status);
+                    if let Err(error) = <Z::Leiter as LeiterAnzeige>::anzeige_update(
+                        geschwindigkeit,
+                        anzeige_status,
+                        nachricht,
+                    ) {
+                        self.zeige_message_box(
+                            format!("Fehler Geschwindigkeit {}", name.0),
+                            format!("{:?}", error),
+                        )
                     }
-                    AnyGleis::KreuzungUnit(kreuzung) => add_grabbed_at_mouse!(kreuzung),
+                } else {
+                    self.zeige_message_box(
+                        "Stellpult".to_string(),
+                        format!("Keine Geschwindigkeit mit Namen {} gefunden!", name.0),
+                    )
                 }
             }
-            Message::Modus(modus) => self.gleise.moduswechsel(modus),
-            Message::Bewegen(bewegen) => {
-                self.gleise.bewege_pivot(
-                    bewegen.bewegen().rotiert(-self.gleise.pivot().winkel)
-                        / self.gleise.skalierfaktor(),
-                );
+            stellpult::Aktion::FahrstraßeStellen(weichen) => {
+                let mut fehler_sammeln = |nur_prüfen| -> Vec<String> {
+                    weichen
+                        .iter()
+                        .filter_map(|(name, richtung)| {
+                            self.weiche_mit_name_stellen(name, richtung, nur_prüfen)
+                                .err()
+                                .map(|fehler| format!("{}: {}", name.0, fehler))
+                        })
+                        .collect()
+                };
+                // keine Weiche wird gestellt, solange eine Weiche oder Richtung ungültig ist
+                let ungültig = fehler_sammeln(true);
+                if !ungültig.is_empty() {
+                    return self.zeige_message_box(
+                        "Fahrstraße nicht gestellt".to_string(),
+                        ungültig.join("\n"),
+                    );
+                }
+                let fehler = fehler_sammeln(false);
+                self.gleise.erzwinge_neuzeichnen();
+                if !fehler.is_empty() {
+                    self.zeige_message_box("Fahrstraße stellen".to_string(), fehler.join("\n"))
+                }
             }
-            Message::Drehen(drehen) => self.gleise.drehen(drehen.drehen()),
-            Message::Skalieren(skalieren) => self.gleise.skalieren(skalieren.skalieren()),
-            Message::SchließeModal => {
-                self.modal_state.show(false);
+        }
+    }
+
+    /// Stelle eine Weiche in die Richtung mit dem Namen `richtung`.
+    ///
+    /// Mit `nur_prüfen` wird nur geprüft, ob die Weiche mit Anschlüssen existiert
+    /// und die Richtung bekannt ist.
+    fn weiche_richtung_stellen<T, Richtung, Anschlüsse>(
+        &mut self,
+        id: GleisId<T>,
+        gleise_steuerung: impl for<'t> Fn(
+            &'t mut Gleise<Z>,
+            &GleisId<T>,
+        ) -> Result<
+            &'t mut Option<steuerung::Weiche<Richtung, Anschlüsse>>,
+            GleisEntferntError,
+        >,
+        richtung: &str,
+        nur_prüfen: bool,
+    ) -> Result<(), String>
+    where
+        Richtung: Clone + for<'de> Deserialize<'de>,
+        Anschlüsse: Lookup<Richtung, OutputAnschluss>,
+    {
+        let richtung_wert: Richtung =
+            serde_json::from_value(serde_json::Value::String(richtung.to_string()))
+                .map_err(|_error| format!("Unbekannte Richtung {}!", richtung))?;
+        match gleise_steuerung(&mut self.gleise, &id) {
+            Ok(Some(_weiche)) if nur_prüfen => Ok(()),
+            Ok(Some(weiche)) => {
+                weiche.schalten(&richtung_wert).map_err(|error| format!("{:?}", error))
+            },
+            Ok(None) => Err("Weiche hat keine Anschlüsse!".to_string()),
+            Err(GleisEntferntError) => Err("Gleis entfernt!".to_string()),
+        }
+    }
+
+    /// Stelle die Weiche mit Namen `name` in die Richtung mit dem Namen `richtung`.
+    ///
+    /// Mit `nur_prüfen` wird die Weiche nicht gestellt, siehe [Self::weiche_richtung_stellen].
+    fn weiche_mit_name_stellen(
+        &mut self,
+        name: &steuerung::weiche::Name,
+        richtung: &str,
+        nur_prüfen: bool,
+    ) -> Result<(), String> {
+        match self.gleise.weiche_mit_name(name) {
+            Some(AnyId::Weiche(id)) => {
+                self.weiche_richtung_stellen(id, Gleise::steuerung_weiche, richtung, nur_prüfen)
+            },
+            Some(AnyId::DreiwegeWeiche(id)) => self.weiche_richtung_stellen(
+                id,
+                Gleise::steuerung_dreiwege_weiche,
+                richtung,
+                nur_prüfen,
+            ),
+            Some(AnyId::KurvenWeiche(id)) => self.weiche_richtung_stellen(
+                id,
+                Gleise::steuerung_kurven_weiche,
+                richtung,
+                nur_prüfen,
+            ),
+            Some(AnyId::SKurvenWeiche(id)) => self.weiche_richtung_stellen(
+                id,
+                Gleise::steuerung_s_kurven_weiche,
+                richtung,
+                nur_prüfen,
+            ),
+            Some(AnyId::Kreuzung(id)) => {
+                self.weiche_richtung_stellen(id, Gleise::steuerung_kreuzung, richtung, nur_prüfen)
+            },
+            Some(AnyId::Gerade(_)) | Some(AnyId::Kurve(_)) | None => {
+                Err(format!("Keine Weiche mit Namen {} gefunden!", name.0))
+            },
+        }
+    }
+
+    /// Stoppe alle Geschwindigkeiten und sperre alle Streckenabschnitte.
+    ///
+    /// Bis zum expliziten Aufheben des Nothalts werden keine Fahren-Aktionen ausgeführt.
+    fn nothalt(&mut self) {
+        self.nothalt = true;
+        for ausführung in self.skripte_aktiv.values() {
+            ausführung.abbrechen()
+        }
+        self.regel_verzögerungen.clear();
+        self.anfahrimpulse.clear();
+        let mut fehler = Vec::new();
+        for (name, (geschwindigkeit, anzeige_status)) in self.geschwindigkeiten.iter_mut() {
+            if let Err(error) = anzeige_status.geschwindigkeit_einstellen(geschwindigkeit, 0) {
+                fehler.push(format!("Geschwindigkeit {}: {:?}", name.0, error))
             }
-            Message::ZeigeAuswahlStreckenabschnitt => {
-                *self.modal_state.inner_mut() = Modal::Streckenabschnitt(
-                    streckenabschnitt::AuswahlStatus::neu(self.gleise.streckenabschnitte()),
-                );
-                self.modal_state.show(true);
+        }
+        for (name, streckenabschnitt) in self.gleise.streckenabschnitte_mut() {
+            if let Err(error) = streckenabschnitt.strom(Fließend::Gesperrt) {
+                fehler.push(format!("Streckenabschnitt {}: {:?}", name.0, error))
+            }
+        }
+        self.gleise.erzwinge_neuzeichnen();
+        if !fehler.is_empty() {
+            self.zeige_message_box("Fehler beim Nothalt".to_string(), fehler.join("\n"))
+        }
+    }
+}
+
+/// Nothalt über die F12-Taste.
+fn nothalt_tastatur<Z>(
+    event: iced_native::Event,
+    _status: iced_native::event::Status,
+) -> Option<Message<Z>>
+where
+    Z: Zugtyp,
+    <<Z as Zugtyp>::Leiter as ToSave>::Save: Debug + Clone,
+{
+    use iced_native::keyboard::{Event::KeyPressed, KeyCode};
+    match event {
+        iced_native::Event::Keyboard(KeyPressed { key_code: KeyCode::F12, .. }) => {
+            Some(Message::Nothalt)
+        }
+        _ => None,
+    }
+}
+
+/// Reserviere einen Pin für einen Nothalt-Taster (gegen Masse geschaltet, interner Pull-up).
+fn reserviere_nothalt_taster<Z>(
+    anschlüsse: &mut Anschlüsse,
+    pin: u8,
+    sender: kanal::Sender<Message<Z>>,
+) -> Result<InputAnschluss, crate::anschluss::Error>
+where
+    Z: 'static + Zugtyp,
+    <<Z as Zugtyp>::Leiter as ToSave>::Save: Debug + Clone,
+    Message<Z>: Send,
+{
+    let pin =
+        anschlüsse.mit_besitzer(Besitzer::Nothalt, |anschlüsse| anschlüsse.reserviere_pin(pin))?;
+    let mut taster = InputAnschluss::Pin(pin.into_input_pullup());
+    taster.set_async_interrupt(Trigger::FallingEdge, move |_level: Level| {
+        if let Err(_nachricht) = sender.send(Message::Nothalt) {
+            error!("Nothalt-Nachricht konnte nicht gesendet werden!")
+        }
+    })?;
+    Ok(taster)
+}
+
+/// Aktiviere den Interrupt eines Stellpult-Tasters, der die zugehörige Aktion über `sender` auslöst.
+fn aktiviere_stellpult_taster<Z>(
+    taster: &mut stellpult::Taster,
+    sender: kanal::Sender<Message<Z>>,
+) -> Result<(), crate::anschluss::Error>
+where
+    Z: 'static + Zugtyp,
+    <<Z as Zugtyp>::Leiter as ToSave>::Save: Debug + Clone,
+    Message<Z>: Send,
+{
+    taster.set_async_interrupt(move |aktion| {
+        if let Err(_nachricht) = sender.send(Message::StellpultAktion(aktion.clone())) {
+            error!("Stellpult-Nachricht konnte nicht gesendet werden!")
+        }
+    })
+}
+
+/// Starte die Abfrage eines Drehreglers, der die Geschwindigkeit `name` über `sender` einstellt.
+fn aktiviere_drehregler<Z>(
+    name: geschwindigkeit::Name,
+    drehregler: &mut steuerung::Drehregler,
+    sender: kanal::Sender<Message<Z>>,
+) where
+    Z: 'static + Zugtyp,
+    <<Z as Zugtyp>::Leiter as ToSave>::Save: Debug + Clone,
+    Message<Z>: Send,
+{
+    drehregler.starte_abfrage(move |position| {
+        if let Err(_nachricht) = sender.send(Message::DrehreglerWert(name.clone(), position)) {
+            error!("Drehregler-Nachricht konnte nicht gesendet werden!")
+        }
+    })
+}
+
+/// Aktiviere den Interrupt eines Kontakts, der das ausgelöste Level über `sender` meldet.
+fn aktiviere_kontakt<Z>(
+    kontakt: &mut Kontakt<InputAnschluss>,
+    sender: kanal::Sender<Message<Z>>,
+) -> Result<(), crate::anschluss::Error>
+where
+    Z: 'static + Zugtyp,
+    <<Z as Zugtyp>::Leiter as ToSave>::Save: Debug + Clone,
+    Message<Z>: Send,
+{
+    let name = kontakt.name.clone();
+    kontakt.set_async_interrupt(move |level| {
+        if let Err(_nachricht) = sender.send(Message::Kontakt(name.clone(), level)) {
+            error!("Kontakt-Nachricht konnte nicht gesendet werden!")
+        }
+    })
+}
+
+impl<Z> Zugkontrolle<Z>
+where
+    Z: 'static + Zugtyp + Debug + PartialEq + for<'de> Deserialize<'de>,
+    Z::Leiter: LeiterAnzeige,
+    <<Z as Zugtyp>::Leiter as ToSave>::Save: Debug + Clone,
+    Message<Z>: Send,
+{
+    /// Lade den Zustand aus `aktueller_pfad`, ggf. mit den Anschlüssen aus `aktueller_profil_pfad`.
+    ///
+    /// Bei Konflikten zwischen Anschlüssen wird ohne `konflikte_ignorieren`
+    /// der bisherige Zustand beibehalten. Gibt zurück, ob der Zustand ersetzt wurde.
+    fn laden(&mut self, konflikte_ignorieren: bool) -> bool
+    where
+        <Z::Leiter as ToSave>::Save: Verkabeln,
+    {
+        let gelesen = if self.aktueller_profil_pfad.is_empty() {
+            Gleise::<Z>::lese(&self.aktueller_pfad).map(|neu| (neu, Vec::new()))
+        } else {
+            Gleise::<Z>::lese_mit_profil(&self.aktueller_pfad, &self.aktueller_profil_pfad)
+        };
+        let (neu, ungebunden) = match gelesen {
+            Ok(gelesen) => gelesen,
+            Err(err) => {
+                self.zeige_message_box(
+                    format!("Fehler beim Laden von {}", self.aktueller_pfad),
+                    format!("{:?}", err),
+                );
+                return false;
+            },
+        };
+        match self.ersetze_zustand(neu, konflikte_ignorieren) {
+            Ok(konflikte) => {
+                self.gleise.ansicht_zurücksetzen();
+                if !konflikte.is_empty() || !ungebunden.is_empty() {
+                    let nachricht: Vec<_> = ungebunden
+                        .iter()
+                        .map(|besitzer| format!("{}: nicht im Profil", besitzer))
+                        .chain(konflikte.iter().map(ToString::to_string))
+                        .collect();
+                    self.zeige_message_box(
+                        "Steuerungen ohne Anschluss geladen".to_string(),
+                        nachricht.join("\n"),
+                    )
+                }
+                // Skripte des bisherigen Zustands werden abgebrochen
+                for (_name, ausführung) in std::mem::take(&mut self.skripte_aktiv) {
+                    ausführung.abbrechen()
+                }
+                let mut fehler = Vec::new();
+                for name in std::mem::take(&mut self.skripte_nach_laden) {
+                    if let Err(nachricht) = self.starte_skript(name.clone()) {
+                        fehler.push(format!("{}: {}", name.0, nachricht))
+                    }
+                }
+                if !fehler.is_empty() {
+                    self.zeige_message_box(
+                        "Fehler beim Starten der Skripte".to_string(),
+                        fehler.join("\n"),
+                    )
+                }
+                true
+            },
+            Err(konflikte) => {
+                self.zeige_konflikte(
+                    format!("Konflikte beim Laden von {}", self.aktueller_pfad),
+                    konflikte,
+                    Trotzdem::Laden,
+                );
+                false
+            },
+        }
+    }
+
+    /// Starte das gespeicherte Skript `name` in einem eigenen Thread.
+    ///
+    /// Aktionen des Skriptes werden wie Netzwerk-Befehle bearbeitet,
+    /// insbesondere werden Fahren-Aktionen während eines Nothalts abgelehnt.
+    fn starte_skript(&mut self, name: skript::Name) -> Result<(), String> {
+        if self.skripte_aktiv.get(&name).map_or(false, skript::Ausführung::aktiv) {
+            return Err("Das Skript läuft bereits.".to_string());
+        }
+        let quelltext = match self.gleise.skripte().get(&name) {
+            Some(quelltext) => quelltext.clone(),
+            None => return Err("Unbekanntes Skript.".to_string()),
+        };
+        let sender = self.kanal.sender();
+        let ausführen = move |aktion: skript::Aktion| {
+            let (antwort, empfänger) = std::sync::mpsc::channel();
+            if let Err(_nachricht) =
+                sender.send(Message::Netzwerk(netzwerk::Anfrage::neu(aktion.into(), antwort)))
+            {
+                return Err("Aktion konnte nicht gesendet werden!".to_string());
+            }
+            match empfänger.recv() {
+                Ok(netzwerk::Ausgabe::Fehler { nachricht }) => Err(nachricht),
+                Ok(_ausgabe) => Ok(()),
+                Err(_error) => Err("Keine Antwort auf die Aktion erhalten!".to_string()),
+            }
+        };
+        let sender = self.kanal.sender();
+        let beendet_name = name.clone();
+        let beendet = move |ende| {
+            if let Err(_nachricht) = sender.send(Message::SkriptBeendet(beendet_name, ende)) {
+                error!("Ende eines Skriptes konnte nicht gemeldet werden!")
+            }
+        };
+        let ausführung = skript::Ausführung::starte(name.clone(), quelltext, ausführen, beendet)
+            .map_err(|error| error.to_string())?;
+        let _ = self.skripte_aktiv.insert(name, ausführung);
+        Ok(())
+    }
+
+    /// Starte die Takte aller Regeln mit [Takt](regel::Auslöser::Takt)-Auslöser neu.
+    fn regel_takte_starten(&mut self) {
+        self.regel_takte.clear();
+        for (name, regel) in self.gleise.regeln() {
+            if let regel::Auslöser::Takt { intervall } = regel.auslöser {
+                let sender = self.kanal.sender();
+                let takt_name = name.clone();
+                let takt = regel::Zeitgeber::takt(intervall, move || {
+                    if let Err(_nachricht) = sender.send(Message::RegelTakt(takt_name.clone())) {
+                        error!("Takt einer Regel konnte nicht gemeldet werden!")
+                    }
+                });
+                let _ = self.regel_takte.insert(name.clone(), takt);
+            }
+        }
+    }
+
+    /// Führe alle Regeln aus, deren Auslöser zum `ereignis` passt.
+    fn regeln_auslösen(&mut self, ereignis: &regel::Ereignis) -> iced::Command<Message<Z>> {
+        let namen: Vec<_> = self
+            .gleise
+            .regeln()
+            .iter()
+            .filter(|(_name, regel)| regel.auslöser.passt(ereignis))
+            .map(|(name, _regel)| name.clone())
+            .collect();
+        iced::Command::batch(namen.iter().map(|name| self.regel_ausführen(name)))
+    }
+
+    /// Prüfe die Bedingungen der Regel `name` und führe ggf. ihre Schritte aus.
+    ///
+    /// Schritte mit Verzögerung werden über einen [Zeitgeber](regel::Zeitgeber) später ausgeführt.
+    fn regel_ausführen(&mut self, name: &regel::Name) -> iced::Command<Message<Z>> {
+        if self.nothalt {
+            debug!("Regel {} wird wegen Nothalt nicht ausgeführt.", name.0);
+            return iced::Command::none();
+        }
+        let regel = match self.gleise.regeln().get(name) {
+            Some(regel) => regel.clone(),
+            None => {
+                error!("Unbekannte Regel {}!", name.0);
+                return iced::Command::none();
+            },
+        };
+        let weichen = self.gleise.weichen_richtungen().into_iter().collect();
+        if !regel.bedingungen_erfüllt(&weichen, regel::Uhrzeit::jetzt()) {
+            return iced::Command::none();
+        }
+        debug!("Regel {} ausgelöst.", name.0);
+        let mut commands = Vec::new();
+        self.regel_verzögerungen.retain(regel::Zeitgeber::aktiv);
+        for regel::Schritt { aktion, verzögerung } in regel.schritte {
+            if verzögerung.as_nanos() == 0 {
+                commands.push(self.regel_aktion(name, aktion))
+            } else {
+                let sender = self.kanal.sender();
+                let aktion_name = name.clone();
+                self.regel_verzögerungen.push(regel::Zeitgeber::einmal(verzögerung, move || {
+                    if let Err(_nachricht) = sender.send(Message::RegelAktion(aktion_name, aktion))
+                    {
+                        error!("Verzögerte Aktion einer Regel konnte nicht gesendet werden!")
+                    }
+                }))
+            }
+        }
+        iced::Command::batch(commands)
+    }
+
+    /// Führe eine Aktion der Regel `name` wie einen Netzwerk-Befehl aus.
+    fn regel_aktion(
+        &mut self,
+        name: &regel::Name,
+        aktion: regel::Aktion,
+    ) -> iced::Command<Message<Z>> {
+        if self.nothalt {
+            debug!("Aktion der Regel {} wird wegen Nothalt nicht ausgeführt.", name.0);
+            return iced::Command::none();
+        }
+        let (antwort, empfänger) = std::sync::mpsc::channel();
+        let command = self.netzwerk_anfrage(netzwerk::Anfrage::neu(aktion.into(), antwort));
+        if let Ok(netzwerk::Ausgabe::Fehler { nachricht }) = empfänger.try_recv() {
+            self.zeige_message_box(format!("Regel {}", name.0), nachricht)
+        }
+        command
+    }
+
+    /// Ersetze die Anschlüsse aller Steuerungen gemäß `zuordnung` und reserviere sie neu.
+    ///
+    /// Bei Konflikten zwischen Anschlüssen wird ohne `konflikte_ignorieren`
+    /// der bisherige Zustand beibehalten.
+    fn zuordnen(&mut self, zuordnung: Zuordnung, konflikte_ignorieren: bool)
+    where
+        <Z::Leiter as ToSave>::Save: Zuordnen + Verkabeln,
+    {
+        let (geschwindigkeiten, stellpult, drehregler) = self.steuerungen_save();
+        let mut neu = self.gleise.zustand(geschwindigkeiten, stellpult, drehregler);
+        let geändert = match neu.zuordnen(&zuordnung) {
+            Ok(geändert) => geändert,
+            Err(fehler) => {
+                return self
+                    .zeige_message_box("Fehler bei der Zuordnung".to_string(), fehler.to_string())
+            },
+        };
+        self.letzte_zuordnung = zuordnung.clone();
+        match self.ersetze_zustand(neu, konflikte_ignorieren) {
+            Ok(konflikte) => {
+                self.modal_state.show(false);
+                let mut nachricht = vec![format!("{} Anschlüsse geändert.", geändert)];
+                nachricht.extend(konflikte.iter().map(ToString::to_string));
+                self.zeige_message_box("Zuordnung angewendet".to_string(), nachricht.join("\n"))
+            },
+            Err(konflikte) => self.zeige_konflikte(
+                "Konflikte bei der Zuordnung".to_string(),
+                konflikte,
+                Trotzdem::Zuordnen(zuordnung),
+            ),
+        }
+    }
+
+    /// Zeige die offenen Anschlusspunkte der Datei in `pfad` und des aktuellen Zustands
+    /// zur Auswahl beim Importieren an.
+    fn importieren_lesen(&mut self, pfad: String)
+    where
+        Z: Serialize,
+    {
+        let gelesen = Gleise::<Z>::lese(&pfad);
+        let (geschwindigkeiten, stellpult, drehregler) = self.steuerungen_save();
+        let aktuell = self.gleise.zustand(geschwindigkeiten, stellpult, drehregler);
+        match self.modal_state.inner_mut() {
+            Modal::Importieren(status) => match gelesen {
+                Ok(import) => status.setze_anchor_points(
+                    import.offene_anchor_points(),
+                    aktuell.offene_anchor_points(),
+                ),
+                Err(fehler) => {
+                    status.zeige_fehler(format!("Fehler beim Lesen von {}: {:?}", pfad, fehler))
+                },
+            },
+            _modal => error!("Falscher Modal-State bei ImportierenLesen!"),
+        }
+    }
+
+    /// Füge die Gleise aus der Datei in `einstellungen` zum aktuellen Zustand hinzu.
+    ///
+    /// Bei Konflikten zwischen Anschlüssen wird ohne `konflikte_ignorieren`
+    /// der bisherige Zustand beibehalten.
+    fn importieren(&mut self, einstellungen: importieren::Einstellungen, konflikte_ignorieren: bool)
+    where
+        Z: Serialize,
+        <Z::Leiter as ToSave>::Save: Verkabeln,
+    {
+        let import = match Gleise::<Z>::lese(&einstellungen.pfad) {
+            Ok(import) => import,
+            Err(fehler) => {
+                return self.zeige_message_box(
+                    format!("Fehler beim Lesen von {}", einstellungen.pfad),
+                    format!("{:?}", fehler),
+                )
+            },
+        };
+        let (geschwindigkeiten, stellpult, drehregler) = self.steuerungen_save();
+        let mut neu = self.gleise.zustand(geschwindigkeiten, stellpult, drehregler);
+        let meldungen = neu.importieren(
+            import,
+            &einstellungen.platzierung.ursprung(),
+            einstellungen.anschlüsse_übernehmen,
+        );
+        match self.ersetze_zustand(neu, konflikte_ignorieren) {
+            Ok(konflikte) => {
+                self.modal_state.show(false);
+                let mut nachricht = vec![format!("{} importiert.", einstellungen.pfad)];
+                nachricht.extend(meldungen);
+                nachricht.extend(konflikte.iter().map(ToString::to_string));
+                self.zeige_message_box("Gleise importiert".to_string(), nachricht.join("\n"))
+            },
+            Err(konflikte) => self.zeige_konflikte(
+                format!("Konflikte beim Importieren von {}", einstellungen.pfad),
+                konflikte,
+                Trotzdem::Importieren(einstellungen),
+            ),
+        }
+    }
+
+    /// Ersetze den aktuellen Zustand durch `neu`, die Anschlüsse werden dabei neu reserviert.
+    ///
+    /// Ohne `konflikte_ignorieren` wird zuerst geprüft, ob die Anschlüsse von `neu` frei sind
+    /// oder vom aktuellen Zustand verwendet werden. Bei Konflikten bleibt der aktuelle Zustand
+    /// unverändert. Nur bei erst beim Reservieren erkannten Konflikten (z.B. mehrfach verwendete
+    /// Anschlüsse) wird der bisherige Zustand neu reserviert.
+    /// Mit `konflikte_ignorieren` werden die ignorierten Konflikte zurückgegeben.
+    fn ersetze_zustand(
+        &mut self,
+        neu: GleiseVecs<Z>,
+        konflikte_ignorieren: bool,
+    ) -> Result<Vec<Konflikt>, ErsetzenKonflikte>
+    where
+        <Z::Leiter as ToSave>::Save: Verkabeln,
+    {
+        let (geschwindigkeiten, stellpult, drehregler) = self.steuerungen_save();
+        let bisher = self.gleise.zustand(geschwindigkeiten, stellpult, drehregler);
+        if !konflikte_ignorieren {
+            if let Err(konflikte) = Gleise::prüfe_verfügbar(&self.anschlüsse, &bisher, &neu) {
+                return Err(ErsetzenKonflikte { neu: konflikte, verloren: Vec::new() });
+            }
+        }
+        // Anschlüsse des bisherigen Zustands freigeben
+        self.gleise.leeren();
+        self.geschwindigkeiten.clear();
+        self.stellpult.clear();
+        self.drehregler.clear();
+        match Gleise::reserviere(&mut self.anschlüsse, neu, konflikte_ignorieren) {
+            Ok(reserviert) => Ok(self.übernehmen(reserviert)),
+            Err(konflikte) => {
+                // die bisherigen Anschlüsse wurden gerade freigegeben und sind wieder verfügbar
+                let verloren = match Gleise::reserviere(&mut self.anschlüsse, bisher, true) {
+                    Ok(reserviert) => self.übernehmen(reserviert),
+                    Err(verloren) => verloren,
+                };
+                Err(ErsetzenKonflikte { neu: konflikte, verloren })
+            },
+        }
+    }
+
+    /// Übernehme reservierte Gleise und Steuerungen, ignorierte Konflikte werden zurückgegeben.
+    fn übernehmen(&mut self, reserviert: GleiseReserviert<Z>) -> Vec<Konflikt> {
+        let (geschwindigkeiten, mut stellpult, mut drehregler, konflikte) =
+            self.gleise.übernehmen(reserviert);
+        self.geschwindigkeiten = geschwindigkeiten
+            .into_iter()
+            .map(|(name, geschwindigkeit)| {
+                (name, (geschwindigkeit, Z::Leiter::anzeige_status_neu()))
+            })
+            .collect();
+        self.streckenabschnitt_aktuell.aktuell = None;
+        let mut fehler = Vec::new();
+        for (name, taster) in stellpult.iter_mut() {
+            if let Err(error) = aktiviere_stellpult_taster(taster, self.kanal.sender()) {
+                fehler.push(format!("Taster {}: {:?}", name.0, error))
+            }
+        }
+        self.stellpult = stellpult;
+        for (name, drehregler) in drehregler.iter_mut() {
+            aktiviere_drehregler(name.clone(), drehregler, self.kanal.sender())
+        }
+        self.drehregler = drehregler;
+        for kontakt in self.gleise.kontakte_mut() {
+            if let Err(error) = aktiviere_kontakt(kontakt, self.kanal.sender()) {
+                fehler.push(format!("Kontakt {}: {:?}", kontakt.name.0, error))
+            }
+        }
+        if !fehler.is_empty() {
+            self.zeige_message_box(
+                "Fehler beim Aktivieren von Stellpult und Kontakten".to_string(),
+                fehler.join("\n"),
+            )
+        }
+        // verzögerte Aktionen gehören zu den Regeln des bisherigen Zustands
+        self.regel_verzögerungen.clear();
+        self.anfahrimpulse.clear();
+        self.regel_takte_starten();
+        self.regel_zustand = self.netzwerk_zustand();
+        konflikte
+    }
+
+    /// Zustand aller Steuerungen für die Netzwerk-Steuerung.
+    fn netzwerk_zustand(&mut self) -> netzwerk::Zustand {
+        let geschwindigkeiten = self
+            .geschwindigkeiten
+            .iter()
+            .map(|(name, (_geschwindigkeit, anzeige_status))| {
+                (name.clone(), anzeige_status.aktuelle_geschwindigkeit())
+            })
+            .collect();
+        let fahrtrichtungen = self
+            .geschwindigkeiten
+            .iter()
+            .filter_map(|(name, (_geschwindigkeit, anzeige_status))| {
+                <Z::Leiter as LeiterAnzeige>::aktuelle_fahrtrichtung(anzeige_status)
+                    .map(|fahrtrichtung| (name.clone(), fahrtrichtung))
+            })
+            .collect();
+        let weichen = self.gleise.weichen_richtungen().into_iter().collect();
+        let streckenabschnitte = self
+            .gleise
+            .streckenabschnitte_mut()
+            .filter_map(|(name, streckenabschnitt)| match streckenabschnitt.ist_fließend() {
+                Ok(fließend) => Some((name.clone(), fließend)),
+                Err(error) => {
+                    error!("Zustand von Streckenabschnitt {} unbekannt: {:?}", name.0, error);
+                    None
+                },
+            })
+            .collect();
+        netzwerk::Zustand {
+            geschwindigkeiten,
+            fahrtrichtungen,
+            weichen,
+            streckenabschnitte,
+            nothalt: self.nothalt,
+        }
+    }
+
+    /// Bearbeite die von `erzeuge_nachricht` erzeugte Nachricht wie eine Eingabe der Anzeige.
+    fn geschwindigkeit_anzeige_update(
+        &mut self,
+        name: &geschwindigkeit::Name,
+        command: &mut iced::Command<Message<Z>>,
+        erzeuge_nachricht: impl FnOnce(
+            &geschwindigkeit::AnzeigeStatus<Z::Leiter>,
+        )
+            -> Result<Option<<Z::Leiter as LeiterAnzeige>::Message>, String>,
+    ) -> Result<(), String> {
+        let (geschwindigkeit, anzeige_status) = self
+            .geschwindigkeiten
+            .get_mut(name)
+            .ok_or_else(|| format!("Keine Geschwindigkeit mit Namen {} gefunden!", name.0))?;
+        let nachricht = match erzeuge_nachricht(anzeige_status)? {
+            Some(nachricht) => nachricht,
+            None => return Ok(()),
+        };
+        let cmd = <Z::Leiter as LeiterAnzeige>::anzeige_update(
+            geschwindigkeit,
+            anzeige_status,
+            nachricht,
+        )
+        .map_err(|error| format!("{:?}", error))?;
+        let name_clone = name.clone();
+        *command = cmd.map(move |nachricht| Message::GeschwindigkeitAnzeige {
+            name: name_clone.clone(),
+            nachricht,
+        });
+        Ok(())
+    }
+
+    /// Führe den Befehl eines Netzwerk-Clients aus, Fehler werden nur an den Client gesendet.
+    fn netzwerk_anfrage(&mut self, anfrage: netzwerk::Anfrage) -> iced::Command<Message<Z>> {
+        use netzwerk::Befehl;
+        let mut command = iced::Command::none();
+        let ergebnis = match &anfrage.befehl {
+            Befehl::Zustand => {
+                anfrage.antworte(netzwerk::Ausgabe::Zustand(self.netzwerk_zustand()));
+                return command;
+            },
+            Befehl::Gleisplan => {
+                let geschwindigkeiten = self
+                    .geschwindigkeiten
+                    .iter()
+                    .map(|(name, (geschwindigkeit, _anzeige_status))| {
+                        (name.clone(), geschwindigkeit.maximal_wert())
+                    })
+                    .collect();
+                anfrage.antworte(netzwerk::Ausgabe::Gleisplan(netzwerk::Gleisplan {
+                    svg: self.gleise.svg(),
+                    geschwindigkeiten,
+                }));
+                return command;
+            },
+            // wird bereits vom Server bearbeitet
+            Befehl::Abonnieren => Ok(()),
+            Befehl::Geschwindigkeit { name, wert } => {
+                if let Some((geschwindigkeit, anzeige_status)) =
+                    self.geschwindigkeiten.get_mut(name)
+                {
+                    let maximal_wert = geschwindigkeit.maximal_wert();
+                    if *wert > maximal_wert {
+                        Err(format!("Geschwindigkeit {} ist maximal {}!", name.0, maximal_wert))
+                    } else {
+                        anzeige_status
+                            .geschwindigkeit_einstellen(geschwindigkeit, *wert)
+                            .map_err(|error| format!("{:?}", error))
+                    }
+                } else {
+                    Err(format!("Keine Geschwindigkeit mit Namen {} gefunden!", name.0))
+                }
+            },
+            Befehl::Umdrehen { name } => {
+                self.geschwindigkeit_anzeige_update(name, &mut command, |anzeige_status| {
+                    Ok(Some(<Z::Leiter as LeiterAnzeige>::umdrehen_nachricht(anzeige_status)))
+                })
+            },
+            Befehl::Fahrtrichtung { name, fahrtrichtung } => {
+                self.geschwindigkeit_anzeige_update(name, &mut command, |anzeige_status| {
+                    <Z::Leiter as LeiterAnzeige>::fahrtrichtung_nachricht(
+                        anzeige_status,
+                        fahrtrichtung,
+                    )
+                })
+            },
+            Befehl::Weiche { name, richtung } => {
+                self.weiche_mit_name_stellen(name, richtung, false)
+            },
+            Befehl::Streckenabschnitt { name, fließend } => {
+                if let Some(streckenabschnitt) = self.gleise.streckenabschnitt_mut(name) {
+                    match fließend {
+                        Some(true) => streckenabschnitt.strom(Fließend::Fließend),
+                        Some(false) => streckenabschnitt.strom(Fließend::Gesperrt),
+                        None => streckenabschnitt.strom_umschalten(),
+                    }
+                    .map_err(|error| format!("{:?}", error))
+                } else {
+                    Err(format!("Kein Streckenabschnitt mit Namen {} gefunden!", name.0))
+                }
+            },
+            Befehl::Nothalt => {
+                self.nothalt();
+                Ok(())
+            },
+        };
+        self.gleise.erzwinge_neuzeichnen();
+        anfrage.antworte_ergebnis(ergebnis);
+        command
+    }
+}
+
+impl<Z> Zugkontrolle<Z>
+where
+    Z: 'static + Zugtyp + Debug + PartialEq + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+    Z::Leiter: Debug,
+    <<Z as Zugtyp>::Leiter as ToSave>::Save: Debug + Clone + Send + Zuordnen + Verkabeln,
+    <<Z as Zugtyp>::Leiter as LeiterAnzeige>::Fahrtrichtung: Debug,
+{
+    /// Bearbeite eine Nachricht der Anzeige oder einer anderen Eingabe (z.B. Interrupts).
+    fn bearbeite(&mut self, message: Message<Z>) -> iced::Command<Message<Z>> {
+        let mut command = iced::Command::none();
+        // Drehregler, Kontakte und Netzwerk-Befehle reservieren keine Anschlüsse
+        let reservierungen_aktualisieren = !matches!(
+            message,
+            Message::DrehreglerWert(..)
+                | Message::AnfahrimpulsEnde(_)
+                | Message::Kontakt(..)
+                | Message::Netzwerk(_)
+        );
+        // Änderungen durch Regeln lösen keine weiteren Regeln aus, um Endlosschleifen zu vermeiden
+        let regeln_auswerten = !matches!(
+            message,
+            Message::Kontakt(..) | Message::RegelTakt(_) | Message::RegelAktion(..)
+        );
+
+        if self.nothalt {
+            match message {
+                // Drehregler senden laufend neue Werte, eine Meldung pro Wert wäre zu viel
+                Message::DrehreglerWert(..) => return command,
+                Message::GeschwindigkeitAnzeige { .. }
+                | Message::FahrenAktion(_)
+                | Message::StellpultAktion(_) => {
+                    self.zeige_message_box(
+                        "Nothalt aktiv!".to_string(),
+                        "Der Nothalt muss vor weiteren Fahren-Aktionen aufgehoben werden."
+                            .to_string(),
+                    );
+                    return command;
+                }
+                Message::Netzwerk(ref anfrage) if anfrage.befehl.ist_fahren_aktion() => {
+                    anfrage.antworte(netzwerk::Ausgabe::Fehler {
+                        nachricht: "Nothalt aktiv!".to_string(),
+                    });
+                    return command;
+                }
+                _ => {}
+            }
+        }
+
+        match message {
+            Message::Gleis { gleis, grab_height } => {
+                let streckenabschnitt = self
+                    .streckenabschnitt_aktuell
+                    .aktuell
+                    .as_ref()
+                    .map(|(name, _farbe)| name.clone());
+                macro_rules! add_grabbed_at_mouse {
+                    ($gleis:expr) => {{
+                        self.gleise.add_grabbed_at_mouse(
+                            $gleis.to_option(),
+                            Vektor { x: Skalar(0.), y: grab_height },
+                            streckenabschnitt,
+                        );
+                    }};
+                }
+                match gleis {
+                    AnyGleis::GeradeUnit(gerade) => add_grabbed_at_mouse!(gerade),
+                    AnyGleis::KurveUnit(kurve) => add_grabbed_at_mouse!(kurve),
+                    AnyGleis::WeicheUnit(weiche) => add_grabbed_at_mouse!(weiche),
+                    AnyGleis::DreiwegeWeicheUnit(dreiwege_weiche) => {
+                        add_grabbed_at_mouse!(dreiwege_weiche)
+                    }
+                    AnyGleis::KurvenWeicheUnit(kurven_weiche) => {
+                        add_grabbed_at_mouse!(kurven_weiche)
+                    }
+                    AnyGleis::SKurvenWeicheUnit(s_kurven_weiche) => {
+                        add_grabbed_at_mouse!(s_kurven_weiche)
+                    }
+                    AnyGleis::KreuzungUnit(kreuzung) => add_grabbed_at_mouse!(kreuzung),
+                }
+            }
+            Message::Modus(modus) => self.gleise.moduswechsel(modus),
+            Message::Bewegen(bewegen) => {
+                self.gleise.bewege_pivot(
+                    bewegen.bewegen().rotiert(-self.gleise.pivot().winkel)
+                        / self.gleise.skalierfaktor(),
+                );
+            }
+            Message::Drehen(drehen) => self.gleise.drehen(drehen.drehen()),
+            Message::Skalieren(skalieren) => self.gleise.skalieren(skalieren.skalieren()),
+            Message::SchließeModal => {
+                self.modal_state.show(false);
+            }
+            Message::ZeigeAuswahlStreckenabschnitt => {
+                *self.modal_state.inner_mut() = Modal::Streckenabschnitt(
+                    streckenabschnitt::AuswahlStatus::neu(self.gleise.streckenabschnitte()),
+                );
+                self.modal_state.show(true);
             }
             Message::WähleStreckenabschnitt(aktuell) => {
                 self.streckenabschnitt_aktuell.aktuell = aktuell;
             }
             Message::HinzufügenStreckenabschnitt(name, farbe, anschluss_definition) => {
-                match anschluss_definition.reserviere(&mut self.anschlüsse) {
+                let besitzer = Besitzer::Streckenabschnitt(name.0.clone());
+                match anschluss_definition.reserviere_für(&mut self.anschlüsse, besitzer) {
                     Ok(anschluss) => {
                         self.streckenabschnitt_aktuell.aktuell = Some((name.clone(), farbe));
                         let streckenabschnitt = Streckenabschnitt { farbe, anschluss };
@@ -711,23 +1879,39 @@ where
             }
             Message::SchließeMessageBox => self.message_box.show(false),
             Message::Speichern => {
-                if let Err(err) = self.gleise.speichern(
-                    &self.aktueller_pfad,
-                    self.geschwindigkeiten
-                        .iter()
-                        .map(|(name, (geschwindigkeit, _anzeige_status))| {
-                            (name.clone(), geschwindigkeit.to_save())
-                        })
-                        .collect(),
-                ) {
+                let (geschwindigkeiten, stellpult, drehregler) = self.steuerungen_save();
+                let ergebnis = if self.aktueller_profil_pfad.is_empty() {
+                    self.gleise.speichern(
+                        &self.aktueller_pfad,
+                        geschwindigkeiten,
+                        stellpult,
+                        drehregler,
+                    )
+                } else {
+                    self.gleise.speichern_mit_profil(
+                        &self.aktueller_pfad,
+                        &self.aktueller_profil_pfad,
+                        geschwindigkeiten,
+                        stellpult,
+                        drehregler,
+                    )
+                };
+                if let Err(err) = ergebnis {
                     self.zeige_message_box(
                         format!("Fehler beim Speichern in {}", self.aktueller_pfad),
                         format!("{:?}", err),
                     )
                 }
             }
-            Message::Laden => self.laden(),
+            Message::Laden => {
+                self.laden(false);
+            },
+            Message::LadenTrotzdem => {
+                self.message_box.show(false);
+                self.laden(true);
+            },
             Message::Pfad(pfad) => self.aktueller_pfad = pfad,
+            Message::ProfilPfad(pfad) => self.aktueller_profil_pfad = pfad,
             Message::GeschwindigkeitAnzeige { name, nachricht } => {
                 if let Some((geschwindigkeit, anzeige_status)) =
                     self.geschwindigkeiten.get_mut(&name)
@@ -763,7 +1947,8 @@ where
                 self.modal_state.show(true);
             }
             Message::HinzufügenGeschwindigkeit(name, geschwindigkeit_save) => {
-                match geschwindigkeit_save.reserviere(&mut self.anschlüsse) {
+                let besitzer = Besitzer::Geschwindigkeit(name.0.clone());
+                match geschwindigkeit_save.reserviere_für(&mut self.anschlüsse, besitzer) {
                     Ok(geschwindigkeit) => {
                         match self.modal_state.inner_mut() {
                             Modal::Geschwindigkeit(geschwindigkeit_auswahl) => {
@@ -873,107 +2058,531 @@ where
                     "DreiwegeWeiche",
                     id,
                     anschlüsse_save,
-                    Gleise::steuerung_dreiwege_weiche,
-                ),
-            Message::KurvenWeicheAnschlüsseAnpassen(id, anschlüsse_save) => self
-                .gleis_anschlüsse_anpassen(
-                    "KurvenWeiche",
-                    id,
-                    anschlüsse_save,
-                    Gleise::steuerung_kurven_weiche,
-                ),
-            Message::SKurvenWeicheAnschlüsseAnpassen(id, anschlüsse_save) => self
-                .gleis_anschlüsse_anpassen(
-                    "SKurvenWeiche",
-                    id,
-                    anschlüsse_save,
-                    Gleise::steuerung_s_kurven_weiche,
-                ),
-            Message::KreuzungAnschlüsseAnpassen(id, anschlüsse_save) => self
-                .gleis_anschlüsse_anpassen(
-                    "Kreuzung",
-                    id,
-                    anschlüsse_save,
-                    Gleise::steuerung_kreuzung,
-                ),
-            Message::FahrenAktion(any_id) => match any_id {
-                // TODO in Methode auslagern
-                AnyId::Gerade(id) => self.streckenabschnitt_umschalten("Gerade", id),
-                AnyId::Kurve(id) => self.streckenabschnitt_umschalten("Kurve", id),
-                AnyId::Weiche(id) => self.weiche_stellen(
-                    "Weiche",
-                    id,
-                    Gleise::steuerung_weiche,
-                    |aktuelle_richtung, _letzte_richtung| {
-                        use gleis::weiche::gerade::Richtung;
-                        if aktuelle_richtung == &Richtung::Gerade {
-                            Richtung::Kurve
-                        } else {
-                            Richtung::Gerade
-                        }
-                    },
-                ),
-                AnyId::DreiwegeWeiche(id) => self.weiche_stellen(
-                    "DreiwegeWeiche",
-                    id,
-                    Gleise::steuerung_dreiwege_weiche,
-                    |aktuelle_richtung, letzte_richtung| {
-                        use gleis::weiche::dreiwege::Richtung;
-                        if aktuelle_richtung == &Richtung::Gerade {
-                            if letzte_richtung == &Richtung::Links {
-                                Richtung::Rechts
-                            } else {
-                                Richtung::Links
-                            }
-                        } else {
-                            Richtung::Gerade
-                        }
-                    },
+                    Gleise::steuerung_dreiwege_weiche,
                 ),
-                AnyId::KurvenWeiche(id) => self.weiche_stellen(
+            Message::KurvenWeicheAnschlüsseAnpassen(id, anschlüsse_save) => self
+                .gleis_anschlüsse_anpassen(
                     "KurvenWeiche",
                     id,
+                    anschlüsse_save,
                     Gleise::steuerung_kurven_weiche,
-                    |aktuelle_richtung, _letzte_richtung| {
-                        use gleis::weiche::kurve::Richtung;
-                        if aktuelle_richtung == &Richtung::Außen {
-                            Richtung::Innen
-                        } else {
-                            Richtung::Außen
-                        }
-                    },
                 ),
-                AnyId::SKurvenWeiche(id) => self.weiche_stellen(
+            Message::SKurvenWeicheAnschlüsseAnpassen(id, anschlüsse_save) => self
+                .gleis_anschlüsse_anpassen(
                     "SKurvenWeiche",
                     id,
+                    anschlüsse_save,
                     Gleise::steuerung_s_kurven_weiche,
-                    |aktuelle_richtung, _letzte_richtung| {
-                        use gleis::weiche::gerade::Richtung;
-                        if aktuelle_richtung == &Richtung::Gerade {
-                            Richtung::Kurve
-                        } else {
-                            Richtung::Gerade
-                        }
-                    },
                 ),
-                AnyId::Kreuzung(id) => self.weiche_stellen(
+            Message::KreuzungAnschlüsseAnpassen(id, anschlüsse_save) => self
+                .gleis_anschlüsse_anpassen(
                     "Kreuzung",
                     id,
+                    anschlüsse_save,
                     Gleise::steuerung_kreuzung,
-                    |aktuelle_richtung, _letzte_richtung| {
-                        use gleis::weiche::gerade::Richtung;
-                        if aktuelle_richtung == &Richtung::Gerade {
-                            Richtung::Kurve
-                        } else {
-                            Richtung::Gerade
+                ),
+            Message::FahrenAktion(any_id) => self.fahren_aktion(any_id),
+            Message::Nothalt => self.nothalt(),
+            Message::NothaltAufheben => match self.anschlüsse.ausgänge_freigeben() {
+                Ok(()) => self.nothalt = false,
+                Err(error) => self.zeige_message_box(
+                    "Ausgänge nicht freigegeben".to_string(),
+                    format!("{:?}", error),
+                ),
+            },
+            Message::Wiederverbunden => {
+                self.nothalt();
+                self.zeige_message_box(
+                    "Verbindung wiederhergestellt".to_string(),
+                    "Alle Ausgänge des entfernten Raspberry Pi bleiben bis zum Aufheben des \
+                    Nothalts im sicheren Zustand."
+                        .to_string(),
+                )
+            },
+            Message::ZeigeAuswahlStellpult => {
+                *self.modal_state.inner_mut() = Modal::Stellpult(stellpult::AuswahlStatus::neu(
+                    self.stellpult.iter(),
+                    self.stellpult_aktionen(),
+                ));
+                self.modal_state.show(true);
+            }
+            Message::HinzufügenStellpultTaster(name, taster_save) => {
+                let bisher = self.stellpult.remove(&name);
+                match self.ersetze_stellpult_taster(&name, bisher, taster_save) {
+                    Ok(taster) => {
+                        let aktionen = self.stellpult_aktionen();
+                        match self.modal_state.inner_mut() {
+                            Modal::Stellpult(stellpult_auswahl) => {
+                                stellpult_auswahl.hinzufügen(&name, &taster)
+                            }
+                            modal => {
+                                error!("Falscher Modal-State bei HinzufügenStellpultTaster!");
+                                *modal = Modal::Stellpult(stellpult::AuswahlStatus::neu(
+                                    self.stellpult.iter(),
+                                    aktionen,
+                                ));
+                            }
+                        }
+                        self.stellpult.insert(name, taster);
+                    }
+                    Err((bisher, nachricht)) => {
+                        if let Some(bisher) = bisher {
+                            self.stellpult.insert(name, bisher);
+                        }
+                        self.zeige_message_box("Hinzufügen Stellpult-Taster".to_string(), nachricht)
+                    }
+                }
+            }
+            Message::LöscheStellpultTaster(name) => {
+                if let Some(mut taster) = self.stellpult.remove(&name) {
+                    if let Err(error) = taster.clear_async_interrupt() {
+                        error!("Interrupt für Taster {} nicht entfernt: {:?}", name.0, error)
+                    }
+                }
+                let aktionen = self.stellpult_aktionen();
+                match self.modal_state.inner_mut() {
+                    Modal::Stellpult(stellpult_auswahl) => stellpult_auswahl.entferne(&name),
+                    modal => {
+                        error!("Falscher Modal-State bei LöscheStellpultTaster!");
+                        *modal = Modal::Stellpult(stellpult::AuswahlStatus::neu(
+                            self.stellpult.iter(),
+                            aktionen,
+                        ));
+                    }
+                }
+            }
+            Message::StellpultAktion(aktion) => self.stellpult_aktion(aktion),
+            Message::ZeigeAuswahlDrehregler => {
+                *self.modal_state.inner_mut() = Modal::Drehregler(drehregler::AuswahlStatus::neu(
+                    self.drehregler.iter(),
+                    self.geschwindigkeiten.keys(),
+                ));
+                self.modal_state.show(true);
+            }
+            Message::HinzufügenDrehregler(name, drehregler_save) => {
+                // bisherigen Drehregler freigeben, damit der Adc-Kanal verfügbar ist
+                self.drehregler.remove(&name);
+                let besitzer = Besitzer::Drehregler(name.0.clone());
+                match drehregler_save.reserviere_für(&mut self.anschlüsse, besitzer) {
+                    Ok(mut drehregler) => {
+                        aktiviere_drehregler(name.clone(), &mut drehregler, self.kanal.sender());
+                        match self.modal_state.inner_mut() {
+                            Modal::Drehregler(drehregler_auswahl) => {
+                                drehregler_auswahl.hinzufügen(&name, &drehregler)
+                            }
+                            modal => {
+                                error!("Falscher Modal-State bei HinzufügenDrehregler!");
+                                *modal = Modal::Drehregler(drehregler::AuswahlStatus::neu(
+                                    self.drehregler.iter(),
+                                    self.geschwindigkeiten.keys(),
+                                ));
+                            }
+                        }
+                        self.drehregler.insert(name, drehregler);
+                    }
+                    Err(error) => self.zeige_message_box(
+                        "Hinzufügen Drehregler".to_string(),
+                        format!("Fehler beim Hinzufügen: {:?}", error),
+                    ),
+                }
+            }
+            Message::LöscheDrehregler(name) => {
+                self.drehregler.remove(&name);
+                match self.modal_state.inner_mut() {
+                    Modal::Drehregler(drehregler_auswahl) => drehregler_auswahl.entferne(&name),
+                    modal => {
+                        error!("Falscher Modal-State bei LöscheDrehregler!");
+                        *modal = Modal::Drehregler(drehregler::AuswahlStatus::neu(
+                            self.drehregler.iter(),
+                            self.geschwindigkeiten.keys(),
+                        ));
+                    }
+                }
+            }
+            Message::DrehreglerWert(name, position) => self.drehregler_wert(name, position),
+            Message::ZeigeHardware => {
+                *self.modal_state.inner_mut() =
+                    Modal::Hardware(hardware::Status::neu(&mut self.anschlüsse));
+                self.modal_state.show(true);
+            }
+            Message::HardwareScannen => match self.modal_state.inner_mut() {
+                Modal::Hardware(status) => status.scanne(&mut self.anschlüsse),
+                modal => {
+                    error!("Falscher Modal-State bei HardwareScannen!");
+                    *modal = Modal::Hardware(hardware::Status::neu(&mut self.anschlüsse));
+                }
+            },
+            Message::ZeigeZuordnung => {
+                *self.modal_state.inner_mut() =
+                    Modal::Zuordnung(zuordnung::Status::neu(&self.letzte_zuordnung));
+                self.modal_state.show(true);
+            },
+            Message::Zuordnen(zuordnung) => self.zuordnen(zuordnung, false),
+            Message::ZuordnenTrotzdem(zuordnung) => {
+                self.message_box.show(false);
+                self.zuordnen(zuordnung, true)
+            },
+            Message::ZeigeImportieren => {
+                *self.modal_state.inner_mut() = Modal::Importieren(importieren::Status::neu());
+                self.modal_state.show(true);
+            },
+            Message::ImportierenLesen(pfad) => self.importieren_lesen(pfad),
+            Message::Importieren(einstellungen) => self.importieren(einstellungen, false),
+            Message::ImportierenTrotzdem(einstellungen) => {
+                self.message_box.show(false);
+                self.importieren(einstellungen, true)
+            },
+            Message::ZeigeAuswahlSkripte => {
+                let skripte_aktiv = &self.skripte_aktiv;
+                *self.modal_state.inner_mut() = Modal::Skripte(skript::AuswahlStatus::neu(
+                    self.gleise.skripte().iter(),
+                    |name| skripte_aktiv.get(name).map_or(false, skript::Ausführung::aktiv),
+                ));
+                self.modal_state.show(true);
+            },
+            Message::HinzufügenSkript(name, pfad) => {
+                let gelesen = std::fs::read_to_string(&pfad)
+                    .map_err(|error| format!("Fehler beim Lesen von {}: {}", pfad, error))
+                    .and_then(|quelltext| {
+                        skript::prüfe(&quelltext)
+                            .map(|()| quelltext)
+                            .map_err(|error| format!("Fehler im Skript {}: {}", pfad, error))
+                    });
+                match gelesen {
+                    Ok(quelltext) => {
+                        let aktiv =
+                            self.skripte_aktiv.get(&name).map_or(false, skript::Ausführung::aktiv);
+                        match self.modal_state.inner_mut() {
+                            Modal::Skripte(status) => status.hinzufügen(&name, &quelltext, aktiv),
+                            _modal => error!("Falscher Modal-State bei HinzufügenSkript!"),
                         }
+                        let _ = self.gleise.neues_skript(name, quelltext);
                     },
-                ),
+                    Err(nachricht) => {
+                        self.zeige_message_box(format!("Skript {}", name.0), nachricht)
+                    },
+                }
+            },
+            Message::LöscheSkript(name) => {
+                if let Some(ausführung) = self.skripte_aktiv.remove(&name) {
+                    ausführung.abbrechen()
+                }
+                let _ = self.gleise.entferne_skript(&name);
+                match self.modal_state.inner_mut() {
+                    Modal::Skripte(status) => status.entferne(&name),
+                    _modal => error!("Falscher Modal-State bei LöscheSkript!"),
+                }
+            },
+            Message::StarteSkript(name) => match self.starte_skript(name.clone()) {
+                Ok(()) => match self.modal_state.inner_mut() {
+                    Modal::Skripte(status) => status.setze_aktiv(&name, true),
+                    _modal => error!("Falscher Modal-State bei StarteSkript!"),
+                },
+                Err(nachricht) => self.zeige_message_box(format!("Skript {}", name.0), nachricht),
+            },
+            Message::StoppeSkript(name) => {
+                if let Some(ausführung) = self.skripte_aktiv.get(&name) {
+                    ausführung.abbrechen()
+                }
+            },
+            Message::SkriptBeendet(name, ende) => {
+                // das Skript könnte inzwischen neu gestartet worden sein
+                if !self.skripte_aktiv.get(&name).map_or(false, skript::Ausführung::aktiv) {
+                    let _ = self.skripte_aktiv.remove(&name);
+                    if let Modal::Skripte(status) = self.modal_state.inner_mut() {
+                        status.setze_aktiv(&name, false)
+                    }
+                }
+                match ende {
+                    skript::Ende::Fehler(nachricht) => {
+                        self.zeige_message_box(format!("Skript {}", name.0), nachricht)
+                    },
+                    ende => debug!("Skript {} beendet: {}", name.0, ende),
+                }
+            },
+            Message::Kontakt(name, level) => {
+                debug!("Kontakt {} ausgelöst: {:?}", name.0, level);
+                for ausführung in self.skripte_aktiv.values() {
+                    ausführung.kontakt(&name)
+                }
+                let ereignis = netzwerk::Ereignis::Kontakt { name, level };
+                if let Some(regel_ereignis) = ereignis.regel_ereignis() {
+                    command = self.regeln_auslösen(&regel_ereignis)
+                }
+                if let Some(brücke) = &self.mqtt {
+                    brücke.melde(&ereignis)
+                }
+                if let Some(server) = &self.netzwerk {
+                    server.melde(ereignis)
+                }
+            },
+            Message::ZeigeAuswahlRegeln => {
+                *self.modal_state.inner_mut() =
+                    Modal::Regeln(regel::AuswahlStatus::neu(self.gleise.regeln().iter()));
+                self.modal_state.show(true);
+            },
+            Message::HinzufügenRegel(name, regel) => {
+                match self.modal_state.inner_mut() {
+                    Modal::Regeln(status) => status.hinzufügen(&name, &regel),
+                    _modal => error!("Falscher Modal-State bei HinzufügenRegel!"),
+                }
+                // ohne Regeln wird der Zustand nicht aktualisiert
+                self.regel_zustand = self.netzwerk_zustand();
+                let _ = self.gleise.neue_regel(name, regel);
+                self.regel_takte_starten();
+            },
+            Message::LöscheRegel(name) => {
+                let _ = self.gleise.entferne_regel(&name);
+                let _ = self.regel_takte.remove(&name);
+                match self.modal_state.inner_mut() {
+                    Modal::Regeln(status) => status.entferne(&name),
+                    _modal => error!("Falscher Modal-State bei LöscheRegel!"),
+                }
             },
+            Message::RegelTakt(name) => command = self.regel_ausführen(&name),
+            Message::RegelAktion(name, aktion) => command = self.regel_aktion(&name, aktion),
+            Message::AnfahrimpulsEnde(name) => self.anfahrimpuls_beenden(name),
+            Message::Netzwerk(anfrage) => command = self.netzwerk_anfrage(anfrage),
+        }
+
+        if !self.gleise.regeln().is_empty() {
+            let zustand = self.netzwerk_zustand();
+            if regeln_auswerten {
+                let ereignisse: Vec<_> = self
+                    .regel_zustand
+                    .änderungen(&zustand)
+                    .iter()
+                    .filter_map(netzwerk::Ereignis::regel_ereignis)
+                    .collect();
+                let mut commands = vec![command];
+                for ereignis in ereignisse {
+                    commands.push(self.regeln_auslösen(&ereignis))
+                }
+                command = iced::Command::batch(commands);
+            }
+            self.regel_zustand = zustand;
+        }
+
+        self.anfahrimpulse_planen();
+
+        if reservierungen_aktualisieren {
+            if let Ok(reservierungen) = self.anschlüsse.reservierungen() {
+                self.modal_state.inner_mut().setze_reservierungen(&reservierungen)
+            }
+        }
+
+        if self.netzwerk.is_some() || self.mqtt.is_some() {
+            let zustand = self.netzwerk_zustand();
+            if let Some(brücke) = &self.mqtt {
+                brücke.aktualisiere(zustand.clone())
+            }
+            if let Some(server) = &mut self.netzwerk {
+                server.aktualisiere(zustand)
+            }
         }
 
         command
     }
+}
+
+impl<Z> iced::Application for Zugkontrolle<Z>
+where
+    Z: 'static + Zugtyp + Debug + PartialEq + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+    Z::Leiter: Debug,
+    <<Z as Zugtyp>::Leiter as ToSave>::Save: Debug + Clone + Send + Zuordnen + Verkabeln,
+    <<Z as Zugtyp>::Leiter as LeiterAnzeige>::Fahrtrichtung: Debug,
+{
+    type Executor = iced::executor::Default;
+    type Flags = (
+        Anschlüsse,
+        Option<String>,
+        Option<String>,
+        Option<Modus>,
+        Option<u8>,
+        Option<String>,
+        Option<String>,
+        Option<mqtt::Einstellungen>,
+        Vec<String>,
+    );
+    type Message = Message<Z>;
+
+    fn new(
+        (
+            mut anschlüsse,
+            pfad_arg,
+            profil_arg,
+            modus_arg,
+            nothalt_pin,
+            netzwerk_adresse,
+            web_adresse,
+            mqtt_einstellungen,
+            skripte_arg,
+        ): Self::Flags,
+    ) -> (Self, iced::Command<Self::Message>) {
+        let mut gleise = Gleise::neu();
+        if let Some(modus) = modus_arg {
+            gleise.moduswechsel(modus)
+        }
+        let auswahl_status = streckenabschnitt::AuswahlStatus::neu(gleise.streckenabschnitte());
+        let command = if pfad_arg.is_some() {
+            iced::Command::perform(Message::laden(), identity)
+        } else {
+            iced::Command::none()
+        };
+        let kanal = kanal::Kanal::neu();
+        let mut nothalt_fehler = None;
+        let nothalt_taster = nothalt_pin.and_then(|pin| {
+            match reserviere_nothalt_taster(&mut anschlüsse, pin, kanal.sender()) {
+                Ok(taster) => Some(taster),
+                Err(error) => {
+                    nothalt_fehler = Some(format!("Nothalt-Taster an Pin {}: {:?}", pin, error));
+                    None
+                }
+            }
+        });
+        let wiederverbunden_sender = kanal.sender();
+        let wiederverbunden_fehler = anschlüsse
+            .bei_wiederverbindung(move || {
+                if let Err(_nachricht) = wiederverbunden_sender.send(Message::Wiederverbunden) {
+                    error!("Wiederverbindung konnte nicht gemeldet werden!")
+                }
+            })
+            .err();
+        let mut netzwerk_fehler = None;
+        let netzwerk = netzwerk_adresse.and_then(|adresse| {
+            let sender = kanal.sender();
+            let weiterleiten = move |anfrage| {
+                if let Err(_nachricht) = sender.send(Message::Netzwerk(anfrage)) {
+                    error!("Netzwerk-Nachricht konnte nicht gesendet werden!")
+                }
+            };
+            match netzwerk::Server::starte(&adresse, weiterleiten) {
+                Ok(server) => Some(server),
+                Err(error) => {
+                    netzwerk_fehler = Some(format!("Netzwerk-Steuerung an {}: {}", adresse, error));
+                    None
+                }
+            }
+        });
+        let web_fehler = web_adresse.and_then(|adresse| match &netzwerk {
+            Some(server) => web::starte(&adresse, server.adresse())
+                .err()
+                .map(|error| format!("Browser-Steuerung an {}: {}", adresse, error)),
+            None => Some("Die Browser-Steuerung benötigt die Netzwerk-Steuerung!".to_string()),
+        });
+        let mut mqtt_fehler = None;
+        let mqtt = mqtt_einstellungen.and_then(|einstellungen| {
+            let sender = kanal.sender();
+            let weiterleiten = move |anfrage| {
+                if let Err(_nachricht) = sender.send(Message::Netzwerk(anfrage)) {
+                    error!("MQTT-Nachricht konnte nicht gesendet werden!")
+                }
+            };
+            let adresse = einstellungen.adresse.clone();
+            match mqtt::Brücke::starte(einstellungen, weiterleiten) {
+                Ok(brücke) => Some(brücke),
+                Err(error) => {
+                    mqtt_fehler = Some(format!("MQTT-Broker {}: {:?}", adresse, error));
+                    None
+                }
+            }
+        });
+        let mut zugkontrolle = Zugkontrolle {
+            anschlüsse,
+            gleise,
+            scrollable_state: iced::scrollable::State::new(),
+            geraden: Z::geraden().into_iter().map(Button::new).collect(),
+            kurven: Z::kurven().into_iter().map(Button::new).collect(),
+            weichen: Z::weichen().into_iter().map(Button::new).collect(),
+            dreiwege_weichen: Z::dreiwege_weichen().into_iter().map(Button::new).collect(),
+            kurven_weichen: Z::kurven_weichen().into_iter().map(Button::new).collect(),
+            s_kurven_weichen: Z::s_kurven_weichen().into_iter().map(Button::new).collect(),
+            kreuzungen: Z::kreuzungen().into_iter().map(Button::new).collect(),
+            geschwindigkeiten: BTreeMap::new(),
+            modal_state: iced_aw::modal::State::new(Modal::Streckenabschnitt(auswahl_status)),
+            streckenabschnitt_aktuell: streckenabschnitt::AnzeigeStatus::neu(),
+            streckenabschnitt_aktuell_festlegen: false,
+            geschwindigkeit_button_state: iced::button::State::new(),
+            message_box: iced_aw::modal::State::new(MessageBox {
+                titel: "Nicht initialisiert".to_string(),
+                nachricht: "Diese Nachricht sollte nicht sichtbar sein!".to_string(),
+                button_state: iced::button::State::new(),
+                trotzdem: None,
+            }),
+            oben: iced::button::State::new(),
+            unten: iced::button::State::new(),
+            links: iced::button::State::new(),
+            rechts: iced::button::State::new(),
+            clockwise: iced::button::State::new(),
+            counter_clockwise: iced::button::State::new(),
+            größer: iced::button::State::new(),
+            kleiner: iced::button::State::new(),
+            speichern: iced::button::State::new(),
+            laden: iced::button::State::new(),
+            pfad: iced::text_input::State::new(),
+            aktueller_pfad: pfad_arg.unwrap_or(format!("{}.zug", Z::NAME)),
+            profil_pfad: iced::text_input::State::new(),
+            aktueller_profil_pfad: profil_arg.unwrap_or_default(),
+            nothalt: false,
+            nothalt_button_state: iced::button::State::new(),
+            nothalt_taster,
+            kanal,
+            stellpult: BTreeMap::new(),
+            stellpult_button_state: iced::button::State::new(),
+            drehregler: BTreeMap::new(),
+            drehregler_button_state: iced::button::State::new(),
+            hardware_button_state: iced::button::State::new(),
+            zuordnung_button_state: iced::button::State::new(),
+            importieren_button_state: iced::button::State::new(),
+            skripte_aktiv: BTreeMap::new(),
+            skripte_nach_laden: skripte_arg.into_iter().map(skript::Name).collect(),
+            skripte_button_state: iced::button::State::new(),
+            regeln_button_state: iced::button::State::new(),
+            regel_takte: BTreeMap::new(),
+            regel_verzögerungen: Vec::new(),
+            anfahrimpulse: BTreeMap::new(),
+            regel_zustand: netzwerk::Zustand::default(),
+            letzte_zuordnung: Zuordnung::default(),
+            netzwerk,
+            mqtt,
+        };
+        if let Some(fehler) = nothalt_fehler {
+            zugkontrolle.zeige_message_box("Nothalt-Taster".to_string(), fehler)
+        }
+        if let Some(error) = wiederverbunden_fehler {
+            zugkontrolle
+                .zeige_message_box("Entfernter Raspberry Pi".to_string(), format!("{:?}", error))
+        }
+        if let Some(fehler) = netzwerk_fehler {
+            zugkontrolle.zeige_message_box("Netzwerk-Steuerung".to_string(), fehler)
+        }
+        if let Some(fehler) = web_fehler {
+            zugkontrolle.zeige_message_box("Browser-Steuerung".to_string(), fehler)
+        }
+        if let Some(fehler) = mqtt_fehler {
+            zugkontrolle.zeige_message_box("MQTT".to_string(), fehler)
+        }
+        (zugkontrolle, command)
+    }
+
+    fn title(&self) -> String {
+        format!("Zugkontrolle {}", version!())
+    }
+
+    fn update(
+        &mut self,
+        message: Self::Message,
+        _clipboard: &mut iced::Clipboard,
+    ) -> iced::Command<Self::Message> {
+        self.bearbeite(message)
+    }
+
+    fn subscription(&self) -> iced::Subscription<Self::Message> {
+        iced::Subscription::batch(vec![
+            self.kanal.subscription(),
+            iced_native::subscription::events_with(nothalt_tastatur::<Z>),
+        ])
+    }
 
     fn view(&mut self) -> iced::Element<Self::Message> {
         let Zugkontrolle {
@@ -1005,6 +2614,30 @@ where
             laden,
             pfad,
             aktueller_pfad,
+            profil_pfad,
+            aktueller_profil_pfad,
+            nothalt,
+            nothalt_button_state,
+            nothalt_taster: _,
+            kanal: _,
+            stellpult: _,
+            stellpult_button_state,
+            drehregler: _,
+            drehregler_button_state,
+            hardware_button_state,
+            zuordnung_button_state,
+            importieren_button_state,
+            skripte_aktiv: _,
+            skripte_nach_laden: _,
+            skripte_button_state,
+            regeln_button_state,
+            regel_takte: _,
+            regel_verzögerungen: _,
+            anfahrimpulse: _,
+            regel_zustand: _,
+            letzte_zuordnung: _,
+            netzwerk: _,
+            mqtt: _,
         } = self;
         let aktueller_modus = gleise.modus();
 
@@ -1025,6 +2658,17 @@ where
             laden,
             pfad,
             aktueller_pfad,
+            profil_pfad,
+            aktueller_profil_pfad,
+            *nothalt,
+            nothalt_button_state,
+            stellpult_button_state,
+            drehregler_button_state,
+            hardware_button_state,
+            zuordnung_button_state,
+            importieren_button_state,
+            regeln_button_state,
+            skripte_button_state,
         );
         let row_with_scrollable = row_with_scrollable(
             aktueller_modus,
@@ -1086,6 +2730,70 @@ where
                     Löschen(name) => Message::LöscheGeschwindigkeit(name),
                 }
             }),
+            Modal::Stellpult(stellpult_auswahl) => {
+                iced::Element::from(stellpult::Auswahl::neu(stellpult_auswahl)).map(|message| {
+                    use stellpult::AuswahlNachricht::*;
+                    match message {
+                        Schließe => Message::SchließeModal,
+                        Hinzufügen(name, taster) => {
+                            Message::HinzufügenStellpultTaster(name, taster)
+                        }
+                        Lösche(name) => Message::LöscheStellpultTaster(name),
+                    }
+                })
+            }
+            Modal::Drehregler(drehregler_auswahl) => {
+                iced::Element::from(drehregler::Auswahl::neu(drehregler_auswahl)).map(|message| {
+                    use drehregler::AuswahlNachricht::*;
+                    match message {
+                        Schließe => Message::SchließeModal,
+                        Hinzufügen(name, drehregler) => {
+                            Message::HinzufügenDrehregler(name, drehregler)
+                        }
+                        Lösche(name) => Message::LöscheDrehregler(name),
+                    }
+                })
+            }
+            Modal::Hardware(status) => {
+                iced::Element::from(hardware::Anzeige::neu(status)).map(|message| match message {
+                    hardware::Nachricht::Schließe => Message::SchließeModal,
+                    hardware::Nachricht::Scannen => Message::HardwareScannen,
+                })
+            }
+            Modal::Zuordnung(status) => {
+                iced::Element::from(zuordnung::Auswahl::neu(status)).map(|message| match message {
+                    zuordnung::Nachricht::Schließe => Message::SchließeModal,
+                    zuordnung::Nachricht::Anwenden(zuordnung) => Message::Zuordnen(zuordnung),
+                })
+            }
+            Modal::Importieren(status) => iced::Element::from(importieren::Auswahl::neu(status))
+                .map(|message| match message {
+                    importieren::Nachricht::Schließe => Message::SchließeModal,
+                    importieren::Nachricht::Lesen(pfad) => Message::ImportierenLesen(pfad),
+                    importieren::Nachricht::Importieren(einstellungen) => {
+                        Message::Importieren(einstellungen)
+                    },
+                }),
+            Modal::Skripte(status) => {
+                iced::Element::from(skript::Auswahl::neu(status)).map(|message| match message {
+                    skript::AuswahlNachricht::Schließe => Message::SchließeModal,
+                    skript::AuswahlNachricht::Hinzufügen(name, pfad) => {
+                        Message::HinzufügenSkript(name, pfad)
+                    },
+                    skript::AuswahlNachricht::Lösche(name) => Message::LöscheSkript(name),
+                    skript::AuswahlNachricht::Starte(name) => Message::StarteSkript(name),
+                    skript::AuswahlNachricht::Stoppe(name) => Message::StoppeSkript(name),
+                })
+            },
+            Modal::Regeln(status) => {
+                iced::Element::from(regel::Auswahl::neu(status)).map(|message| match message {
+                    regel::AuswahlNachricht::Schließe => Message::SchließeModal,
+                    regel::AuswahlNachricht::Hinzufügen(name, regel) => {
+                        Message::HinzufügenRegel(name, regel)
+                    },
+                    regel::AuswahlNachricht::Lösche(name) => Message::LöscheRegel(name),
+                })
+            },
             Modal::Weiche(status, als_message) => {
                 let als_message_clone = als_message.clone();
                 iced::Element::from(weiche::Auswahl::neu(status)).map(move |message| {
@@ -1119,18 +2827,32 @@ where
         })
         .on_esc(Message::SchließeModal);
 
-        iced_aw::Modal::new(message_box, modal, |MessageBox { titel, nachricht, button_state }| {
-            iced::Element::from(
-                iced_aw::Card::new(
-                    iced::Text::new(&*titel),
-                    iced::Column::new().push(iced::Text::new(&*nachricht)).push(
-                        iced::Button::new(button_state, iced::Text::new("Ok"))
-                            .on_press(Message::SchließeMessageBox),
-                    ),
+        iced_aw::Modal::new(
+            message_box,
+            modal,
+            |MessageBox { titel, nachricht, button_state, trotzdem }| {
+                let mut buttons = iced::Row::new().spacing(5).push(
+                    iced::Button::new(button_state, iced::Text::new("Ok"))
+                        .on_press(Message::SchließeMessageBox),
+                );
+                if let Some((trotzdem, trotzdem_button_state)) = trotzdem {
+                    buttons = buttons.push(
+                        iced::Button::new(
+                            trotzdem_button_state,
+                            iced::Text::new(trotzdem.beschriftung()),
+                        )
+                        .on_press(trotzdem.message::<Z>()),
+                    )
+                }
+                iced::Element::from(
+                    iced_aw::Card::new(
+                        iced::Text::new(&*titel),
+                        iced::Column::new().push(iced::Text::new(&*nachricht)).push(buttons),
+                    )
+                    .width(iced::Length::Shrink),
                 )
-                .width(iced::Length::Shrink),
-            )
-        })
+            },
+        )
         .on_esc(Message::SchließeMessageBox)
         .into()
     }
@@ -1153,6 +2875,17 @@ fn top_row<'t, Z>(
     laden: &'t mut iced::button::State,
     pfad: &'t mut iced::text_input::State,
     aktueller_pfad: &'t str,
+    profil_pfad: &'t mut iced::text_input::State,
+    aktueller_profil_pfad: &'t str,
+    nothalt: bool,
+    nothalt_button_state: &'t mut iced::button::State,
+    stellpult_button_state: &'t mut iced::button::State,
+    drehregler_button_state: &'t mut iced::button::State,
+    hardware_button_state: &'t mut iced::button::State,
+    zuordnung_button_state: &'t mut iced::button::State,
+    importieren_button_state: &'t mut iced::button::State,
+    regeln_button_state: &'t mut iced::button::State,
+    skripte_button_state: &'t mut iced::button::State,
 ) -> iced::Row<'t, Message<Z>>
 where
     Z: 'static + Zugtyp,
@@ -1196,20 +2929,44 @@ where
                 .align_items(iced::Align::End),
         )
         .push(
-            iced::TextInput::new(pfad, "pfad", aktueller_pfad, Message::Pfad)
-                .width(iced::Length::Units(250))
-                .padding(1),
+            iced::Column::new()
+                .push(
+                    iced::TextInput::new(pfad, "pfad", aktueller_pfad, Message::Pfad)
+                        .width(iced::Length::Units(250))
+                        .padding(1),
+                )
+                .push(
+                    iced::TextInput::new(
+                        profil_pfad,
+                        "profil (optional)",
+                        aktueller_profil_pfad,
+                        Message::ProfilPfad,
+                    )
+                    .width(iced::Length::Units(250))
+                    .padding(1),
+                ),
         )
         .spacing(5)
         .align_items(iced::Align::Center)
         .width(iced::Length::Shrink);
+    let nothalt_button = if nothalt {
+        iced::Button::new(nothalt_button_state, iced::Text::new("Nothalt aufheben"))
+            .on_press(Message::NothaltAufheben)
+            .style(style::button::NothaltAufheben)
+    } else {
+        iced::Button::new(nothalt_button_state, iced::Text::new("NOTHALT").size(30))
+            .on_press(Message::Nothalt)
+            .style(style::button::Nothalt)
+    };
     let mut row = iced::Row::new()
+        .push(nothalt_button)
         .push(modus_radios.mit_teil_nachricht(Message::Modus))
         .push(move_buttons.mit_teil_nachricht(Message::Bewegen))
         .push(drehen_buttons.mit_teil_nachricht(Message::Drehen))
         .push(skalieren_buttons.mit_teil_nachricht(Message::Skalieren));
 
-    // Streckenabschnitte und Geschwindigkeiten können nur im Bauen-Modus geändert werden
+    // Streckenabschnitte, Geschwindigkeiten, Stellpult, Drehregler und Regeln
+    // können nur im Bauen-Modus geändert werden, dort wird auch die Hardware angezeigt
     if let Modus::Bauen { .. } = aktueller_modus {
         row = row
             .push(
@@ -1232,8 +2989,37 @@ where
                     iced::Text::new("Geschwindigkeiten"),
                 )
                 .on_press(Message::ZeigeAuswahlGeschwindigkeit),
+            )
+            .push(
+                iced::Button::new(stellpult_button_state, iced::Text::new("Stellpult"))
+                    .on_press(Message::ZeigeAuswahlStellpult),
+            )
+            .push(
+                iced::Button::new(drehregler_button_state, iced::Text::new("Drehregler"))
+                    .on_press(Message::ZeigeAuswahlDrehregler),
+            )
+            .push(
+                iced::Button::new(hardware_button_state, iced::Text::new("Hardware"))
+                    .on_press(Message::ZeigeHardware),
+            )
+            .push(
+                iced::Button::new(zuordnung_button_state, iced::Text::new("Zuordnung"))
+                    .on_press(Message::ZeigeZuordnung),
+            )
+            .push(
+                iced::Button::new(importieren_button_state, iced::Text::new("Importieren"))
+                    .on_press(Message::ZeigeImportieren),
+            )
+            .push(
+                iced::Button::new(regeln_button_state, iced::Text::new("Regeln"))
+                    .on_press(Message::ZeigeAuswahlRegeln),
             );
     }
+    // Skripte können auch im Fahren-Modus gestartet und gestoppt werden
+    row = row.push(
+        iced::Button::new(skripte_button_state, iced::Text::new("Skripte"))
+            .on_press(Message::ZeigeAuswahlSkripte),
+    );
 
     row.push(iced::Space::new(iced::Length::Fill, iced::Length::Shrink))
         .push(speichern_laden)
@@ -4,63 +4,144 @@ use std::collections::HashMap;
 
 use iced_aw::native::{number_input, tab_bar, tabs, NumberInput, TabLabel, Tabs};
 use iced_native::{
-    button, column, container, event, radio, row, text, Clipboard, Column, Element, Event, Layout,
-    Length, Point, Radio, Renderer, Row, Text, Widget,
+    button, column, container, event, radio, row, text, Button, Clipboard, Color, Column, Element,
+    Event, Layout, Length, Point, Radio, Renderer, Row, Text, Widget,
 };
 
 use super::{macros::reexport_no_event_methods, style::tab_bar::TabBar};
 use crate::anschluss::{
-    level::Level, pcf8574::Variante, pin::pwm, polarity::Polarität, InputSave, OutputSave,
+    anschlüsse::{Reservierungen, Verfügbarkeit},
+    i2c::{self, Bus},
+    level::Level,
+    mcp23017, pca9685,
+    pcf8574::{self, Variante},
+    pin::pwm,
+    polarity::Polarität,
+    InputSave, OutputSave,
 };
 
+const PIN_TAB: usize = 0;
+const PCF8574_TAB: usize = 1;
+const MCP23017_TAB: usize = 2;
+
 /// Status eines Widgets zur Auswahl eines Anschlusses.
 #[derive(Debug)]
 pub struct Status<T> {
     active_tab: usize,
     pin_state: number_input::State,
     pin: u8,
+    bus: u8,
     a0: Level,
     a1: Level,
     a2: Level,
     variante: Variante,
+    pcf8574_bus_state: number_input::State,
     port_state: number_input::State,
     port: u8,
+    mcp23017_bus_state: number_input::State,
+    mcp23017_port_state: number_input::State,
+    mcp23017_port: u8,
     modus: T,
+    reservierungen: Reservierungen,
+    pin_frei_state: button::State,
+    pcf8574_frei_state: button::State,
+    mcp23017_frei_state: button::State,
+}
+
+/// Baustein mit eigenem Interrupt-Pin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InterruptBaustein {
+    Pcf8574(pcf8574::Adresse),
+    /// Jede Bank eines Mcp23017 hat einen eigenen Interrupt-Ausgang.
+    Mcp23017(mcp23017::Adresse, mcp23017::Bank),
+}
+
+/// Bausteine mit bereits festgelegtem Interrupt-Pin.
+pub type InterruptPins = HashMap<InterruptBaustein, u8>;
+
+/// Interrupt-Pin und Pull-up (nur Mcp23017) eines Input-Anschlusses.
+#[derive(Debug, Clone, Copy)]
+pub struct InputEinstellungen {
+    interrupt: u8,
+    pull_up: bool,
 }
 
 #[derive(Debug, Clone)]
-pub struct Input<'t> {
+pub struct Input {
     number_input_state: number_input::State,
-    pin: u8,
-    interrupt_pins: &'t HashMap<(Level, Level, Level, Variante), u8>,
+    einstellungen: InputEinstellungen,
+    interrupt_pins: InterruptPins,
+}
+
+fn input_pcf8574_port(
+    interrupt_pins: &InterruptPins,
+    adresse: pcf8574::Adresse,
+    port: u8,
+    InputEinstellungen { interrupt, .. }: &InputEinstellungen,
+) -> InputSave {
+    let pcf8574::Adresse { bus, a0, a1, a2, variante } = adresse;
+    let baustein = InterruptBaustein::Pcf8574(adresse);
+    InputSave::Pcf8574Port {
+        bus,
+        a0,
+        a1,
+        a2,
+        variante,
+        port,
+        interrupt: if interrupt_pins.get(&baustein).is_some() { None } else { Some(*interrupt) },
+    }
+}
+
+fn input_mcp23017_port(
+    interrupt_pins: &InterruptPins,
+    adresse: mcp23017::Adresse,
+    port: u8,
+    InputEinstellungen { interrupt, pull_up }: &InputEinstellungen,
+) -> InputSave {
+    let mcp23017::Adresse { bus, a0, a1, a2 } = adresse;
+    let baustein = InterruptBaustein::Mcp23017(adresse, mcp23017::Bank::von_port(port));
+    InputSave::Mcp23017Port {
+        bus,
+        a0,
+        a1,
+        a2,
+        port,
+        pull_up: *pull_up,
+        interrupt: if interrupt_pins.get(&baustein).is_some() { None } else { Some(*interrupt) },
+    }
 }
-impl<'t> Status<Input<'t>> {
+
+impl Status<Input> {
     #[inline]
-    pub fn neu_input(interrupt_pins: &'t HashMap<(Level, Level, Level, Variante), u8>) -> Self {
+    pub fn neu_input(interrupt_pins: InterruptPins) -> Self {
         Self::neu_mit_interrupt(Input {
             number_input_state: number_input::State::new(),
-            pin: 0,
+            einstellungen: InputEinstellungen { interrupt: 0, pull_up: false },
             interrupt_pins,
         })
     }
 
     #[inline]
-    pub fn von_input_save(
-        initial: InputSave,
-        interrupt_pins: &'t HashMap<(Level, Level, Level, Variante), u8>,
-    ) -> Self {
-        let make_modus =
-            |pin: u8| Input { number_input_state: number_input::State::new(), pin, interrupt_pins };
+    pub fn von_input_save(initial: InputSave, interrupt_pins: InterruptPins) -> Self {
+        let make_modus = |interrupt: u8, pull_up: bool| Input {
+            number_input_state: number_input::State::new(),
+            einstellungen: InputEinstellungen { interrupt, pull_up },
+            interrupt_pins,
+        };
         match initial {
-            InputSave::Pin { pin } => Self::neu_mit_initial_pin(pin, make_modus(0)),
-            InputSave::Pcf8574Port { a0, a1, a2, variante, port, interrupt } => {
+            InputSave::Pin { pin } => Self::neu_mit_initial_pin(pin, make_modus(0, false)),
+            InputSave::Pcf8574Port { bus, a0, a1, a2, variante, port, interrupt } => {
                 Self::neu_mit_initial_port(
-                    a0,
-                    a1,
-                    a2,
-                    variante,
+                    pcf8574::Adresse { bus, a0, a1, a2, variante },
                     port,
-                    make_modus(interrupt.unwrap_or(0)),
+                    make_modus(interrupt.unwrap_or(0), false),
+                )
+            }
+            InputSave::Mcp23017Port { bus, a0, a1, a2, port, pull_up, interrupt } => {
+                Self::neu_mit_initial_mcp23017_port(
+                    mcp23017::Adresse { bus, a0, a1, a2 },
+                    port,
+                    make_modus(interrupt.unwrap_or(0), pull_up),
                 )
             }
         }
@@ -70,19 +151,11 @@ impl<'t> Status<Input<'t>> {
     pub fn input_anschluss(&self) -> InputSave {
         self.anschluss(
             |pin, _input| InputSave::Pin { pin },
-            |a0, a1, a2, variante, port, Input { pin, interrupt_pins, .. }| {
-                InputSave::Pcf8574Port {
-                    a0,
-                    a1,
-                    a2,
-                    variante,
-                    port,
-                    interrupt: if interrupt_pins.get(&(a0, a1, a2, variante)).is_some() {
-                        None
-                    } else {
-                        Some(*pin)
-                    },
-                }
+            |adresse, port, Input { einstellungen, interrupt_pins, .. }| {
+                input_pcf8574_port(interrupt_pins, adresse, port, einstellungen)
+            },
+            |adresse, port, Input { einstellungen, interrupt_pins, .. }| {
+                input_mcp23017_port(interrupt_pins, adresse, port, einstellungen)
             },
         )
     }
@@ -92,6 +165,18 @@ impl<'t> Status<Input<'t>> {
 pub struct Output {
     polarität: Polarität,
 }
+
+fn output_pcf8574_port(adresse: pcf8574::Adresse, port: u8, polarität: Polarität) -> OutputSave {
+    let pcf8574::Adresse { bus, a0, a1, a2, variante } = adresse;
+    OutputSave::Pcf8574Port { bus, a0, a1, a2, variante, port, polarität }
+}
+
+fn output_mcp23017_port(
+    adresse: mcp23017::Adresse, port: u8, polarität: Polarität
+) -> OutputSave {
+    let mcp23017::Adresse { bus, a0, a1, a2 } = adresse;
+    OutputSave::Mcp23017Port { bus, a0, a1, a2, port, polarität }
+}
 impl Status<Output> {
     #[inline]
     pub fn neu_output() -> Self {
@@ -104,8 +189,19 @@ impl Status<Output> {
             OutputSave::Pin { pin, polarität } => {
                 Self::neu_mit_initial_pin(pin, Output { polarität })
             }
-            OutputSave::Pcf8574Port { a0, a1, a2, variante, port, polarität } => {
-                Self::neu_mit_initial_port(a0, a1, a2, variante, port, Output { polarität })
+            OutputSave::Pcf8574Port { bus, a0, a1, a2, variante, port, polarität } => {
+                Self::neu_mit_initial_port(
+                    pcf8574::Adresse { bus, a0, a1, a2, variante },
+                    port,
+                    Output { polarität },
+                )
+            }
+            OutputSave::Mcp23017Port { bus, a0, a1, a2, port, polarität } => {
+                Self::neu_mit_initial_mcp23017_port(
+                    mcp23017::Adresse { bus, a0, a1, a2 },
+                    port,
+                    Output { polarität },
+                )
             }
         }
     }
@@ -114,98 +210,155 @@ impl Status<Output> {
     pub fn output_anschluss(&self) -> OutputSave {
         self.anschluss(
             |pin, Output { polarität }| OutputSave::Pin { pin, polarität: *polarität },
-            |a0, a1, a2, variante, port, Output { polarität }| OutputSave::Pcf8574Port {
-                a0,
-                a1,
-                a2,
-                variante,
-                port,
-                polarität: *polarität,
-            },
+            |adresse, port, Output { polarität }| output_pcf8574_port(adresse, port, *polarität),
+            |adresse, port, Output { polarität }| output_mcp23017_port(adresse, port, *polarität),
         )
     }
 }
 
 impl<T> Status<T> {
+    /// Aktualisiere die Hinweise auf bereits reservierte Anschlüsse.
+    pub fn setze_reservierungen(&mut self, reservierungen: &Reservierungen) {
+        self.reservierungen = reservierungen.clone()
+    }
+
     fn anschluss<M>(
         &self,
         make_pin: impl Fn(u8, &T) -> M,
-        make_port: impl Fn(Level, Level, Level, Variante, u8, &T) -> M,
+        make_port: impl Fn(pcf8574::Adresse, u8, &T) -> M,
+        make_mcp23017_port: impl Fn(mcp23017::Adresse, u8, &T) -> M,
     ) -> M {
-        if self.active_tab == 0 {
-            make_pin(self.pin, &self.modus)
-        } else {
-            make_port(self.a0, self.a1, self.a2, self.variante, self.port, &self.modus)
+        match self.active_tab {
+            PIN_TAB => make_pin(self.pin, &self.modus),
+            PCF8574_TAB => make_port(
+                pcf8574_adresse(self.bus, self.a0, self.a1, self.a2, self.variante),
+                self.port,
+                &self.modus,
+            ),
+            _ => make_mcp23017_port(
+                mcp23017_adresse(self.bus, self.a0, self.a1, self.a2),
+                self.mcp23017_port,
+                &self.modus,
+            ),
         }
     }
 
     fn neu_mit_interrupt(modus: T) -> Self {
-        Status {
-            active_tab: 0,
-            pin_state: number_input::State::new(),
-            pin: 0,
-            a0: Level::Low,
-            a1: Level::Low,
-            a2: Level::Low,
-            variante: Variante::Normal,
-            port_state: number_input::State::new(),
-            port: 0,
-            modus,
-        }
+        Self::neu_mit_initial_pin(0, modus)
     }
 
     fn neu_mit_initial_pin(pin: u8, modus: T) -> Self {
         Status {
-            active_tab: 0,
+            active_tab: PIN_TAB,
             pin_state: number_input::State::new(),
             pin,
+            bus: Bus::STANDARD.0,
             a0: Level::Low,
             a1: Level::Low,
             a2: Level::Low,
             variante: Variante::Normal,
+            pcf8574_bus_state: number_input::State::new(),
             port_state: number_input::State::new(),
             port: 0,
+            mcp23017_bus_state: number_input::State::new(),
+            mcp23017_port_state: number_input::State::new(),
+            mcp23017_port: 0,
             modus,
+            reservierungen: Reservierungen::default(),
+            pin_frei_state: button::State::new(),
+            pcf8574_frei_state: button::State::new(),
+            mcp23017_frei_state: button::State::new(),
         }
     }
 
     fn neu_mit_initial_port(
-        a0: Level,
-        a1: Level,
-        a2: Level,
-        variante: Variante,
+        pcf8574::Adresse { bus, a0, a1, a2, variante }: pcf8574::Adresse,
         port: u8,
         modus: T,
     ) -> Self {
         Status {
-            active_tab: 1,
+            active_tab: PCF8574_TAB,
             pin_state: number_input::State::new(),
             pin: 0,
+            bus: bus.0,
             a0,
             a1,
             a2,
             variante,
+            pcf8574_bus_state: number_input::State::new(),
             port_state: number_input::State::new(),
             port,
+            mcp23017_bus_state: number_input::State::new(),
+            mcp23017_port_state: number_input::State::new(),
+            mcp23017_port: 0,
             modus,
+            reservierungen: Reservierungen::default(),
+            pin_frei_state: button::State::new(),
+            pcf8574_frei_state: button::State::new(),
+            mcp23017_frei_state: button::State::new(),
+        }
+    }
+
+    fn neu_mit_initial_mcp23017_port(
+        mcp23017::Adresse { bus, a0, a1, a2 }: mcp23017::Adresse,
+        port: u8,
+        modus: T,
+    ) -> Self {
+        Status {
+            active_tab: MCP23017_TAB,
+            pin_state: number_input::State::new(),
+            pin: 0,
+            bus: bus.0,
+            a0,
+            a1,
+            a2,
+            variante: Variante::Normal,
+            pcf8574_bus_state: number_input::State::new(),
+            port_state: number_input::State::new(),
+            port: 0,
+            mcp23017_bus_state: number_input::State::new(),
+            mcp23017_port_state: number_input::State::new(),
+            mcp23017_port: port,
+            modus,
+            reservierungen: Reservierungen::default(),
+            pin_frei_state: button::State::new(),
+            pcf8574_frei_state: button::State::new(),
+            mcp23017_frei_state: button::State::new(),
         }
     }
 }
 
+fn pcf8574_adresse(
+    bus: u8,
+    a0: Level,
+    a1: Level,
+    a2: Level,
+    variante: Variante,
+) -> pcf8574::Adresse {
+    pcf8574::Adresse { bus: Bus(bus), a0, a1, a2, variante }
+}
+
+fn mcp23017_adresse(bus: u8, a0: Level, a1: Level, a2: Level) -> mcp23017::Adresse {
+    mcp23017::Adresse { bus: Bus(bus), a0, a1, a2 }
+}
+
 #[derive(Debug, Clone)]
 enum InternalMessage<T> {
     TabSelected(usize),
     Pin(u8),
+    Bus(u8),
     A0(Level),
     A1(Level),
     A2(Level),
     Variante(Variante),
     Port(u8),
+    Mcp23017Port(u8),
     Modus(T),
 }
 #[derive(Debug, Clone)]
 pub enum InputMessage {
     Interrupt(u8),
+    PullUp(bool),
 }
 #[derive(Debug, Clone)]
 pub enum OutputMessage {
@@ -216,18 +369,21 @@ pub struct Auswahl<'a, T, I, M, R: row::Renderer> {
     row: Row<'a, InternalMessage<I>, R>,
     active_tab: &'a mut usize,
     pin: &'a mut u8,
+    bus: &'a mut u8,
     a0: &'a mut Level,
     a1: &'a mut Level,
     a2: &'a mut Level,
     variante: &'a mut Variante,
     port: &'a mut u8,
+    mcp23017_port: &'a mut u8,
     modus: &'a mut T,
     update_modus: &'a dyn Fn(&mut T, I),
     make_pin: &'a dyn Fn(u8, &T) -> M,
-    make_port: Box<dyn Fn(Level, Level, Level, Variante, u8, &T) -> M>,
+    make_port: Box<dyn Fn(pcf8574::Adresse, u8, &T) -> M>,
+    make_mcp23017_port: Box<dyn Fn(mcp23017::Adresse, u8, &T) -> M>,
 }
 
-impl<'a, R> Auswahl<'a, u8, InputMessage, InputSave, R>
+impl<'a, R> Auswahl<'a, InputEinstellungen, InputMessage, InputSave, R>
 where
     R: 'a
         + Renderer
@@ -241,34 +397,47 @@ where
         + tabs::Renderer,
     <R as tab_bar::Renderer>::Style: From<TabBar>,
 {
-    pub fn neu_input(status: &'a mut Status<Input<'a>>) -> Self {
+    pub fn neu_input(status: &'a mut Status<Input>) -> Self {
         let interrupt_pins = status.modus.interrupt_pins.clone();
+        let interrupt_pins_mcp23017 = interrupt_pins.clone();
         Auswahl::neu_mit_interrupt_view(
             status,
-            ZeigeModus::Pcf8574,
-            |Input { number_input_state, pin, interrupt_pins }, a0, a1, a2, variante| {
-                (
-                    interrupt_pins.get(&(a0, a1, a2, variante)).map_or(
-                        NumberInput::new(number_input_state, *pin, 32, InputMessage::Interrupt)
-                            .into(),
+            ZeigeModus::NurPorts,
+            |Input { number_input_state, einstellungen, interrupt_pins }, baustein| {
+                let interrupt: Element<'a, InputMessage, R> =
+                    baustein.and_then(|baustein| interrupt_pins.get(&baustein)).map_or(
+                        NumberInput::new(
+                            number_input_state,
+                            einstellungen.interrupt,
+                            32,
+                            InputMessage::Interrupt,
+                        )
+                        .into(),
                         |pin| Text::new(pin.to_string()).into(),
-                    ),
-                    pin,
-                )
+                    );
+                let mut column = Column::new().push(interrupt);
+                if let Some(InterruptBaustein::Mcp23017(..)) = baustein {
+                    column = column.push(make_radios(
+                        &einstellungen.pull_up,
+                        true,
+                        "Pull-up",
+                        false,
+                        "Kein Pull-up",
+                        InputMessage::PullUp,
+                    ));
+                }
+                (column.into(), einstellungen)
             },
-            &|modus: &mut u8, InputMessage::Interrupt(pin)| *modus = pin,
-            &|pin, _input| InputSave::Pin { pin },
-            move |a0, a1, a2, variante, port, pin| InputSave::Pcf8574Port {
-                a0,
-                a1,
-                a2,
-                variante,
-                port,
-                interrupt: if interrupt_pins.get(&(a0, a1, a2, variante)).is_some() {
-                    None
-                } else {
-                    Some(*pin)
-                },
+            &|einstellungen: &mut InputEinstellungen, message| match message {
+                InputMessage::Interrupt(pin) => einstellungen.interrupt = pin,
+                InputMessage::PullUp(pull_up) => einstellungen.pull_up = pull_up,
+            },
+            &|pin, _einstellungen| InputSave::Pin { pin },
+            move |adresse, port, einstellungen| {
+                input_pcf8574_port(&interrupt_pins, adresse, port, einstellungen)
+            },
+            move |adresse, port, einstellungen| {
+                input_mcp23017_port(&interrupt_pins_mcp23017, adresse, port, einstellungen)
             },
         )
     }
@@ -292,7 +461,7 @@ where
         Auswahl::neu_mit_interrupt_view(
             status,
             ZeigeModus::Beide,
-            |Output { polarität }, _a0, _a1, _a2, _variante| {
+            |Output { polarität }, _baustein| {
                 (
                     Column::new()
                         .push(Radio::new(
@@ -313,21 +482,41 @@ where
             },
             &|modus, OutputMessage::Polarität(polarität)| *modus = polarität,
             &|pin, polarität| OutputSave::Pin { pin, polarität: *polarität },
-            |a0, a1, a2, variante, port, polarität| OutputSave::Pcf8574Port {
-                a0,
-                a1,
-                a2,
-                variante,
-                port,
-                polarität: *polarität,
-            },
+            |adresse, port, polarität| output_pcf8574_port(adresse, port, *polarität),
+            |adresse, port, polarität| output_mcp23017_port(adresse, port, *polarität),
         )
     }
 }
 
+/// Hinweis, falls der Anschluss nicht frei ist, mit Knopf zur Auswahl des nächsten freien.
+fn hinweis<'a, M, R>(
+    verfügbarkeit: Verfügbarkeit,
+    nächster_freier: Option<u8>,
+    button_state: &'a mut button::State,
+    als_nachricht: impl Fn(u8) -> M,
+) -> Row<'a, M, R>
+where
+    M: 'a + Clone,
+    R: 'a + row::Renderer + text::Renderer + button::Renderer,
+{
+    let mut row = Row::new();
+    if verfügbarkeit != Verfügbarkeit::Frei {
+        row = row.push(Text::new(verfügbarkeit.to_string()).color(Color::from_rgb(0.5, 0.5, 0.5)));
+        if let Some(frei) = nächster_freier {
+            row = row.push(
+                Button::new(button_state, Text::new(format!("Nächster freier: {}", frei)))
+                    .on_press(als_nachricht(frei)),
+            );
+        }
+    }
+    row
+}
+
 enum ZeigeModus {
+    /// Modus wird immer angezeigt.
     Beide,
-    Pcf8574,
+    /// Modus wird nur für Ports (Pcf8574, Mcp23017) angezeigt.
+    NurPorts,
 }
 
 fn make_radios<'a, T, M, R>(
@@ -367,32 +556,55 @@ where
             active_tab,
             pin_state,
             pin,
+            bus,
             a0,
             a1,
             a2,
             variante,
+            pcf8574_bus_state,
             port_state,
             port,
+            mcp23017_bus_state,
+            mcp23017_port_state,
+            mcp23017_port,
             modus,
+            reservierungen,
+            pin_frei_state,
+            pcf8574_frei_state,
+            mcp23017_frei_state,
         }: &'a mut Status<IO>,
         zeige_modus: ZeigeModus,
-        view_modus: impl FnOnce(
-            &'a mut IO,
-            Level,
-            Level,
-            Level,
-            Variante,
-        ) -> (Element<'a, I, R>, &'a mut T),
+        view_modus: impl FnOnce(&'a mut IO, Option<InterruptBaustein>) -> (Element<'a, I, R>, &'a mut T),
         update_modus: &'a impl Fn(&mut T, I),
         make_pin: &'a impl Fn(u8, &T) -> M,
-        make_port: impl 'static + Fn(Level, Level, Level, Variante, u8, &T) -> M,
+        make_port: impl 'static + Fn(pcf8574::Adresse, u8, &T) -> M,
+        make_mcp23017_port: impl 'static + Fn(mcp23017::Adresse, u8, &T) -> M,
     ) -> Self {
-        let (view_modus, modus) = view_modus(modus, *a0, *a1, *a2, *variante);
+        let baustein = match *active_tab {
+            PIN_TAB => None,
+            PCF8574_TAB => {
+                Some(InterruptBaustein::Pcf8574(pcf8574_adresse(*bus, *a0, *a1, *a2, *variante)))
+            }
+            _ => Some(InterruptBaustein::Mcp23017(
+                mcp23017_adresse(*bus, *a0, *a1, *a2),
+                mcp23017::Bank::von_port(*mcp23017_port),
+            )),
+        };
+        let (view_modus, modus) = view_modus(modus, baustein);
         let view_modus_mapped = view_modus.map(InternalMessage::Modus);
         let high_low_column = |level: &Level, to_message: fn(Level) -> InternalMessage<I>| {
             make_radios(level, Level::High, "H", Level::Low, "L", to_message)
         };
+        let bus_input = |state, bus: &u8| {
+            Column::new().push(Text::new("Bus")).push(NumberInput::new(
+                state,
+                *bus,
+                Bus::MAXIMUM,
+                InternalMessage::Bus,
+            ))
+        };
         let pcf8574_row = Row::new()
+            .push(bus_input(pcf8574_bus_state, bus))
             .push(high_low_column(a0, InternalMessage::A0))
             .push(high_low_column(a1, InternalMessage::A1))
             .push(high_low_column(a2, InternalMessage::A2))
@@ -405,37 +617,68 @@ where
                 InternalMessage::Variante,
             ))
             .push(NumberInput::new(port_state, *port, 7, InternalMessage::Port));
+        let pcf8574_i2c = i2c::Adresse::from(pcf8574_adresse(*bus, *a0, *a1, *a2, *variante));
+        let pcf8574_art = i2c::Art::Pcf8574(*variante);
+        let pcf8574_hinweis = hinweis(
+            reservierungen.i2c_port(pcf8574_i2c, pcf8574_art, *port),
+            reservierungen.nächster_freier_i2c_port(pcf8574_i2c, pcf8574_art, *port),
+            pcf8574_frei_state,
+            InternalMessage::Port,
+        );
+        let mcp23017_i2c = i2c::Adresse::from(mcp23017_adresse(*bus, *a0, *a1, *a2));
+        let mcp23017_hinweis = hinweis(
+            reservierungen.i2c_port(mcp23017_i2c, i2c::Art::Mcp23017, *mcp23017_port),
+            reservierungen.nächster_freier_i2c_port(
+                mcp23017_i2c,
+                i2c::Art::Mcp23017,
+                *mcp23017_port,
+            ),
+            mcp23017_frei_state,
+            InternalMessage::Mcp23017Port,
+        );
+        let pin_hinweis = hinweis(
+            reservierungen.pin(*pin),
+            reservierungen.nächster_freier_pin(*pin),
+            pin_frei_state,
+            InternalMessage::Pin,
+        );
+        let mcp23017_row = Row::new()
+            .push(bus_input(mcp23017_bus_state, bus))
+            .push(high_low_column(a0, InternalMessage::A0))
+            .push(high_low_column(a1, InternalMessage::A1))
+            .push(high_low_column(a2, InternalMessage::A2))
+            .push(NumberInput::new(
+                mcp23017_port_state,
+                *mcp23017_port,
+                mcp23017::ANZAHL_PORTS - 1,
+                InternalMessage::Mcp23017Port,
+            ));
         // TODO Length::Fill/Shrink funktioniert nicht richtig (Card zu klein)
-        let width = Length::Units(350);
+        let width = Length::Units(550);
+        let tabs = vec![
+            (
+                TabLabel::Text("Pin".to_string()),
+                Column::new()
+                    .push(NumberInput::new(pin_state, *pin, 32, InternalMessage::Pin))
+                    .push(pin_hinweis)
+                    .into(),
+            ),
+            (
+                TabLabel::Text("Pcf8574-Port".to_string()),
+                Column::new().push(pcf8574_row).push(pcf8574_hinweis).into(),
+            ),
+            (
+                TabLabel::Text("Mcp23017-Port".to_string()),
+                Column::new().push(mcp23017_row).push(mcp23017_hinweis).into(),
+            ),
+        ];
+        let tabs = Tabs::with_tabs(*active_tab, tabs, InternalMessage::TabSelected)
+            .tab_bar_style(TabBar)
+            .height(Length::Shrink)
+            .width(width);
         let row = match zeige_modus {
-            ZeigeModus::Pcf8574 => {
-                let tabs = vec![
-                    (
-                        TabLabel::Text("Pin".to_string()),
-                        NumberInput::new(pin_state, *pin, 32, InternalMessage::Pin).into(),
-                    ),
-                    (TabLabel::Text("Pcf8574-Port".to_string()), {
-                        pcf8574_row.push(view_modus_mapped).into()
-                    }),
-                ];
-                let tabs = Tabs::with_tabs(*active_tab, tabs, InternalMessage::TabSelected)
-                    .tab_bar_style(TabBar)
-                    .height(Length::Shrink)
-                    .width(width);
-                Row::new().push(tabs)
-            }
-            ZeigeModus::Beide => {
-                let tabs = vec![
-                    (
-                        TabLabel::Text("Pin".to_string()),
-                        NumberInput::new(pin_state, *pin, 32, InternalMessage::Pin).into(),
-                    ),
-                    (TabLabel::Text("Pcf8574-Port".to_string()), { pcf8574_row.into() }),
-                ];
-                let tabs = Tabs::with_tabs(*active_tab, tabs, InternalMessage::TabSelected)
-                    .tab_bar_style(TabBar)
-                    .height(Length::Shrink)
-                    .width(width);
+            ZeigeModus::NurPorts if *active_tab == PIN_TAB => Row::new().push(tabs),
+            ZeigeModus::Beide | ZeigeModus::NurPorts => {
                 Row::new().push(tabs).push(view_modus_mapped)
             }
         };
@@ -443,15 +686,18 @@ where
             row,
             active_tab,
             pin,
+            bus,
             a0,
             a1,
             a2,
             variante,
             port,
+            mcp23017_port,
             modus,
             update_modus,
             make_pin,
             make_port: Box::new(make_port),
+            make_mcp23017_port: Box::new(make_mcp23017_port),
         }
     }
 }
@@ -487,27 +733,30 @@ where
             match message {
                 InternalMessage::TabSelected(tab) => *self.active_tab = tab,
                 InternalMessage::Pin(pin) => *self.pin = pin,
+                InternalMessage::Bus(bus) => *self.bus = bus,
                 InternalMessage::A0(a0) => *self.a0 = a0,
                 InternalMessage::A1(a1) => *self.a1 = a1,
                 InternalMessage::A2(a2) => *self.a2 = a2,
                 InternalMessage::Variante(variante) => *self.variante = variante,
                 InternalMessage::Port(port) => *self.port = port,
+                InternalMessage::Mcp23017Port(port) => *self.mcp23017_port = port,
                 InternalMessage::Modus(msg) => (self.update_modus)(self.modus, msg),
             }
             status = event::Status::Captured;
         }
         if changed {
-            messages.push(if *self.active_tab == 0 {
-                (self.make_pin)(*self.pin, &self.modus)
-            } else {
-                (self.make_port)(
-                    *self.a0,
-                    *self.a1,
-                    *self.a2,
-                    *self.variante,
+            messages.push(match *self.active_tab {
+                PIN_TAB => (self.make_pin)(*self.pin, &self.modus),
+                PCF8574_TAB => (self.make_port)(
+                    pcf8574_adresse(*self.bus, *self.a0, *self.a1, *self.a2, *self.variante),
                     *self.port,
                     self.modus,
-                )
+                ),
+                _ => (self.make_mcp23017_port)(
+                    mcp23017_adresse(*self.bus, *self.a0, *self.a1, *self.a2),
+                    *self.mcp23017_port,
+                    self.modus,
+                ),
             })
         }
         status
@@ -526,18 +775,54 @@ where
 
 #[derive(Debug)]
 pub struct PwmState {
+    active_tab: usize,
     pin: u8,
     number_input_state: number_input::State,
+    pca9685_bus: u8,
+    bus_state: number_input::State,
+    pca9685_offset: u8,
+    offset_state: number_input::State,
+    pca9685_kanal: u8,
+    kanal_state: number_input::State,
+    pca9685_frequenz: u16,
+    frequenz_state: number_input::State,
 }
 impl PwmState {
     pub fn neu() -> Self {
-        PwmState { pin: 0, number_input_state: number_input::State::new() }
+        PwmState {
+            active_tab: PIN_TAB,
+            pin: 0,
+            number_input_state: number_input::State::new(),
+            pca9685_bus: Bus::STANDARD.0,
+            bus_state: number_input::State::new(),
+            pca9685_offset: 0,
+            offset_state: number_input::State::new(),
+            pca9685_kanal: 0,
+            kanal_state: number_input::State::new(),
+            pca9685_frequenz: 50,
+            frequenz_state: number_input::State::new(),
+        }
     }
 }
 
-pub struct Pwm<'a, R: 'a + Renderer + number_input::Renderer> {
-    number_input: NumberInput<'a, u8, pwm::Save, R>,
+#[derive(Debug, Clone)]
+enum InternalPwmMessage {
+    TabSelected(usize),
+    Pin(u8),
+    Pca9685Bus(u8),
+    Pca9685Offset(u8),
+    Pca9685Kanal(u8),
+    Pca9685Frequenz(u16),
+}
+
+pub struct Pwm<'a, R: 'a + Renderer + row::Renderer> {
+    row: Row<'a, InternalPwmMessage, R>,
+    active_tab: &'a mut usize,
     pin: &'a mut u8,
+    pca9685_bus: &'a mut u8,
+    pca9685_offset: &'a mut u8,
+    pca9685_kanal: &'a mut u8,
+    pca9685_frequenz: &'a mut u16,
 }
 
 impl<'a, R> Pwm<'a, R>
@@ -549,23 +834,83 @@ where
         + column::Renderer
         + row::Renderer
         + container::Renderer
-        + number_input::Renderer,
+        + number_input::Renderer
+        + tabs::Renderer,
+    <R as tab_bar::Renderer>::Style: From<TabBar>,
 {
-    pub fn neu(PwmState { pin, number_input_state }: &'a mut PwmState) -> Self {
-        Pwm { number_input: NumberInput::new(number_input_state, *pin, 32, pwm::Save), pin }
+    pub fn neu(
+        PwmState {
+            active_tab,
+            pin,
+            number_input_state,
+            pca9685_bus,
+            bus_state,
+            pca9685_offset,
+            offset_state,
+            pca9685_kanal,
+            kanal_state,
+            pca9685_frequenz,
+            frequenz_state,
+        }: &'a mut PwmState,
+    ) -> Self {
+        let pca9685_row = Row::new()
+            .push(Text::new("Bus"))
+            .push(NumberInput::new(
+                bus_state,
+                *pca9685_bus,
+                Bus::MAXIMUM,
+                InternalPwmMessage::Pca9685Bus,
+            ))
+            .push(Text::new("Adresse"))
+            .push(NumberInput::new(
+                offset_state,
+                *pca9685_offset,
+                pca9685::ANZAHL_ADRESSEN - 1,
+                InternalPwmMessage::Pca9685Offset,
+            ))
+            .push(Text::new("Kanal"))
+            .push(NumberInput::new(
+                kanal_state,
+                *pca9685_kanal,
+                pca9685::ANZAHL_KANÄLE - 1,
+                InternalPwmMessage::Pca9685Kanal,
+            ))
+            .push(Text::new("Frequenz [Hz]"))
+            .push(NumberInput::new(
+                frequenz_state,
+                *pca9685_frequenz,
+                u16::MAX,
+                InternalPwmMessage::Pca9685Frequenz,
+            ));
+        let tabs = vec![
+            (
+                TabLabel::Text("Pin".to_string()),
+                NumberInput::new(number_input_state, *pin, 32, InternalPwmMessage::Pin).into(),
+            ),
+            (TabLabel::Text("Pca9685".to_string()), pca9685_row.into()),
+        ];
+        // TODO Length::Fill/Shrink funktioniert nicht richtig (Card zu klein)
+        let tabs = Tabs::with_tabs(*active_tab, tabs, InternalPwmMessage::TabSelected)
+            .tab_bar_style(TabBar)
+            .height(Length::Shrink)
+            .width(Length::Units(400));
+        Pwm {
+            row: Row::new().push(tabs),
+            active_tab,
+            pin,
+            pca9685_bus,
+            pca9685_offset,
+            pca9685_kanal,
+            pca9685_frequenz,
+        }
     }
 }
 
 impl<'a, R> Widget<pwm::Save, R> for Pwm<'a, R>
 where
-    R: 'a
-        + Renderer
-        + container::Renderer
-        + column::Renderer
-        + row::Renderer
-        + number_input::Renderer,
+    R: 'a + Renderer + row::Renderer,
 {
-    reexport_no_event_methods! {NumberInput<'a, u8, pwm::Save, R>, number_input, pwm::Save, R}
+    reexport_no_event_methods! {Row<'a, InternalPwmMessage, R>, row, InternalPwmMessage, R}
 
     fn on_event(
         &mut self,
@@ -576,30 +921,51 @@ where
         clipboard: &mut dyn Clipboard,
         messages: &mut Vec<pwm::Save>,
     ) -> event::Status {
-        let mut status = self.number_input.on_event(
+        let mut internal_messages = Vec::new();
+        let mut status = self.row.on_event(
             event,
             layout,
             cursor_position,
             renderer,
             clipboard,
-            messages,
+            &mut internal_messages,
         );
-        if let Some(pwm::Save(pin)) = messages.last() {
-            *self.pin = *pin;
+        let mut changed = false;
+        for message in internal_messages {
+            changed = true;
+            match message {
+                InternalPwmMessage::TabSelected(tab) => *self.active_tab = tab,
+                InternalPwmMessage::Pin(pin) => *self.pin = pin,
+                InternalPwmMessage::Pca9685Bus(bus) => *self.pca9685_bus = bus,
+                InternalPwmMessage::Pca9685Offset(offset) => *self.pca9685_offset = offset,
+                InternalPwmMessage::Pca9685Kanal(kanal) => *self.pca9685_kanal = kanal,
+                InternalPwmMessage::Pca9685Frequenz(frequenz) => {
+                    *self.pca9685_frequenz = frequenz
+                },
+            }
             status = event::Status::Captured;
         }
+        if changed {
+            messages.push(if *self.active_tab == PIN_TAB {
+                pwm::Save::Pin(*self.pin)
+            } else {
+                pwm::Save::Pca9685(pca9685::Save {
+                    adresse: pca9685::Adresse {
+                        bus: Bus(*self.pca9685_bus),
+                        offset: *self.pca9685_offset,
+                    },
+                    kanal: *self.pca9685_kanal,
+                    frequenz: Some(f64::from(*self.pca9685_frequenz)),
+                })
+            })
+        }
         status
     }
 }
 
 impl<'a, R> From<Pwm<'a, R>> for Element<'a, pwm::Save, R>
 where
-    R: 'a
-        + Renderer
-        + container::Renderer
-        + column::Renderer
-        + row::Renderer
-        + number_input::Renderer,
+    R: 'a + Renderer + row::Renderer,
 {
     fn from(auswahl: Pwm<'a, R>) -> Self {
         Element::new(auswahl)
@@ -0,0 +1,275 @@
+//! Anzeige & Erstellen der Drehregler für Geschwindigkeiten.
+
+use std::collections::BTreeMap;
+
+use iced_aw::native::{card, number_input, Card, NumberInput};
+use iced_native::{
+    button, column, container, event, pick_list, radio, row, scrollable, text, Button, Clipboard,
+    Column, Element, Event, Layout, Length, PickList, Point, Radio, Renderer, Row, Scrollable,
+    Text, Widget,
+};
+
+use super::macros::reexport_no_event_methods;
+use crate::anschluss::{adc, i2c::Bus, ToSave};
+pub use crate::steuerung::drehregler::{Drehregler, DrehreglerSave, Map};
+use crate::steuerung::geschwindigkeit::Name;
+
+#[derive(Debug)]
+pub struct AuswahlStatus {
+    neu_geschwindigkeit: Option<String>,
+    geschwindigkeiten: Vec<String>,
+    geschwindigkeit_state: pick_list::State<String>,
+    neu_variante: adc::Variante,
+    neu_bus: u8,
+    bus_state: number_input::State,
+    neu_offset: u8,
+    offset_state: number_input::State,
+    neu_kanal: u8,
+    kanal_state: number_input::State,
+    neu_button_state: button::State,
+    drehregler: BTreeMap<Name, (String, button::State)>,
+    scrollable_state: scrollable::State,
+}
+
+impl AuswahlStatus {
+    /// Erzeuge einen neuen AuswahlStatus.
+    ///
+    /// `geschwindigkeiten` sind alle Geschwindigkeiten, denen ein Drehregler zugeordnet werden kann.
+    pub fn neu<'t>(
+        drehregler: impl Iterator<Item = (&'t Name, &'t Drehregler)>,
+        geschwindigkeiten: impl Iterator<Item = &'t Name>,
+    ) -> Self {
+        let geschwindigkeiten: Vec<String> = geschwindigkeiten.map(|name| name.0.clone()).collect();
+        AuswahlStatus {
+            neu_geschwindigkeit: geschwindigkeiten.first().cloned(),
+            geschwindigkeiten,
+            geschwindigkeit_state: pick_list::State::default(),
+            neu_variante: adc::Variante::Ads1115,
+            neu_bus: Bus::STANDARD.0,
+            bus_state: number_input::State::new(),
+            neu_offset: 0,
+            offset_state: number_input::State::new(),
+            neu_kanal: 0,
+            kanal_state: number_input::State::new(),
+            neu_button_state: button::State::new(),
+            drehregler: drehregler.map(Self::iter_map).collect(),
+            scrollable_state: scrollable::State::new(),
+        }
+    }
+
+    fn iter_map<'t>(
+        (name, drehregler): (&'t Name, &'t Drehregler),
+    ) -> (Name, (String, button::State)) {
+        let adc::Save { adresse, kanal } = drehregler.to_save().anschluss;
+        (name.clone(), (format!("{}-{}", adresse, kanal), button::State::new()))
+    }
+
+    /// Entferne den Drehregler der übergebenen Geschwindigkeit.
+    pub fn entferne(&mut self, name: &Name) {
+        self.drehregler.remove(name);
+    }
+
+    /// Füge einen neuen Drehregler hinzu.
+    /// Falls für die Geschwindigkeit bereits ein Drehregler existiert wird der bisherige ersetzt.
+    pub fn hinzufügen(&mut self, name: &Name, drehregler: &Drehregler) {
+        let (key, value) = Self::iter_map((name, drehregler));
+        self.drehregler.insert(key, value);
+    }
+}
+
+#[derive(Debug, Clone)]
+enum InterneAuswahlNachricht {
+    Schließe,
+    Hinzufügen,
+    Lösche(Name),
+    Geschwindigkeit(String),
+    Variante(adc::Variante),
+    Bus(u8),
+    Offset(u8),
+    Kanal(u8),
+}
+
+#[derive(Debug, Clone)]
+pub enum AuswahlNachricht {
+    Schließe,
+    Hinzufügen(Name, DrehreglerSave),
+    Lösche(Name),
+}
+
+pub struct Auswahl<'a, R: Renderer + card::Renderer> {
+    card: Card<'a, InterneAuswahlNachricht, R>,
+    neu_geschwindigkeit: &'a mut Option<String>,
+    neu_variante: &'a mut adc::Variante,
+    neu_bus: &'a mut u8,
+    neu_offset: &'a mut u8,
+    neu_kanal: &'a mut u8,
+}
+
+impl<'a, R> Auswahl<'a, R>
+where
+    R: 'a
+        + Renderer
+        + text::Renderer
+        + radio::Renderer
+        + column::Renderer
+        + row::Renderer
+        + container::Renderer
+        + button::Renderer
+        + scrollable::Renderer
+        + pick_list::Renderer
+        + number_input::Renderer
+        + card::Renderer,
+{
+    pub fn neu(
+        AuswahlStatus {
+            neu_geschwindigkeit,
+            geschwindigkeiten,
+            geschwindigkeit_state,
+            neu_variante,
+            neu_bus,
+            bus_state,
+            neu_offset,
+            offset_state,
+            neu_kanal,
+            kanal_state,
+            neu_button_state,
+            drehregler,
+            scrollable_state,
+        }: &'a mut AuswahlStatus,
+    ) -> Self {
+        let varianten_radios = [adc::Variante::Ads1115, adc::Variante::Pcf8591].iter().fold(
+            Column::new(),
+            |column, variante| {
+                column.push(Radio::new(
+                    *variante,
+                    variante.to_string(),
+                    Some(*neu_variante),
+                    InterneAuswahlNachricht::Variante,
+                ))
+            },
+        );
+        let card = Card::new(Text::new("Drehregler").width(Length::Fill), {
+            let mut scrollable = Scrollable::new(scrollable_state)
+                .push(
+                    Column::new()
+                        .push(PickList::new(
+                            geschwindigkeit_state,
+                            &geschwindigkeiten[..],
+                            neu_geschwindigkeit.clone(),
+                            InterneAuswahlNachricht::Geschwindigkeit,
+                        ))
+                        .push(
+                            Row::new()
+                                .push(varianten_radios)
+                                .push(Text::new("Bus"))
+                                .push(NumberInput::new(
+                                    bus_state,
+                                    *neu_bus,
+                                    Bus::MAXIMUM,
+                                    InterneAuswahlNachricht::Bus,
+                                ))
+                                .push(Text::new("Adresse"))
+                                .push(NumberInput::new(
+                                    offset_state,
+                                    *neu_offset,
+                                    neu_variante.anzahl_adressen() - 1,
+                                    InterneAuswahlNachricht::Offset,
+                                ))
+                                .push(Text::new("Kanal"))
+                                .push(NumberInput::new(
+                                    kanal_state,
+                                    *neu_kanal,
+                                    adc::ANZAHL_KANÄLE - 1,
+                                    InterneAuswahlNachricht::Kanal,
+                                )),
+                        )
+                        .push(
+                            Button::new(neu_button_state, Text::new("Hinzufügen"))
+                                .on_press(InterneAuswahlNachricht::Hinzufügen),
+                        ),
+                )
+                .width(Length::Shrink);
+            for (name, (beschreibung, delete_state)) in drehregler {
+                scrollable = scrollable.push(
+                    Row::new().push(Text::new(&format!("{}: {}", name.0, beschreibung))).push(
+                        Button::new(delete_state, Text::new("X"))
+                            .on_press(InterneAuswahlNachricht::Lösche(name.clone())),
+                    ),
+                );
+            }
+            scrollable
+        })
+        .on_close(InterneAuswahlNachricht::Schließe)
+        .width(Length::Shrink);
+        Auswahl { card, neu_geschwindigkeit, neu_variante, neu_bus, neu_offset, neu_kanal }
+    }
+}
+
+impl<'a, R: 'a + Renderer + card::Renderer> Widget<AuswahlNachricht, R> for Auswahl<'a, R> {
+    reexport_no_event_methods! {Card<'a, InterneAuswahlNachricht, R>, card, InterneAuswahlNachricht, R}
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &R,
+        clipboard: &mut dyn Clipboard,
+        messages: &mut Vec<AuswahlNachricht>,
+    ) -> event::Status {
+        let mut card_messages = Vec::new();
+        let mut status = self.card.on_event(
+            event,
+            layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            &mut card_messages,
+        );
+        for message in card_messages {
+            match message {
+                InterneAuswahlNachricht::Schließe => messages.push(AuswahlNachricht::Schließe),
+                InterneAuswahlNachricht::Hinzufügen => {
+                    if let Some(geschwindigkeit) = self.neu_geschwindigkeit.clone() {
+                        messages.push(AuswahlNachricht::Hinzufügen(
+                            Name(geschwindigkeit),
+                            DrehreglerSave {
+                                anschluss: adc::Save {
+                                    adresse: adc::Adresse {
+                                        bus: Bus(*self.neu_bus),
+                                        variante: *self.neu_variante,
+                                        offset: *self.neu_offset,
+                                    },
+                                    kanal: *self.neu_kanal,
+                                },
+                            },
+                        ))
+                    }
+                }
+                InterneAuswahlNachricht::Lösche(name) => {
+                    messages.push(AuswahlNachricht::Lösche(name))
+                }
+                InterneAuswahlNachricht::Geschwindigkeit(geschwindigkeit) => {
+                    *self.neu_geschwindigkeit = Some(geschwindigkeit)
+                }
+                InterneAuswahlNachricht::Variante(variante) => {
+                    *self.neu_variante = variante;
+                    *self.neu_offset = (*self.neu_offset).min(variante.anzahl_adressen() - 1);
+                }
+                InterneAuswahlNachricht::Bus(bus) => *self.neu_bus = bus,
+                InterneAuswahlNachricht::Offset(offset) => *self.neu_offset = offset,
+                InterneAuswahlNachricht::Kanal(kanal) => *self.neu_kanal = kanal,
+            }
+            status = event::Status::Captured;
+        }
+        status
+    }
+}
+
+impl<'a, R: 'a + Renderer + card::Renderer> From<Auswahl<'a, R>>
+    for Element<'a, AuswahlNachricht, R>
+{
+    fn from(auswahl: Auswahl<'a, R>) -> Self {
+        Element::new(auswahl)
+    }
+}
@@ -5,9 +5,10 @@ use std::{
     fmt::{Debug, Display},
     iter,
     num::NonZeroUsize,
+    time::Duration,
 };
 
-use iced_aw::native::{card, number_input, tab_bar, tabs, Card, TabLabel, Tabs};
+use iced_aw::native::{card, number_input, tab_bar, tabs, Card, NumberInput, TabLabel, Tabs};
 use iced_native::{
     button, column, container, event, overlay, radio, row, scrollable, slider, text, text_input,
     Button, Clipboard, Column, Element, Event, Layout, Length, Point, Radio, Renderer, Row,
@@ -16,10 +17,15 @@ use iced_native::{
 use log::error;
 
 use super::{anschluss, macros::reexport_no_event_methods, style::tab_bar::TabBar};
-use crate::anschluss::{polarity::Polarität, pwm, OutputSave, ToSave};
+use crate::anschluss::{
+    anschlüsse::Reservierungen, polarity::Polarität, pwm, OutputSave, ToSave,
+};
 use crate::non_empty::{MaybeEmpty, NonEmpty};
 pub use crate::steuerung::geschwindigkeit::{Error, Geschwindigkeit, Name};
-use crate::steuerung::geschwindigkeit::{Fahrtrichtung, Mittelleiter, Zweileiter};
+use crate::steuerung::geschwindigkeit::{
+    Anfahrimpuls, Fahrtrichtung, Leiter, Mittelleiter, PwmProfil, Zweileiter,
+    MAX_ANFAHRIMPULS_DAUER,
+};
 
 pub type Map<Leiter> = BTreeMap<Name, (Geschwindigkeit<Leiter>, AnzeigeStatus<Leiter>)>;
 
@@ -28,14 +34,44 @@ pub struct AnzeigeStatus<Leiter: LeiterAnzeige> {
     aktuelle_geschwindigkeit: u8,
     pwm_slider_state: slider::State,
     fahrtrichtung_state: Leiter::Fahrtrichtung,
+    /// Dauer eines gestarteten Anfahrimpulses, bis sein Ende geplant wurde.
+    anfahrimpuls: Option<Duration>,
+}
+
+impl<Leiter: LeiterAnzeige> AnzeigeStatus<Leiter> {
+    /// Stelle die Geschwindigkeit ein und merke einen dabei gestarteten Anfahrimpuls.
+    pub fn geschwindigkeit_einstellen(
+        &mut self,
+        geschwindigkeit: &mut Geschwindigkeit<Leiter>,
+        wert: u8,
+    ) -> Result<(), Error> {
+        self.aktuelle_geschwindigkeit = wert;
+        self.anfahrimpuls = geschwindigkeit.geschwindigkeit(wert)?;
+        Ok(())
+    }
+
+    /// Dauer eines seit dem letzten Aufruf gestarteten Anfahrimpulses.
+    ///
+    /// Danach muss die Geschwindigkeit erneut eingestellt werden.
+    pub fn anfahrimpuls_gestartet(&mut self) -> Option<Duration> {
+        self.anfahrimpuls.take()
+    }
+
+    /// Die zuletzt eingestellte Geschwindigkeit.
+    pub fn aktuelle_geschwindigkeit(&self) -> u8 {
+        self.aktuelle_geschwindigkeit
+    }
 }
 
-pub trait LeiterAnzeige: ToSave + Sized {
+pub trait LeiterAnzeige: Leiter + ToSave + Sized {
     type Fahrtrichtung;
     type Message: Debug + Clone + Send;
 
     fn anzeige_status_neu() -> AnzeigeStatus<Self>;
 
+    /// Initiales PwmProfil bei der Auswahl einer neuen Geschwindigkeit.
+    fn standard_pwm_profil() -> PwmProfil;
+
     fn anzeige_neu<'t, R>(
         name: &'t Name,
         geschwindigkeit: &'t Geschwindigkeit<Self>,
@@ -56,6 +92,20 @@ pub trait LeiterAnzeige: ToSave + Sized {
         message: Self::Message,
     ) -> Result<iced::Command<Self::Message>, Error>;
 
+    /// Nachricht, um die Fahrtrichtung umzukehren (z.B. ausgelöst durch einen Stellpult-Taster).
+    fn umdrehen_nachricht(anzeige_status: &AnzeigeStatus<Self>) -> Self::Message;
+
+    /// Die aktuelle Fahrtrichtung, falls sie bekannt ist.
+    fn aktuelle_fahrtrichtung(anzeige_status: &AnzeigeStatus<Self>) -> Option<String>;
+
+    /// Nachricht, um die Fahrtrichtung mit Namen `fahrtrichtung` einzustellen.
+    ///
+    /// Ist die Fahrtrichtung bereits eingestellt wird `None` zurückgegeben.
+    fn fahrtrichtung_nachricht(
+        anzeige_status: &AnzeigeStatus<Self>,
+        fahrtrichtung: &str,
+    ) -> Result<Option<Self::Message>, String>;
+
     fn auswahl_neu<'t, R>(status: &'t mut AuswahlStatus) -> Auswahl<'t, Self, R>
     where
         R: 't
@@ -88,9 +138,14 @@ impl LeiterAnzeige for Mittelleiter {
             aktuelle_geschwindigkeit: 0,
             pwm_slider_state: slider::State::new(),
             fahrtrichtung_state: button::State::new(),
+            anfahrimpuls: None,
         }
     }
 
+    fn standard_pwm_profil() -> PwmProfil {
+        PwmProfil::mittelleiter()
+    }
+
     fn anzeige_neu<'t, R>(
         name: &'t Name,
         geschwindigkeit: &'t Geschwindigkeit<Mittelleiter>,
@@ -131,8 +186,7 @@ impl LeiterAnzeige for Mittelleiter {
     ) -> Result<iced::Command<Self::Message>, Error> {
         match message {
             MessageMittelleiter::Geschwindigkeit(wert) => {
-                anzeige_status.aktuelle_geschwindigkeit = wert;
-                geschwindigkeit.geschwindigkeit(wert)
+                anzeige_status.geschwindigkeit_einstellen(geschwindigkeit, wert)
             }
             MessageMittelleiter::Umdrehen => {
                 anzeige_status.aktuelle_geschwindigkeit = 0;
@@ -142,6 +196,22 @@ impl LeiterAnzeige for Mittelleiter {
         .map(|()| iced::Command::none())
     }
 
+    fn umdrehen_nachricht(_anzeige_status: &AnzeigeStatus<Self>) -> Self::Message {
+        MessageMittelleiter::Umdrehen
+    }
+
+    fn aktuelle_fahrtrichtung(_anzeige_status: &AnzeigeStatus<Self>) -> Option<String> {
+        None
+    }
+
+    /// Die Fahrtrichtung ist unbekannt, daher wird immer umgedreht.
+    fn fahrtrichtung_nachricht(
+        _anzeige_status: &AnzeigeStatus<Self>,
+        _fahrtrichtung: &str,
+    ) -> Result<Option<Self::Message>, String> {
+        Ok(Some(MessageMittelleiter::Umdrehen))
+    }
+
     fn auswahl_neu<'t, R>(status: &'t mut AuswahlStatus) -> Auswahl<'t, Self, R>
     where
         R: 't
@@ -162,7 +232,7 @@ impl LeiterAnzeige for Mittelleiter {
             status,
             UmdrehenAnzeige::KonstanteSpannung,
             "Umdrehen",
-            &|_umdrehen, pin, polarität| Mittelleiter::Pwm { pin, polarität },
+            &|_umdrehen, pin, polarität, profil| Mittelleiter::Pwm { pin, polarität, profil },
             &|umdrehen, geschwindigkeit| Mittelleiter::KonstanteSpannung {
                 geschwindigkeit,
                 letzter_wert: 0,
@@ -187,9 +257,14 @@ impl LeiterAnzeige for Zweileiter {
             aktuelle_geschwindigkeit: 0,
             pwm_slider_state: slider::State::new(),
             fahrtrichtung_state: Fahrtrichtung::Vorwärts,
+            anfahrimpuls: None,
         }
     }
 
+    fn standard_pwm_profil() -> PwmProfil {
+        PwmProfil::zweileiter()
+    }
+
     fn anzeige_neu<'t, R>(
         name: &'t Name,
         geschwindigkeit: &'t Geschwindigkeit<Zweileiter>,
@@ -239,8 +314,7 @@ impl LeiterAnzeige for Zweileiter {
     ) -> Result<iced::Command<Self::Message>, Error> {
         match message {
             MessageZweileiter::Geschwindigkeit(wert) => {
-                anzeige_status.aktuelle_geschwindigkeit = wert;
-                geschwindigkeit.geschwindigkeit(wert)
+                anzeige_status.geschwindigkeit_einstellen(geschwindigkeit, wert)
             }
             MessageZweileiter::Fahrtrichtung(fahrtrichtung) => {
                 anzeige_status.aktuelle_geschwindigkeit = 0;
@@ -251,6 +325,33 @@ impl LeiterAnzeige for Zweileiter {
         .map(|()| iced::Command::none())
     }
 
+    fn umdrehen_nachricht(anzeige_status: &AnzeigeStatus<Self>) -> Self::Message {
+        MessageZweileiter::Fahrtrichtung(match anzeige_status.fahrtrichtung_state {
+            Fahrtrichtung::Vorwärts => Fahrtrichtung::Rückwärts,
+            Fahrtrichtung::Rückwärts => Fahrtrichtung::Vorwärts,
+        })
+    }
+
+    fn aktuelle_fahrtrichtung(anzeige_status: &AnzeigeStatus<Self>) -> Option<String> {
+        Some(format!("{:?}", anzeige_status.fahrtrichtung_state))
+    }
+
+    fn fahrtrichtung_nachricht(
+        anzeige_status: &AnzeigeStatus<Self>,
+        fahrtrichtung: &str,
+    ) -> Result<Option<Self::Message>, String> {
+        let fahrtrichtung = match fahrtrichtung {
+            "Vorwärts" => Fahrtrichtung::Vorwärts,
+            "Rückwärts" => Fahrtrichtung::Rückwärts,
+            _ => return Err(format!("Unbekannte Fahrtrichtung: {}", fahrtrichtung)),
+        };
+        Ok(if fahrtrichtung == anzeige_status.fahrtrichtung_state {
+            None
+        } else {
+            Some(MessageZweileiter::Fahrtrichtung(fahrtrichtung))
+        })
+    }
+
     fn auswahl_neu<'t, R>(status: &'t mut AuswahlStatus) -> Auswahl<'t, Self, R>
     where
         R: 't
@@ -271,9 +372,10 @@ impl LeiterAnzeige for Zweileiter {
             status,
             UmdrehenAnzeige::Immer,
             "Fahrtrichtung",
-            &|fahrtrichtung, geschwindigkeit, polarität| Zweileiter::Pwm {
+            &|fahrtrichtung, geschwindigkeit, polarität, profil| Zweileiter::Pwm {
                 geschwindigkeit,
                 polarität,
+                profil,
                 fahrtrichtung,
             },
             &|fahrtrichtung, geschwindigkeit| Zweileiter::KonstanteSpannung {
@@ -387,6 +489,118 @@ enum KonstanteSpannungAnpassen {
     Entfernen(NonZeroUsize),
 }
 
+/// Eingabe-Werte für ein PwmProfil.
+///
+/// Duty-cycle werden in Prozent, die Dauer des Anfahrimpulses in Millisekunden
+/// (höchstens [MAX_ANFAHRIMPULS_DAUER]) angegeben.
+/// Eine Anfahrimpuls-Dauer von 0 deaktiviert den Anfahrimpuls.
+/// Die Stützstellen der Geschwindigkeitskurve werden als Komma-getrennte Prozentwerte angegeben.
+#[derive(Debug, Clone)]
+struct PwmProfilEingabe {
+    frequenz: u16,
+    min_duty_cycle: u8,
+    max_duty_cycle: u8,
+    anfahrimpuls_duty_cycle: u8,
+    anfahrimpuls_dauer: u16,
+    kurve: String,
+}
+
+impl PwmProfilEingabe {
+    fn neu(profil: &PwmProfil) -> Self {
+        let prozent = |wert: f64| (100. * wert).round().max(0.).min(100.) as u8;
+        let (anfahrimpuls_duty_cycle, anfahrimpuls_dauer) = match &profil.anfahrimpuls {
+            Some(Anfahrimpuls { duty_cycle, dauer }) => {
+                (prozent(*duty_cycle), (*dauer).min(MAX_ANFAHRIMPULS_DAUER).as_millis() as u16)
+            }
+            None => (100, 0),
+        };
+        PwmProfilEingabe {
+            frequenz: profil.frequenz.round().max(1.).min(u16::MAX as f64) as u16,
+            min_duty_cycle: prozent(profil.min_duty_cycle),
+            max_duty_cycle: prozent(profil.max_duty_cycle),
+            anfahrimpuls_duty_cycle,
+            anfahrimpuls_dauer,
+            kurve: profil
+                .kurve
+                .iter()
+                .map(|stützstelle| (100. * stützstelle).to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+
+    /// Ungültige Eingaben werden als Fehlermeldung für den Dialog zurückgegeben.
+    fn profil(&self) -> Result<PwmProfil, String> {
+        let anteil = |prozent: u8| prozent as f64 / 100.;
+        if self.min_duty_cycle > self.max_duty_cycle {
+            return Err(format!(
+                "Minimaler Duty-cycle ({}%) ist größer als der maximale ({}%)!",
+                self.min_duty_cycle, self.max_duty_cycle
+            ));
+        }
+        let mut kurve = Vec::new();
+        for stützstelle in self.kurve.split(',').map(str::trim) {
+            if stützstelle.is_empty() {
+                continue;
+            }
+            let prozent = stützstelle
+                .parse::<f64>()
+                .map_err(|_error| format!("Ungültige Stützstelle {}!", stützstelle))?;
+            if !(0. ..=100.).contains(&prozent) {
+                return Err(format!("Stützstelle {} liegt nicht zwischen 0 und 100!", stützstelle));
+            }
+            let wert = prozent / 100.;
+            if let Some(letzte) = kurve.last() {
+                if wert < *letzte {
+                    return Err(format!(
+                        "Stützstelle {} ist kleiner als die vorherige ({})!",
+                        stützstelle,
+                        100. * letzte
+                    ));
+                }
+            }
+            kurve.push(wert);
+        }
+        Ok(PwmProfil {
+            frequenz: self.frequenz.max(1) as f64,
+            min_duty_cycle: anteil(self.min_duty_cycle),
+            max_duty_cycle: anteil(self.max_duty_cycle),
+            anfahrimpuls: if self.anfahrimpuls_dauer > 0 {
+                Some(Anfahrimpuls {
+                    duty_cycle: anteil(self.anfahrimpuls_duty_cycle),
+                    dauer: Duration::from_millis(self.anfahrimpuls_dauer as u64),
+                })
+            } else {
+                None
+            },
+            kurve,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct PwmProfilStatus {
+    frequenz_state: number_input::State,
+    min_duty_cycle_state: number_input::State,
+    max_duty_cycle_state: number_input::State,
+    anfahrimpuls_duty_cycle_state: number_input::State,
+    anfahrimpuls_dauer_state: number_input::State,
+    kurve_state: text_input::State,
+}
+
+impl PwmProfilStatus {
+    fn neu() -> Self {
+        PwmProfilStatus {
+            frequenz_state: number_input::State::new(),
+            min_duty_cycle_state: number_input::State::new(),
+            max_duty_cycle_state: number_input::State::new(),
+            anfahrimpuls_duty_cycle_state: number_input::State::new(),
+            anfahrimpuls_dauer_state: number_input::State::new(),
+            kurve_state: text_input::State::new(),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct AuswahlStatus {
     neu_name: String,
@@ -397,16 +611,20 @@ pub struct AuswahlStatus {
     pwm_pin: pwm::Save,
     pwm_polarität: Polarität,
     pwm_state: anschluss::PwmState,
+    pwm_profil: PwmProfilEingabe,
+    pwm_profil_state: PwmProfilStatus,
+    pwm_profil_fehler: Option<String>,
     ks_anschlüsse_anpassen: Option<KonstanteSpannungAnpassen>,
     ks_anschlüsse: NonEmpty<(OutputSave, anschluss::Status<anschluss::Output>, button::State)>,
     ks_scrollable_state: scrollable::State,
     hinzufügen_button_state: button::State,
     geschwindigkeiten: BTreeMap<Name, (String, button::State)>,
     scrollable_state: scrollable::State,
+    reservierungen: Reservierungen,
 }
 
 impl AuswahlStatus {
-    pub fn neu<'t, Leiter: 't + Display, A: 't>(
+    pub fn neu<'t, Leiter: 't + Display + LeiterAnzeige, A: 't>(
         geschwindigkeiten: impl Iterator<Item = (&'t Name, &'t (Geschwindigkeit<Leiter>, A))>,
     ) -> Self {
         AuswahlStatus {
@@ -415,9 +633,12 @@ impl AuswahlStatus {
             aktueller_tab: 0,
             umdrehen_anschluss: OutputSave::Pin { pin: 0, polarität: Polarität::Normal },
             umdrehen_state: anschluss::Status::neu_output(),
-            pwm_pin: pwm::Save(0),
+            pwm_pin: pwm::Save::Pin(0),
             pwm_polarität: Polarität::Normal,
             pwm_state: anschluss::PwmState::neu(),
+            pwm_profil: PwmProfilEingabe::neu(&Leiter::standard_pwm_profil()),
+            pwm_profil_state: PwmProfilStatus::neu(),
+            pwm_profil_fehler: None,
             ks_anschlüsse_anpassen: None,
             ks_anschlüsse: NonEmpty::singleton((
                 OutputSave::Pin { pin: 0, polarität: Polarität::Normal },
@@ -431,6 +652,7 @@ impl AuswahlStatus {
                 .map(Self::iter_map)
                 .collect(),
             scrollable_state: scrollable::State::new(),
+            reservierungen: Reservierungen::default(),
         }
     }
 
@@ -452,6 +674,15 @@ impl AuswahlStatus {
     pub fn entfernen(&mut self, name: &Name) {
         self.geschwindigkeiten.remove(name);
     }
+
+    /// Aktualisiere die Hinweise auf bereits reservierte Anschlüsse.
+    pub fn setze_reservierungen(&mut self, reservierungen: &Reservierungen) {
+        self.umdrehen_state.setze_reservierungen(reservierungen);
+        for (_save, status, _button_state) in self.ks_anschlüsse.iter_mut() {
+            status.setze_reservierungen(reservierungen)
+        }
+        self.reservierungen = reservierungen.clone()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -462,6 +693,12 @@ enum InterneAuswahlNachricht {
     UmdrehenAnschluss(OutputSave),
     PwmPin(pwm::Save),
     PwmPolarität(Polarität),
+    PwmFrequenz(u16),
+    PwmMinDutyCycle(u8),
+    PwmMaxDutyCycle(u8),
+    AnfahrimpulsDutyCycle(u8),
+    AnfahrimpulsDauer(u16),
+    PwmKurve(String),
     KonstanteSpannungAnschluss(usize, OutputSave),
     NeuerKonstanteSpannungAnschluss,
     LöscheKonstanteSpannungAnschluss(NonZeroUsize),
@@ -491,9 +728,12 @@ where
     umdrehen_anschluss: &'t mut OutputSave,
     pwm_pin: &'t mut pwm::Save,
     pwm_polarität: &'t mut Polarität,
+    pwm_profil: &'t mut PwmProfilEingabe,
+    pwm_profil_fehler: &'t mut Option<String>,
     ks_anschlüsse_anpassen: &'t mut Option<KonstanteSpannungAnpassen>,
     ks_anschlüsse: NonEmpty<&'t mut OutputSave>,
-    pwm_nachricht: &'t dyn Fn(OutputSave, pwm::Save, Polarität) -> <Leiter as ToSave>::Save,
+    pwm_nachricht:
+        &'t dyn Fn(OutputSave, pwm::Save, Polarität, PwmProfil) -> <Leiter as ToSave>::Save,
     ks_nachricht: &'t dyn Fn(OutputSave, NonEmpty<OutputSave>) -> <Leiter as ToSave>::Save,
 }
 
@@ -524,7 +764,12 @@ where
         status: &'t mut AuswahlStatus,
         umdrehen_anzeige: UmdrehenAnzeige,
         umdrehen_beschreibung: impl Into<String>,
-        pwm_nachricht: &'t impl Fn(OutputSave, pwm::Save, Polarität) -> <Leiter as ToSave>::Save,
+        pwm_nachricht: &'t impl Fn(
+            OutputSave,
+            pwm::Save,
+            Polarität,
+            PwmProfil,
+        ) -> <Leiter as ToSave>::Save,
         ks_nachricht: &'t impl Fn(OutputSave, NonEmpty<OutputSave>) -> <Leiter as ToSave>::Save,
     ) -> Self {
         let AuswahlStatus {
@@ -536,20 +781,28 @@ where
             pwm_pin,
             pwm_polarität,
             pwm_state,
+            pwm_profil,
+            pwm_profil_state,
+            pwm_profil_fehler,
             ks_anschlüsse_anpassen,
             ks_anschlüsse,
             ks_scrollable_state,
             hinzufügen_button_state,
             geschwindigkeiten,
             scrollable_state,
+            reservierungen,
         } = status;
         if let Some(anpassen) = ks_anschlüsse_anpassen {
             match anpassen {
-                KonstanteSpannungAnpassen::Hinzufügen => ks_anschlüsse.push((
-                    OutputSave::Pin { pin: 0, polarität: Polarität::Normal },
-                    anschluss::Status::neu_output(),
-                    button::State::new(),
-                )),
+                KonstanteSpannungAnpassen::Hinzufügen => {
+                    let mut status = anschluss::Status::neu_output();
+                    status.setze_reservierungen(reservierungen);
+                    ks_anschlüsse.push((
+                        OutputSave::Pin { pin: 0, polarität: Polarität::Normal },
+                        status,
+                        button::State::new(),
+                    ))
+                }
                 KonstanteSpannungAnpassen::Entfernen(ix) => {
                     ks_anschlüsse.remove(ix.get());
                 }
@@ -583,6 +836,85 @@ where
                 InterneAuswahlNachricht::PwmPolarität,
             )
         };
+        let PwmProfilStatus {
+            frequenz_state,
+            min_duty_cycle_state,
+            max_duty_cycle_state,
+            anfahrimpuls_duty_cycle_state,
+            anfahrimpuls_dauer_state,
+            kurve_state,
+        } = pwm_profil_state;
+        let profil_zeile = |beschreibung: &str, eingabe: Element<'t, InterneAuswahlNachricht, R>| {
+            Row::new()
+                .spacing(5)
+                .push(Text::new(beschreibung).width(Length::Units(250)))
+                .push(eingabe)
+        };
+        let mut profil_auswahl = Column::new()
+            .push(profil_zeile(
+                "Frequenz [Hz]",
+                NumberInput::new(
+                    frequenz_state,
+                    pwm_profil.frequenz,
+                    u16::MAX,
+                    InterneAuswahlNachricht::PwmFrequenz,
+                )
+                .into(),
+            ))
+            .push(profil_zeile(
+                "Minimaler Duty-cycle [%]",
+                NumberInput::new(
+                    min_duty_cycle_state,
+                    pwm_profil.min_duty_cycle,
+                    100,
+                    InterneAuswahlNachricht::PwmMinDutyCycle,
+                )
+                .into(),
+            ))
+            .push(profil_zeile(
+                "Maximaler Duty-cycle [%]",
+                NumberInput::new(
+                    max_duty_cycle_state,
+                    pwm_profil.max_duty_cycle,
+                    100,
+                    InterneAuswahlNachricht::PwmMaxDutyCycle,
+                )
+                .into(),
+            ))
+            .push(profil_zeile(
+                "Anfahrimpuls Duty-cycle [%]",
+                NumberInput::new(
+                    anfahrimpuls_duty_cycle_state,
+                    pwm_profil.anfahrimpuls_duty_cycle,
+                    100,
+                    InterneAuswahlNachricht::AnfahrimpulsDutyCycle,
+                )
+                .into(),
+            ))
+            .push(profil_zeile(
+                "Anfahrimpuls Dauer [ms] (0 = aus)",
+                NumberInput::new(
+                    anfahrimpuls_dauer_state,
+                    pwm_profil.anfahrimpuls_dauer,
+                    MAX_ANFAHRIMPULS_DAUER.as_millis() as u16,
+                    InterneAuswahlNachricht::AnfahrimpulsDauer,
+                )
+                .into(),
+            ))
+            .push(profil_zeile(
+                "Geschwindigkeitskurve [%]",
+                TextInput::new(
+                    kurve_state,
+                    "<linear>",
+                    &pwm_profil.kurve,
+                    InterneAuswahlNachricht::PwmKurve,
+                )
+                .width(Length::Units(300))
+                .into(),
+            ));
+        if let Some(fehler) = pwm_profil_fehler.as_ref() {
+            profil_auswahl = profil_auswahl.push(Text::new(fehler.as_str()));
+        }
         let pwm_auswahl = Row::new()
             .push(
                 Element::from(anschluss::Pwm::neu(pwm_state)).map(InterneAuswahlNachricht::PwmPin),
@@ -591,7 +923,8 @@ where
                 Column::new()
                     .push(make_radio(Polarität::Normal))
                     .push(make_radio(Polarität::Invertiert)),
-            );
+            )
+            .push(profil_auswahl);
         let mut ks_auswahl = Row::new();
         match umdrehen_anzeige {
             UmdrehenAnzeige::KonstanteSpannung => ks_auswahl = ks_auswahl.push(umdrehen_auswahl),
@@ -647,6 +980,8 @@ where
             umdrehen_anschluss,
             pwm_pin,
             pwm_polarität,
+            pwm_profil,
+            pwm_profil_fehler,
             ks_anschlüsse_anpassen,
             ks_anschlüsse: anschlüsse_save,
             pwm_nachricht,
@@ -695,6 +1030,22 @@ where
                 InterneAuswahlNachricht::PwmPolarität(polarität) => {
                     *self.pwm_polarität = polarität
                 }
+                InterneAuswahlNachricht::PwmFrequenz(frequenz) => {
+                    self.pwm_profil.frequenz = frequenz
+                }
+                InterneAuswahlNachricht::PwmMinDutyCycle(duty_cycle) => {
+                    self.pwm_profil.min_duty_cycle = duty_cycle
+                }
+                InterneAuswahlNachricht::PwmMaxDutyCycle(duty_cycle) => {
+                    self.pwm_profil.max_duty_cycle = duty_cycle
+                }
+                InterneAuswahlNachricht::AnfahrimpulsDutyCycle(duty_cycle) => {
+                    self.pwm_profil.anfahrimpuls_duty_cycle = duty_cycle
+                }
+                InterneAuswahlNachricht::AnfahrimpulsDauer(dauer) => {
+                    self.pwm_profil.anfahrimpuls_dauer = dauer
+                }
+                InterneAuswahlNachricht::PwmKurve(kurve) => self.pwm_profil.kurve = kurve,
                 InterneAuswahlNachricht::KonstanteSpannungAnschluss(ix, anschluss_neu) => {
                     if let Some(anschluss) = self.ks_anschlüsse.get_mut(ix) {
                         **anschluss = anschluss_neu
@@ -714,26 +1065,35 @@ where
                     self.ks_anschlüsse.remove(ix.get());
                 }
                 InterneAuswahlNachricht::Hinzufügen => {
-                    messages.push(AuswahlNachricht::Hinzufügen(
-                        Name(self.neu_name.clone()),
-                        Geschwindigkeit {
-                            leiter: if self.aktueller_tab == &0 {
+                    let leiter = if self.aktueller_tab == &0 {
+                        match self.pwm_profil.profil() {
+                            Ok(profil) => {
+                                *self.pwm_profil_fehler = None;
                                 (self.pwm_nachricht)(
                                     self.umdrehen_anschluss.clone(),
                                     self.pwm_pin.clone(),
                                     *self.pwm_polarität,
-                                )
-                            } else {
-                                (self.ks_nachricht)(
-                                    self.umdrehen_anschluss.clone(),
-                                    self.ks_anschlüsse
-                                        .iter()
-                                        .map(|output_save| (*output_save).clone())
-                                        .collect::<MaybeEmpty<_>>()
-                                        .unwrap(),
+                                    profil,
                                 )
                             },
-                        },
+                            Err(fehler) => {
+                                *self.pwm_profil_fehler = Some(fehler);
+                                continue;
+                            },
+                        }
+                    } else {
+                        (self.ks_nachricht)(
+                            self.umdrehen_anschluss.clone(),
+                            self.ks_anschlüsse
+                                .iter()
+                                .map(|output_save| (*output_save).clone())
+                                .collect::<MaybeEmpty<_>>()
+                                .unwrap(),
+                        )
+                    };
+                    messages.push(AuswahlNachricht::Hinzufügen(
+                        Name(self.neu_name.clone()),
+                        Geschwindigkeit { leiter },
                     ))
                 }
                 InterneAuswahlNachricht::Löschen(name) => {
@@ -1,6 +1,7 @@
 //! Anzeige der GleisDefinition auf einem Canvas
 
-use std::fmt::Debug;
+use std::collections::HashSet;
+use std::fmt::{self, Debug, Display, Formatter};
 use std::time::{Duration, Instant};
 
 use log::error;
@@ -8,11 +9,21 @@ use serde::{Deserialize, Serialize};
 
 use self::id::with_any_id;
 use crate::{
-    anschluss::{self, Anschlüsse, Reserviere, ToSave},
+    anschluss::{
+        self,
+        anschlüsse::{Besitzer, SyncError, Verfügbarkeit},
+        verkabelung::{Verkabeln, Verkabelung},
+        zuordnung::{self, Zuordnen, Zuordnung},
+        Anschlüsse, InputAnschluss, Reserviere, ToSave,
+    },
     application::{anchor, typen::*},
     farbe::Farbe,
     lookup::Lookup,
-    steuerung::{geschwindigkeit, streckenabschnitt, weiche, Streckenabschnitt},
+    speicherformat::{self, Speicherstand},
+    steuerung::{
+        drehregler, geschwindigkeit, kontakt::Kontakt, regel, skript, stellpult, streckenabschnitt,
+        weiche, Streckenabschnitt,
+    },
 };
 
 pub mod id;
@@ -21,6 +32,15 @@ pub use id::*;
 pub mod maps;
 pub use maps::*;
 
+pub mod profil;
+use profil::{GleisplanVecs, Profil};
+
+mod importieren;
+pub use importieren::Platzierung;
+mod inhalt;
+mod svg;
+mod vergleich;
+
 #[derive(zugkontrolle_derive::Debug)]
 struct Grabbed<Z> {
     gleis_id: AnyId<Z>,
@@ -227,6 +247,56 @@ impl<Z> Gleise<Z> {
         self.maps.streckenabschnitte.iter()
     }
 
+    /// Veränderliche Referenzen auf alle aktuell bekannten Streckenabschnitte.
+    pub(crate) fn streckenabschnitte_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (&streckenabschnitt::Name, &mut Streckenabschnitt)> {
+        self.maps.streckenabschnitte.iter_mut()
+    }
+
+    /// Veränderliche Referenzen auf die Kontakte aller Geraden und Kurven.
+    pub(crate) fn kontakte_mut(&mut self) -> impl Iterator<Item = &mut Kontakt<InputAnschluss>> {
+        let geraden =
+            self.maps.geraden.values_mut().filter_map(|gleis| gleis.definition.kontakt.as_mut());
+        let kurven =
+            self.maps.kurven.values_mut().filter_map(|gleis| gleis.definition.kontakt.as_mut());
+        geraden.chain(kurven)
+    }
+
+    /// Quelltext aller gespeicherten Skripte.
+    pub(crate) fn skripte(&self) -> &skript::Map {
+        &self.maps.skripte
+    }
+
+    /// Füge ein Skript hinzu, ein Skript mit identischem Namen wird ersetzt und zurückgegeben.
+    pub(crate) fn neues_skript(&mut self, name: skript::Name, quelltext: String) -> Option<String> {
+        self.maps.skripte.insert(name, quelltext)
+    }
+
+    /// Entferne ein Skript, falls es vorhanden war wird es zurückgegeben.
+    pub(crate) fn entferne_skript(&mut self, name: &skript::Name) -> Option<String> {
+        self.maps.skripte.remove(name)
+    }
+
+    /// Alle gespeicherten Regeln.
+    pub(crate) fn regeln(&self) -> &regel::Map {
+        &self.maps.regeln
+    }
+
+    /// Füge eine Regel hinzu, eine Regel mit identischem Namen wird ersetzt und zurückgegeben.
+    pub(crate) fn neue_regel(
+        &mut self,
+        name: regel::Name,
+        regel: regel::Regel,
+    ) -> Option<regel::Regel> {
+        self.maps.regeln.insert(name, regel)
+    }
+
+    /// Entferne eine Regel, falls sie vorhanden war wird sie zurückgegeben.
+    pub(crate) fn entferne_regel(&mut self, name: &regel::Name) -> Option<regel::Regel> {
+        self.maps.regeln.remove(name)
+    }
+
     /// Setze den Streckenabschnitt für das spezifizierte Gleis.
     /// Der bisherige Wert wird zurückgegeben.
     pub fn setze_streckenabschnitt<T: GleiseMap<Z>>(
@@ -521,6 +591,8 @@ impl<Z: Zugtyp> iced::canvas::Program<Message<Z>> for Gleise<Z> {
                     s_kurven_weichen,
                     kreuzungen,
                     streckenabschnitte,
+                    skripte: _,
+                    regeln: _,
                 },
             anchor_points,
             modus,
@@ -889,31 +961,184 @@ impl<Z: Zugtyp> Gleise<Z> {
     }
 }
 
+impl<Z: Zugtyp> Gleise<Z> {
+    /// Aktueller Zustand in speicherbarer Form.
+    pub(crate) fn zustand(
+        &self,
+        geschwindigkeiten: geschwindigkeit::Map<<Z::Leiter as ToSave>::Save>,
+        stellpult: stellpult::Map<anschluss::InputSave>,
+        drehregler: drehregler::MapSave,
+    ) -> GleiseVecs<Z> {
+        (&self.maps, geschwindigkeiten, stellpult, drehregler).into()
+    }
+}
+
 impl<Z: Zugtyp + Serialize> Gleise<Z> {
     #[must_use]
     pub fn speichern(
         &self,
         pfad: impl AsRef<std::path::Path>,
         geschwindigkeiten: geschwindigkeit::Map<<Z::Leiter as ToSave>::Save>,
+        stellpult: stellpult::Map<anschluss::InputSave>,
+        drehregler: drehregler::MapSave,
     ) -> std::result::Result<(), Error> {
-        let Gleise { maps, .. } = self;
-        let vecs: GleiseVecs<Z> = (maps, geschwindigkeiten).into();
+        let vecs = self.zustand(geschwindigkeiten, stellpult, drehregler);
         let file = std::fs::File::create(pfad)?;
-        bincode::serialize_into(file, &vecs)?;
+        speicherformat::schreibe(file, &vecs)?;
+        Ok(())
+    }
+
+    /// Speichere den Gleisplan unter `pfad` und die Anschlüsse getrennt davon unter `profil_pfad`.
+    #[must_use]
+    pub fn speichern_mit_profil(
+        &self,
+        pfad: impl AsRef<std::path::Path>,
+        profil_pfad: impl AsRef<std::path::Path>,
+        geschwindigkeiten: geschwindigkeit::Map<<Z::Leiter as ToSave>::Save>,
+        stellpult: stellpult::Map<anschluss::InputSave>,
+        drehregler: drehregler::MapSave,
+    ) -> std::result::Result<(), Error> {
+        let (gleisplan, anschlüsse) =
+            self.zustand(geschwindigkeiten, stellpult, drehregler).trennen();
+        speicherformat::schreibe(std::fs::File::create(pfad)?, &gleisplan)?;
+        speicherformat::schreibe(std::fs::File::create(profil_pfad)?, &anschlüsse)?;
         Ok(())
     }
 }
 
+/// Konflikt beim Reservieren der Anschlüsse eines gespeicherten Zustands.
+#[derive(Debug)]
+pub struct Konflikt {
+    pub besitzer: Besitzer,
+    /// Beschreibung der gespeicherten Steuerung, inklusive ihrer Anschlüsse.
+    pub steuerung: String,
+    pub fehler: anschluss::Error,
+}
+
+impl Display for Konflikt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}): {:?}", self.besitzer, self.steuerung, self.fehler)
+    }
+}
+
+/// Reserviere die Anschlüsse für `besitzer`, ein Fehler wird als Konflikt vermerkt.
+fn reserviere_oder_vermerke<S: Reserviere<R> + Debug, R>(
+    save: S,
+    anschlüsse: &mut Anschlüsse,
+    besitzer: Besitzer,
+    konflikte: &mut Vec<Konflikt>,
+) -> Option<R> {
+    let beschreibung = format!("{:?}", save);
+    match save.reserviere_für(anschlüsse, besitzer.clone()) {
+        Ok(reserviert) => Some(reserviert),
+        Err(fehler) => {
+            konflikte.push(Konflikt { besitzer, steuerung: beschreibung, fehler });
+            None
+        },
+    }
+}
+
 impl<Z: Zugtyp + PartialEq + std::fmt::Debug + for<'de> Deserialize<'de>> Gleise<Z> {
+    /// Lese einen gespeicherten Zustand, ohne Anschlüsse zu reservieren.
+    pub(crate) fn lese(
+        pfad: impl AsRef<std::path::Path>,
+    ) -> std::result::Result<GleiseVecs<Z>, Error> {
+        let bytes = std::fs::read(pfad)?;
+        let speicherstand = Speicherstand::neu(&bytes)?;
+        let name = speicherstand.name()?;
+        if name == profil::gleisplan_name::<Z>() {
+            return Err(Error::ProfilBenötigt);
+        } else if name != Z::NAME {
+            return Err(Error::FalscherZugtyp(name));
+        }
+        Ok(speicherstand.lese()?)
+    }
+
+    /// Lese einen gespeicherten Zustand und verbinde ihn mit den Anschlüssen aus `profil_pfad`.
+    ///
+    /// Die Anschlüsse eines vollständigen Zustands werden dabei ignoriert.
+    /// Namen ohne Eintrag im Profil werden zurückgegeben.
+    pub(crate) fn lese_mit_profil(
+        pfad: impl AsRef<std::path::Path>,
+        profil_pfad: impl AsRef<std::path::Path>,
+    ) -> std::result::Result<(GleiseVecs<Z>, Vec<Besitzer>), Error>
+    where
+        <Z::Leiter as ToSave>::Save: Clone,
+    {
+        let bytes = std::fs::read(pfad)?;
+        let speicherstand = Speicherstand::neu(&bytes)?;
+        let name = speicherstand.name()?;
+        let gleisplan = if name == Z::NAME {
+            speicherstand.lese::<GleiseVecs<Z>>()?.trennen().0
+        } else if name == profil::gleisplan_name::<Z>() {
+            speicherstand.lese::<GleisplanVecs<Z>>()?
+        } else {
+            return Err(Error::FalscherZugtyp(name));
+        };
+        let profil_bytes = std::fs::read(profil_pfad)?;
+        let anschlüsse: Profil<Z> = Speicherstand::neu(&profil_bytes)?.lese()?;
+        if anschlüsse.name != Z::NAME {
+            return Err(Error::FalscherZugtyp(anschlüsse.name));
+        }
+        Ok(gleisplan.verbinden(&anschlüsse))
+    }
+
+    /// Lese einen gespeicherten Zustand, ggf. mit den Anschlüssen aus `profil_pfad`.
+    fn lese_optional_profil(
+        pfad: impl AsRef<std::path::Path>,
+        profil_pfad: Option<impl AsRef<std::path::Path>>,
+    ) -> std::result::Result<(GleiseVecs<Z>, Vec<Besitzer>), Error>
+    where
+        <Z::Leiter as ToSave>::Save: Clone,
+    {
+        match profil_pfad {
+            Some(profil_pfad) => Self::lese_mit_profil(pfad, profil_pfad),
+            None => Ok((Self::lese(pfad)?, Vec::new())),
+        }
+    }
+
+    /// Verkabelung eines gespeicherten Zustands, ggf. mit den Anschlüssen aus `profil_pfad`.
+    ///
+    /// Namen ohne Eintrag im Profil werden zusätzlich zurückgegeben.
+    pub fn verkabelung(
+        pfad: impl AsRef<std::path::Path>,
+        profil_pfad: Option<impl AsRef<std::path::Path>>,
+    ) -> std::result::Result<(Verkabelung, Vec<Besitzer>), Error>
+    where
+        <Z::Leiter as ToSave>::Save: Clone + Verkabeln,
+    {
+        let (vecs, ungebunden) = Self::lese_optional_profil(pfad, profil_pfad)?;
+        Ok((vecs.verkabelung(), ungebunden))
+    }
+
+    /// Inhalt eines gespeicherten Zustands als Text, ggf. mit den Anschlüssen aus `profil_pfad`.
+    ///
+    /// Namen ohne Eintrag im Profil werden zusätzlich zurückgegeben.
+    pub fn inhalt(
+        pfad: impl AsRef<std::path::Path>,
+        profil_pfad: Option<impl AsRef<std::path::Path>>,
+    ) -> std::result::Result<(String, Vec<Besitzer>), Error>
+    where
+        <Z::Leiter as ToSave>::Save: Clone + Verkabeln,
+    {
+        let titel = pfad.as_ref().display().to_string();
+        let (vecs, ungebunden) = Self::lese_optional_profil(pfad, profil_pfad)?;
+        Ok((vecs.inhalt(&titel), ungebunden))
+    }
+
+    /// Reserviere die Anschlüsse aller Steuerungen eines gespeicherten Zustands.
+    ///
+    /// Alle Konflikte werden gesammelt. Ohne `konflikte_ignorieren` werden dann
+    /// alle bereits reservierten Anschlüsse wieder freigegeben,
+    /// ansonsten bleiben die betroffenen Steuerungen ohne Anschluss.
     #[must_use]
-    pub fn laden(
-        &mut self,
+    pub(crate) fn reserviere(
         anschlüsse: &mut Anschlüsse,
-        pfad: impl AsRef<std::path::Path>,
-    ) -> std::result::Result<geschwindigkeit::Map<Z::Leiter>, Error> {
-        let file = std::fs::File::open(pfad)?;
+        vecs: GleiseVecs<Z>,
+        konflikte_ignorieren: bool,
+    ) -> std::result::Result<GleiseReserviert<Z>, Vec<Konflikt>> {
         let GleiseVecs {
-            name,
+            name: _,
             geraden,
             kurven,
             weichen,
@@ -924,28 +1149,16 @@ impl<Z: Zugtyp + PartialEq + std::fmt::Debug + for<'de> Deserialize<'de>> Gleise
             streckenabschnitte,
             geschwindigkeiten,
             pläne: _, // TODO verwenden, sobald Plan implementiert ist
-        } = bincode::deserialize_from(file)?;
-
-        if name != Z::NAME {
-            return Err(Error::FalscherZugtyp(name));
-        }
-
-        // reset current state
-        self.canvas.clear();
-        // TODO pivot, skalieren?
-        self.pivot = Position { punkt: Vektor::null_vektor(), winkel: winkel::ZERO };
-        self.skalieren = Skalar::multiplikativ_neutral();
-        self.maps = GleiseMaps::neu();
-        self.anchor_points = anchor::rstar::RTree::new();
-        self.next_id = 0;
-        // don't reset last_mouse, last_size
-        // TODO Modus?
+            stellpult,
+            drehregler,
+            skripte,
+            regeln,
+        } = vecs;
+        let mut konflikte = Vec::new();
 
         macro_rules! reserviere_anschlüsse {
-            ($name:ident, $source:ident, $(:: $weiche:ident ::)? $module:ident, $data:ident {$steuerung:ident, $($data_feld:ident),*}) => {
-                // collect to Vec to fail on first error
-                // match to fix error type of closure
-                let $name: Vec<_> = match $source
+            ($source:ident, $(:: $weiche:ident ::)? $module:ident, $data:ident {$steuerung:ident: $besitzer:ident, $($data_feld:ident),*}) => {
+                $source
                     .into_iter()
                     .map(
                         |Gleis {
@@ -957,63 +1170,68 @@ impl<Z: Zugtyp + PartialEq + std::fmt::Debug + for<'de> Deserialize<'de>> Gleise
                             position,
                             streckenabschnitt,
                         }| {
-                            let steuerung_result: Option<Result<_, anschluss::Error>> = $steuerung.map(
-                                |steuerung| Ok(steuerung.reserviere(anschlüsse)?)
-                            );
-                            let steuerung_reserviert = steuerung_result.transpose()?;
-                            Ok(Gleis {
+                            let $steuerung = $steuerung.and_then(|steuerung| {
+                                let besitzer = Besitzer::$besitzer(steuerung.name.0.clone());
+                                reserviere_oder_vermerke(
+                                    steuerung,
+                                    anschlüsse,
+                                    besitzer,
+                                    &mut konflikte,
+                                )
+                            });
+                            Gleis {
                                 definition: super::$($weiche::)?$module::$data {
-                                    $steuerung: steuerung_reserviert,
+                                    $steuerung,
                                     $($data_feld),*
                                 },
                                 position,
                                 streckenabschnitt,
-                            })
+                            }
                         },
                     )
                     .collect()
-                {
-                    Ok(vec) => vec,
-                    Err(error) => return Err(error),
-                };
             };
         }
-        reserviere_anschlüsse!(
-            geraden_reserviert,
+        let geraden = reserviere_anschlüsse!(
             geraden,
             gerade,
-            Gerade { kontakt, zugtyp, länge, beschreibung }
+            Gerade { kontakt: Kontakt, zugtyp, länge, beschreibung }
         );
-        reserviere_anschlüsse!(
-            kurven_reserviert,
+        let kurven = reserviere_anschlüsse!(
             kurven,
             kurve,
-            Kurve { kontakt, zugtyp, radius, winkel, beschreibung }
+            Kurve { kontakt: Kontakt, zugtyp, radius, winkel, beschreibung }
         );
-        reserviere_anschlüsse!(
-            weichen_reserviert,
+        let weichen = reserviere_anschlüsse!(
             weichen,
             ::weiche::gerade,
-            Weiche { steuerung, zugtyp, länge, radius, winkel, orientierung, beschreibung }
+            Weiche {
+                steuerung: Weiche, zugtyp, länge, radius, winkel, orientierung, beschreibung
+            }
         );
-        reserviere_anschlüsse!(
-            dreiwege_weichen_reserviert,
+        let dreiwege_weichen = reserviere_anschlüsse!(
             dreiwege_weichen,
             ::weiche::dreiwege,
-            DreiwegeWeiche { steuerung, zugtyp, länge, radius, winkel, beschreibung }
+            DreiwegeWeiche { steuerung: Weiche, zugtyp, länge, radius, winkel, beschreibung }
         );
-        reserviere_anschlüsse!(
-            kurven_weichen_reserviert,
+        let kurven_weichen = reserviere_anschlüsse!(
             kurven_weichen,
             ::weiche::kurve,
-            KurvenWeiche { steuerung, zugtyp, länge, radius, winkel, orientierung, beschreibung }
+            KurvenWeiche {
+                steuerung: Weiche,
+                zugtyp,
+                länge,
+                radius,
+                winkel,
+                orientierung,
+                beschreibung
+            }
         );
-        reserviere_anschlüsse!(
-            s_kurven_weichen_reserviert,
+        let s_kurven_weichen = reserviere_anschlüsse!(
             s_kurven_weichen,
             ::weiche::s_kurve,
             SKurvenWeiche {
-                steuerung,
+                steuerung: Weiche,
                 zugtyp,
                 länge,
                 radius,
@@ -1024,13 +1242,115 @@ impl<Z: Zugtyp + PartialEq + std::fmt::Debug + for<'de> Deserialize<'de>> Gleise
                 beschreibung
             }
         );
-        reserviere_anschlüsse!(
-            kreuzungen_reserviert,
+        let kreuzungen = reserviere_anschlüsse!(
             kreuzungen,
             kreuzung,
-            Kreuzung { steuerung, zugtyp, länge, radius, variante, beschreibung }
+            Kreuzung { steuerung: Weiche, zugtyp, länge, radius, variante, beschreibung }
         );
-        // restore state from data
+        let streckenabschnitte = streckenabschnitte
+            .into_iter()
+            .filter_map(|(name, Streckenabschnitt { farbe, anschluss })| {
+                let besitzer = Besitzer::Streckenabschnitt(name.0.clone());
+                let anschluss =
+                    reserviere_oder_vermerke(anschluss, anschlüsse, besitzer, &mut konflikte)?;
+                Some((name, Streckenabschnitt { farbe, anschluss }))
+            })
+            .collect();
+        let geschwindigkeiten = geschwindigkeiten
+            .into_iter()
+            .filter_map(|(name, geschwindigkeit)| {
+                let besitzer = Besitzer::Geschwindigkeit(name.0.clone());
+                let geschwindigkeit = reserviere_oder_vermerke(
+                    geschwindigkeit,
+                    anschlüsse,
+                    besitzer,
+                    &mut konflikte,
+                )?;
+                Some((name, geschwindigkeit))
+            })
+            .collect();
+        let stellpult = stellpult
+            .into_iter()
+            .filter_map(|(name, taster)| {
+                let besitzer = Besitzer::Taster(name.0.clone());
+                let taster =
+                    reserviere_oder_vermerke(taster, anschlüsse, besitzer, &mut konflikte)?;
+                Some((name, taster))
+            })
+            .collect();
+        let drehregler = drehregler
+            .into_iter()
+            .filter_map(|(name, drehregler)| {
+                let besitzer = Besitzer::Drehregler(name.0.clone());
+                let drehregler =
+                    reserviere_oder_vermerke(drehregler, anschlüsse, besitzer, &mut konflikte)?;
+                Some((name, drehregler))
+            })
+            .collect();
+
+        if !konflikte.is_empty() && !konflikte_ignorieren {
+            // alle bisher reservierten Anschlüsse werden beim drop freigegeben
+            return Err(konflikte);
+        }
+        Ok(GleiseReserviert {
+            geraden,
+            kurven,
+            weichen,
+            dreiwege_weichen,
+            kurven_weichen,
+            s_kurven_weichen,
+            kreuzungen,
+            streckenabschnitte,
+            geschwindigkeiten,
+            stellpult,
+            drehregler,
+            skripte,
+            regeln,
+            konflikte,
+        })
+    }
+
+    /// Entferne alle Gleise und Streckenabschnitte, ihre Anschlüsse werden dabei freigegeben.
+    pub(crate) fn leeren(&mut self) {
+        self.canvas.clear();
+        self.maps = GleiseMaps::neu();
+        self.anchor_points = anchor::rstar::RTree::new();
+        self.next_id = 0;
+        // don't reset last_mouse, last_size
+        // TODO Modus?
+    }
+
+    /// Setze die Ansicht auf den Ursprung zurück.
+    pub(crate) fn ansicht_zurücksetzen(&mut self) {
+        self.canvas.clear();
+        // TODO pivot, skalieren?
+        self.pivot = Position { punkt: Vektor::null_vektor(), winkel: winkel::ZERO };
+        self.skalieren = Skalar::multiplikativ_neutral();
+    }
+
+    /// Füge alle reservierten Gleise, Streckenabschnitte, Skripte und Regeln hinzu.
+    ///
+    /// Die übrigen Steuerungen und die ignorierten Konflikte werden zurückgegeben.
+    pub(crate) fn übernehmen(
+        &mut self,
+        reserviert: GleiseReserviert<Z>,
+    ) -> (geschwindigkeit::Map<Z::Leiter>, stellpult::Map, drehregler::Map, Vec<Konflikt>) {
+        let GleiseReserviert {
+            geraden,
+            kurven,
+            weichen,
+            dreiwege_weichen,
+            kurven_weichen,
+            s_kurven_weichen,
+            kreuzungen,
+            streckenabschnitte,
+            geschwindigkeiten,
+            stellpult,
+            drehregler,
+            skripte,
+            regeln,
+            konflikte,
+        } = reserviert;
         macro_rules! add_gleise {
             ($($gleise: ident,)*) => {
                 $(
@@ -1041,36 +1361,161 @@ impl<Z: Zugtyp + PartialEq + std::fmt::Debug + for<'de> Deserialize<'de>> Gleise
             }
         }
         add_gleise!(
-            geraden_reserviert,
-            kurven_reserviert,
-            weichen_reserviert,
-            dreiwege_weichen_reserviert,
-            kurven_weichen_reserviert,
-            s_kurven_weichen_reserviert,
-            kreuzungen_reserviert,
+            geraden,
+            kurven,
+            weichen,
+            dreiwege_weichen,
+            kurven_weichen,
+            s_kurven_weichen,
+            kreuzungen,
         );
-        let streckenabschnitte_reserviert: Vec<_> = match streckenabschnitte
-            .into_iter()
-            .map(|(name, Streckenabschnitt { farbe, anschluss })| {
-                Ok((
-                    name,
-                    Streckenabschnitt { farbe, anschluss: anschluss.reserviere(anschlüsse)? },
-                ))
-            })
-            .collect()
-        {
-            Ok(map) => map,
-            Err(error) => return Err(error),
-        };
-        for (name, streckenabschnitt) in streckenabschnitte_reserviert {
+        for (name, streckenabschnitt) in streckenabschnitte {
             self.neuer_streckenabschnitt(name, streckenabschnitt);
         }
+        self.maps.skripte = skripte;
+        self.maps.regeln = regeln;
+        (geschwindigkeiten, stellpult, drehregler, konflikte)
+    }
+}
 
-        let geschwindigkeiten_reserviert = geschwindigkeiten
+impl<Z: Zugtyp> Gleise<Z>
+where
+    <Z::Leiter as ToSave>::Save: Debug + Verkabeln,
+{
+    /// Prüfe ohne Reservierung, ob die Anschlüsse von `neu` verfügbar sind,
+    /// nachdem die Anschlüsse von `bisher` freigegeben wurden.
+    ///
+    /// Konflikte sind Anschlüsse, die weder frei sind noch von `bisher` verwendet werden,
+    /// z.B. der Pin des Nothalt-Tasters. Anschlüsse der Drehregler (Adc-Kanäle)
+    /// und mehrfach verwendete Anschlüsse werden erst beim Reservieren erkannt.
+    pub(crate) fn prüfe_verfügbar(
+        anschlüsse: &Anschlüsse,
+        bisher: &GleiseVecs<Z>,
+        neu: &GleiseVecs<Z>,
+    ) -> std::result::Result<(), Vec<Konflikt>> {
+        let reservierungen = match anschlüsse.reservierungen() {
+            Ok(reservierungen) => reservierungen,
+            // der Fehler tritt ebenso beim Reservieren auf
+            Err(_fehler) => return Ok(()),
+        };
+        let bisher: HashSet<_> = bisher
+            .anschlüsse()
             .into_iter()
-            .map(|(name, geschwindigkeit)| Ok((name, geschwindigkeit.reserviere(anschlüsse)?)))
-            .collect::<Result<_, anschluss::Error>>()?;
-        Ok(geschwindigkeiten_reserviert)
+            .flat_map(|(_besitzer, _beschreibung, kennungen)| kennungen)
+            .collect();
+        let mut konflikte = Vec::new();
+        for (besitzer, beschreibung, kennungen) in neu.anschlüsse() {
+            for kennung in kennungen {
+                let verfügbarkeit = reservierungen.anschluss(kennung);
+                if let Verfügbarkeit::Reserviert(_) = verfügbarkeit {
+                    if !bisher.contains(&kennung) {
+                        konflikte.push(Konflikt {
+                            besitzer: besitzer.clone(),
+                            steuerung: format!("{}, {}: {}", beschreibung, kennung, verfügbarkeit),
+                            fehler: SyncError::InVerwendung.into(),
+                        });
+                    }
+                }
+            }
+        }
+        if konflikte.is_empty() {
+            Ok(())
+        } else {
+            Err(konflikte)
+        }
+    }
+}
+
+impl<Z> Gleise<Z>
+where
+    Z: Zugtyp + PartialEq + Debug + Serialize + for<'de> Deserialize<'de>,
+    <Z::Leiter as ToSave>::Save: Zuordnen,
+{
+    /// Ersetze die Anschlüsse der Datei `pfad` gemäß `zuordnung` und speichere unter `ziel`.
+    ///
+    /// Gespeichert wird nur, wenn danach alle Anschlüsse ohne Konflikt reserviert werden können.
+    /// Sie werden anschließend sofort wieder freigegeben.
+    /// Gibt die Anzahl der geänderten Anschlüsse zurück.
+    pub fn zuordnung_anwenden(
+        anschlüsse: &mut Anschlüsse,
+        pfad: impl AsRef<std::path::Path>,
+        ziel: impl AsRef<std::path::Path>,
+        zuordnung: &Zuordnung,
+    ) -> std::result::Result<usize, Error> {
+        let mut vecs = Self::lese(pfad)?;
+        let geändert = vecs.zuordnen(zuordnung)?;
+        let mut bytes = Vec::new();
+        speicherformat::schreibe(&mut bytes, &vecs)?;
+        if let Err(konflikte) = Self::reserviere(anschlüsse, vecs, false) {
+            return Err(Error::Konflikte(konflikte));
+        }
+        std::fs::write(ziel, bytes)?;
+        Ok(geändert)
+    }
+}
+
+impl<Z> Gleise<Z>
+where
+    Z: Zugtyp + PartialEq + Debug + Serialize + for<'de> Deserialize<'de>,
+    <Z::Leiter as ToSave>::Save: Clone,
+{
+    /// Lese einen gespeicherten Zustand, ggf. mit den Anschlüssen aus `profil_pfad`,
+    /// und speichere ihn unter `ziel`, ohne Anschlüsse zu reservieren.
+    ///
+    /// Mit `profil_ziel` werden Gleisplan und Anschlüsse getrennt gespeichert,
+    /// ansonsten als vollständiger Zustand. Namen ohne Eintrag im Profil werden zurückgegeben.
+    pub fn konvertieren(
+        pfad: impl AsRef<std::path::Path>,
+        profil_pfad: Option<impl AsRef<std::path::Path>>,
+        ziel: impl AsRef<std::path::Path>,
+        profil_ziel: Option<impl AsRef<std::path::Path>>,
+    ) -> std::result::Result<Vec<Besitzer>, Error> {
+        let (vecs, ungebunden) = Self::lese_optional_profil(pfad, profil_pfad)?;
+        match profil_ziel {
+            Some(profil_ziel) => {
+                let (gleisplan, anschlüsse) = vecs.trennen();
+                speicherformat::schreibe(std::fs::File::create(ziel)?, &gleisplan)?;
+                speicherformat::schreibe(std::fs::File::create(profil_ziel)?, &anschlüsse)?;
+            },
+            None => speicherformat::schreibe(std::fs::File::create(ziel)?, &vecs)?,
+        }
+        Ok(ungebunden)
+    }
+
+    /// Unterschiede zwischen zwei gespeicherten Zuständen, eine Zeile pro Änderung.
+    ///
+    /// Mit `profil_pfad` werden beide Zustände mit den Anschlüssen aus dem Profil verglichen.
+    pub fn vergleiche(
+        pfad_alt: impl AsRef<std::path::Path>,
+        pfad_neu: impl AsRef<std::path::Path>,
+        profil_pfad: Option<impl AsRef<std::path::Path> + Copy>,
+    ) -> std::result::Result<Vec<String>, Error>
+    where
+        <Z::Leiter as ToSave>::Save: Verkabeln,
+    {
+        let (alt, _ungebunden) = Self::lese_optional_profil(pfad_alt, profil_pfad)?;
+        let (neu, _ungebunden) = Self::lese_optional_profil(pfad_neu, profil_pfad)?;
+        alt.unterschiede(&neu)
+    }
+
+    /// Führe die Änderungen zweier bearbeiteter Kopien des Zustands `basis` zusammen
+    /// und speichere das Ergebnis unter `ziel`.
+    ///
+    /// Bei widersprüchlichen Änderungen wird nichts gespeichert
+    /// und alle Konflikte als [Error::Vereinigen] zurückgegeben.
+    pub fn vereinige(
+        basis: impl AsRef<std::path::Path>,
+        pfad: impl AsRef<std::path::Path>,
+        andere: impl AsRef<std::path::Path>,
+        ziel: impl AsRef<std::path::Path>,
+    ) -> std::result::Result<(), Error>
+    where
+        <Z::Leiter as ToSave>::Save: Verkabeln,
+    {
+        let vecs =
+            GleiseVecs::vereinigen(Self::lese(basis)?, Self::lese(pfad)?, Self::lese(andere)?)?;
+        speicherformat::schreibe(std::fs::File::create(ziel)?, &vecs)?;
+        Ok(())
     }
 }
 
@@ -1127,6 +1572,66 @@ impl<Z: Zugtyp> Gleise<Z> {
         super::weiche::gerade::Richtung,
         super::weiche::gerade::RichtungAnschlüsse
     }
+
+    /// Namen aller Weichen mit Anschlüssen.
+    pub(crate) fn weichen_namen(&self) -> Vec<weiche::Name> {
+        let mut namen = Vec::new();
+        macro_rules! sammle_namen {
+            ($($map:ident),*) => {
+                $(
+                    for Gleis { definition, .. } in self.maps.$map.values() {
+                        if let Some(steuerung) = &definition.steuerung {
+                            namen.push(steuerung.name.clone())
+                        }
+                    }
+                )*
+            };
+        }
+        sammle_namen!(weichen, dreiwege_weichen, kurven_weichen, s_kurven_weichen, kreuzungen);
+        namen
+    }
+
+    /// Namen und aktuelle Richtung aller Weichen mit Anschlüssen.
+    pub(crate) fn weichen_richtungen(&self) -> Vec<(weiche::Name, String)> {
+        let mut richtungen = Vec::new();
+        macro_rules! sammle_richtungen {
+            ($($map:ident),*) => {
+                $(
+                    for Gleis { definition, .. } in self.maps.$map.values() {
+                        if let Some(steuerung) = &definition.steuerung {
+                            richtungen.push((
+                                steuerung.name.clone(),
+                                format!("{:?}", steuerung.aktuelle_richtung),
+                            ))
+                        }
+                    }
+                )*
+            };
+        }
+        sammle_richtungen!(weichen, dreiwege_weichen, kurven_weichen, s_kurven_weichen, kreuzungen);
+        richtungen
+    }
+
+    /// Finde das Gleis, dessen Weichen-Steuerung den übergebenen Namen hat.
+    pub(crate) fn weiche_mit_name(&self, name: &weiche::Name) -> Option<AnyId<Z>> {
+        macro_rules! suche_name {
+            ($($map:ident),*) => {
+                $(
+                    for (gleis_id, Gleis { definition, .. }) in self.maps.$map.iter() {
+                        if definition
+                            .steuerung
+                            .as_ref()
+                            .map_or(false, |steuerung| &steuerung.name == name)
+                        {
+                            return Some(AnyId::from(gleis_id.clone()))
+                        }
+                    }
+                )*
+            };
+        }
+        suche_name!(weichen, dreiwege_weichen, kurven_weichen, s_kurven_weichen, kreuzungen);
+        None
+    }
 }
 
 #[derive(Debug)]
@@ -1135,7 +1640,13 @@ pub enum Error {
     Bincode(bincode::Error),
     FalscherZugtyp(String),
     Anschluss(anschluss::Error),
+    Zuordnung(zuordnung::Error),
+    Konflikte(Vec<Konflikt>),
+    /// Ein Gleisplan ohne Anschlüsse kann nur zusammen mit einem Profil geladen werden.
+    ProfilBenötigt,
     GleisEntfernt,
+    /// Widersprüchliche Änderungen beim Zusammenführen zweier Zustände.
+    Vereinigen(Vec<String>),
 }
 impl From<std::io::Error> for Error {
     fn from(error: std::io::Error) -> Self {
@@ -1152,6 +1663,11 @@ impl From<anschluss::Error> for Error {
         Error::Anschluss(error)
     }
 }
+impl From<zuordnung::Error> for Error {
+    fn from(error: zuordnung::Error) -> Self {
+        Error::Zuordnung(error)
+    }
+}
 
 #[derive(Debug)]
 pub struct GleisEntferntError;
@@ -0,0 +1,344 @@
+//! Hinzufügen der Gleise und Steuerungen eines anderen gespeicherten Zustands,
+//! z.B. um getrennt geplante Module zu einer Anlage zusammenzusetzen.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
+
+use super::{
+    maps::{Gleis, GleiseVecs},
+    vergleich::{beschreibe, MitSteuerung},
+};
+use crate::{
+    application::{anchor, typen::*},
+    lookup::Lookup,
+    steuerung::{
+        geschwindigkeit, regel, skript,
+        stellpult::{self, Aktion},
+        streckenabschnitt,
+    },
+};
+
+/// Maximaler Abstand zweier Anschlusspunkte, die als verbunden gelten.
+const VERBUNDEN: Skalar = Skalar(5.);
+
+/// Platzierung der importierten Gleise.
+#[derive(Debug, Clone)]
+pub enum Platzierung {
+    /// Verschiebe den Ursprung der importierten Gleise an `punkt` und drehe sie um `winkel`.
+    Verschiebung(Position),
+    /// Lege den Anschlusspunkt `import` der importierten Gleise an den Anschlusspunkt `ziel`.
+    Anlegen { import: anchor::Anchor, ziel: anchor::Anchor },
+}
+
+impl Platzierung {
+    /// Position, an die der Ursprung der importierten Gleise bewegt wird.
+    pub fn ursprung(&self) -> Position {
+        match self {
+            Platzierung::Verschiebung(position) => position.clone(),
+            Platzierung::Anlegen { import, ziel } => {
+                // entgegengesetzte Richtung, damit die Gleise verbunden sind
+                let winkel = winkel::PI - import.richtung + ziel.richtung;
+                Position { punkt: ziel.position - import.position.rotiert(winkel), winkel }
+            },
+        }
+    }
+}
+
+/// Alle Anschlusspunkte der Gleise einer Art, mit Beschreibung und Index des Gleises.
+fn sammle_anchor_points<T: Zeichnen>(
+    anchor_points: &mut Vec<(String, (&'static str, usize), anchor::Anchor)>,
+    art: &'static str,
+    gleise: &[Gleis<T>],
+) {
+    for (index, gleis) in gleise.iter().enumerate() {
+        let beschreibung = beschreibe(art, gleis);
+        let position = &gleis.position;
+        gleis.definition.anchor_points().for_each(|_name, anchor| {
+            let anchor = anchor::Anchor {
+                position: position.transformation(anchor.position),
+                richtung: position.winkel + anchor.richtung,
+            };
+            anchor_points.push((beschreibung.clone(), (art, index), anchor))
+        })
+    }
+}
+
+/// Neue Namen für alle Namen aus `andere`, die bereits `vorhanden` sind.
+///
+/// Dafür wird eine Zahl angehängt, die weder in `vorhanden` noch in `andere` vorkommt.
+fn umbenennungen<'t>(
+    vorhanden: impl Iterator<Item = &'t String>,
+    andere: impl Iterator<Item = &'t String>,
+) -> BTreeMap<String, String> {
+    let vorhanden: BTreeSet<&String> = vorhanden.collect();
+    let andere: Vec<&String> = andere.collect();
+    let mut belegt: BTreeSet<String> =
+        vorhanden.iter().chain(andere.iter()).map(|name| name.to_string()).collect();
+    let mut umbenennungen = BTreeMap::new();
+    for name in andere {
+        if vorhanden.contains(name) {
+            let mut nummer = 2;
+            let neu = loop {
+                let neu = format!("{} {}", name, nummer);
+                if !belegt.contains(&neu) {
+                    break neu;
+                }
+                nummer += 1;
+            };
+            belegt.insert(neu.clone());
+            umbenennungen.insert(name.clone(), neu);
+        }
+    }
+    umbenennungen
+}
+
+fn umbenennen(umbenennungen: &BTreeMap<String, String>, name: String) -> String {
+    umbenennungen.get(&name).cloned().unwrap_or(name)
+}
+
+/// Verwende die neuen Namen umbenannter Streckenabschnitte und Geschwindigkeiten in `regel`.
+fn regel_umbenennen(
+    mut regel: regel::Regel,
+    streckenabschnitte: &BTreeMap<String, String>,
+    geschwindigkeiten: &BTreeMap<String, String>,
+) -> regel::Regel {
+    if let regel::Auslöser::Streckenabschnitt { name, .. } = &mut regel.auslöser {
+        name.0 = umbenennen(streckenabschnitte, std::mem::take(&mut name.0))
+    }
+    for regel::Schritt { aktion, .. } in regel.schritte.iter_mut() {
+        match aktion {
+            regel::Aktion::Streckenabschnitt { name, .. } => {
+                name.0 = umbenennen(streckenabschnitte, std::mem::take(&mut name.0))
+            },
+            regel::Aktion::Geschwindigkeit { name, .. } => {
+                name.0 = umbenennen(geschwindigkeiten, std::mem::take(&mut name.0))
+            },
+            regel::Aktion::Weiche { .. } => {},
+        }
+    }
+    regel
+}
+
+/// Füge die Gleise einer Art verschoben an `ursprung` zu `ziel` hinzu.
+///
+/// Ohne `streckenabschnitte` werden Steuerung und Streckenabschnitt entfernt,
+/// ansonsten werden die Streckenabschnitte umbenannt.
+fn übernehme_gleise<T: MitSteuerung>(
+    ziel: &mut Vec<Gleis<T>>,
+    gleise: Vec<Gleis<T>>,
+    ursprung: &Position,
+    streckenabschnitte: Option<&BTreeMap<String, String>>,
+) {
+    for Gleis { definition, position, streckenabschnitt } in gleise {
+        let position = Position {
+            punkt: ursprung.transformation(position.punkt),
+            winkel: ursprung.winkel + position.winkel,
+        };
+        ziel.push(match streckenabschnitte {
+            Some(umbenennungen) => Gleis {
+                definition,
+                position,
+                streckenabschnitt: streckenabschnitt.map(|streckenabschnitt::Name(name)| {
+                    streckenabschnitt::Name(umbenennen(umbenennungen, name))
+                }),
+            },
+            None => {
+                Gleis { definition: definition.ohne_steuerung(), position, streckenabschnitt: None }
+            },
+        })
+    }
+}
+
+impl<Z: Zugtyp + Serialize> GleiseVecs<Z> {
+    /// Anschlusspunkte, an denen kein anderes Gleis anliegt, mit Beschreibung des Gleises.
+    pub(crate) fn offene_anchor_points(&self) -> Vec<(String, anchor::Anchor)> {
+        let mut alle = Vec::new();
+        sammle_anchor_points(&mut alle, "Gerade", &self.geraden);
+        sammle_anchor_points(&mut alle, "Kurve", &self.kurven);
+        sammle_anchor_points(&mut alle, "Weiche", &self.weichen);
+        sammle_anchor_points(&mut alle, "Dreiwege-Weiche", &self.dreiwege_weichen);
+        sammle_anchor_points(&mut alle, "Kurven-Weiche", &self.kurven_weichen);
+        sammle_anchor_points(&mut alle, "S-Kurven-Weiche", &self.s_kurven_weichen);
+        sammle_anchor_points(&mut alle, "Kreuzung", &self.kreuzungen);
+        alle.iter()
+            .filter(|(_beschreibung, gleis, anchor)| {
+                !alle.iter().any(|(_, anderes_gleis, anderer_anchor)| {
+                    anderes_gleis != gleis
+                        && (anchor.position - anderer_anchor.position).länge() < VERBUNDEN
+                })
+            })
+            .map(|(beschreibung, _gleis, anchor)| {
+                let text = format!(
+                    "{}, Anschlusspunkt ({:.1}, {:.1}) {:.0}°",
+                    beschreibung,
+                    anchor.position.x.0,
+                    anchor.position.y.0,
+                    anchor.richtung.0.to_degrees()
+                );
+                (text, *anchor)
+            })
+            .collect()
+    }
+
+    /// Füge alle Gleise von `andere` hinzu, ihr Ursprung wird dabei nach `ursprung` verschoben.
+    ///
+    /// Mit `anschlüsse_übernehmen` werden auch Steuerungen, Streckenabschnitte,
+    /// Geschwindigkeiten, Stellpult, Drehregler, Skripte und Regeln übernommen,
+    /// bereits vorhandene Namen werden umbenannt.
+    /// Ansonsten werden nur die Gleise ohne Steuerung hinzugefügt.
+    /// Gibt alle Umbenennungen und nicht übernommene Drehregler zurück.
+    pub(crate) fn importieren(
+        &mut self,
+        andere: GleiseVecs<Z>,
+        ursprung: &Position,
+        anschlüsse_übernehmen: bool,
+    ) -> Vec<String> {
+        let GleiseVecs {
+            name: _,
+            geraden,
+            kurven,
+            weichen,
+            dreiwege_weichen,
+            kurven_weichen,
+            s_kurven_weichen,
+            kreuzungen,
+            streckenabschnitte,
+            geschwindigkeiten,
+            pläne,
+            stellpult,
+            drehregler,
+            skripte,
+            regeln,
+        } = andere;
+        let mut meldungen = Vec::new();
+        let umbenennungen_streckenabschnitte = umbenennungen(
+            self.streckenabschnitte.keys().map(|name| &name.0),
+            streckenabschnitte.keys().map(|name| &name.0),
+        );
+        let streckenabschnitt_umbenennungen =
+            if anschlüsse_übernehmen { Some(&umbenennungen_streckenabschnitte) } else { None };
+        übernehme_gleise(&mut self.geraden, geraden, ursprung, streckenabschnitt_umbenennungen);
+        übernehme_gleise(&mut self.kurven, kurven, ursprung, streckenabschnitt_umbenennungen);
+        übernehme_gleise(&mut self.weichen, weichen, ursprung, streckenabschnitt_umbenennungen);
+        übernehme_gleise(
+            &mut self.dreiwege_weichen,
+            dreiwege_weichen,
+            ursprung,
+            streckenabschnitt_umbenennungen,
+        );
+        übernehme_gleise(
+            &mut self.kurven_weichen,
+            kurven_weichen,
+            ursprung,
+            streckenabschnitt_umbenennungen,
+        );
+        übernehme_gleise(
+            &mut self.s_kurven_weichen,
+            s_kurven_weichen,
+            ursprung,
+            streckenabschnitt_umbenennungen,
+        );
+        übernehme_gleise(
+            &mut self.kreuzungen,
+            kreuzungen,
+            ursprung,
+            streckenabschnitt_umbenennungen,
+        );
+        if !anschlüsse_übernehmen {
+            return meldungen;
+        }
+
+        let umbenennungen_geschwindigkeiten = umbenennungen(
+            self.geschwindigkeiten.keys().map(|name| &name.0),
+            geschwindigkeiten.keys().map(|name| &name.0),
+        );
+        let umbenennungen_stellpult = umbenennungen(
+            self.stellpult.keys().map(|name| &name.0),
+            stellpult.keys().map(|name| &name.0),
+        );
+        let umbenennungen_skripte = umbenennungen(
+            self.skripte.keys().map(|name| &name.0),
+            skripte.keys().map(|name| &name.0),
+        );
+        let umbenennungen_regeln = umbenennungen(
+            self.regeln.keys().map(|name| &name.0),
+            regeln.keys().map(|name| &name.0),
+        );
+        for (art, namen) in [
+            ("Streckenabschnitt", &umbenennungen_streckenabschnitte),
+            ("Geschwindigkeit", &umbenennungen_geschwindigkeiten),
+            ("Taster", &umbenennungen_stellpult),
+            ("Skript", &umbenennungen_skripte),
+            ("Regel", &umbenennungen_regeln),
+        ]
+        .iter()
+        {
+            for (bisher, neu) in namen.iter() {
+                meldungen.push(format!("{} {} umbenannt in {}", art, bisher, neu))
+            }
+        }
+
+        for (streckenabschnitt::Name(name), abschnitt) in streckenabschnitte {
+            let name = umbenennen(&umbenennungen_streckenabschnitte, name);
+            self.streckenabschnitte.insert(streckenabschnitt::Name(name), abschnitt);
+        }
+        for (geschwindigkeit::Name(name), wert) in geschwindigkeiten {
+            let name = umbenennen(&umbenennungen_geschwindigkeiten, name);
+            self.geschwindigkeiten.insert(geschwindigkeit::Name(name), wert);
+        }
+        for (stellpult::Name(name), mut taster) in stellpult {
+            taster.aktion = match taster.aktion {
+                Aktion::StreckenabschnittUmschalten(streckenabschnitt::Name(name)) => {
+                    Aktion::StreckenabschnittUmschalten(streckenabschnitt::Name(umbenennen(
+                        &umbenennungen_streckenabschnitte,
+                        name,
+                    )))
+                },
+                Aktion::GeschwindigkeitUmdrehen(geschwindigkeit::Name(name)) => {
+                    Aktion::GeschwindigkeitUmdrehen(geschwindigkeit::Name(umbenennen(
+                        &umbenennungen_geschwindigkeiten,
+                        name,
+                    )))
+                },
+                aktion @ (Aktion::WeicheUmschalten(_) | Aktion::FahrstraßeStellen(_)) => aktion,
+            };
+            let name = umbenennen(&umbenennungen_stellpult, name);
+            self.stellpult.insert(stellpult::Name(name), taster);
+        }
+        for (geschwindigkeit::Name(name), drehregler) in drehregler {
+            let name = geschwindigkeit::Name(umbenennen(&umbenennungen_geschwindigkeiten, name));
+            if self.drehregler.contains_key(&name) {
+                meldungen.push(format!("Drehregler {} bereits vorhanden, nicht übernommen", name.0))
+            } else {
+                self.drehregler.insert(name, drehregler);
+            }
+        }
+        // der Quelltext wird nicht angepasst
+        if !skripte.is_empty()
+            && !(umbenennungen_streckenabschnitte.is_empty()
+                && umbenennungen_geschwindigkeiten.is_empty())
+        {
+            meldungen.push(
+                "Importierte Skripte verwenden weiterhin die bisherigen Namen der umbenannten \
+                Streckenabschnitte und Geschwindigkeiten"
+                    .to_string(),
+            )
+        }
+        for (skript::Name(name), quelltext) in skripte {
+            let name = umbenennen(&umbenennungen_skripte, name);
+            self.skripte.insert(skript::Name(name), quelltext);
+        }
+        for (regel::Name(name), regel) in regeln {
+            let name = umbenennen(&umbenennungen_regeln, name);
+            let regel = regel_umbenennen(
+                regel,
+                &umbenennungen_streckenabschnitte,
+                &umbenennungen_geschwindigkeiten,
+            );
+            self.regeln.insert(regel::Name(name), regel);
+        }
+        self.pläne.extend(pläne);
+        meldungen
+    }
+}
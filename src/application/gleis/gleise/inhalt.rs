@@ -0,0 +1,130 @@
+//! Lesbare Darstellung eines gespeicherten Zustands, z.B. für die Kommandozeile.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use super::maps::{Gleis, GleiseVecs};
+use crate::{
+    anschluss::{adc, verkabelung::Verkabeln, ToSave},
+    application::typen::*,
+};
+
+/// Anzahl der Gleise je Art und Beschreibung.
+type Anzahl = BTreeMap<(&'static str, String), usize>;
+
+/// Eine Zeile pro Gleis mit Beschreibung, Position, Streckenabschnitt und Steuerung.
+fn schreibe_gleise<T: Zeichnen>(
+    s: &mut String,
+    art: &'static str,
+    gleise: &[Gleis<T>],
+    anzahl: &mut Anzahl,
+) {
+    if gleise.is_empty() {
+        return;
+    }
+    let _ = writeln!(s, "\n## {} ({})\n", art, gleise.len());
+    for Gleis { definition, position, streckenabschnitt } in gleise {
+        let (_position, beschreibung, name) = definition.beschreibung_und_name();
+        let beschreibung = beschreibung.cloned().unwrap_or_default();
+        let _ = write!(
+            s,
+            "- {} bei ({:.1}, {:.1}), {:.1}°",
+            if beschreibung.is_empty() { art } else { beschreibung.as_str() },
+            position.punkt.x.0,
+            position.punkt.y.0,
+            position.winkel.0.to_degrees()
+        );
+        if let Some(streckenabschnitt) = streckenabschnitt {
+            let _ = write!(s, ", Streckenabschnitt {}", streckenabschnitt.0);
+        }
+        if let Some(name) = name {
+            let _ = write!(s, ", Steuerung {}", name);
+        }
+        let _ = writeln!(s);
+        *anzahl.entry((art, beschreibung)).or_insert(0) += 1;
+    }
+}
+
+impl<Z: Zugtyp> GleiseVecs<Z>
+where
+    <Z::Leiter as ToSave>::Save: Verkabeln,
+{
+    /// Alle Gleise, Steuerungen und Anschlüsse als Markdown-Text,
+    /// zusätzlich die Anzahl der Gleise je Beschreibung.
+    pub(crate) fn inhalt(&self, titel: &str) -> String {
+        let mut s = format!("# {} ({})\n", titel, self.name);
+        let mut anzahl = Anzahl::new();
+        schreibe_gleise(&mut s, "Gerade", &self.geraden, &mut anzahl);
+        schreibe_gleise(&mut s, "Kurve", &self.kurven, &mut anzahl);
+        schreibe_gleise(&mut s, "Weiche", &self.weichen, &mut anzahl);
+        schreibe_gleise(&mut s, "Dreiwege-Weiche", &self.dreiwege_weichen, &mut anzahl);
+        schreibe_gleise(&mut s, "Kurven-Weiche", &self.kurven_weichen, &mut anzahl);
+        schreibe_gleise(&mut s, "S-Kurven-Weiche", &self.s_kurven_weichen, &mut anzahl);
+        schreibe_gleise(&mut s, "Kreuzung", &self.kreuzungen, &mut anzahl);
+
+        if !self.streckenabschnitte.is_empty() {
+            let _ = writeln!(s, "\n## Streckenabschnitte ({})\n", self.streckenabschnitte.len());
+            let mut streckenabschnitte: Vec<_> = self.streckenabschnitte.iter().collect();
+            streckenabschnitte.sort_by(|(a, _), (b, _)| a.cmp(b));
+            for (name, streckenabschnitt) in streckenabschnitte {
+                let farbe = streckenabschnitt.farbe;
+                let _ = writeln!(
+                    s,
+                    "- {}: Farbe ({:.2}, {:.2}, {:.2})",
+                    name.0, farbe.r, farbe.g, farbe.b
+                );
+            }
+        }
+        if !self.geschwindigkeiten.is_empty() {
+            let _ = writeln!(s, "\n## Geschwindigkeiten ({})\n", self.geschwindigkeiten.len());
+            for name in self.geschwindigkeiten.keys() {
+                let _ = writeln!(s, "- {}", name.0);
+            }
+        }
+        if !self.stellpult.is_empty() {
+            let _ = writeln!(s, "\n## Stellpult ({})\n", self.stellpult.len());
+            for (name, taster) in self.stellpult.iter() {
+                let _ = writeln!(s, "- {}: {} ({})", name.0, taster.aktion, taster.trigger);
+            }
+        }
+        if !self.skripte.is_empty() {
+            let _ = writeln!(s, "\n## Skripte ({})\n", self.skripte.len());
+            for (name, quelltext) in self.skripte.iter() {
+                let _ = writeln!(s, "- {}: {} Zeilen", name.0, quelltext.lines().count());
+            }
+        }
+        if !self.regeln.is_empty() {
+            let _ = writeln!(s, "\n## Regeln ({})\n", self.regeln.len());
+            for (name, regel) in self.regeln.iter() {
+                let _ = writeln!(s, "- {}: {}", name.0, regel);
+            }
+        }
+
+        let verkabelung = self.verkabelung();
+        let _ = writeln!(
+            s,
+            "\n## Anschlüsse ({})\n",
+            verkabelung.verbindungen().len() + self.drehregler.len()
+        );
+        for verbindung in verkabelung.verbindungen() {
+            let _ = write!(s, "- {}: {}", verbindung.kennung, verbindung.objekt);
+            if !verbindung.funktion.is_empty() {
+                let _ = write!(s, " {}", verbindung.funktion);
+            }
+            let _ = writeln!(s, " ({})", verbindung.richtung);
+        }
+        for (name, drehregler) in self.drehregler.iter() {
+            let adc::Save { adresse, kanal } = &drehregler.anschluss;
+            let _ = writeln!(s, "- {}-{}: Drehregler {} (Adc)", adresse, kanal, name.0);
+        }
+
+        let _ = writeln!(s, "\n## Anzahl je Beschreibung\n");
+        for ((art, beschreibung), anzahl) in anzahl {
+            if beschreibung.is_empty() {
+                let _ = writeln!(s, "- {} ohne Beschreibung: {}", art, anzahl);
+            } else {
+                let _ = writeln!(s, "- {} {}: {}", art, beschreibung, anzahl);
+            }
+        }
+        s
+    }
+}
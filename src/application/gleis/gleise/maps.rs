@@ -3,15 +3,22 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use super::id::GleisId;
 use crate::{
-    anschluss::{OutputSave, ToSave},
+    anschluss::{
+        anschlüsse::Besitzer,
+        verkabelung::{Beschreibung, Verkabeln, Verkabelung},
+        zuordnung::{self, Kennung, Zuordnen, Zuordnung},
+        InputSave, OutputSave, ToSave,
+    },
     application::{gleis::*, typen::*},
+    speicherformat::{neu_seit, Version},
     steuerung::{
-        geschwindigkeit,
+        drehregler, geschwindigkeit,
         plan::Plan,
+        regel, skript, stellpult,
         streckenabschnitt::{self, Streckenabschnitt},
     },
 };
@@ -34,6 +41,8 @@ pub struct GleiseMaps<Z> {
     pub(crate) s_kurven_weichen: Map<SKurvenWeiche<Z>>,
     pub(crate) kreuzungen: Map<Kreuzung<Z>>,
     pub(crate) streckenabschnitte: streckenabschnitt::Map,
+    pub(crate) skripte: skript::Map,
+    pub(crate) regeln: regel::Map,
 }
 impl<Z> GleiseMaps<Z> {
     pub(crate) fn neu() -> Self {
@@ -46,6 +55,8 @@ impl<Z> GleiseMaps<Z> {
             s_kurven_weichen: HashMap::new(),
             kreuzungen: HashMap::new(),
             streckenabschnitte: HashMap::new(),
+            skripte: skript::Map::new(),
+            regeln: regel::Map::new(),
         }
     }
 }
@@ -89,6 +100,27 @@ impl<Z> GleiseMap<Z> for Kreuzung<Z> {
     }
 }
 
+macro_rules! leer_vor {
+    ($($funktion:ident : $version:ident),* $(,)?) => {
+        $(
+            /// Lese ein später hinzugefügtes Feld, ältere Speicherstände enthalten es nicht.
+            pub(crate) fn $funktion<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+            where
+                D: Deserializer<'de>,
+                T: Deserialize<'de> + Default,
+            {
+                neu_seit(Version::$version, deserializer, T::default)
+            }
+        )*
+    };
+}
+leer_vor!(
+    leer_vor_stellpult: Stellpult,
+    leer_vor_drehregler: Drehregler,
+    leer_vor_skripte: Skripte,
+    leer_vor_regeln: Regeln,
+);
+
 #[derive(Serialize, Deserialize)]
 pub(crate) struct GleiseVecs<Z: Zugtyp> {
     pub(crate) name: String,
@@ -102,15 +134,198 @@ pub(crate) struct GleiseVecs<Z: Zugtyp> {
     pub(crate) streckenabschnitte: streckenabschnitt::Map<OutputSave>,
     pub(crate) geschwindigkeiten: geschwindigkeit::Map<<Z::Leiter as ToSave>::Save>,
     pub(crate) pläne: Vec<Plan>,
+    #[serde(deserialize_with = "leer_vor_stellpult")]
+    pub(crate) stellpult: stellpult::Map<InputSave>,
+    #[serde(deserialize_with = "leer_vor_drehregler")]
+    pub(crate) drehregler: drehregler::MapSave,
+    #[serde(deserialize_with = "leer_vor_skripte")]
+    pub(crate) skripte: skript::Map,
+    #[serde(deserialize_with = "leer_vor_regeln")]
+    pub(crate) regeln: regel::Map,
+}
+
+impl<Z: Zugtyp> GleiseVecs<Z>
+where
+    <Z::Leiter as ToSave>::Save: Zuordnen,
+{
+    /// Ersetze die Anschlüsse aller Steuerungen gemäß `zuordnung`.
+    ///
+    /// Gibt die Anzahl der geänderten Anschlüsse zurück.
+    /// Anschlüsse der Drehregler (Adc-Kanäle) werden nicht verändert.
+    pub(crate) fn zuordnen(&mut self, zuordnung: &Zuordnung) -> Result<usize, zuordnung::Error> {
+        let mut geändert = 0;
+        macro_rules! zuordnen {
+            ($($vec:ident . $feld:ident),* $(,)?) => {
+                $(
+                    for gleis in self.$vec.iter_mut() {
+                        geändert += gleis.definition.$feld.zuordnen(zuordnung)?;
+                    }
+                )*
+            };
+        }
+        zuordnen!(
+            geraden.kontakt,
+            kurven.kontakt,
+            weichen.steuerung,
+            dreiwege_weichen.steuerung,
+            kurven_weichen.steuerung,
+            s_kurven_weichen.steuerung,
+            kreuzungen.steuerung,
+        );
+        for streckenabschnitt in self.streckenabschnitte.values_mut() {
+            geändert += streckenabschnitt.zuordnen(zuordnung)?;
+        }
+        for geschwindigkeit in self.geschwindigkeiten.values_mut() {
+            geändert += geschwindigkeit.zuordnen(zuordnung)?;
+        }
+        for taster in self.stellpult.values_mut() {
+            geändert += taster.zuordnen(zuordnung)?;
+        }
+        Ok(geändert)
+    }
+}
+
+impl<Z: Zugtyp> GleiseVecs<Z>
+where
+    <Z::Leiter as ToSave>::Save: Verkabeln,
+{
+    /// Alle verwendeten Anschlüsse, beschrieben über ihre Steuerung.
+    ///
+    /// Anschlüsse der Drehregler (Adc-Kanäle) sind nicht enthalten.
+    pub(crate) fn verkabelung(&self) -> Verkabelung {
+        let mut verbindungen = Vec::new();
+        macro_rules! verkabeln {
+            ($($vec:ident . $feld:ident : $art:expr),* $(,)?) => {
+                $(
+                    for gleis in self.$vec.iter() {
+                        if let Some(steuerung) = &gleis.definition.$feld {
+                            let beschreibung =
+                                Beschreibung::neu(format!("{} {}", $art, steuerung.name.0));
+                            steuerung.verkabeln(&beschreibung, &mut verbindungen);
+                        }
+                    }
+                )*
+            };
+        }
+        verkabeln!(
+            geraden.kontakt: "Kontakt",
+            kurven.kontakt: "Kontakt",
+            weichen.steuerung: "Weiche",
+            dreiwege_weichen.steuerung: "Dreiwege-Weiche",
+            kurven_weichen.steuerung: "Kurven-Weiche",
+            s_kurven_weichen.steuerung: "S-Kurven-Weiche",
+            kreuzungen.steuerung: "Kreuzung",
+        );
+        for (name, streckenabschnitt) in self.streckenabschnitte.iter() {
+            let beschreibung = Beschreibung::neu(format!("Streckenabschnitt {}", name.0));
+            streckenabschnitt.verkabeln(&beschreibung, &mut verbindungen);
+        }
+        for (name, geschwindigkeit) in self.geschwindigkeiten.iter() {
+            let beschreibung = Beschreibung::neu(format!("Geschwindigkeit {}", name.0));
+            geschwindigkeit.verkabeln(&beschreibung, &mut verbindungen);
+        }
+        for (name, taster) in self.stellpult.iter() {
+            let beschreibung = Beschreibung::neu(format!("Taster {}", name.0));
+            taster.verkabeln(&beschreibung, &mut verbindungen);
+        }
+        Verkabelung::neu(verbindungen)
+    }
+
+    /// Alle Steuerungen mit Besitzer, Beschreibung und ihren verwendeten Anschlüssen.
+    ///
+    /// Anschlüsse der Drehregler (Adc-Kanäle) sind nicht enthalten.
+    pub(crate) fn anschlüsse(&self) -> Vec<(Besitzer, String, Vec<Kennung>)>
+    where
+        <Z::Leiter as ToSave>::Save: Debug,
+    {
+        fn kennungen(steuerung: &impl Verkabeln) -> Vec<Kennung> {
+            let mut verbindungen = Vec::new();
+            steuerung.verkabeln(&Beschreibung::neu(String::new()), &mut verbindungen);
+            verbindungen.into_iter().map(|verbindung| verbindung.kennung).collect()
+        }
+        let mut anschlüsse = Vec::new();
+        macro_rules! anschlüsse {
+            ($($vec:ident . $feld:ident : $besitzer:ident),* $(,)?) => {
+                $(
+                    for gleis in self.$vec.iter() {
+                        if let Some(steuerung) = &gleis.definition.$feld {
+                            anschlüsse.push((
+                                Besitzer::$besitzer(steuerung.name.0.clone()),
+                                format!("{:?}", steuerung),
+                                kennungen(steuerung),
+                            ));
+                        }
+                    }
+                )*
+            };
+        }
+        anschlüsse!(
+            geraden.kontakt: Kontakt,
+            kurven.kontakt: Kontakt,
+            weichen.steuerung: Weiche,
+            dreiwege_weichen.steuerung: Weiche,
+            kurven_weichen.steuerung: Weiche,
+            s_kurven_weichen.steuerung: Weiche,
+            kreuzungen.steuerung: Weiche,
+        );
+        for (name, streckenabschnitt) in self.streckenabschnitte.iter() {
+            anschlüsse.push((
+                Besitzer::Streckenabschnitt(name.0.clone()),
+                format!("{:?}", streckenabschnitt),
+                kennungen(streckenabschnitt),
+            ));
+        }
+        for (name, geschwindigkeit) in self.geschwindigkeiten.iter() {
+            anschlüsse.push((
+                Besitzer::Geschwindigkeit(name.0.clone()),
+                format!("{:?}", geschwindigkeit),
+                kennungen(geschwindigkeit),
+            ));
+        }
+        for (name, taster) in self.stellpult.iter() {
+            anschlüsse.push((
+                Besitzer::Taster(name.0.clone()),
+                format!("{:?}", taster),
+                kennungen(taster),
+            ));
+        }
+        anschlüsse
+    }
+}
+
+/// Gespeicherter Zustand mit reservierten Anschlüssen, noch nicht übernommen.
+pub(crate) struct GleiseReserviert<Z: Zugtyp> {
+    pub(crate) geraden: Vec<Gleis<Gerade<Z>>>,
+    pub(crate) kurven: Vec<Gleis<Kurve<Z>>>,
+    pub(crate) weichen: Vec<Gleis<Weiche<Z>>>,
+    pub(crate) dreiwege_weichen: Vec<Gleis<DreiwegeWeiche<Z>>>,
+    pub(crate) kurven_weichen: Vec<Gleis<KurvenWeiche<Z>>>,
+    pub(crate) s_kurven_weichen: Vec<Gleis<SKurvenWeiche<Z>>>,
+    pub(crate) kreuzungen: Vec<Gleis<Kreuzung<Z>>>,
+    pub(crate) streckenabschnitte: streckenabschnitt::Map,
+    pub(crate) geschwindigkeiten: geschwindigkeit::Map<Z::Leiter>,
+    pub(crate) stellpult: stellpult::Map,
+    pub(crate) drehregler: drehregler::Map,
+    pub(crate) skripte: skript::Map,
+    pub(crate) regeln: regel::Map,
+    /// Ignorierte Konflikte, die betroffenen Steuerungen haben keinen Anschluss.
+    pub(crate) konflikte: Vec<super::Konflikt>,
 }
 
-impl<Z: Zugtyp> From<(&GleiseMaps<Z>, geschwindigkeit::Map<<Z::Leiter as ToSave>::Save>)>
-    for GleiseVecs<Z>
+impl<Z: Zugtyp>
+    From<(
+        &GleiseMaps<Z>,
+        geschwindigkeit::Map<<Z::Leiter as ToSave>::Save>,
+        stellpult::Map<InputSave>,
+        drehregler::MapSave,
+    )> for GleiseVecs<Z>
 {
     fn from(
-        (maps, geschwindigkeiten): (
+        (maps, geschwindigkeiten, stellpult, drehregler): (
             &GleiseMaps<Z>,
             geschwindigkeit::Map<<Z::Leiter as ToSave>::Save>,
+            stellpult::Map<InputSave>,
+            drehregler::MapSave,
         ),
     ) -> Self {
         macro_rules! hashmaps_to_vecs {
@@ -124,6 +339,10 @@ impl<Z: Zugtyp> From<(&GleiseMaps<Z>, geschwindigkeit::Map<<Z::Leiter as ToSave>
                     geschwindigkeiten,
                     // TODO wirkliche Konvertierung, sobald Plan implementiert ist
                     pläne: Vec::new(),
+                    stellpult,
+                    drehregler,
+                    skripte: maps.skripte.clone(),
+                    regeln: maps.regeln.clone(),
                     $($map: maps.$map.values().map(
                         |Gleis {position, definition, streckenabschnitt}|
                         Gleis {
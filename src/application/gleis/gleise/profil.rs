@@ -0,0 +1,388 @@
+//! Trennung von Gleisplan (Gleise, Namen der Steuerungen) und Hardware-Profil (Anschlüsse).
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::maps::{leer_vor_regeln, leer_vor_skripte, Gleis, GleiseVecs};
+use crate::{
+    anschluss::{anschlüsse::Besitzer, InputSave, OutputSave, ToSave},
+    application::{
+        gleis::{weiche, *},
+        typen::*,
+    },
+    steuerung::{
+        self, drehregler, geschwindigkeit, kontakt, plan::Plan, regel, skript, stellpult,
+        streckenabschnitt, Streckenabschnitt,
+    },
+};
+
+/// Wird zur Kennzeichnung eines Gleisplans an den Namen des Zugtyps angehängt.
+const GLEISPLAN: &str = "Gleisplan";
+
+/// Name eines gespeicherten Gleisplans ohne Anschlüsse.
+pub(crate) fn gleisplan_name<Z: Zugtyp>() -> String {
+    format!("{} {}", Z::NAME, GLEISPLAN)
+}
+
+type KontaktName = Option<kontakt::Kontakt<()>>;
+type WeicheName<Richtung> = Option<steuerung::Weiche<Richtung, ()>>;
+
+/// Gleisplan ohne Anschlüsse, Steuerungen werden nur über ihren Namen referenziert.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct GleisplanVecs<Z: Zugtyp> {
+    pub(crate) name: String,
+    geraden: Vec<Gleis<Gerade<Z, KontaktName>>>,
+    kurven: Vec<Gleis<Kurve<Z, KontaktName>>>,
+    weichen: Vec<Gleis<Weiche<Z, WeicheName<weiche::gerade::Richtung>>>>,
+    dreiwege_weichen: Vec<Gleis<DreiwegeWeiche<Z, WeicheName<weiche::dreiwege::Richtung>>>>,
+    kurven_weichen: Vec<Gleis<KurvenWeiche<Z, WeicheName<weiche::kurve::Richtung>>>>,
+    s_kurven_weichen: Vec<Gleis<SKurvenWeiche<Z, WeicheName<weiche::gerade::Richtung>>>>,
+    kreuzungen: Vec<Gleis<Kreuzung<Z, WeicheName<weiche::gerade::Richtung>>>>,
+    streckenabschnitte: streckenabschnitt::Map<()>,
+    geschwindigkeiten: geschwindigkeit::Map<()>,
+    pläne: Vec<Plan>,
+    stellpult: stellpult::Map<()>,
+    #[serde(deserialize_with = "leer_vor_skripte")]
+    skripte: skript::Map,
+    #[serde(deserialize_with = "leer_vor_regeln")]
+    regeln: regel::Map,
+}
+
+/// Anschlüsse aller Steuerungen, zugeordnet über ihren Namen.
+///
+/// Weichen, S-Kurven-Weichen und Kreuzungen teilen sich einen Namensraum.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Profil<Z: Zugtyp> {
+    pub(crate) name: String,
+    weichen: BTreeMap<steuerung::weiche::Name, weiche::gerade::RichtungAnschlüsseSave>,
+    dreiwege_weichen: BTreeMap<steuerung::weiche::Name, weiche::dreiwege::RichtungAnschlüsseSave>,
+    kurven_weichen: BTreeMap<steuerung::weiche::Name, weiche::kurve::RichtungAnschlüsseSave>,
+    kontakte: BTreeMap<kontakt::Name, InputSave>,
+    streckenabschnitte: BTreeMap<streckenabschnitt::Name, OutputSave>,
+    geschwindigkeiten: BTreeMap<geschwindigkeit::Name, <Z::Leiter as ToSave>::Save>,
+    stellpult: BTreeMap<stellpult::Name, InputSave>,
+    drehregler: drehregler::MapSave,
+}
+
+impl<Z: Zugtyp> Profil<Z> {
+    /// Ein Profil ohne Anschlüsse, alle Namen bleiben ungebunden.
+    pub(crate) fn leer() -> Self {
+        Profil {
+            name: Z::NAME.to_string(),
+            weichen: BTreeMap::new(),
+            dreiwege_weichen: BTreeMap::new(),
+            kurven_weichen: BTreeMap::new(),
+            kontakte: BTreeMap::new(),
+            streckenabschnitte: BTreeMap::new(),
+            geschwindigkeiten: BTreeMap::new(),
+            stellpult: BTreeMap::new(),
+            drehregler: BTreeMap::new(),
+        }
+    }
+}
+
+fn weiche_trennen<Richtung, A>(
+    steuerung: Option<steuerung::Weiche<Richtung, A>>,
+    anschlüsse: &mut BTreeMap<steuerung::weiche::Name, A>,
+) -> WeicheName<Richtung> {
+    steuerung.map(
+        |steuerung::Weiche { name, aktuelle_richtung, letzte_richtung, anschlüsse: a }| {
+            anschlüsse.insert(name.clone(), a);
+            steuerung::Weiche { name, aktuelle_richtung, letzte_richtung, anschlüsse: () }
+        },
+    )
+}
+
+fn weiche_verbinden<Richtung, A: Clone>(
+    steuerung: WeicheName<Richtung>,
+    anschlüsse: &BTreeMap<steuerung::weiche::Name, A>,
+    ungebunden: &mut Vec<Besitzer>,
+) -> Option<steuerung::Weiche<Richtung, A>> {
+    let steuerung::Weiche { name, aktuelle_richtung, letzte_richtung, anschlüsse: () } = steuerung?;
+    match anschlüsse.get(&name) {
+        Some(a) => Some(steuerung::Weiche {
+            name,
+            aktuelle_richtung,
+            letzte_richtung,
+            anschlüsse: a.clone(),
+        }),
+        None => {
+            ungebunden.push(Besitzer::Weiche(name.0));
+            None
+        },
+    }
+}
+
+fn kontakt_trennen(
+    kontakt: Option<kontakt::Kontakt<InputSave>>,
+    anschlüsse: &mut BTreeMap<kontakt::Name, InputSave>,
+) -> KontaktName {
+    kontakt.map(|kontakt::Kontakt { name, anschluss, trigger }| {
+        anschlüsse.insert(name.clone(), anschluss);
+        kontakt::Kontakt { name, anschluss: (), trigger }
+    })
+}
+
+fn kontakt_verbinden(
+    kontakt: KontaktName,
+    anschlüsse: &BTreeMap<kontakt::Name, InputSave>,
+    ungebunden: &mut Vec<Besitzer>,
+) -> Option<kontakt::Kontakt<InputSave>> {
+    let kontakt::Kontakt { name, anschluss: (), trigger } = kontakt?;
+    match anschlüsse.get(&name) {
+        Some(anschluss) => Some(kontakt::Kontakt { name, anschluss: anschluss.clone(), trigger }),
+        None => {
+            ungebunden.push(Besitzer::Kontakt(name.0));
+            None
+        },
+    }
+}
+
+/// Wandle die Steuerung aller Gleise in `$vec` um, alle anderen Felder bleiben unverändert.
+macro_rules! steuerung_umwandeln {
+    ($vec:expr, $gleis:ident { $steuerung:ident => $umwandeln:expr ; $($feld:ident),* }) => {
+        $vec.into_iter()
+            .map(|Gleis { definition: $gleis { $steuerung, $($feld),* }, position, streckenabschnitt }| {
+                Gleis {
+                    definition: $gleis { $steuerung: $umwandeln, $($feld),* },
+                    position,
+                    streckenabschnitt,
+                }
+            })
+            .collect()
+    };
+}
+
+impl<Z: Zugtyp> GleiseVecs<Z> {
+    /// Trenne die Anschlüsse aller Steuerungen vom Gleisplan.
+    pub(crate) fn trennen(self) -> (GleisplanVecs<Z>, Profil<Z>) {
+        let GleiseVecs {
+            name: _,
+            geraden,
+            kurven,
+            weichen,
+            dreiwege_weichen,
+            kurven_weichen,
+            s_kurven_weichen,
+            kreuzungen,
+            streckenabschnitte,
+            geschwindigkeiten,
+            pläne,
+            stellpult,
+            drehregler,
+            skripte,
+            regeln,
+        } = self;
+        let mut profil = Profil::leer();
+        profil.drehregler = drehregler;
+        let gleisplan = GleisplanVecs {
+            name: gleisplan_name::<Z>(),
+            geraden: steuerung_umwandeln!(
+                geraden,
+                Gerade {
+                    kontakt => kontakt_trennen(kontakt, &mut profil.kontakte);
+                    zugtyp, länge, beschreibung
+                }
+            ),
+            kurven: steuerung_umwandeln!(
+                kurven,
+                Kurve {
+                    kontakt => kontakt_trennen(kontakt, &mut profil.kontakte);
+                    zugtyp, radius, winkel, beschreibung
+                }
+            ),
+            weichen: steuerung_umwandeln!(
+                weichen,
+                Weiche {
+                    steuerung => weiche_trennen(steuerung, &mut profil.weichen);
+                    zugtyp, länge, radius, winkel, orientierung, beschreibung
+                }
+            ),
+            dreiwege_weichen: steuerung_umwandeln!(
+                dreiwege_weichen,
+                DreiwegeWeiche {
+                    steuerung => weiche_trennen(steuerung, &mut profil.dreiwege_weichen);
+                    zugtyp, länge, radius, winkel, beschreibung
+                }
+            ),
+            kurven_weichen: steuerung_umwandeln!(
+                kurven_weichen,
+                KurvenWeiche {
+                    steuerung => weiche_trennen(steuerung, &mut profil.kurven_weichen);
+                    zugtyp, länge, radius, winkel, orientierung, beschreibung
+                }
+            ),
+            s_kurven_weichen: steuerung_umwandeln!(
+                s_kurven_weichen,
+                SKurvenWeiche {
+                    steuerung => weiche_trennen(steuerung, &mut profil.weichen);
+                    zugtyp, länge, radius, winkel, radius_reverse, winkel_reverse, orientierung,
+                    beschreibung
+                }
+            ),
+            kreuzungen: steuerung_umwandeln!(
+                kreuzungen,
+                Kreuzung {
+                    steuerung => weiche_trennen(steuerung, &mut profil.weichen);
+                    zugtyp, länge, radius, variante, beschreibung
+                }
+            ),
+            streckenabschnitte: streckenabschnitte
+                .into_iter()
+                .map(|(name, Streckenabschnitt { farbe, anschluss })| {
+                    profil.streckenabschnitte.insert(name.clone(), anschluss);
+                    (name, Streckenabschnitt { farbe, anschluss: () })
+                })
+                .collect(),
+            geschwindigkeiten: geschwindigkeiten
+                .into_iter()
+                .map(|(name, geschwindigkeit::Geschwindigkeit { leiter })| {
+                    profil.geschwindigkeiten.insert(name.clone(), leiter);
+                    (name, geschwindigkeit::Geschwindigkeit { leiter: () })
+                })
+                .collect(),
+            pläne,
+            stellpult: stellpult
+                .into_iter()
+                .map(|(name, stellpult::Taster { anschluss, trigger, aktion })| {
+                    profil.stellpult.insert(name.clone(), anschluss);
+                    (name, stellpult::Taster { anschluss: (), trigger, aktion })
+                })
+                .collect(),
+            skripte,
+            regeln,
+        };
+        (gleisplan, profil)
+    }
+}
+
+impl<Z: Zugtyp> GleisplanVecs<Z>
+where
+    <Z::Leiter as ToSave>::Save: Clone,
+{
+    /// Verbinde den Gleisplan mit den Anschlüssen aus `profil`.
+    ///
+    /// Namen ohne Eintrag im Profil werden zurückgegeben.
+    /// Weichen und Kontakte bleiben dann ohne Anschluss, alle anderen Steuerungen werden entfernt.
+    pub(crate) fn verbinden(self, profil: &Profil<Z>) -> (GleiseVecs<Z>, Vec<Besitzer>) {
+        let GleisplanVecs {
+            name: _,
+            geraden,
+            kurven,
+            weichen,
+            dreiwege_weichen,
+            kurven_weichen,
+            s_kurven_weichen,
+            kreuzungen,
+            streckenabschnitte,
+            geschwindigkeiten,
+            pläne,
+            stellpult,
+            skripte,
+            regeln,
+        } = self;
+        let mut ungebunden = Vec::new();
+        let vecs = GleiseVecs {
+            name: Z::NAME.to_string(),
+            geraden: steuerung_umwandeln!(
+                geraden,
+                Gerade {
+                    kontakt => kontakt_verbinden(kontakt, &profil.kontakte, &mut ungebunden);
+                    zugtyp, länge, beschreibung
+                }
+            ),
+            kurven: steuerung_umwandeln!(
+                kurven,
+                Kurve {
+                    kontakt => kontakt_verbinden(kontakt, &profil.kontakte, &mut ungebunden);
+                    zugtyp, radius, winkel, beschreibung
+                }
+            ),
+            weichen: steuerung_umwandeln!(
+                weichen,
+                Weiche {
+                    steuerung => weiche_verbinden(steuerung, &profil.weichen, &mut ungebunden);
+                    zugtyp, länge, radius, winkel, orientierung, beschreibung
+                }
+            ),
+            dreiwege_weichen: steuerung_umwandeln!(
+                dreiwege_weichen,
+                DreiwegeWeiche {
+                    steuerung =>
+                        weiche_verbinden(steuerung, &profil.dreiwege_weichen, &mut ungebunden);
+                    zugtyp, länge, radius, winkel, beschreibung
+                }
+            ),
+            kurven_weichen: steuerung_umwandeln!(
+                kurven_weichen,
+                KurvenWeiche {
+                    steuerung =>
+                        weiche_verbinden(steuerung, &profil.kurven_weichen, &mut ungebunden);
+                    zugtyp, länge, radius, winkel, orientierung, beschreibung
+                }
+            ),
+            s_kurven_weichen: steuerung_umwandeln!(
+                s_kurven_weichen,
+                SKurvenWeiche {
+                    steuerung => weiche_verbinden(steuerung, &profil.weichen, &mut ungebunden);
+                    zugtyp, länge, radius, winkel, radius_reverse, winkel_reverse, orientierung,
+                    beschreibung
+                }
+            ),
+            kreuzungen: steuerung_umwandeln!(
+                kreuzungen,
+                Kreuzung {
+                    steuerung => weiche_verbinden(steuerung, &profil.weichen, &mut ungebunden);
+                    zugtyp, länge, radius, variante, beschreibung
+                }
+            ),
+            streckenabschnitte: streckenabschnitte
+                .into_iter()
+                .filter_map(|(name, Streckenabschnitt { farbe, anschluss: () })| {
+                    match profil.streckenabschnitte.get(&name) {
+                        Some(anschluss) => {
+                            Some((name, Streckenabschnitt { farbe, anschluss: anschluss.clone() }))
+                        },
+                        None => {
+                            ungebunden.push(Besitzer::Streckenabschnitt(name.0));
+                            None
+                        },
+                    }
+                })
+                .collect(),
+            geschwindigkeiten: geschwindigkeiten
+                .into_iter()
+                .filter_map(|(name, geschwindigkeit::Geschwindigkeit { leiter: () })| {
+                    let leiter = profil.geschwindigkeiten.get(&name);
+                    if leiter.is_none() {
+                        ungebunden.push(Besitzer::Geschwindigkeit(name.0.clone()))
+                    }
+                    leiter.map(|leiter| {
+                        (name, geschwindigkeit::Geschwindigkeit { leiter: leiter.clone() })
+                    })
+                })
+                .collect(),
+            pläne,
+            stellpult: stellpult
+                .into_iter()
+                .filter_map(|(name, stellpult::Taster { anschluss: (), trigger, aktion })| {
+                    match profil.stellpult.get(&name) {
+                        Some(anschluss) => Some((
+                            name,
+                            stellpult::Taster { anschluss: anschluss.clone(), trigger, aktion },
+                        )),
+                        None => {
+                            ungebunden.push(Besitzer::Taster(name.0));
+                            None
+                        },
+                    }
+                })
+                .collect(),
+            drehregler: profil.drehregler.clone(),
+            skripte,
+            regeln,
+        };
+        (vecs, ungebunden)
+    }
+}
@@ -0,0 +1,199 @@
+//! Export aller Gleise als SVG-Bild, z.B. für die Anzeige im Browser.
+
+use std::fmt::{Display, Write};
+
+use super::{Gleis, Gleise, GleiseMaps, Map};
+use crate::{
+    application::{
+        gleis::weiche::{dreiwege, gerade, kurve},
+        typen::*,
+    },
+    farbe::Farbe,
+    steuerung::{streckenabschnitt, weiche, Streckenabschnitt},
+};
+
+/// Abstand zwischen den Gleisen und dem Rand des Bildes.
+const RAND: Skalar = Skalar(20.);
+
+/// Ersetze Zeichen mit besonderer Bedeutung in XML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Attribute einer Weiche, um sie im Browser stellen zu können.
+fn weiche_attribute<Richtung: Display>(name: &weiche::Name, richtungen: &[Richtung]) -> String {
+    let richtungen: Vec<_> = richtungen.iter().map(ToString::to_string).collect();
+    format!(
+        "class=\"weiche\" data-weiche=\"{}\" data-richtungen=\"{}\"",
+        escape(&name.0),
+        richtungen.join(" ")
+    )
+}
+
+#[derive(Debug)]
+struct Grenzen {
+    minimum: Vektor,
+    maximum: Vektor,
+}
+
+impl Grenzen {
+    fn erweitere(&mut self, punkt: Vektor) {
+        self.minimum.x = self.minimum.x.min(&punkt.x);
+        self.minimum.y = self.minimum.y.min(&punkt.y);
+        self.maximum.x = self.maximum.x.max(&punkt.x);
+        self.maximum.y = self.maximum.y.max(&punkt.y);
+    }
+}
+
+fn keine_attribute<T>(_definition: &T) -> String {
+    String::new()
+}
+
+fn schreibe_alle_gleise<T: Zeichnen>(
+    svg: &mut String,
+    grenzen: &mut Option<Grenzen>,
+    map: &Map<T>,
+    streckenabschnitte: &streckenabschnitt::Map,
+    attribute: impl Fn(&T) -> String,
+) {
+    for Gleis { definition, position, streckenabschnitt } in map.values() {
+        let size = definition.size();
+        for ecke in [
+            Vektor::null_vektor(),
+            Vektor { x: size.x, y: Skalar(0.) },
+            Vektor { x: Skalar(0.), y: size.y },
+            size,
+        ]
+        .iter()
+        {
+            let punkt = position.punkt + ecke.rotiert(position.winkel);
+            grenzen.get_or_insert(Grenzen { minimum: punkt, maximum: punkt }).erweitere(punkt);
+        }
+        let _ = write!(
+            svg,
+            "<g transform=\"translate({} {}) rotate({})\" {}>",
+            position.punkt.x.0,
+            position.punkt.y.0,
+            position.winkel.0.to_degrees(),
+            attribute(definition)
+        );
+        if let Some((name, Streckenabschnitt { farbe, .. })) =
+            streckenabschnitt.as_ref().and_then(|name| {
+                streckenabschnitte.get(name).map(|streckenabschnitt| (name, streckenabschnitt))
+            })
+        {
+            let Farbe { r, g, b } = *farbe;
+            let füllung = format!(
+                "fill=\"rgb({},{},{})\" fill-rule=\"evenodd\" class=\"streckenabschnitt\" \
+                 data-streckenabschnitt=\"{}\"",
+                (255. * r).round(),
+                (255. * g).round(),
+                (255. * b).round(),
+                escape(&name.0)
+            );
+            for pfad in definition.fülle() {
+                svg.push_str(&pfad.svg(&füllung))
+            }
+        }
+        for pfad in definition.zeichne() {
+            svg.push_str(&pfad.svg("fill=\"none\" stroke=\"black\" stroke-width=\"1.5\""))
+        }
+        svg.push_str("</g>\n");
+    }
+}
+
+impl<Z: Zugtyp> Gleise<Z> {
+    /// Erzeuge ein SVG-Bild aller Gleise.
+    ///
+    /// Weichen mit Anschlüssen haben die Attribute `data-weiche` und `data-richtungen`,
+    /// Gleise eines Streckenabschnitts sind in dessen Farbe gefüllt
+    /// und haben das Attribut `data-streckenabschnitt`.
+    pub(crate) fn svg(&self) -> String {
+        let GleiseMaps {
+            geraden,
+            kurven,
+            weichen,
+            dreiwege_weichen,
+            kurven_weichen,
+            s_kurven_weichen,
+            kreuzungen,
+            streckenabschnitte,
+            skripte: _,
+            regeln: _,
+        } = &self.maps;
+        let mut gleise = String::new();
+        let mut grenzen = None;
+        macro_rules! mit_steuerung {
+            ($richtung:ty) => {
+                |definition: &_| match &definition.steuerung {
+                    Some(steuerung) => weiche_attribute(&steuerung.name, <$richtung>::ALLE),
+                    None => String::new(),
+                }
+            };
+        }
+        schreibe_alle_gleise(
+            &mut gleise,
+            &mut grenzen,
+            geraden,
+            streckenabschnitte,
+            keine_attribute,
+        );
+        schreibe_alle_gleise(
+            &mut gleise,
+            &mut grenzen,
+            kurven,
+            streckenabschnitte,
+            keine_attribute,
+        );
+        schreibe_alle_gleise(
+            &mut gleise,
+            &mut grenzen,
+            weichen,
+            streckenabschnitte,
+            mit_steuerung!(gerade::Richtung),
+        );
+        schreibe_alle_gleise(
+            &mut gleise,
+            &mut grenzen,
+            dreiwege_weichen,
+            streckenabschnitte,
+            mit_steuerung!(dreiwege::Richtung),
+        );
+        schreibe_alle_gleise(
+            &mut gleise,
+            &mut grenzen,
+            kurven_weichen,
+            streckenabschnitte,
+            mit_steuerung!(kurve::Richtung),
+        );
+        schreibe_alle_gleise(
+            &mut gleise,
+            &mut grenzen,
+            s_kurven_weichen,
+            streckenabschnitte,
+            mit_steuerung!(gerade::Richtung),
+        );
+        schreibe_alle_gleise(
+            &mut gleise,
+            &mut grenzen,
+            kreuzungen,
+            streckenabschnitte,
+            mit_steuerung!(gerade::Richtung),
+        );
+        let Grenzen { minimum, maximum } = grenzen
+            .unwrap_or(Grenzen { minimum: Vektor::null_vektor(), maximum: Vektor::null_vektor() });
+        let größe = maximum - minimum;
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n{}</svg>\n",
+            (minimum.x - RAND).0,
+            (minimum.y - RAND).0,
+            (größe.x + RAND.doppelt()).0,
+            (größe.y + RAND.doppelt()).0,
+            gleise
+        )
+    }
+}
@@ -0,0 +1,627 @@
+//! Vergleich und Zusammenführen gespeicherter Zustände auf Ebene von Gleisen und Steuerungen.
+//!
+//! Gleise haben keine gespeicherte Identität, sie werden über Art, Typ (Definition ohne
+//! Steuerung) und Position einander zugeordnet. Gleise gleichen Typs, die an keiner
+//! gemeinsamen Position liegen, gelten als verschoben.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    iter::FromIterator,
+};
+
+use serde::Serialize;
+
+use super::{
+    maps::{Gleis, GleiseVecs},
+    Error,
+};
+use crate::{
+    anschluss::{verkabelung::Verkabeln, ToSave},
+    application::{
+        gleis::{DreiwegeWeiche, Gerade, Kreuzung, Kurve, KurvenWeiche, SKurvenWeiche, Weiche},
+        typen::*,
+    },
+    steuerung::{
+        geschwindigkeit, regel, skript, stellpult,
+        streckenabschnitt::{self, Streckenabschnitt},
+    },
+};
+
+/// Maximaler Abstand zweier Positionen, die als gleich gelten.
+const TOLERANZ: f32 = 0.01;
+
+/// Bytes für den Vergleich beliebiger serialisierbarer Werte.
+fn bytes<T: Serialize>(wert: &T) -> Result<Vec<u8>, Error> {
+    Ok(bincode::serialize(wert)?)
+}
+
+fn abstand(a: &Position, b: &Position) -> f32 {
+    let x = a.punkt.x.0 - b.punkt.x.0;
+    let y = a.punkt.y.0 - b.punkt.y.0;
+    (x * x + y * y).sqrt()
+}
+
+fn gleiche_position(a: &Position, b: &Position) -> bool {
+    abstand(a, b) < TOLERANZ && (a.winkel.0 - b.winkel.0).abs() < TOLERANZ
+}
+
+/// Gleis-Definition, deren Steuerung für einen Vergleich des Typs entfernt werden kann.
+pub(super) trait MitSteuerung: Clone + Serialize + Zeichnen {
+    fn ohne_steuerung(&self) -> Self;
+}
+
+macro_rules! impl_mit_steuerung {
+    ($type:ident, $feld:ident) => {
+        impl<Z: Zugtyp, S: Clone> MitSteuerung for $type<Z, Option<S>>
+        where
+            $type<Z, Option<S>>: Clone + Serialize,
+            Option<S>: MitName,
+        {
+            fn ohne_steuerung(&self) -> Self {
+                $type { $feld: None, ..self.clone() }
+            }
+        }
+    };
+}
+
+impl_mit_steuerung! {Gerade, kontakt}
+impl_mit_steuerung! {Kurve, kontakt}
+impl_mit_steuerung! {Weiche, steuerung}
+impl_mit_steuerung! {DreiwegeWeiche, steuerung}
+impl_mit_steuerung! {KurvenWeiche, steuerung}
+impl_mit_steuerung! {SKurvenWeiche, steuerung}
+impl_mit_steuerung! {Kreuzung, steuerung}
+
+/// Beschreibung eines Gleises für die Ausgabe, z.B. "Weiche 5137 (W1) bei (10.0, 20.0)".
+pub(super) fn beschreibe<T: Zeichnen>(art: &str, gleis: &Gleis<T>) -> String {
+    let (_position, beschreibung, name) = gleis.definition.beschreibung_und_name();
+    let mut text = art.to_string();
+    if let Some(beschreibung) = beschreibung {
+        text.push(' ');
+        text.push_str(beschreibung);
+    }
+    if let Some(name) = name {
+        text.push_str(&format!(" ({})", name));
+    }
+    text.push_str(&format!(
+        " bei ({:.1}, {:.1})",
+        gleis.position.punkt.x.0, gleis.position.punkt.y.0
+    ));
+    text
+}
+
+/// Zuordnung der Gleise zweier Zustände.
+#[derive(Debug, Default)]
+struct Abgleich {
+    /// Indices einander zugeordneter Gleise (alt, neu).
+    paare: Vec<(usize, usize)>,
+    entfernt: Vec<usize>,
+    hinzugefügt: Vec<usize>,
+}
+
+impl Abgleich {
+    /// Index des zugeordneten neuen Gleises für jedes alte Gleis.
+    fn partner(&self, anzahl: usize) -> Vec<Option<usize>> {
+        let mut partner = vec![None; anzahl];
+        for (alt, neu) in self.paare.iter() {
+            partner[*alt] = Some(*neu);
+        }
+        partner
+    }
+}
+
+/// Ordne Gleise gleichen Typs einander zu.
+///
+/// Zuerst werden Gleise an gleicher Position zugeordnet, danach verschobene Gleise
+/// mit gleicher Steuerung und zuletzt verschobene Gleise gleichen Typs, jeweils das nächste.
+fn abgleichen<T: MitSteuerung>(alt: &[Gleis<T>], neu: &[Gleis<T>]) -> Result<Abgleich, Error> {
+    let typ = |gleis: &Gleis<T>| bytes(&gleis.definition.ohne_steuerung());
+    let definition = |gleis: &Gleis<T>| bytes(&gleis.definition);
+    let typ_alt = alt.iter().map(typ).collect::<Result<Vec<_>, _>>()?;
+    let typ_neu = neu.iter().map(typ).collect::<Result<Vec<_>, _>>()?;
+    let definition_alt = alt.iter().map(definition).collect::<Result<Vec<_>, _>>()?;
+    let definition_neu = neu.iter().map(definition).collect::<Result<Vec<_>, _>>()?;
+
+    let mut abgleich = Abgleich::default();
+    let mut frei = vec![true; neu.len()];
+    let mut offen: Vec<usize> = (0..alt.len()).collect();
+    let kriterien: [&dyn Fn(usize, usize) -> bool; 3] = [
+        &|i, j| typ_alt[i] == typ_neu[j] && gleiche_position(&alt[i].position, &neu[j].position),
+        &|i, j| definition_alt[i] == definition_neu[j],
+        &|i, j| typ_alt[i] == typ_neu[j],
+    ];
+    for kriterium in kriterien.iter() {
+        offen.retain(|&i| {
+            let nächstes = (0..neu.len())
+                .filter(|&j| frei[j] && kriterium(i, j))
+                .map(|j| (j, abstand(&alt[i].position, &neu[j].position)))
+                .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            match nächstes {
+                Some((j, _abstand)) => {
+                    frei[j] = false;
+                    abgleich.paare.push((i, j));
+                    false
+                },
+                None => true,
+            }
+        });
+    }
+    abgleich.entfernt = offen;
+    abgleich.hinzugefügt = (0..neu.len()).filter(|&j| frei[j]).collect();
+    Ok(abgleich)
+}
+
+/// Unterschiede der Gleise einer Art.
+fn vergleiche_gleise<T: MitSteuerung>(
+    zeilen: &mut Vec<String>,
+    art: &str,
+    alt: &[Gleis<T>],
+    neu: &[Gleis<T>],
+) -> Result<(), Error> {
+    let Abgleich { paare, entfernt, hinzugefügt } = abgleichen(alt, neu)?;
+    for i in entfernt {
+        zeilen.push(format!("- {}", beschreibe(art, &alt[i])))
+    }
+    for j in hinzugefügt {
+        zeilen.push(format!("+ {}", beschreibe(art, &neu[j])))
+    }
+    for (i, j) in paare {
+        let (a, n) = (&alt[i], &neu[j]);
+        let beschreibung = beschreibe(art, a);
+        if !gleiche_position(&a.position, &n.position) {
+            zeilen.push(format!(
+                "~ {}: verschoben nach ({:.1}, {:.1}), {:.1}°",
+                beschreibung,
+                n.position.punkt.x.0,
+                n.position.punkt.y.0,
+                n.position.winkel.0.to_degrees()
+            ))
+        }
+        if a.streckenabschnitt != n.streckenabschnitt {
+            let name = |streckenabschnitt: &Option<streckenabschnitt::Name>| {
+                streckenabschnitt.as_ref().map_or("keiner".to_string(), |name| name.0.clone())
+            };
+            zeilen.push(format!(
+                "~ {}: Streckenabschnitt {} -> {}",
+                beschreibung,
+                name(&a.streckenabschnitt),
+                name(&n.streckenabschnitt)
+            ))
+        }
+        if bytes(&a.definition)? != bytes(&n.definition)? {
+            let (_, _, name) = n.definition.beschreibung_und_name();
+            zeilen.push(format!(
+                "~ {}: Steuerung geändert ({})",
+                beschreibung,
+                name.map_or("ohne Steuerung", String::as_str)
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Hinzugefügte, entfernte und geänderte Einträge einer über Namen zugeordneten Sammlung.
+fn vergleiche_map<'t, K: 't + Ord, V: 't + Serialize>(
+    zeilen: &mut Vec<String>,
+    art: &str,
+    name: impl Fn(&K) -> &str,
+    alt: impl IntoIterator<Item = (&'t K, &'t V)>,
+    neu: impl IntoIterator<Item = (&'t K, &'t V)>,
+) -> Result<(), Error> {
+    let alt: BTreeMap<_, _> = alt.into_iter().collect();
+    let neu: BTreeMap<_, _> = neu.into_iter().collect();
+    for (schlüssel, wert) in alt.iter() {
+        match neu.get(schlüssel) {
+            None => zeilen.push(format!("- {} {}", art, name(schlüssel))),
+            Some(neuer_wert) if bytes(wert)? != bytes(neuer_wert)? => {
+                zeilen.push(format!("~ {} {} geändert", art, name(schlüssel)))
+            },
+            Some(_) => {},
+        }
+    }
+    for schlüssel in neu.keys().filter(|schlüssel| !alt.contains_key(*schlüssel)) {
+        zeilen.push(format!("+ {} {}", art, name(schlüssel)))
+    }
+    Ok(())
+}
+
+/// Übernehme die Änderung einer Seite, bei Änderungen auf beiden Seiten müssen sie gleich sein.
+///
+/// Bei einem Konflikt wird `None` zurückgegeben.
+fn drei_wege<T: Serialize>(basis: &impl Serialize, a: T, c: T) -> Result<Option<T>, Error> {
+    let (basis, a_bytes, c_bytes) = (bytes(basis)?, bytes(&a)?, bytes(&c)?);
+    Ok(if a_bytes == basis {
+        Some(c)
+    } else if c_bytes == basis || a_bytes == c_bytes {
+        Some(a)
+    } else {
+        None
+    })
+}
+
+/// Führe die Änderungen der Gleise einer Art zusammen, Konflikte werden in `konflikte` gesammelt.
+fn vereinige_gleise<T: MitSteuerung>(
+    konflikte: &mut Vec<String>,
+    art: &str,
+    basis: Vec<Gleis<T>>,
+    a: Vec<Gleis<T>>,
+    c: Vec<Gleis<T>>,
+) -> Result<Vec<Gleis<T>>, Error> {
+    let abgleich_a = abgleichen(&basis, &a)?;
+    let abgleich_c = abgleichen(&basis, &c)?;
+    let partner_a = abgleich_a.partner(basis.len());
+    let partner_c = abgleich_c.partner(basis.len());
+    let mut a: Vec<Option<Gleis<T>>> = a.into_iter().map(Some).collect();
+    let mut c: Vec<Option<Gleis<T>>> = c.into_iter().map(Some).collect();
+    let mut ergebnis = Vec::new();
+    for (i, gleis) in basis.into_iter().enumerate() {
+        let beschreibung = beschreibe(art, &gleis);
+        let gleis_a = partner_a[i].and_then(|j| a[j].take());
+        let gleis_c = partner_c[i].and_then(|k| c[k].take());
+        match (gleis_a, gleis_c) {
+            (None, None) => {},
+            (Some(geändert), None) | (None, Some(geändert)) => {
+                // Entfernen hat nur Vorrang, wenn das Gleis auf der anderen Seite unverändert ist
+                if bytes(&geändert)? != bytes(&gleis)? {
+                    konflikte.push(format!("{}: entfernt und geändert", beschreibung));
+                    ergebnis.push(geändert)
+                }
+            },
+            (Some(gleis_a), Some(gleis_c)) => {
+                let Gleis { definition, position, streckenabschnitt } = gleis;
+                let definition = drei_wege(&definition, gleis_a.definition, gleis_c.definition)?;
+                let position = drei_wege(&position, gleis_a.position, gleis_c.position)?;
+                let streckenabschnitt = drei_wege(
+                    &streckenabschnitt,
+                    gleis_a.streckenabschnitt,
+                    gleis_c.streckenabschnitt,
+                )?;
+                match (definition, position, streckenabschnitt) {
+                    (Some(definition), Some(position), Some(streckenabschnitt)) => {
+                        ergebnis.push(Gleis { definition, position, streckenabschnitt })
+                    },
+                    (definition, position, streckenabschnitt) => {
+                        let unterschiedlich: Vec<_> = [
+                            (definition.is_none(), "Steuerung"),
+                            (position.is_none(), "Position"),
+                            (streckenabschnitt.is_none(), "Streckenabschnitt"),
+                        ]
+                        .iter()
+                        .filter_map(|(konflikt, teil)| if *konflikt { Some(*teil) } else { None })
+                        .collect();
+                        konflikte.push(format!(
+                            "{}: {} unterschiedlich geändert",
+                            beschreibung,
+                            unterschiedlich.join(", ")
+                        ))
+                    },
+                }
+            },
+        }
+    }
+    let hinzugefügt_a: Vec<_> = a.into_iter().flatten().collect();
+    let bytes_a = hinzugefügt_a.iter().map(bytes).collect::<Result<BTreeSet<_>, _>>()?;
+    ergebnis.extend(hinzugefügt_a);
+    for gleis in c.into_iter().flatten() {
+        // auf beiden Seiten gleich hinzugefügte Gleise nur einmal übernehmen
+        if !bytes_a.contains(&bytes(&gleis)?) {
+            ergebnis.push(gleis)
+        }
+    }
+    Ok(ergebnis)
+}
+
+/// Führe die Änderungen einer über Namen zugeordneten Sammlung zusammen.
+fn vereinige_map<K: Ord + Clone, V: Serialize, M: FromIterator<(K, V)>>(
+    konflikte: &mut Vec<String>,
+    art: &str,
+    name: impl Fn(&K) -> &str,
+    basis: impl IntoIterator<Item = (K, V)>,
+    a: impl IntoIterator<Item = (K, V)>,
+    c: impl IntoIterator<Item = (K, V)>,
+) -> Result<M, Error> {
+    let basis: BTreeMap<K, V> = basis.into_iter().collect();
+    let mut a: BTreeMap<K, V> = a.into_iter().collect();
+    let mut c: BTreeMap<K, V> = c.into_iter().collect();
+    let schlüssel: BTreeSet<K> = basis.keys().chain(a.keys()).chain(c.keys()).cloned().collect();
+    let mut ergebnis = Vec::new();
+    for schlüssel in schlüssel {
+        match drei_wege(&basis.get(&schlüssel), a.remove(&schlüssel), c.remove(&schlüssel))? {
+            Some(Some(wert)) => ergebnis.push((schlüssel, wert)),
+            Some(None) => {},
+            None => {
+                konflikte.push(format!("{} {}: unterschiedlich geändert", art, name(&schlüssel)))
+            },
+        }
+    }
+    Ok(ergebnis.into_iter().collect())
+}
+
+impl<Z: Zugtyp + Serialize> GleiseVecs<Z>
+where
+    <Z::Leiter as ToSave>::Save: Verkabeln,
+{
+    /// Alle Unterschiede von `self` zu `neu`, eine Zeile pro Änderung.
+    ///
+    /// Zeilen beginnen mit `+` (hinzugefügt), `-` (entfernt) oder `~` (geändert).
+    pub(crate) fn unterschiede(&self, neu: &GleiseVecs<Z>) -> Result<Vec<String>, Error> {
+        let mut zeilen = Vec::new();
+        macro_rules! vergleiche_gleise {
+            ($($vec:ident: $art:expr),* $(,)?) => {
+                $(vergleiche_gleise(&mut zeilen, $art, &self.$vec, &neu.$vec)?;)*
+            };
+        }
+        vergleiche_gleise!(
+            geraden: "Gerade",
+            kurven: "Kurve",
+            weichen: "Weiche",
+            dreiwege_weichen: "Dreiwege-Weiche",
+            kurven_weichen: "Kurven-Weiche",
+            s_kurven_weichen: "S-Kurven-Weiche",
+            kreuzungen: "Kreuzung",
+        );
+        for (name, Streckenabschnitt { farbe, .. }) in self.streckenabschnitte.iter() {
+            if let Some(neu) = neu.streckenabschnitte.get(name) {
+                if (farbe.r, farbe.g, farbe.b) != (neu.farbe.r, neu.farbe.g, neu.farbe.b) {
+                    zeilen.push(format!("~ Streckenabschnitt {}: Farbe geändert", name.0))
+                }
+            }
+        }
+        vergleiche_map(
+            &mut zeilen,
+            "Streckenabschnitt",
+            |name: &streckenabschnitt::Name| &name.0,
+            self.streckenabschnitte.iter().map(|(name, alt)| (name, &alt.anschluss)),
+            neu.streckenabschnitte.iter().map(|(name, neu)| (name, &neu.anschluss)),
+        )?;
+        vergleiche_map(
+            &mut zeilen,
+            "Geschwindigkeit",
+            |name: &geschwindigkeit::Name| &name.0,
+            self.geschwindigkeiten.iter(),
+            neu.geschwindigkeiten.iter(),
+        )?;
+        vergleiche_map(
+            &mut zeilen,
+            "Taster",
+            |name: &stellpult::Name| &name.0,
+            self.stellpult.iter(),
+            neu.stellpult.iter(),
+        )?;
+        vergleiche_map(
+            &mut zeilen,
+            "Drehregler",
+            |name: &geschwindigkeit::Name| &name.0,
+            self.drehregler.iter(),
+            neu.drehregler.iter(),
+        )?;
+        vergleiche_map(
+            &mut zeilen,
+            "Skript",
+            |name: &skript::Name| &name.0,
+            self.skripte.iter(),
+            neu.skripte.iter(),
+        )?;
+        vergleiche_map(
+            &mut zeilen,
+            "Regel",
+            |name: &regel::Name| &name.0,
+            self.regeln.iter(),
+            neu.regeln.iter(),
+        )?;
+
+        // geänderte Anschlüsse aller Steuerungen im Detail
+        let verbindungen_alt = self.verkabelung();
+        let verbindungen_neu = neu.verkabelung();
+        for verbindung in verbindungen_alt.verbindungen() {
+            if !verbindungen_neu.verbindungen().contains(verbindung) {
+                zeilen.push(format!(
+                    "- Anschluss {}: {} {}",
+                    verbindung.kennung, verbindung.objekt, verbindung.funktion
+                ))
+            }
+        }
+        for verbindung in verbindungen_neu.verbindungen() {
+            if !verbindungen_alt.verbindungen().contains(verbindung) {
+                zeilen.push(format!(
+                    "+ Anschluss {}: {} {}",
+                    verbindung.kennung, verbindung.objekt, verbindung.funktion
+                ))
+            }
+        }
+        Ok(zeilen)
+    }
+
+    /// Führe die Änderungen von `a` und `c` gegenüber dem gemeinsamen Ursprung `basis` zusammen.
+    ///
+    /// Widersprechen sich die Änderungen, werden alle Konflikte als [Error::Vereinigen] gemeldet.
+    pub(crate) fn vereinigen(
+        basis: GleiseVecs<Z>,
+        a: GleiseVecs<Z>,
+        c: GleiseVecs<Z>,
+    ) -> Result<GleiseVecs<Z>, Error> {
+        let mut konflikte = Vec::new();
+        macro_rules! vereinige_gleise {
+            ($($vec:ident: $art:expr),* $(,)?) => {
+                $(let $vec = vereinige_gleise(&mut konflikte, $art, basis.$vec, a.$vec, c.$vec)?;)*
+            };
+        }
+        vereinige_gleise!(
+            geraden: "Gerade",
+            kurven: "Kurve",
+            weichen: "Weiche",
+            dreiwege_weichen: "Dreiwege-Weiche",
+            kurven_weichen: "Kurven-Weiche",
+            s_kurven_weichen: "S-Kurven-Weiche",
+            kreuzungen: "Kreuzung",
+        );
+        let streckenabschnitte = vereinige_map(
+            &mut konflikte,
+            "Streckenabschnitt",
+            |name: &streckenabschnitt::Name| &name.0,
+            basis.streckenabschnitte,
+            a.streckenabschnitte,
+            c.streckenabschnitte,
+        )?;
+        let geschwindigkeiten = vereinige_map(
+            &mut konflikte,
+            "Geschwindigkeit",
+            |name: &geschwindigkeit::Name| &name.0,
+            basis.geschwindigkeiten,
+            a.geschwindigkeiten,
+            c.geschwindigkeiten,
+        )?;
+        let stellpult = vereinige_map(
+            &mut konflikte,
+            "Taster",
+            |name: &stellpult::Name| &name.0,
+            basis.stellpult,
+            a.stellpult,
+            c.stellpult,
+        )?;
+        let drehregler = vereinige_map(
+            &mut konflikte,
+            "Drehregler",
+            |name: &geschwindigkeit::Name| &name.0,
+            basis.drehregler,
+            a.drehregler,
+            c.drehregler,
+        )?;
+        let skripte = vereinige_map(
+            &mut konflikte,
+            "Skript",
+            |name: &skript::Name| &name.0,
+            basis.skripte,
+            a.skripte,
+            c.skripte,
+        )?;
+        let regeln = vereinige_map(
+            &mut konflikte,
+            "Regel",
+            |name: &regel::Name| &name.0,
+            basis.regeln,
+            a.regeln,
+            c.regeln,
+        )?;
+        if !konflikte.is_empty() {
+            return Err(Error::Vereinigen(konflikte));
+        }
+        Ok(GleiseVecs {
+            name: a.name,
+            geraden,
+            kurven,
+            weichen,
+            dreiwege_weichen,
+            kurven_weichen,
+            s_kurven_weichen,
+            kreuzungen,
+            streckenabschnitte,
+            geschwindigkeiten,
+            // TODO zusammenführen, sobald Plan implementiert ist
+            pläne: a.pläne,
+            stellpult,
+            drehregler,
+            skripte,
+            regeln,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::HashMap, marker::PhantomData};
+
+    use super::*;
+    use crate::{application::gleis::GeradeSave, zugtyp::Märklin};
+
+    fn gerade(x: f32) -> Gleis<GeradeSave<Märklin>> {
+        Gleis {
+            definition: Gerade {
+                zugtyp: PhantomData,
+                länge: Skalar(180.),
+                beschreibung: Some("5106".to_string()),
+                kontakt: None,
+            },
+            position: Position {
+                punkt: Vektor { x: Skalar(x), y: Skalar(0.) },
+                winkel: Winkel(0.),
+            },
+            streckenabschnitt: None,
+        }
+    }
+
+    /// Zustand mit Geraden an den x-Koordinaten `geraden` und den Skripten `skripte`.
+    fn zustand(geraden: &[f32], skripte: &[(&str, &str)]) -> GleiseVecs<Märklin> {
+        GleiseVecs {
+            name: Märklin::NAME.to_string(),
+            geraden: geraden.iter().map(|x| gerade(*x)).collect(),
+            kurven: Vec::new(),
+            weichen: Vec::new(),
+            dreiwege_weichen: Vec::new(),
+            kurven_weichen: Vec::new(),
+            s_kurven_weichen: Vec::new(),
+            kreuzungen: Vec::new(),
+            streckenabschnitte: HashMap::new(),
+            geschwindigkeiten: BTreeMap::new(),
+            pläne: Vec::new(),
+            stellpult: BTreeMap::new(),
+            drehregler: BTreeMap::new(),
+            skripte: skripte
+                .iter()
+                .map(|(name, quelltext)| (skript::Name(name.to_string()), quelltext.to_string()))
+                .collect(),
+            regeln: BTreeMap::new(),
+        }
+    }
+
+    fn positionen(gleise: &GleiseVecs<Märklin>) -> Vec<f32> {
+        let mut positionen: Vec<f32> =
+            gleise.geraden.iter().map(|gleis| gleis.position.punkt.x.0).collect();
+        positionen.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        positionen
+    }
+
+    fn skripte(gleise: &GleiseVecs<Märklin>) -> Vec<(&str, &str)> {
+        gleise
+            .skripte
+            .iter()
+            .map(|(name, quelltext)| (name.0.as_str(), quelltext.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn einseitig_hinzugefügt_und_entfernt() {
+        let basis = zustand(&[0., 200.], &[("A", "a")]);
+        let a = zustand(&[0., 200., 400.], &[("A", "a"), ("B", "b")]);
+        let c = zustand(&[0.], &[]);
+        let ergebnis = GleiseVecs::vereinigen(basis, a, c).expect("Kein Konflikt erwartet");
+        assert_eq!(positionen(&ergebnis), vec![0., 400.]);
+        assert_eq!(skripte(&ergebnis), vec![("B", "b")]);
+    }
+
+    #[test]
+    fn beidseitig_gleich_hinzugefügt() {
+        let basis = zustand(&[0.], &[]);
+        let a = zustand(&[0., 200.], &[("B", "b")]);
+        let c = zustand(&[0., 200.], &[("B", "b")]);
+        let ergebnis = GleiseVecs::vereinigen(basis, a, c).expect("Kein Konflikt erwartet");
+        assert_eq!(positionen(&ergebnis), vec![0., 200.]);
+        assert_eq!(skripte(&ergebnis), vec![("B", "b")]);
+    }
+
+    #[test]
+    fn widersprüchliche_änderungen() {
+        let basis = zustand(&[0.], &[("A", "a")]);
+        let a = zustand(&[50.], &[("A", "x")]);
+        let c = zustand(&[100.], &[("A", "y")]);
+        match GleiseVecs::vereinigen(basis, a, c) {
+            Err(Error::Vereinigen(konflikte)) => {
+                assert_eq!(konflikte.len(), 2, "{:?}", konflikte);
+                assert!(konflikte.iter().any(|konflikt| konflikt.contains("Position")));
+                assert!(konflikte.iter().any(|konflikt| konflikt.starts_with("Skript A")));
+            },
+            Err(fehler) => panic!("Unerwarteter Fehler: {:?}", fehler),
+            Ok(_gleise) => panic!("Konflikt nicht erkannt!"),
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Anzeige der angeschlossenen Hardware (I2C-Bausteine, reservierte Pins).
+
+use iced_aw::native::{card, Card};
+use iced_native::{
+    button, column, container, event, row, scrollable, text, Button, Clipboard, Column, Element,
+    Event, Layout, Length, Point, Renderer, Row, Scrollable, Text, Widget,
+};
+
+use super::macros::reexport_no_event_methods;
+use crate::anschluss::{
+    anschlüsse::Anschlüsse,
+    i2c::{Antwort, Bus},
+    level::Level,
+};
+
+#[derive(Debug)]
+pub struct Status {
+    reservierte_pins: Result<Vec<u8>, String>,
+    busse: Vec<(Bus, Result<Vec<Antwort>, String>)>,
+    scannen_button_state: button::State,
+    scrollable_state: scrollable::State,
+}
+
+impl Status {
+    /// Scanne alle vorhandenen und verwendeten I2C-Busse.
+    pub fn neu(anschlüsse: &mut Anschlüsse) -> Self {
+        let mut status = Status {
+            reservierte_pins: Ok(Vec::new()),
+            busse: Vec::new(),
+            scannen_button_state: button::State::new(),
+            scrollable_state: scrollable::State::new(),
+        };
+        status.scanne(anschlüsse);
+        status
+    }
+
+    /// Aktualisiere reservierte Pins und antwortende I2C-Adressen.
+    pub fn scanne(&mut self, anschlüsse: &mut Anschlüsse) {
+        self.reservierte_pins =
+            anschlüsse.reservierte_pins().map_err(|error| format!("{:?}", error));
+        let mut busse = Bus::vorhandene();
+        if let Ok(belegung) = anschlüsse.i2c_belegung() {
+            busse.extend(belegung.into_iter().map(|belegung| belegung.adresse.bus));
+        }
+        if busse.is_empty() {
+            busse.push(Bus::STANDARD);
+        }
+        busse.sort_unstable();
+        busse.dedup();
+        self.busse = busse
+            .into_iter()
+            .map(|bus| {
+                let antworten =
+                    anschlüsse.scanne_i2c_bus(bus).map_err(|error| format!("{:?}", error));
+                (bus, antworten)
+            })
+            .collect();
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Nachricht {
+    Schließe,
+    Scannen,
+}
+
+pub struct Anzeige<'a, R: Renderer + card::Renderer> {
+    card: Card<'a, Nachricht, R>,
+}
+
+fn beschreibung(antwort: &Antwort) -> String {
+    let mut beschreibung = format!("{:#04x}: ", antwort.adresse.adresse);
+    if antwort.kandidaten.is_empty() {
+        beschreibung.push_str("unbekannt");
+    } else {
+        let kandidaten: Vec<_> = antwort.kandidaten.iter().map(ToString::to_string).collect();
+        beschreibung.push_str(&kandidaten.join(" / "));
+    }
+    if let Some(art) = antwort.verwendet {
+        beschreibung.push_str(&format!(" (verwendet als {})", art));
+    }
+    if let Some(ports) = antwort.pcf8574_ports() {
+        beschreibung.push_str(", Ports 0-7: ");
+        for level in ports.iter() {
+            beschreibung.push(match level {
+                Level::Low => 'L',
+                Level::High => 'H',
+            })
+        }
+    }
+    beschreibung
+}
+
+impl<'a, R> Anzeige<'a, R>
+where
+    R: 'a
+        + Renderer
+        + text::Renderer
+        + column::Renderer
+        + row::Renderer
+        + container::Renderer
+        + button::Renderer
+        + scrollable::Renderer
+        + card::Renderer,
+{
+    pub fn neu(
+        Status { reservierte_pins, busse, scannen_button_state, scrollable_state }: &'a mut Status,
+    ) -> Self {
+        let pins = match reservierte_pins {
+            Ok(pins) => {
+                let pins: Vec<_> = pins.iter().map(ToString::to_string).collect();
+                format!("Reservierte Gpio-Pins: {}", pins.join(", "))
+            }
+            Err(fehler) => format!("Reservierte Gpio-Pins unbekannt: {}", fehler),
+        };
+        let mut scrollable = Scrollable::new(scrollable_state)
+            .push(
+                Row::new().push(Text::new(pins)).push(
+                    Button::new(scannen_button_state, Text::new("Erneut scannen"))
+                        .on_press(Nachricht::Scannen),
+                ),
+            )
+            .spacing(5)
+            .width(Length::Shrink);
+        for (bus, antworten) in busse.iter() {
+            let mut column = Column::new().push(Text::new(bus.to_string()));
+            match antworten {
+                Ok(antworten) if antworten.is_empty() => {
+                    column = column.push(Text::new("Keine antwortenden Adressen."))
+                }
+                Ok(antworten) => {
+                    for antwort in antworten {
+                        column = column.push(Text::new(beschreibung(antwort)))
+                    }
+                }
+                Err(fehler) => column = column.push(Text::new(format!("Fehler: {}", fehler))),
+            }
+            scrollable = scrollable.push(column);
+        }
+        let card = Card::new(Text::new("Hardware").width(Length::Fill), scrollable)
+            .on_close(Nachricht::Schließe)
+            .width(Length::Shrink);
+        Anzeige { card }
+    }
+}
+
+impl<'a, R: 'a + Renderer + card::Renderer> Widget<Nachricht, R> for Anzeige<'a, R> {
+    reexport_no_event_methods! {Card<'a, Nachricht, R>, card, Nachricht, R}
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &R,
+        clipboard: &mut dyn Clipboard,
+        messages: &mut Vec<Nachricht>,
+    ) -> event::Status {
+        self.card.on_event(event, layout, cursor_position, renderer, clipboard, messages)
+    }
+}
+
+impl<'a, R: 'a + Renderer + card::Renderer> From<Anzeige<'a, R>> for Element<'a, Nachricht, R> {
+    fn from(anzeige: Anzeige<'a, R>) -> Self {
+        Element::new(anzeige)
+    }
+}
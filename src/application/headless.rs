@@ -0,0 +1,130 @@
+//! Betrieb ohne Anzeige, z.B. für dauerhafte Installationen ohne Bildschirm.
+//!
+//! Nach dem Laden des Zustands werden Nachrichten von Stellpult, Drehreglern, Kontakten,
+//! Nothalt-Taster, Netzwerk-Steuerung und MQTT wie mit Anzeige bearbeitet.
+//! Mit `--skript` angegebene Skripte werden nach dem Laden gestartet.
+//! Meldungen, die sonst in einem Dialog erscheinen, werden stattdessen protokolliert.
+//! Nach SIGTERM (oder SIGINT) wird ein Nothalt ausgelöst und alle Anschlüsse freigegeben.
+
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    io,
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+use iced_native::futures::executor::block_on;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use super::{geschwindigkeit::LeiterAnzeige, Message, MessageBox, Zugkontrolle};
+use crate::{
+    anschluss::{verkabelung::Verkabeln, zuordnung::Zuordnen, ToSave},
+    zugtyp::Zugtyp,
+};
+
+/// Wartezeit, wenn keine Nachricht vorliegt.
+const WARTEZEIT: Duration = Duration::from_millis(20);
+
+/// Wird durch SIGTERM oder SIGINT gesetzt.
+static BEENDEN: AtomicBool = AtomicBool::new(false);
+
+/// Lade den Zustand und bearbeite alle Nachrichten, bis SIGTERM oder SIGINT empfangen wird.
+///
+/// Die `flags` entsprechen denen der Anzeige, der Pfad muss angegeben sein.
+pub fn starte<Z>(flags: <Zugkontrolle<Z> as iced::Application>::Flags) -> Result<(), Error>
+where
+    Z: 'static + Zugtyp + Debug + PartialEq + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+    Z::Leiter: Debug,
+    <<Z as Zugtyp>::Leiter as ToSave>::Save: Debug + Clone + Send + Zuordnen + Verkabeln,
+    <<Z as Zugtyp>::Leiter as LeiterAnzeige>::Fahrtrichtung: Debug,
+{
+    signale_abfangen().map_err(Error::Signal)?;
+    // der Zustand wird direkt geladen, um Fehler erkennen zu können
+    let (mut zugkontrolle, _laden) = <Zugkontrolle<Z> as iced::Application>::new(flags);
+    zugkontrolle.message_box_protokollieren();
+    if !zugkontrolle.laden(false) {
+        let MessageBox { titel, nachricht, .. } = zugkontrolle.message_box.inner();
+        return Err(Error::Laden { titel: titel.clone(), nachricht: nachricht.clone() });
+    }
+    zugkontrolle.message_box_protokollieren();
+    let mut empfänger = zugkontrolle.kanal.empfänger().ok_or(Error::KanalVerwendet)?;
+    info!("{} ohne Anzeige geladen, beenden mit SIGTERM.", zugkontrolle.aktueller_pfad);
+    while !BEENDEN.load(Ordering::SeqCst) {
+        match empfänger.try_next() {
+            Ok(Some(message)) => zugkontrolle.bearbeite_ohne_anzeige(message),
+            // nicht erwartet, die Anwendung besitzt selbst einen Sender
+            Ok(None) => break,
+            Err(_leer) => thread::sleep(WARTEZEIT),
+        }
+    }
+    info!("Beende Zugkontrolle, alle Anschlüsse werden freigegeben.");
+    zugkontrolle.nothalt();
+    zugkontrolle.message_box_protokollieren();
+    Ok(())
+}
+
+impl<Z> Zugkontrolle<Z>
+where
+    Z: 'static + Zugtyp + Debug + PartialEq + Serialize + for<'de> Deserialize<'de> + Send + Sync,
+    Z::Leiter: Debug,
+    <<Z as Zugtyp>::Leiter as ToSave>::Save: Debug + Clone + Send + Zuordnen + Verkabeln,
+    <<Z as Zugtyp>::Leiter as LeiterAnzeige>::Fahrtrichtung: Debug,
+{
+    /// Bearbeite eine Nachricht und alle dadurch erzeugten Commands.
+    fn bearbeite_ohne_anzeige(&mut self, message: Message<Z>) {
+        let mut ausstehend = VecDeque::new();
+        ausstehend.push_back(message);
+        while let Some(message) = ausstehend.pop_front() {
+            let command = self.bearbeite(message);
+            self.message_box_protokollieren();
+            ausstehend.extend(command.futures().into_iter().map(block_on));
+        }
+    }
+
+    /// Protokolliere eine angezeigte Meldung und schließe sie.
+    fn message_box_protokollieren(&mut self) {
+        if self.message_box.is_shown() {
+            let MessageBox { titel, nachricht, .. } = self.message_box.inner();
+            warn!("{}: {}", titel, nachricht);
+            self.message_box.show(false);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+extern "C" fn signal_empfangen(_signal: libc::c_int) {
+    BEENDEN.store(true, Ordering::SeqCst)
+}
+
+/// Setze [BEENDEN] bei SIGTERM und SIGINT, statt das Programm sofort zu beenden.
+#[cfg(target_os = "linux")]
+fn signale_abfangen() -> io::Result<()> {
+    let handler = signal_empfangen as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    for signal in [libc::SIGTERM, libc::SIGINT].iter() {
+        if unsafe { libc::signal(*signal, handler) } == libc::SIG_ERR {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn signale_abfangen() -> io::Result<()> {
+    warn!("Signale werden nur unter Linux abgefangen, beim Beenden gibt es keinen Nothalt.");
+    Ok(())
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// Ohne Anzeige muss der Pfad zum Zustand angegeben werden.
+    KeinPfad,
+    /// Die Behandlung von SIGTERM konnte nicht eingerichtet werden.
+    Signal(io::Error),
+    /// Der Zustand konnte nicht geladen werden.
+    Laden { titel: String, nachricht: String },
+    /// Der Empfänger für Nachrichten wurde bereits verwendet.
+    KanalVerwendet,
+}
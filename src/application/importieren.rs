@@ -0,0 +1,412 @@
+//! Auswahl einer Datei, deren Gleise zum aktuellen Zustand hinzugefügt werden.
+
+use std::fmt::{self, Display, Formatter};
+
+use iced_aw::native::{card, Card};
+use iced_native::{
+    button, checkbox, column, container, event, pick_list, radio, row, scrollable, text,
+    text_input, Button, Checkbox, Clipboard, Column, Element, Event, Layout, Length, PickList,
+    Point, Radio, Renderer, Row, Scrollable, Text, TextInput, Widget,
+};
+
+use super::{
+    anchor::Anchor,
+    gleis::gleise::Platzierung,
+    macros::reexport_no_event_methods,
+    typen::{Position, Skalar, Vektor, Winkel},
+};
+
+/// Ein offener Anschlusspunkt zur Auswahl.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Eintrag {
+    index: usize,
+    beschreibung: String,
+}
+
+impl Display for Eintrag {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.beschreibung)
+    }
+}
+
+/// Art der Platzierung der importierten Gleise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Art {
+    Verschiebung,
+    Anlegen,
+}
+
+#[derive(Debug)]
+pub struct Status {
+    pfad: String,
+    pfad_state: text_input::State,
+    anschlüsse_übernehmen: bool,
+    art: Art,
+    verschiebung: [String; 3],
+    verschiebung_states: [text_input::State; 3],
+    import_einträge: Vec<Eintrag>,
+    import_anchor_points: Vec<Anchor>,
+    import_auswahl: Option<Eintrag>,
+    import_state: pick_list::State<Eintrag>,
+    ziel_einträge: Vec<Eintrag>,
+    ziel_anchor_points: Vec<Anchor>,
+    ziel_auswahl: Option<Eintrag>,
+    ziel_state: pick_list::State<Eintrag>,
+    fehler: Option<String>,
+    lesen_button_state: button::State,
+    importieren_button_state: button::State,
+    scrollable_state: scrollable::State,
+}
+
+impl Status {
+    pub fn neu() -> Self {
+        Status {
+            pfad: String::new(),
+            pfad_state: text_input::State::new(),
+            anschlüsse_übernehmen: true,
+            art: Art::Verschiebung,
+            verschiebung: Default::default(),
+            verschiebung_states: Default::default(),
+            import_einträge: Vec::new(),
+            import_anchor_points: Vec::new(),
+            import_auswahl: None,
+            import_state: pick_list::State::default(),
+            ziel_einträge: Vec::new(),
+            ziel_anchor_points: Vec::new(),
+            ziel_auswahl: None,
+            ziel_state: pick_list::State::default(),
+            fehler: None,
+            lesen_button_state: button::State::new(),
+            importieren_button_state: button::State::new(),
+            scrollable_state: scrollable::State::new(),
+        }
+    }
+
+    /// Zeige die offenen Anschlusspunkte der gelesenen Datei (`import`)
+    /// und des aktuellen Zustands (`ziel`) zur Auswahl an.
+    pub fn setze_anchor_points(
+        &mut self,
+        import: Vec<(String, Anchor)>,
+        ziel: Vec<(String, Anchor)>,
+    ) {
+        fn aufteilen(anchor_points: Vec<(String, Anchor)>) -> (Vec<Eintrag>, Vec<Anchor>) {
+            anchor_points
+                .into_iter()
+                .enumerate()
+                .map(|(index, (beschreibung, anchor))| (Eintrag { index, beschreibung }, anchor))
+                .unzip()
+        }
+        let (import_einträge, import_anchor_points) = aufteilen(import);
+        let (ziel_einträge, ziel_anchor_points) = aufteilen(ziel);
+        self.import_einträge = import_einträge;
+        self.import_anchor_points = import_anchor_points;
+        self.import_auswahl = None;
+        self.ziel_einträge = ziel_einträge;
+        self.ziel_anchor_points = ziel_anchor_points;
+        self.ziel_auswahl = None;
+        self.fehler = None;
+    }
+
+    /// Zeige einen Fehler beim Lesen der Datei an.
+    pub fn zeige_fehler(&mut self, fehler: String) {
+        self.fehler = Some(fehler)
+    }
+}
+
+/// Gewählte Einstellungen für das Importieren.
+#[derive(Debug, Clone)]
+pub struct Einstellungen {
+    pub pfad: String,
+    pub anschlüsse_übernehmen: bool,
+    pub platzierung: Platzierung,
+}
+
+#[derive(Debug, Clone)]
+enum InterneNachricht {
+    Schließe,
+    Pfad(String),
+    Lesen,
+    AnschlüsseÜbernehmen(bool),
+    Art(Art),
+    Verschiebung(usize, String),
+    Import(Eintrag),
+    Ziel(Eintrag),
+    Importieren,
+}
+
+#[derive(Debug, Clone)]
+pub enum Nachricht {
+    Schließe,
+    /// Lese die offenen Anschlusspunkte der Datei.
+    Lesen(String),
+    Importieren(Einstellungen),
+}
+
+pub struct Auswahl<'a, R: Renderer + card::Renderer> {
+    card: Card<'a, InterneNachricht, R>,
+    pfad: &'a mut String,
+    anschlüsse_übernehmen: &'a mut bool,
+    art: &'a mut Art,
+    verschiebung: &'a mut [String; 3],
+    import_anchor_points: &'a [Anchor],
+    import_auswahl: &'a mut Option<Eintrag>,
+    ziel_anchor_points: &'a [Anchor],
+    ziel_auswahl: &'a mut Option<Eintrag>,
+    fehler: &'a mut Option<String>,
+}
+
+impl<'a, R> Auswahl<'a, R>
+where
+    R: 'a
+        + Renderer
+        + text::Renderer
+        + text_input::Renderer
+        + column::Renderer
+        + row::Renderer
+        + container::Renderer
+        + button::Renderer
+        + checkbox::Renderer
+        + radio::Renderer
+        + pick_list::Renderer
+        + scrollable::Renderer
+        + card::Renderer,
+{
+    pub fn neu(
+        Status {
+            pfad,
+            pfad_state,
+            anschlüsse_übernehmen,
+            art,
+            verschiebung,
+            verschiebung_states,
+            import_einträge,
+            import_anchor_points,
+            import_auswahl,
+            import_state,
+            ziel_einträge,
+            ziel_anchor_points,
+            ziel_auswahl,
+            ziel_state,
+            fehler,
+            lesen_button_state,
+            importieren_button_state,
+            scrollable_state,
+        }: &'a mut Status,
+    ) -> Self {
+        let mut column = Column::new()
+            .push(
+                Row::new()
+                    .push(
+                        TextInput::new(pfad_state, "<Pfad>", pfad, InterneNachricht::Pfad)
+                            .width(Length::Units(300)),
+                    )
+                    .push(
+                        Button::new(lesen_button_state, Text::new("Lesen"))
+                            .on_press(InterneNachricht::Lesen),
+                    )
+                    .spacing(5),
+            )
+            .push(Checkbox::new(
+                *anschlüsse_übernehmen,
+                "Anschlüsse übernehmen",
+                InterneNachricht::AnschlüsseÜbernehmen,
+            ))
+            .push(Radio::new(
+                Art::Verschiebung,
+                "Verschieben um x, y und Winkel [°]",
+                Some(*art),
+                InterneNachricht::Art,
+            ));
+        let mut verschiebung_row = Row::new().spacing(5);
+        for (index, ((wert, state), platzhalter)) in verschiebung
+            .iter()
+            .zip(verschiebung_states.iter_mut())
+            .zip(["x", "y", "winkel"].iter())
+            .enumerate()
+        {
+            verschiebung_row = verschiebung_row.push(
+                TextInput::new(state, platzhalter, wert, move |wert| {
+                    InterneNachricht::Verschiebung(index, wert)
+                })
+                .width(Length::Units(80)),
+            )
+        }
+        column = column.push(verschiebung_row).push(Radio::new(
+            Art::Anlegen,
+            "An Anschlusspunkt anlegen",
+            Some(*art),
+            InterneNachricht::Art,
+        ));
+        column = if import_einträge.is_empty() {
+            column.push(Text::new("Datei lesen, um Anschlusspunkte auszuwählen."))
+        } else {
+            column
+                .push(Text::new("Anschlusspunkt der importierten Gleise"))
+                .push(PickList::new(
+                    import_state,
+                    &import_einträge[..],
+                    import_auswahl.clone(),
+                    InterneNachricht::Import,
+                ))
+                .push(Text::new("Anschlusspunkt der aktuellen Gleise"))
+                .push(PickList::new(
+                    ziel_state,
+                    &ziel_einträge[..],
+                    ziel_auswahl.clone(),
+                    InterneNachricht::Ziel,
+                ))
+        };
+        column = column.push(
+            Button::new(importieren_button_state, Text::new("Importieren"))
+                .on_press(InterneNachricht::Importieren),
+        );
+        if let Some(text) = fehler.as_ref() {
+            column = column.push(Text::new(text.clone()))
+        }
+        let card = Card::new(
+            Text::new("Gleise importieren").width(Length::Fill),
+            Scrollable::new(scrollable_state).push(column.spacing(5)).width(Length::Shrink),
+        )
+        .on_close(InterneNachricht::Schließe)
+        .width(Length::Shrink);
+        Auswahl {
+            card,
+            pfad,
+            anschlüsse_übernehmen,
+            art,
+            verschiebung,
+            import_anchor_points,
+            import_auswahl,
+            ziel_anchor_points,
+            ziel_auswahl,
+            fehler,
+        }
+    }
+
+    /// Platzierung gemäß der aktuellen Eingaben.
+    fn platzierung(&self) -> Result<Platzierung, String> {
+        match *self.art {
+            Art::Verschiebung => {
+                let mut werte = [0.; 3];
+                for (wert, text) in werte.iter_mut().zip(self.verschiebung.iter()) {
+                    if !text.trim().is_empty() {
+                        *wert = text
+                            .trim()
+                            .parse()
+                            .map_err(|_| format!("Ungültige Verschiebung: {}", text))?;
+                    }
+                }
+                let [x, y, winkel] = werte;
+                Ok(Platzierung::Verschiebung(Position {
+                    punkt: Vektor { x: Skalar(x), y: Skalar(y) },
+                    winkel: Winkel(f32::to_radians(winkel)),
+                }))
+            },
+            Art::Anlegen => {
+                let anchor = |auswahl: &Option<Eintrag>, anchor_points: &[Anchor]| {
+                    auswahl.as_ref().and_then(|Eintrag { index, .. }| anchor_points.get(*index))
+                };
+                match (
+                    anchor(&*self.import_auswahl, self.import_anchor_points),
+                    anchor(&*self.ziel_auswahl, self.ziel_anchor_points),
+                ) {
+                    (Some(import), Some(ziel)) => {
+                        Ok(Platzierung::Anlegen { import: *import, ziel: *ziel })
+                    },
+                    _ => Err("Bitte beide Anschlusspunkte auswählen.".to_string()),
+                }
+            },
+        }
+    }
+}
+
+impl<'a, R> Widget<Nachricht, R> for Auswahl<'a, R>
+where
+    R: 'a
+        + Renderer
+        + text::Renderer
+        + text_input::Renderer
+        + column::Renderer
+        + row::Renderer
+        + container::Renderer
+        + button::Renderer
+        + checkbox::Renderer
+        + radio::Renderer
+        + pick_list::Renderer
+        + scrollable::Renderer
+        + card::Renderer,
+{
+    reexport_no_event_methods! {Card<'a, InterneNachricht, R>, card, InterneNachricht, R}
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &R,
+        clipboard: &mut dyn Clipboard,
+        messages: &mut Vec<Nachricht>,
+    ) -> event::Status {
+        let mut card_messages = Vec::new();
+        let mut status = self.card.on_event(
+            event,
+            layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            &mut card_messages,
+        );
+        for message in card_messages {
+            match message {
+                InterneNachricht::Schließe => messages.push(Nachricht::Schließe),
+                InterneNachricht::Pfad(pfad) => *self.pfad = pfad,
+                InterneNachricht::Lesen => messages.push(Nachricht::Lesen(self.pfad.clone())),
+                InterneNachricht::AnschlüsseÜbernehmen(übernehmen) => {
+                    *self.anschlüsse_übernehmen = übernehmen
+                },
+                InterneNachricht::Art(art) => *self.art = art,
+                InterneNachricht::Verschiebung(index, wert) => {
+                    if let Some(bisher) = self.verschiebung.get_mut(index) {
+                        *bisher = wert
+                    }
+                },
+                InterneNachricht::Import(eintrag) => *self.import_auswahl = Some(eintrag),
+                InterneNachricht::Ziel(eintrag) => *self.ziel_auswahl = Some(eintrag),
+                InterneNachricht::Importieren => match self.platzierung() {
+                    Ok(platzierung) => {
+                        *self.fehler = None;
+                        messages.push(Nachricht::Importieren(Einstellungen {
+                            pfad: self.pfad.clone(),
+                            anschlüsse_übernehmen: *self.anschlüsse_übernehmen,
+                            platzierung,
+                        }))
+                    },
+                    Err(fehler) => *self.fehler = Some(fehler),
+                },
+            }
+            status = event::Status::Captured;
+        }
+        status
+    }
+}
+
+impl<'a, R> From<Auswahl<'a, R>> for Element<'a, Nachricht, R>
+where
+    R: 'a
+        + Renderer
+        + text::Renderer
+        + text_input::Renderer
+        + column::Renderer
+        + row::Renderer
+        + container::Renderer
+        + button::Renderer
+        + checkbox::Renderer
+        + radio::Renderer
+        + pick_list::Renderer
+        + scrollable::Renderer
+        + card::Renderer,
+{
+    fn from(auswahl: Auswahl<'a, R>) -> Self {
+        Element::new(auswahl)
+    }
+}
@@ -0,0 +1,102 @@
+//! Kanal für Nachrichten, die außerhalb des GUI-Threads erzeugt werden (z.B. durch Interrupts).
+
+use std::{
+    any::TypeId,
+    hash::Hash,
+    sync::{Arc, Mutex},
+};
+
+use iced_native::{
+    event,
+    futures::{
+        channel::mpsc,
+        stream::{self, BoxStream},
+        StreamExt,
+    },
+    subscription::Recipe,
+    Event, Hasher, Subscription,
+};
+use log::error;
+
+/// Kanal, dessen Nachrichten über eine Subscription an die Anwendung weitergeleitet werden.
+#[derive(Debug)]
+pub struct Kanal<T> {
+    sender: mpsc::UnboundedSender<T>,
+    empfänger: Arc<Mutex<Option<mpsc::UnboundedReceiver<T>>>>,
+}
+
+impl<T: 'static + Send> Kanal<T> {
+    pub fn neu() -> Self {
+        let (sender, empfänger) = mpsc::unbounded();
+        Kanal { sender, empfänger: Arc::new(Mutex::new(Some(empfänger))) }
+    }
+
+    /// Erzeuge einen neuen Sender für diesen Kanal.
+    pub fn sender(&self) -> Sender<T> {
+        Sender(self.sender.clone())
+    }
+
+    /// Empfänger für die Verwendung ohne Subscription, z.B. im Betrieb ohne Anzeige.
+    ///
+    /// Wie bei [subscription](Kanal::subscription) kann der Empfänger nur einmal verwendet werden.
+    pub fn empfänger(&self) -> Option<mpsc::UnboundedReceiver<T>> {
+        match self.empfänger.lock() {
+            Ok(mut guard) => guard.take(),
+            Err(poison_error) => poison_error.into_inner().take(),
+        }
+    }
+
+    /// Subscription, die alle über den Kanal gesendeten Nachrichten liefert.
+    ///
+    /// Der Empfänger kann nur von einer Subscription verwendet werden,
+    /// diese muss daher bei jedem Aufruf von `Application::subscription` zurückgegeben werden.
+    pub fn subscription(&self) -> Subscription<T> {
+        Subscription::from_recipe(Empfänger(self.empfänger.clone()))
+    }
+}
+
+/// Sender für Nachrichten eines Kanals.
+#[derive(Debug)]
+pub struct Sender<T>(mpsc::UnboundedSender<T>);
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Self {
+        Sender(self.0.clone())
+    }
+}
+
+impl<T> Sender<T> {
+    /// Sende eine Nachricht.
+    ///
+    /// Falls der Kanal bereits geschlossen wurde wird die Nachricht zurückgegeben.
+    pub fn send(&self, nachricht: T) -> Result<(), T> {
+        self.0.unbounded_send(nachricht).map_err(mpsc::TrySendError::into_inner)
+    }
+}
+
+struct Empfänger<T>(Arc<Mutex<Option<mpsc::UnboundedReceiver<T>>>>);
+
+impl<T: 'static + Send> Recipe<Hasher, (Event, event::Status)> for Empfänger<T> {
+    type Output = T;
+
+    fn hash(&self, state: &mut Hasher) {
+        TypeId::of::<Self>().hash(state);
+        (Arc::as_ptr(&self.0) as usize).hash(state);
+    }
+
+    fn stream(
+        self: Box<Self>,
+        _input: BoxStream<'static, (Event, event::Status)>,
+    ) -> BoxStream<'static, T> {
+        let empfänger = match self.0.lock() {
+            Ok(mut guard) => guard.take(),
+            Err(poison_error) => poison_error.into_inner().take(),
+        };
+        if let Some(empfänger) = empfänger {
+            empfänger.boxed()
+        } else {
+            error!("Empfänger eines Kanals wurde bereits verwendet!");
+            stream::empty().boxed()
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! Anbindung an einen MQTT-Broker, z.B. für Home Assistant oder Node-RED.
+//!
+//! Der Zustand aller Steuerungen wird unter `BASIS/ART/NAME` veröffentlicht,
+//! Befehle werden unter `BASIS/ART/NAME/set` empfangen und wie Befehle der
+//! [Netzwerk-Steuerung](super::netzwerk) an die Anwendung weitergeleitet.
+//! Namen bilden immer genau ein Topic-Level, `/`, `+`, `#` und `%` werden als `%XX` kodiert.
+
+use std::{
+    fmt::{self, Debug, Formatter},
+    num::ParseIntError,
+    sync::{mpsc, Arc, Mutex, PoisonError},
+    thread,
+    time::Duration,
+};
+
+use log::{debug, error, warn};
+use rumqttc::{Client, Event, LastWill, MqttOptions, Packet, QoS};
+
+use super::netzwerk::{Anfrage, Ausgabe, Befehl, Ereignis, Zustand};
+use crate::steuerung::{geschwindigkeit, streckenabschnitt, weiche};
+
+/// Standard-Port eines MQTT-Brokers.
+const STANDARD_PORT: u16 = 1883;
+/// Kapazität der Warteschlange für ausgehende Nachrichten.
+const KAPAZITÄT: usize = 100;
+/// Wartezeit vor einem erneuten Verbindungsversuch.
+const WARTEZEIT: Duration = Duration::from_secs(1);
+
+/// Einstellungen für die Verbindung zum Broker.
+#[derive(Debug, Clone)]
+pub struct Einstellungen {
+    /// Adresse des Brokers, `HOST` oder `HOST:PORT`.
+    pub adresse: String,
+    /// Basis aller Topics.
+    pub basis: String,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Port(ParseIntError),
+    /// Die Basis darf keine Wildcards (`+`, `#`) enthalten.
+    UngültigeBasis(String),
+}
+impl From<ParseIntError> for Error {
+    fn from(error: ParseIntError) -> Self {
+        Error::Port(error)
+    }
+}
+
+/// Verbindung zu einem MQTT-Broker.
+pub struct Brücke {
+    client: Client,
+    basis: String,
+    zustand: Arc<Mutex<Zustand>>,
+}
+impl Debug for Brücke {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Brücke {{client: _, basis: {:?}, zustand: {:?}}}", self.basis, self.zustand)
+    }
+}
+
+impl Brücke {
+    /// Verbinde zum Broker, empfangene Befehle werden an `weiterleiten` übergeben.
+    ///
+    /// Bei Verbindungsabbrüchen wird automatisch eine neue Verbindung aufgebaut.
+    pub fn starte(
+        Einstellungen { adresse, basis }: Einstellungen,
+        weiterleiten: impl Fn(Anfrage) + Send + 'static,
+    ) -> Result<Self, Error> {
+        if basis.contains(|c| c == '+' || c == '#') {
+            return Err(Error::UngültigeBasis(basis));
+        }
+        let mut teile = adresse.rsplitn(2, ':');
+        let (host, port) = match (teile.next(), teile.next()) {
+            (Some(port), Some(host)) => (host, port.parse()?),
+            _ => (adresse.as_str(), STANDARD_PORT),
+        };
+        let mut optionen =
+            MqttOptions::new(format!("zugkontrolle-{}", std::process::id()), host, port);
+        optionen.set_keep_alive(Duration::from_secs(5)).set_last_will(LastWill::new(
+            format!("{}/status", basis),
+            "offline",
+            QoS::AtLeastOnce,
+            true,
+        ));
+        let (client, mut connection) = Client::new(optionen, KAPAZITÄT);
+        let zustand = Arc::new(Mutex::new(Zustand::default()));
+        let brücke =
+            Brücke { client: client.clone(), basis: basis.clone(), zustand: zustand.clone() };
+        let (sender, empfänger) = mpsc::channel();
+        thread::spawn(move || {
+            for ausgabe in empfänger {
+                if let Ausgabe::Fehler { nachricht } = ausgabe {
+                    warn!("MQTT-Befehl fehlgeschlagen: {}", nachricht)
+                }
+            }
+        });
+        thread::spawn(move || {
+            let brücke = Brücke { client, basis, zustand };
+            for benachrichtigung in connection.iter() {
+                match benachrichtigung {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => brücke.verbunden(),
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        let payload = String::from_utf8_lossy(&publish.payload);
+                        match brücke.befehl(&publish.topic, payload.trim()) {
+                            Ok(befehl) => weiterleiten(Anfrage::neu(befehl, sender.clone())),
+                            Err(fehler) => warn!("{}: {}", publish.topic, fehler),
+                        }
+                    },
+                    Ok(_event) => {},
+                    Err(fehler) => {
+                        warn!("Verbindung zum MQTT-Broker unterbrochen: {}", fehler);
+                        thread::sleep(WARTEZEIT)
+                    },
+                }
+            }
+        });
+        Ok(brücke)
+    }
+
+    fn veröffentliche(&self, topic: String, payload: String, retain: bool) {
+        if let Err(fehler) = self.client.try_publish(&topic, QoS::AtLeastOnce, retain, payload) {
+            error!("Nachricht an {} nicht gesendet: {}", topic, fehler)
+        }
+    }
+
+    /// Nach jeder (erneuten) Verbindung: Befehle abonnieren und den Zustand veröffentlichen.
+    fn verbunden(&self) {
+        debug!("Mit MQTT-Broker verbunden.");
+        let basis = &self.basis;
+        for topic in [
+            format!("{}/+/+/set", basis),
+            format!("{}/geschwindigkeit/+/fahrtrichtung/set", basis),
+            format!("{}/nothalt/set", basis),
+        ]
+        .iter()
+        {
+            if let Err(fehler) = self.client.try_subscribe(topic, QoS::AtLeastOnce) {
+                error!("{} nicht abonniert: {}", topic, fehler)
+            }
+        }
+        self.veröffentliche(format!("{}/status", basis), "online".to_string(), true);
+        let zustand = self.zustand.lock().unwrap_or_else(PoisonError::into_inner);
+        for ereignis in Zustand::default().änderungen(&zustand) {
+            self.melde(&ereignis)
+        }
+    }
+
+    /// Veröffentliche ein Ereignis, Änderungen des Zustands werden mit retain-Flag gesendet.
+    pub fn melde(&self, ereignis: &Ereignis) {
+        let basis = &self.basis;
+        let ein_aus = |wert: bool| if wert { "ON" } else { "OFF" }.to_string();
+        let (topic, payload, retain) = match ereignis {
+            Ereignis::Geschwindigkeit { name, wert } => {
+                (format!("{}/geschwindigkeit/{}", basis, kodiere(&name.0)), wert.to_string(), true)
+            },
+            Ereignis::Fahrtrichtung { name, fahrtrichtung } => (
+                format!("{}/geschwindigkeit/{}/fahrtrichtung", basis, kodiere(&name.0)),
+                fahrtrichtung.clone(),
+                true,
+            ),
+            Ereignis::Weiche { name, richtung } => {
+                (format!("{}/weiche/{}", basis, kodiere(&name.0)), richtung.clone(), true)
+            },
+            Ereignis::Streckenabschnitt { name, fließend } => (
+                format!("{}/streckenabschnitt/{}", basis, kodiere(&name.0)),
+                ein_aus(*fließend),
+                true,
+            ),
+            Ereignis::Kontakt { name, level } => {
+                (format!("{}/kontakt/{}", basis, kodiere(&name.0)), format!("{:?}", level), false)
+            },
+            Ereignis::Nothalt { aktiv } => (format!("{}/nothalt", basis), ein_aus(*aktiv), true),
+        };
+        self.veröffentliche(topic, payload, retain)
+    }
+
+    /// Veröffentliche alle Änderungen gegenüber dem zuletzt übergebenen Zustand.
+    pub fn aktualisiere(&self, zustand: Zustand) {
+        let mut letzter_zustand = self.zustand.lock().unwrap_or_else(PoisonError::into_inner);
+        if zustand == *letzter_zustand {
+            return;
+        }
+        for ereignis in letzter_zustand.änderungen(&zustand) {
+            self.melde(&ereignis)
+        }
+        *letzter_zustand = zustand;
+    }
+
+    /// Übersetze eine Nachricht an `BASIS/ART/NAME/set` in einen Befehl.
+    fn befehl(&self, topic: &str, payload: &str) -> Result<Befehl, String> {
+        let pfad = topic
+            .strip_prefix(&self.basis)
+            .and_then(|pfad| pfad.strip_prefix('/'))
+            .and_then(|pfad| pfad.strip_suffix("/set"))
+            .ok_or_else(|| "Unbekanntes Topic".to_string())?;
+        if pfad == "nothalt" {
+            return match payload {
+                "ON" => Ok(Befehl::Nothalt),
+                _ => Err("Der Nothalt kann nur in der Anzeige aufgehoben werden!".to_string()),
+            };
+        }
+        let (art, name) = pfad.split_at(pfad.find('/').ok_or("Name fehlt")?);
+        let (name, fahrtrichtung) = match name[1..].strip_suffix("/fahrtrichtung") {
+            Some(name) if art == "geschwindigkeit" => (name, true),
+            _ => (&name[1..], false),
+        };
+        let name = dekodiere(name)?;
+        match art {
+            "geschwindigkeit" => match fahrtrichtung {
+                true => Ok(Befehl::Fahrtrichtung {
+                    name: geschwindigkeit::Name(name),
+                    fahrtrichtung: payload.to_string(),
+                }),
+                false => Ok(Befehl::Geschwindigkeit {
+                    name: geschwindigkeit::Name(name),
+                    wert: payload
+                        .parse()
+                        .map_err(|fehler| format!("Ungültige Geschwindigkeit: {}", fehler))?,
+                }),
+            },
+            "weiche" => {
+                Ok(Befehl::Weiche { name: weiche::Name(name), richtung: payload.to_string() })
+            },
+            "streckenabschnitt" => Ok(Befehl::Streckenabschnitt {
+                name: streckenabschnitt::Name(name),
+                fließend: match payload {
+                    "ON" => Some(true),
+                    "OFF" => Some(false),
+                    "TOGGLE" => None,
+                    _ => return Err(format!("Erwarte ON, OFF oder TOGGLE, nicht {}", payload)),
+                },
+            }),
+            _ => Err(format!("Unbekannte Art: {}", art)),
+        }
+    }
+}
+
+/// Kodiere einen Namen als ein Topic-Level.
+fn kodiere(name: &str) -> String {
+    let mut level = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '/' | '+' | '#' | '%' => level.push_str(&format!("%{:02X}", c as u8)),
+            _ => level.push(c),
+        }
+    }
+    level
+}
+
+/// Umkehrung von [kodiere], ein `/` oder eine Wildcard im Level ist ein Fehler.
+fn dekodiere(level: &str) -> Result<String, String> {
+    let ungültig = || format!("Ungültiger Name: {}", level);
+    if level.is_empty() || level.contains(|c| c == '/' || c == '+' || c == '#') {
+        return Err(ungültig());
+    }
+    let mut bytes = Vec::with_capacity(level.len());
+    let mut rest = level.as_bytes();
+    while let Some((byte, weitere)) = rest.split_first() {
+        if *byte == b'%' {
+            let hex = weitere.get(..2).and_then(|hex| std::str::from_utf8(hex).ok());
+            let wert = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()).ok_or_else(ungültig)?;
+            bytes.push(wert);
+            rest = &weitere[2..];
+        } else {
+            bytes.push(*byte);
+            rest = weitere;
+        }
+    }
+    String::from_utf8(bytes).map_err(|_error| ungültig())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn brücke(basis: &str) -> Brücke {
+        let (client, _connection) =
+            Client::new(MqttOptions::new("test", "localhost", STANDARD_PORT), KAPAZITÄT);
+        Brücke {
+            client,
+            basis: basis.to_string(),
+            zustand: Arc::new(Mutex::new(Zustand::default())),
+        }
+    }
+
+    #[test]
+    fn befehle() {
+        let brücke = brücke("zugkontrolle");
+        assert!(matches!(
+            brücke.befehl("zugkontrolle/geschwindigkeit/Lok/set", "42"),
+            Ok(Befehl::Geschwindigkeit { name, wert: 42 }) if name.0 == "Lok"
+        ));
+        assert!(matches!(
+            brücke.befehl("zugkontrolle/geschwindigkeit/Lok/fahrtrichtung/set", "Vorwärts"),
+            Ok(Befehl::Fahrtrichtung { name, fahrtrichtung })
+                if name.0 == "Lok" && fahrtrichtung == "Vorwärts"
+        ));
+        assert!(matches!(
+            brücke.befehl("zugkontrolle/weiche/W1/set", "Kurve"),
+            Ok(Befehl::Weiche { name, richtung }) if name.0 == "W1" && richtung == "Kurve"
+        ));
+        assert!(matches!(
+            brücke.befehl("zugkontrolle/streckenabschnitt/S1/set", "TOGGLE"),
+            Ok(Befehl::Streckenabschnitt { name, fließend: None }) if name.0 == "S1"
+        ));
+        assert!(matches!(brücke.befehl("zugkontrolle/nothalt/set", "ON"), Ok(Befehl::Nothalt)));
+    }
+
+    #[test]
+    fn ungültige_befehle() {
+        let brücke = brücke("zugkontrolle");
+        for (topic, payload) in [
+            ("andere/weiche/W1/set", "Kurve"),
+            ("zugkontrolle/weiche/W1", "Kurve"),
+            ("zugkontrolle/nothalt/set", "OFF"),
+            ("zugkontrolle/geschwindigkeit/Lok/set", "schnell"),
+            ("zugkontrolle/streckenabschnitt/S1/set", "AN"),
+            ("zugkontrolle/unbekannt/X/set", "ON"),
+            ("zugkontrolle/weiche/W/1/set", "Kurve"),
+            ("zugkontrolle/weiche/W%2/set", "Kurve"),
+        ]
+        .iter()
+        {
+            assert!(brücke.befehl(topic, payload).is_err(), "{} {}", topic, payload);
+        }
+    }
+
+    #[test]
+    fn sonderzeichen_in_namen() {
+        let brücke = brücke("anlage/zugkontrolle");
+        for name in ["W/1", "+", "#", "100%", "Kurve/fahrtrichtung"].iter() {
+            assert!(!kodiere(name).contains(|c| c == '/' || c == '+' || c == '#'));
+            let topic = format!("anlage/zugkontrolle/weiche/{}/set", kodiere(name));
+            assert!(matches!(
+                brücke.befehl(&topic, "Gerade"),
+                Ok(Befehl::Weiche { name: weiche::Name(befehl_name), .. }) if befehl_name == *name
+            ));
+        }
+        let topic =
+            format!("anlage/zugkontrolle/geschwindigkeit/{}/fahrtrichtung/set", kodiere("a/b"));
+        assert!(matches!(
+            brücke.befehl(&topic, "Rückwärts"),
+            Ok(Befehl::Fahrtrichtung { name, .. }) if name.0 == "a/b"
+        ));
+    }
+}
@@ -0,0 +1,399 @@
+//! Steuerung über das Netzwerk (WebSocket mit JSON-Nachrichten).
+//!
+//! Befehle der Clients werden als [Anfrage] an die Anwendung weitergeleitet
+//! und dort wie Eingaben der Anzeige behandelt.
+//! Clients mit Abonnement erhalten zusätzlich alle Änderungen als [Ereignis].
+
+use std::{
+    collections::BTreeMap,
+    io,
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        mpsc, Arc, Mutex, PoisonError,
+    },
+    thread,
+    time::Duration,
+};
+
+use log::{debug, error, warn};
+use serde::{Deserialize, Serialize};
+use tungstenite::{Message as WsNachricht, WebSocket};
+
+use crate::{
+    anschluss::Level,
+    steuerung::{geschwindigkeit, kontakt, regel, skript, streckenabschnitt, weiche},
+};
+
+/// Maximale Wartezeit auf einen Befehl, bevor ausstehende Nachrichten gesendet werden.
+const LESE_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// Host, wenn nur ein Port angegeben wird; der Server ist dann nur vom gleichen Rechner erreichbar.
+const STANDARD_HOST: &str = "127.0.0.1";
+
+/// Ergänze [STANDARD_HOST], wenn `adresse` nur aus einem Port (`PORT` oder `:PORT`) besteht.
+pub(crate) fn mit_standard_host(adresse: &str) -> String {
+    let port = adresse.strip_prefix(':').unwrap_or(adresse);
+    if port.parse::<u16>().is_ok() {
+        format!("{}:{}", STANDARD_HOST, port)
+    } else {
+        adresse.to_string()
+    }
+}
+
+/// Warne, wenn ein Server ohne Authentifizierung von anderen Rechnern erreichbar ist.
+pub(crate) fn warne_falls_erreichbar(beschreibung: &str, adresse: SocketAddr) {
+    if !adresse.ip().is_loopback() {
+        warn!(
+            "{} an {} ist ohne Authentifizierung von anderen Rechnern im Netzwerk erreichbar!",
+            beschreibung, adresse
+        )
+    }
+}
+
+/// Befehl eines Clients, z.B. `{"befehl": "Weiche", "name": "W1", "richtung": "Kurve"}`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "befehl")]
+pub enum Befehl {
+    /// Liefere den aktuellen Zustand aller Steuerungen.
+    Zustand,
+    /// Liefere den Gleisplan als SVG-Bild und die Maximalwerte aller Geschwindigkeiten.
+    Gleisplan,
+    /// Erhalte ab jetzt alle Ereignisse.
+    Abonnieren,
+    /// Stelle eine Geschwindigkeit ein (0 bis zum Maximalwert der Geschwindigkeit).
+    Geschwindigkeit { name: geschwindigkeit::Name, wert: u8 },
+    /// Drehe die Fahrtrichtung einer Geschwindigkeit um.
+    Umdrehen { name: geschwindigkeit::Name },
+    /// Stelle die Fahrtrichtung einer Geschwindigkeit ein ("Vorwärts", "Rückwärts").
+    ///
+    /// Bei einem Mittelleiter ist die Fahrtrichtung unbekannt, es wird immer umgedreht.
+    Fahrtrichtung { name: geschwindigkeit::Name, fahrtrichtung: String },
+    /// Stelle eine Weiche in die angegebene Richtung (z.B. "Gerade", "Kurve", "Links").
+    Weiche { name: weiche::Name, richtung: String },
+    /// Schalte den Strom eines Streckenabschnitts, ohne Angabe wird umgeschaltet.
+    Streckenabschnitt { name: streckenabschnitt::Name, fließend: Option<bool> },
+    /// Löse den Nothalt aus, aufheben ist nur über die Anzeige möglich.
+    Nothalt,
+}
+
+impl Befehl {
+    /// Befehle, die während eines Nothalts abgelehnt werden.
+    pub fn ist_fahren_aktion(&self) -> bool {
+        matches!(
+            self,
+            Befehl::Geschwindigkeit { .. }
+                | Befehl::Umdrehen { .. }
+                | Befehl::Fahrtrichtung { .. }
+                | Befehl::Weiche { .. }
+                | Befehl::Streckenabschnitt { .. }
+        )
+    }
+}
+
+impl From<skript::Aktion> for Befehl {
+    fn from(aktion: skript::Aktion) -> Self {
+        match aktion {
+            skript::Aktion::Weiche { name, richtung } => Befehl::Weiche { name, richtung },
+            skript::Aktion::Geschwindigkeit { name, wert } => {
+                Befehl::Geschwindigkeit { name, wert }
+            },
+            skript::Aktion::Umdrehen { name } => Befehl::Umdrehen { name },
+            skript::Aktion::Streckenabschnitt { name, fließend } => {
+                Befehl::Streckenabschnitt { name, fließend: Some(fließend) }
+            },
+        }
+    }
+}
+
+impl From<regel::Aktion> for Befehl {
+    fn from(aktion: regel::Aktion) -> Self {
+        match aktion {
+            regel::Aktion::Weiche { name, richtung } => Befehl::Weiche { name, richtung },
+            regel::Aktion::Geschwindigkeit { name, wert } => Befehl::Geschwindigkeit { name, wert },
+            regel::Aktion::Streckenabschnitt { name, fließend } => {
+                Befehl::Streckenabschnitt { name, fließend }
+            },
+        }
+    }
+}
+
+/// Zustand aller Steuerungen.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Zustand {
+    pub geschwindigkeiten: BTreeMap<geschwindigkeit::Name, u8>,
+    /// Nur Geschwindigkeiten mit bekannter Fahrtrichtung (Zweileiter).
+    pub fahrtrichtungen: BTreeMap<geschwindigkeit::Name, String>,
+    pub weichen: BTreeMap<weiche::Name, String>,
+    pub streckenabschnitte: BTreeMap<streckenabschnitt::Name, bool>,
+    pub nothalt: bool,
+}
+
+impl Zustand {
+    /// Alle Änderungen von `self` zu `neu`.
+    pub fn änderungen(&self, neu: &Zustand) -> Vec<Ereignis> {
+        let mut änderungen = Vec::new();
+        for (name, wert) in neu.geschwindigkeiten.iter() {
+            if self.geschwindigkeiten.get(name) != Some(wert) {
+                änderungen.push(Ereignis::Geschwindigkeit { name: name.clone(), wert: *wert })
+            }
+        }
+        for (name, fahrtrichtung) in neu.fahrtrichtungen.iter() {
+            if self.fahrtrichtungen.get(name) != Some(fahrtrichtung) {
+                änderungen.push(Ereignis::Fahrtrichtung {
+                    name: name.clone(),
+                    fahrtrichtung: fahrtrichtung.clone(),
+                })
+            }
+        }
+        for (name, richtung) in neu.weichen.iter() {
+            if self.weichen.get(name) != Some(richtung) {
+                änderungen.push(Ereignis::Weiche { name: name.clone(), richtung: richtung.clone() })
+            }
+        }
+        for (name, wert) in neu.streckenabschnitte.iter() {
+            if self.streckenabschnitte.get(name) != Some(wert) {
+                änderungen
+                    .push(Ereignis::Streckenabschnitt { name: name.clone(), fließend: *wert })
+            }
+        }
+        if neu.nothalt != self.nothalt {
+            änderungen.push(Ereignis::Nothalt { aktiv: neu.nothalt })
+        }
+        änderungen
+    }
+}
+
+/// Gleisplan für die Anzeige in einem Browser.
+#[derive(Debug, Clone, Serialize)]
+pub struct Gleisplan {
+    /// SVG-Bild aller Gleise, Weichen und Streckenabschnitte haben `data-`-Attribute.
+    pub svg: String,
+    /// Maximal erlaubter Wert jeder Geschwindigkeit.
+    pub geschwindigkeiten: BTreeMap<geschwindigkeit::Name, u8>,
+}
+
+/// Änderung, die an alle Clients mit Abonnement gesendet wird.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "ereignis")]
+pub enum Ereignis {
+    Geschwindigkeit { name: geschwindigkeit::Name, wert: u8 },
+    Fahrtrichtung { name: geschwindigkeit::Name, fahrtrichtung: String },
+    Weiche { name: weiche::Name, richtung: String },
+    Streckenabschnitt { name: streckenabschnitt::Name, fließend: bool },
+    Kontakt { name: kontakt::Name, level: Level },
+    Nothalt { aktiv: bool },
+}
+
+impl Ereignis {
+    /// Das Ereignis, falls Regeln darauf reagieren können.
+    pub fn regel_ereignis(&self) -> Option<regel::Ereignis> {
+        match self {
+            Ereignis::Weiche { name, richtung } => {
+                Some(regel::Ereignis::Weiche { name: name.clone(), richtung: richtung.clone() })
+            },
+            Ereignis::Streckenabschnitt { name, fließend } => {
+                Some(regel::Ereignis::Streckenabschnitt {
+                    name: name.clone(), fließend: *fließend
+                })
+            },
+            Ereignis::Kontakt { name, level } => {
+                Some(regel::Ereignis::Kontakt { name: name.clone(), level: *level })
+            },
+            Ereignis::Geschwindigkeit { .. }
+            | Ereignis::Fahrtrichtung { .. }
+            | Ereignis::Nothalt { .. } => None,
+        }
+    }
+}
+
+/// Nachricht an einen Client.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "art")]
+pub enum Ausgabe {
+    Ok,
+    Fehler { nachricht: String },
+    Zustand(Zustand),
+    Gleisplan(Gleisplan),
+    Ereignis(Ereignis),
+}
+
+/// Befehl eines Clients, die Antwort wird über [antworte](Anfrage::antworte) gesendet.
+#[derive(Debug, Clone)]
+pub struct Anfrage {
+    pub befehl: Befehl,
+    antwort: mpsc::Sender<Ausgabe>,
+}
+
+impl Anfrage {
+    /// Erstelle eine Anfrage, die Antwort wird an `antwort` gesendet.
+    pub fn neu(befehl: Befehl, antwort: mpsc::Sender<Ausgabe>) -> Self {
+        Anfrage { befehl, antwort }
+    }
+
+    pub fn antworte(&self, ausgabe: Ausgabe) {
+        if let Err(_ausgabe) = self.antwort.send(ausgabe) {
+            debug!("Client für die Antwort auf {:?} nicht mehr verbunden.", self.befehl)
+        }
+    }
+
+    /// Antworte mit `Ok` oder der Fehlermeldung.
+    pub fn antworte_ergebnis(&self, ergebnis: Result<(), String>) {
+        self.antworte(match ergebnis {
+            Ok(()) => Ausgabe::Ok,
+            Err(nachricht) => Ausgabe::Fehler { nachricht },
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Client {
+    id: usize,
+    sender: mpsc::Sender<Ausgabe>,
+    abonniert: Arc<AtomicBool>,
+}
+
+type Clients = Arc<Mutex<Vec<Client>>>;
+
+/// Server für WebSocket-Verbindungen, jeder Client wird in einem eigenen Thread bearbeitet.
+#[derive(Debug)]
+pub struct Server {
+    adresse: SocketAddr,
+    clients: Clients,
+    zustand: Zustand,
+}
+
+impl Server {
+    /// Starte einen Server an `adresse`, empfangene Befehle werden an `weiterleiten` übergeben.
+    ///
+    /// Besteht `adresse` nur aus einem Port wird an [STANDARD_HOST] gebunden.
+    pub fn starte(
+        adresse: &str,
+        weiterleiten: impl Fn(Anfrage) + Clone + Send + 'static,
+    ) -> io::Result<Self> {
+        let listener = TcpListener::bind(mit_standard_host(adresse))?;
+        let adresse = listener.local_addr()?;
+        warne_falls_erreichbar("Die Netzwerk-Steuerung", adresse);
+        let clients = Arc::new(Mutex::new(Vec::new()));
+        let clients_clone = clients.clone();
+        thread::spawn(move || {
+            let nächste_id = AtomicUsize::new(0);
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(stream) => {
+                        let id = nächste_id.fetch_add(1, Ordering::Relaxed);
+                        let clients = clients_clone.clone();
+                        let weiterleiten = weiterleiten.clone();
+                        thread::spawn(move || verbinde(id, stream, clients, weiterleiten));
+                    },
+                    Err(fehler) => warn!("Verbindung nicht angenommen: {}", fehler),
+                }
+            }
+        });
+        Ok(Server { adresse, clients, zustand: Zustand::default() })
+    }
+
+    /// Adresse, an der der Server auf Verbindungen wartet.
+    pub fn adresse(&self) -> SocketAddr {
+        self.adresse
+    }
+
+    /// Sende ein Ereignis an alle Clients mit Abonnement.
+    pub fn melde(&self, ereignis: Ereignis) {
+        let clients = self.clients.lock().unwrap_or_else(PoisonError::into_inner);
+        for client in clients.iter().filter(|client| client.abonniert.load(Ordering::Relaxed)) {
+            // getrennte Clients entfernen sich selbst
+            let _ = client.sender.send(Ausgabe::Ereignis(ereignis.clone()));
+        }
+    }
+
+    /// Melde alle Änderungen gegenüber dem zuletzt übergebenen Zustand.
+    pub fn aktualisiere(&mut self, zustand: Zustand) {
+        if zustand == self.zustand {
+            return;
+        }
+        for ereignis in self.zustand.änderungen(&zustand) {
+            self.melde(ereignis)
+        }
+        self.zustand = zustand;
+    }
+}
+
+/// Führe den WebSocket-Handshake durch und bearbeite den Client bis die Verbindung beendet wird.
+fn verbinde(id: usize, stream: TcpStream, clients: Clients, weiterleiten: impl Fn(Anfrage)) {
+    let adresse = match stream.peer_addr() {
+        Ok(adresse) => adresse.to_string(),
+        Err(_fehler) => "unbekannt".to_string(),
+    };
+    let mut websocket = match tungstenite::accept(stream) {
+        Ok(websocket) => websocket,
+        Err(fehler) => return warn!("Handshake mit {} fehlgeschlagen: {}", adresse, fehler),
+    };
+    if let Err(fehler) = websocket.get_ref().set_read_timeout(Some(LESE_TIMEOUT)) {
+        return error!("Timeout für {} nicht gesetzt: {}", adresse, fehler);
+    }
+    debug!("Neue Verbindung von {}", adresse);
+    let (sender, empfänger) = mpsc::channel();
+    let abonniert = Arc::new(AtomicBool::new(false));
+    clients.lock().unwrap_or_else(PoisonError::into_inner).push(Client {
+        id,
+        sender: sender.clone(),
+        abonniert: abonniert.clone(),
+    });
+    match bearbeite(&mut websocket, sender, empfänger, &abonniert, weiterleiten) {
+        Ok(()) => debug!("Verbindung von {} beendet.", adresse),
+        Err(fehler) => warn!("Verbindung von {} abgebrochen: {}", adresse, fehler),
+    }
+    clients.lock().unwrap_or_else(PoisonError::into_inner).retain(|client| client.id != id);
+}
+
+fn sende(
+    websocket: &mut WebSocket<TcpStream>,
+    ausgabe: &Ausgabe,
+) -> Result<(), tungstenite::Error> {
+    match serde_json::to_string(ausgabe) {
+        Ok(json) => websocket.write_message(WsNachricht::Text(json)),
+        Err(fehler) => {
+            error!("{:?} konnte nicht serialisiert werden: {}", ausgabe, fehler);
+            Ok(())
+        },
+    }
+}
+
+/// Abwechselnd ausstehende Nachrichten senden und auf Befehle warten.
+fn bearbeite(
+    websocket: &mut WebSocket<TcpStream>,
+    sender: mpsc::Sender<Ausgabe>,
+    empfänger: mpsc::Receiver<Ausgabe>,
+    abonniert: &AtomicBool,
+    weiterleiten: impl Fn(Anfrage),
+) -> Result<(), tungstenite::Error> {
+    loop {
+        for ausgabe in empfänger.try_iter() {
+            sende(websocket, &ausgabe)?
+        }
+        let text = match websocket.read_message() {
+            Ok(WsNachricht::Text(text)) => text,
+            // Ping und Close werden von tungstenite beantwortet
+            Ok(_nachricht) => continue,
+            Err(tungstenite::Error::Io(fehler))
+                if matches!(fehler.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) =>
+            {
+                continue
+            },
+            Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Err(fehler) => return Err(fehler),
+        };
+        match serde_json::from_str(&text) {
+            Ok(Befehl::Abonnieren) => {
+                abonniert.store(true, Ordering::Relaxed);
+                sende(websocket, &Ausgabe::Ok)?
+            },
+            Ok(befehl) => weiterleiten(Anfrage { befehl, antwort: sender.clone() }),
+            Err(fehler) => sende(
+                websocket,
+                &Ausgabe::Fehler { nachricht: format!("Unbekannter Befehl: {}", fehler) },
+            )?,
+        }
+    }
+}
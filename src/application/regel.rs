@@ -0,0 +1,606 @@
+//! Anzeige & Erstellen von Regeln, die bei Ereignissen auf der Anlage Aktionen auslösen.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+};
+
+use iced_aw::native::{card, Card};
+use iced_native::{
+    button, column, container, event, pick_list, row, scrollable, text, text_input, Button,
+    Clipboard, Column, Element, Event, Layout, Length, PickList, Point, Renderer, Row, Scrollable,
+    Text, TextInput, Widget,
+};
+
+use super::macros::reexport_no_event_methods;
+pub use crate::steuerung::regel::{
+    Aktion, Auslöser, Ereignis, Name, Regel, Schritt, Uhrzeit, Zeitgeber,
+};
+use crate::steuerung::{
+    geschwindigkeit, kontakt,
+    regel::{
+        lese_flanke, lese_name, lese_sekunden, lese_strom, lese_takt, lese_wert, lese_zeitfenster,
+        Bedingung, Error,
+    },
+    streckenabschnitt, weiche,
+};
+
+/// Art des Auslösers einer neuen Regel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuslöserArt {
+    Kontakt,
+    Streckenabschnitt,
+    Weiche,
+    Takt,
+}
+
+impl AuslöserArt {
+    const ALLE: &'static [AuslöserArt] = &[
+        AuslöserArt::Kontakt,
+        AuslöserArt::Streckenabschnitt,
+        AuslöserArt::Weiche,
+        AuslöserArt::Takt,
+    ];
+
+    fn hinweis(&self) -> &'static str {
+        match self {
+            AuslöserArt::Kontakt => "steigend/fallend/beide",
+            AuslöserArt::Streckenabschnitt => "an/aus (leer: jede Änderung)",
+            AuslöserArt::Weiche => "<Richtung> (leer: jede Änderung)",
+            AuslöserArt::Takt => "<Sekunden>",
+        }
+    }
+}
+
+impl Display for AuslöserArt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Art einer neuen Bedingung.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BedingungArt {
+    Weiche,
+    Zeitfenster,
+}
+
+impl BedingungArt {
+    const ALLE: &'static [BedingungArt] = &[BedingungArt::Weiche, BedingungArt::Zeitfenster];
+
+    fn hinweis(&self) -> &'static str {
+        match self {
+            BedingungArt::Weiche => "<Richtung>",
+            BedingungArt::Zeitfenster => "HH:MM-HH:MM",
+        }
+    }
+}
+
+impl Display for BedingungArt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Art einer neuen Aktion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AktionArt {
+    Weiche,
+    Geschwindigkeit,
+    Streckenabschnitt,
+}
+
+impl AktionArt {
+    const ALLE: &'static [AktionArt] =
+        &[AktionArt::Weiche, AktionArt::Geschwindigkeit, AktionArt::Streckenabschnitt];
+
+    fn hinweis(&self) -> &'static str {
+        match self {
+            AktionArt::Weiche => "<Richtung>",
+            AktionArt::Geschwindigkeit => "<Wert>",
+            AktionArt::Streckenabschnitt => "an/aus (leer: umschalten)",
+        }
+    }
+}
+
+impl Display for AktionArt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Eingaben für eine neue Regel.
+#[derive(Debug)]
+struct Entwurf {
+    name: String,
+    auslöser_art: AuslöserArt,
+    auslöser_name: String,
+    auslöser_wert: String,
+    bedingung_art: BedingungArt,
+    bedingung_name: String,
+    bedingung_wert: String,
+    bedingungen: Vec<Bedingung>,
+    aktion_art: AktionArt,
+    aktion_name: String,
+    aktion_wert: String,
+    aktion_verzögerung: String,
+    schritte: Vec<Schritt>,
+    fehler: Option<String>,
+}
+
+impl Entwurf {
+    fn neu() -> Self {
+        Entwurf {
+            name: String::new(),
+            auslöser_art: AuslöserArt::Kontakt,
+            auslöser_name: String::new(),
+            auslöser_wert: String::new(),
+            bedingung_art: BedingungArt::Weiche,
+            bedingung_name: String::new(),
+            bedingung_wert: String::new(),
+            bedingungen: Vec::new(),
+            aktion_art: AktionArt::Weiche,
+            aktion_name: String::new(),
+            aktion_wert: String::new(),
+            aktion_verzögerung: String::new(),
+            schritte: Vec::new(),
+            fehler: None,
+        }
+    }
+
+    fn auslöser(&self) -> Result<Auslöser, Error> {
+        Ok(match self.auslöser_art {
+            AuslöserArt::Kontakt => Auslöser::Kontakt {
+                name: kontakt::Name(lese_name(&self.auslöser_name)?),
+                trigger: lese_flanke(&self.auslöser_wert)?,
+            },
+            AuslöserArt::Streckenabschnitt => Auslöser::Streckenabschnitt {
+                name: streckenabschnitt::Name(lese_name(&self.auslöser_name)?),
+                fließend: lese_strom(&self.auslöser_wert)?,
+            },
+            AuslöserArt::Weiche => {
+                let richtung = self.auslöser_wert.trim();
+                Auslöser::Weiche {
+                    name: weiche::Name(lese_name(&self.auslöser_name)?),
+                    richtung: if richtung.is_empty() { None } else { Some(richtung.to_string()) },
+                }
+            },
+            AuslöserArt::Takt => Auslöser::Takt { intervall: lese_takt(&self.auslöser_wert)? },
+        })
+    }
+
+    fn bedingung(&self) -> Result<Bedingung, Error> {
+        match self.bedingung_art {
+            BedingungArt::Weiche => Ok(Bedingung::Weiche {
+                name: weiche::Name(lese_name(&self.bedingung_name)?),
+                richtung: lese_name(&self.bedingung_wert)?,
+            }),
+            BedingungArt::Zeitfenster => lese_zeitfenster(&self.bedingung_wert),
+        }
+    }
+
+    fn schritt(&self) -> Result<Schritt, Error> {
+        let aktion = match self.aktion_art {
+            AktionArt::Weiche => Aktion::Weiche {
+                name: weiche::Name(lese_name(&self.aktion_name)?),
+                richtung: lese_name(&self.aktion_wert)?,
+            },
+            AktionArt::Geschwindigkeit => Aktion::Geschwindigkeit {
+                name: geschwindigkeit::Name(lese_name(&self.aktion_name)?),
+                wert: lese_wert(&self.aktion_wert)?,
+            },
+            AktionArt::Streckenabschnitt => Aktion::Streckenabschnitt {
+                name: streckenabschnitt::Name(lese_name(&self.aktion_name)?),
+                fließend: lese_strom(&self.aktion_wert)?,
+            },
+        };
+        Ok(Schritt { aktion, verzögerung: lese_sekunden(&self.aktion_verzögerung)? })
+    }
+
+    fn regel(&self) -> Result<(Name, Regel), Error> {
+        let name = Name(lese_name(&self.name)?);
+        let auslöser = self.auslöser()?;
+        if self.schritte.is_empty() {
+            return Err(Error::KeineAktion);
+        }
+        Ok((
+            name,
+            Regel {
+                auslöser,
+                bedingungen: self.bedingungen.clone(),
+                schritte: self.schritte.clone(),
+            },
+        ))
+    }
+}
+
+#[derive(Debug)]
+pub struct AuswahlStatus {
+    entwurf: Entwurf,
+    name_state: text_input::State,
+    auslöser_state: pick_list::State<AuslöserArt>,
+    auslöser_name_state: text_input::State,
+    auslöser_wert_state: text_input::State,
+    bedingung_state: pick_list::State<BedingungArt>,
+    bedingung_name_state: text_input::State,
+    bedingung_wert_state: text_input::State,
+    bedingung_button_state: button::State,
+    bedingungen_löschen_states: Vec<button::State>,
+    aktion_state: pick_list::State<AktionArt>,
+    aktion_name_state: text_input::State,
+    aktion_wert_state: text_input::State,
+    aktion_verzögerung_state: text_input::State,
+    aktion_button_state: button::State,
+    schritte_löschen_states: Vec<button::State>,
+    neu_button_state: button::State,
+    regeln: BTreeMap<Name, (String, button::State)>,
+    scrollable_state: scrollable::State,
+}
+
+impl AuswahlStatus {
+    /// Erzeuge einen neuen AuswahlStatus.
+    pub fn neu<'t>(regeln: impl Iterator<Item = (&'t Name, &'t Regel)>) -> Self {
+        AuswahlStatus {
+            entwurf: Entwurf::neu(),
+            name_state: text_input::State::new(),
+            auslöser_state: pick_list::State::default(),
+            auslöser_name_state: text_input::State::new(),
+            auslöser_wert_state: text_input::State::new(),
+            bedingung_state: pick_list::State::default(),
+            bedingung_name_state: text_input::State::new(),
+            bedingung_wert_state: text_input::State::new(),
+            bedingung_button_state: button::State::new(),
+            bedingungen_löschen_states: Vec::new(),
+            aktion_state: pick_list::State::default(),
+            aktion_name_state: text_input::State::new(),
+            aktion_wert_state: text_input::State::new(),
+            aktion_verzögerung_state: text_input::State::new(),
+            aktion_button_state: button::State::new(),
+            schritte_löschen_states: Vec::new(),
+            neu_button_state: button::State::new(),
+            regeln: regeln
+                .map(|(name, regel)| (name.clone(), (regel.to_string(), button::State::new())))
+                .collect(),
+            scrollable_state: scrollable::State::new(),
+        }
+    }
+
+    /// Entferne die Regel mit übergebenen Namen.
+    pub fn entferne(&mut self, name: &Name) {
+        self.regeln.remove(name);
+    }
+
+    /// Füge eine neue Regel hinzu.
+    /// Falls der Name bereits existiert wird die bisherige ersetzt.
+    pub fn hinzufügen(&mut self, name: &Name, regel: &Regel) {
+        self.regeln.insert(name.clone(), (regel.to_string(), button::State::new()));
+    }
+}
+
+#[derive(Debug, Clone)]
+enum InterneAuswahlNachricht {
+    Schließe,
+    Name(String),
+    AuslöserArt(AuslöserArt),
+    AuslöserName(String),
+    AuslöserWert(String),
+    BedingungArt(BedingungArt),
+    BedingungName(String),
+    BedingungWert(String),
+    BedingungHinzufügen,
+    BedingungLöschen(usize),
+    AktionArt(AktionArt),
+    AktionName(String),
+    AktionWert(String),
+    AktionVerzögerung(String),
+    AktionHinzufügen,
+    AktionLöschen(usize),
+    Hinzufügen,
+    Lösche(Name),
+}
+
+#[derive(Debug, Clone)]
+pub enum AuswahlNachricht {
+    Schließe,
+    Hinzufügen(Name, Regel),
+    Lösche(Name),
+}
+
+pub struct Auswahl<'a, R: Renderer + card::Renderer> {
+    card: Card<'a, InterneAuswahlNachricht, R>,
+    entwurf: &'a mut Entwurf,
+}
+
+impl<'a, R> Auswahl<'a, R>
+where
+    R: 'a
+        + Renderer
+        + text::Renderer
+        + text_input::Renderer
+        + column::Renderer
+        + row::Renderer
+        + container::Renderer
+        + button::Renderer
+        + scrollable::Renderer
+        + pick_list::Renderer
+        + card::Renderer,
+{
+    pub fn neu(
+        AuswahlStatus {
+            entwurf,
+            name_state,
+            auslöser_state,
+            auslöser_name_state,
+            auslöser_wert_state,
+            bedingung_state,
+            bedingung_name_state,
+            bedingung_wert_state,
+            bedingung_button_state,
+            bedingungen_löschen_states,
+            aktion_state,
+            aktion_name_state,
+            aktion_wert_state,
+            aktion_verzögerung_state,
+            aktion_button_state,
+            schritte_löschen_states,
+            neu_button_state,
+            regeln,
+            scrollable_state,
+        }: &'a mut AuswahlStatus,
+    ) -> Self {
+        bedingungen_löschen_states.resize_with(entwurf.bedingungen.len(), button::State::new);
+        schritte_löschen_states.resize_with(entwurf.schritte.len(), button::State::new);
+        let auslöser_name_hinweis =
+            if entwurf.auslöser_art == AuslöserArt::Takt { "-" } else { "<Name>" };
+        let auslöser = Row::new()
+            .push(Text::new("Wenn"))
+            .push(PickList::new(
+                auslöser_state,
+                AuslöserArt::ALLE,
+                Some(entwurf.auslöser_art),
+                InterneAuswahlNachricht::AuslöserArt,
+            ))
+            .push(
+                TextInput::new(
+                    auslöser_name_state,
+                    auslöser_name_hinweis,
+                    &entwurf.auslöser_name,
+                    InterneAuswahlNachricht::AuslöserName,
+                )
+                .width(Length::Units(150)),
+            )
+            .push(
+                TextInput::new(
+                    auslöser_wert_state,
+                    entwurf.auslöser_art.hinweis(),
+                    &entwurf.auslöser_wert,
+                    InterneAuswahlNachricht::AuslöserWert,
+                )
+                .width(Length::Units(250)),
+            )
+            .spacing(5);
+        let mut bedingungen = Column::new().push(
+            Row::new()
+                .push(Text::new("Falls"))
+                .push(PickList::new(
+                    bedingung_state,
+                    BedingungArt::ALLE,
+                    Some(entwurf.bedingung_art),
+                    InterneAuswahlNachricht::BedingungArt,
+                ))
+                .push(
+                    TextInput::new(
+                        bedingung_name_state,
+                        if entwurf.bedingung_art == BedingungArt::Weiche { "<Name>" } else { "-" },
+                        &entwurf.bedingung_name,
+                        InterneAuswahlNachricht::BedingungName,
+                    )
+                    .width(Length::Units(150)),
+                )
+                .push(
+                    TextInput::new(
+                        bedingung_wert_state,
+                        entwurf.bedingung_art.hinweis(),
+                        &entwurf.bedingung_wert,
+                        InterneAuswahlNachricht::BedingungWert,
+                    )
+                    .width(Length::Units(150)),
+                )
+                .push(
+                    Button::new(bedingung_button_state, Text::new("+"))
+                        .on_press(InterneAuswahlNachricht::BedingungHinzufügen),
+                )
+                .spacing(5),
+        );
+        for (i, (bedingung, löschen_state)) in
+            entwurf.bedingungen.iter().zip(bedingungen_löschen_states.iter_mut()).enumerate()
+        {
+            bedingungen = bedingungen.push(
+                Row::new().push(Text::new(bedingung.to_string())).push(
+                    Button::new(löschen_state, Text::new("X"))
+                        .on_press(InterneAuswahlNachricht::BedingungLöschen(i)),
+                ),
+            )
+        }
+        let mut schritte = Column::new().push(
+            Row::new()
+                .push(Text::new("Dann"))
+                .push(PickList::new(
+                    aktion_state,
+                    AktionArt::ALLE,
+                    Some(entwurf.aktion_art),
+                    InterneAuswahlNachricht::AktionArt,
+                ))
+                .push(
+                    TextInput::new(
+                        aktion_name_state,
+                        "<Name>",
+                        &entwurf.aktion_name,
+                        InterneAuswahlNachricht::AktionName,
+                    )
+                    .width(Length::Units(150)),
+                )
+                .push(
+                    TextInput::new(
+                        aktion_wert_state,
+                        entwurf.aktion_art.hinweis(),
+                        &entwurf.aktion_wert,
+                        InterneAuswahlNachricht::AktionWert,
+                    )
+                    .width(Length::Units(200)),
+                )
+                .push(
+                    TextInput::new(
+                        aktion_verzögerung_state,
+                        "nach <Sekunden>",
+                        &entwurf.aktion_verzögerung,
+                        InterneAuswahlNachricht::AktionVerzögerung,
+                    )
+                    .width(Length::Units(120)),
+                )
+                .push(
+                    Button::new(aktion_button_state, Text::new("+"))
+                        .on_press(InterneAuswahlNachricht::AktionHinzufügen),
+                )
+                .spacing(5),
+        );
+        for (i, (schritt, löschen_state)) in
+            entwurf.schritte.iter().zip(schritte_löschen_states.iter_mut()).enumerate()
+        {
+            schritte = schritte.push(
+                Row::new().push(Text::new(schritt.to_string())).push(
+                    Button::new(löschen_state, Text::new("X"))
+                        .on_press(InterneAuswahlNachricht::AktionLöschen(i)),
+                ),
+            )
+        }
+        let mut neu = Column::new()
+            .push(
+                TextInput::new(
+                    name_state,
+                    "<Name der Regel>",
+                    &entwurf.name,
+                    InterneAuswahlNachricht::Name,
+                )
+                .width(Length::Units(200)),
+            )
+            .push(auslöser)
+            .push(bedingungen)
+            .push(schritte)
+            .push(
+                Button::new(neu_button_state, Text::new("Hinzufügen"))
+                    .on_press(InterneAuswahlNachricht::Hinzufügen),
+            )
+            .spacing(5);
+        if let Some(fehler) = &entwurf.fehler {
+            neu = neu.push(Text::new(fehler))
+        }
+        let card = Card::new(Text::new("Regeln").width(Length::Fill), {
+            let mut scrollable = Scrollable::new(scrollable_state).push(neu).width(Length::Shrink);
+            for (name, (beschreibung, delete_state)) in regeln {
+                scrollable = scrollable.push(
+                    Row::new().push(Text::new(&format!("{}: {}", name.0, beschreibung))).push(
+                        Button::new(delete_state, Text::new("X"))
+                            .on_press(InterneAuswahlNachricht::Lösche(name.clone())),
+                    ),
+                );
+            }
+            scrollable
+        })
+        .on_close(InterneAuswahlNachricht::Schließe)
+        .width(Length::Shrink);
+        Auswahl { card, entwurf }
+    }
+}
+
+impl<'a, R: 'a + Renderer + card::Renderer> Widget<AuswahlNachricht, R> for Auswahl<'a, R> {
+    reexport_no_event_methods! {Card<'a, InterneAuswahlNachricht, R>, card, InterneAuswahlNachricht, R}
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &R,
+        clipboard: &mut dyn Clipboard,
+        messages: &mut Vec<AuswahlNachricht>,
+    ) -> event::Status {
+        let mut card_messages = Vec::new();
+        let mut status = self.card.on_event(
+            event,
+            layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            &mut card_messages,
+        );
+        let entwurf = &mut *self.entwurf;
+        for message in card_messages {
+            match message {
+                InterneAuswahlNachricht::Schließe => messages.push(AuswahlNachricht::Schließe),
+                InterneAuswahlNachricht::Name(name) => entwurf.name = name,
+                InterneAuswahlNachricht::AuslöserArt(art) => entwurf.auslöser_art = art,
+                InterneAuswahlNachricht::AuslöserName(name) => entwurf.auslöser_name = name,
+                InterneAuswahlNachricht::AuslöserWert(wert) => entwurf.auslöser_wert = wert,
+                InterneAuswahlNachricht::BedingungArt(art) => entwurf.bedingung_art = art,
+                InterneAuswahlNachricht::BedingungName(name) => entwurf.bedingung_name = name,
+                InterneAuswahlNachricht::BedingungWert(wert) => entwurf.bedingung_wert = wert,
+                InterneAuswahlNachricht::BedingungHinzufügen => match entwurf.bedingung() {
+                    Ok(bedingung) => {
+                        entwurf.bedingungen.push(bedingung);
+                        entwurf.bedingung_name.clear();
+                        entwurf.bedingung_wert.clear();
+                        entwurf.fehler = None;
+                    },
+                    Err(fehler) => entwurf.fehler = Some(fehler.to_string()),
+                },
+                InterneAuswahlNachricht::BedingungLöschen(i) => {
+                    if i < entwurf.bedingungen.len() {
+                        let _ = entwurf.bedingungen.remove(i);
+                    }
+                },
+                InterneAuswahlNachricht::AktionArt(art) => entwurf.aktion_art = art,
+                InterneAuswahlNachricht::AktionName(name) => entwurf.aktion_name = name,
+                InterneAuswahlNachricht::AktionWert(wert) => entwurf.aktion_wert = wert,
+                InterneAuswahlNachricht::AktionVerzögerung(verzögerung) => {
+                    entwurf.aktion_verzögerung = verzögerung
+                },
+                InterneAuswahlNachricht::AktionHinzufügen => match entwurf.schritt() {
+                    Ok(schritt) => {
+                        entwurf.schritte.push(schritt);
+                        entwurf.aktion_name.clear();
+                        entwurf.aktion_wert.clear();
+                        entwurf.aktion_verzögerung.clear();
+                        entwurf.fehler = None;
+                    },
+                    Err(fehler) => entwurf.fehler = Some(fehler.to_string()),
+                },
+                InterneAuswahlNachricht::AktionLöschen(i) => {
+                    if i < entwurf.schritte.len() {
+                        let _ = entwurf.schritte.remove(i);
+                    }
+                },
+                InterneAuswahlNachricht::Hinzufügen => match entwurf.regel() {
+                    Ok((name, regel)) => {
+                        messages.push(AuswahlNachricht::Hinzufügen(name, regel));
+                        *entwurf = Entwurf::neu();
+                    },
+                    Err(fehler) => entwurf.fehler = Some(fehler.to_string()),
+                },
+                InterneAuswahlNachricht::Lösche(name) => {
+                    messages.push(AuswahlNachricht::Lösche(name))
+                },
+            }
+            status = event::Status::Captured;
+        }
+        status
+    }
+}
+
+impl<'a, R: 'a + Renderer + card::Renderer> From<Auswahl<'a, R>>
+    for Element<'a, AuswahlNachricht, R>
+{
+    fn from(auswahl: Auswahl<'a, R>) -> Self {
+        Element::new(auswahl)
+    }
+}
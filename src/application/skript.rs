@@ -0,0 +1,251 @@
+//! Anzeige, Hinzufügen und Starten von Skripten.
+
+use std::collections::BTreeMap;
+
+use iced_aw::native::{card, Card};
+use iced_native::{
+    button, column, container, event, row, scrollable, text, text_input, Button, Clipboard, Column,
+    Element, Event, Layout, Length, Point, Renderer, Row, Scrollable, Text, TextInput, Widget,
+};
+
+use super::macros::reexport_no_event_methods;
+pub use crate::steuerung::skript::{prüfe, Aktion, Ausführung, Ende, Name};
+
+#[derive(Debug)]
+struct Eintrag {
+    beschreibung: String,
+    aktiv: bool,
+    starten_state: button::State,
+    löschen_state: button::State,
+}
+
+impl Eintrag {
+    fn neu(quelltext: &str, aktiv: bool) -> Self {
+        Eintrag {
+            beschreibung: format!("{} Zeilen", quelltext.lines().count()),
+            aktiv,
+            starten_state: button::State::new(),
+            löschen_state: button::State::new(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AuswahlStatus {
+    neu_name: String,
+    neu_pfad: String,
+    neu_name_state: text_input::State,
+    neu_pfad_state: text_input::State,
+    neu_button_state: button::State,
+    skripte: BTreeMap<Name, Eintrag>,
+    scrollable_state: scrollable::State,
+}
+
+impl AuswahlStatus {
+    /// Erzeuge einen neuen AuswahlStatus, `aktiv` gibt an, ob ein Skript gerade läuft.
+    pub fn neu<'t>(
+        skripte: impl Iterator<Item = (&'t Name, &'t String)>,
+        aktiv: impl Fn(&Name) -> bool,
+    ) -> Self {
+        AuswahlStatus {
+            neu_name: String::new(),
+            neu_pfad: String::new(),
+            neu_name_state: text_input::State::new(),
+            neu_pfad_state: text_input::State::new(),
+            neu_button_state: button::State::new(),
+            skripte: skripte
+                .map(|(name, quelltext)| (name.clone(), Eintrag::neu(quelltext, aktiv(name))))
+                .collect(),
+            scrollable_state: scrollable::State::new(),
+        }
+    }
+
+    /// Entferne das Skript mit übergebenen Namen.
+    pub fn entferne(&mut self, name: &Name) {
+        self.skripte.remove(name);
+    }
+
+    /// Füge ein neues Skript hinzu.
+    /// Falls der Name bereits existiert wird das bisherige ersetzt.
+    pub fn hinzufügen(&mut self, name: &Name, quelltext: &str, aktiv: bool) {
+        self.skripte.insert(name.clone(), Eintrag::neu(quelltext, aktiv));
+    }
+
+    /// Aktualisiere, ob das Skript gerade läuft.
+    pub fn setze_aktiv(&mut self, name: &Name, aktiv: bool) {
+        if let Some(eintrag) = self.skripte.get_mut(name) {
+            eintrag.aktiv = aktiv
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum InterneAuswahlNachricht {
+    Schließe,
+    Hinzufügen,
+    Lösche(Name),
+    Starte(Name),
+    Stoppe(Name),
+    Name(String),
+    Pfad(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum AuswahlNachricht {
+    Schließe,
+    /// Lese den Quelltext aus der Datei `pfad`.
+    Hinzufügen(Name, String),
+    Lösche(Name),
+    Starte(Name),
+    Stoppe(Name),
+}
+
+pub struct Auswahl<'a, R: Renderer + card::Renderer> {
+    card: Card<'a, InterneAuswahlNachricht, R>,
+    neu_name: &'a mut String,
+    neu_pfad: &'a mut String,
+}
+
+impl<'a, R> Auswahl<'a, R>
+where
+    R: 'a
+        + Renderer
+        + text::Renderer
+        + text_input::Renderer
+        + column::Renderer
+        + row::Renderer
+        + container::Renderer
+        + button::Renderer
+        + scrollable::Renderer
+        + card::Renderer,
+{
+    pub fn neu(
+        AuswahlStatus {
+            neu_name,
+            neu_pfad,
+            neu_name_state,
+            neu_pfad_state,
+            neu_button_state,
+            skripte,
+            scrollable_state,
+        }: &'a mut AuswahlStatus,
+    ) -> Self {
+        let card = Card::new(Text::new("Skripte").width(Length::Fill), {
+            let mut scrollable = Scrollable::new(scrollable_state)
+                .push(
+                    Column::new()
+                        .push(Text::new(
+                            "Der Quelltext (Rhai) wird aus der Datei gelesen und mit dem \
+                            Gleisplan gespeichert.",
+                        ))
+                        .push(
+                            Row::new()
+                                .push(
+                                    TextInput::new(
+                                        neu_name_state,
+                                        "<Name>",
+                                        neu_name,
+                                        InterneAuswahlNachricht::Name,
+                                    )
+                                    .width(Length::Units(200)),
+                                )
+                                .push(
+                                    TextInput::new(
+                                        neu_pfad_state,
+                                        "<Datei>",
+                                        neu_pfad,
+                                        InterneAuswahlNachricht::Pfad,
+                                    )
+                                    .width(Length::Units(250)),
+                                )
+                                .push(
+                                    Button::new(neu_button_state, Text::new("Hinzufügen"))
+                                        .on_press(InterneAuswahlNachricht::Hinzufügen),
+                                )
+                                .spacing(5),
+                        )
+                        .spacing(5),
+                )
+                .width(Length::Shrink);
+            for (name, Eintrag { beschreibung, aktiv, starten_state, löschen_state }) in skripte {
+                let (beschriftung, nachricht) = if *aktiv {
+                    ("Stoppen", InterneAuswahlNachricht::Stoppe(name.clone()))
+                } else {
+                    ("Starten", InterneAuswahlNachricht::Starte(name.clone()))
+                };
+                scrollable = scrollable.push(
+                    Row::new()
+                        .push(Text::new(&format!("{}: {}", name.0, beschreibung)))
+                        .push(
+                            Button::new(starten_state, Text::new(beschriftung)).on_press(nachricht),
+                        )
+                        .push(
+                            Button::new(löschen_state, Text::new("X"))
+                                .on_press(InterneAuswahlNachricht::Lösche(name.clone())),
+                        )
+                        .spacing(5),
+                );
+            }
+            scrollable
+        })
+        .on_close(InterneAuswahlNachricht::Schließe)
+        .width(Length::Shrink);
+        Auswahl { card, neu_name, neu_pfad }
+    }
+}
+
+impl<'a, R: 'a + Renderer + card::Renderer> Widget<AuswahlNachricht, R> for Auswahl<'a, R> {
+    reexport_no_event_methods! {Card<'a, InterneAuswahlNachricht, R>, card, InterneAuswahlNachricht, R}
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &R,
+        clipboard: &mut dyn Clipboard,
+        messages: &mut Vec<AuswahlNachricht>,
+    ) -> event::Status {
+        let mut card_messages = Vec::new();
+        let mut status = self.card.on_event(
+            event,
+            layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            &mut card_messages,
+        );
+        for message in card_messages {
+            match message {
+                InterneAuswahlNachricht::Schließe => messages.push(AuswahlNachricht::Schließe),
+                InterneAuswahlNachricht::Hinzufügen => {
+                    messages.push(AuswahlNachricht::Hinzufügen(
+                        Name(self.neu_name.clone()),
+                        self.neu_pfad.clone(),
+                    ))
+                },
+                InterneAuswahlNachricht::Lösche(name) => {
+                    messages.push(AuswahlNachricht::Lösche(name))
+                },
+                InterneAuswahlNachricht::Starte(name) => {
+                    messages.push(AuswahlNachricht::Starte(name))
+                },
+                InterneAuswahlNachricht::Stoppe(name) => {
+                    messages.push(AuswahlNachricht::Stoppe(name))
+                },
+                InterneAuswahlNachricht::Name(name) => *self.neu_name = name,
+                InterneAuswahlNachricht::Pfad(pfad) => *self.neu_pfad = pfad,
+            }
+            status = event::Status::Captured;
+        }
+        status
+    }
+}
+
+impl<'a, R: 'a + Renderer + card::Renderer> From<Auswahl<'a, R>>
+    for Element<'a, AuswahlNachricht, R>
+{
+    fn from(auswahl: Auswahl<'a, R>) -> Self {
+        Element::new(auswahl)
+    }
+}
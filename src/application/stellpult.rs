@@ -0,0 +1,339 @@
+//! Anzeige & Erstellen der Taster eines Stellpults.
+
+use std::collections::BTreeMap;
+
+use iced_aw::native::{card, number_input, tab_bar, tabs, Card};
+use iced_native::{
+    button, column, container, event, pick_list, radio, row, scrollable, text, text_input, Button,
+    Clipboard, Column, Element, Event, Layout, Length, PickList, Point, Radio, Renderer, Row,
+    Scrollable, Text, TextInput, Widget,
+};
+
+use super::{anschluss, macros::reexport_no_event_methods, style::tab_bar::TabBar};
+use crate::anschluss::{
+    anschlüsse::Reservierungen, mcp23017, pcf8574, InputSave, ToSave, Trigger,
+};
+pub use crate::steuerung::stellpult::{lese_fahrstraße, Aktion, Map, Name, Taster, TasterSave};
+
+#[derive(Debug)]
+pub struct AuswahlStatus {
+    neu_name: String,
+    neu_anschluss: InputSave,
+    neu_name_state: text_input::State,
+    neu_anschluss_state: anschluss::Status<anschluss::Input>,
+    neu_trigger: Trigger,
+    neu_aktion: Option<Aktion>,
+    aktionen: Vec<Aktion>,
+    aktion_state: pick_list::State<Aktion>,
+    neu_fahrstraße: String,
+    neu_fahrstraße_state: text_input::State,
+    fahrstraße_fehler: Option<String>,
+    neu_button_state: button::State,
+    taster: BTreeMap<Name, (String, button::State)>,
+    scrollable_state: scrollable::State,
+}
+
+impl AuswahlStatus {
+    /// Erzeuge einen neuen AuswahlStatus.
+    ///
+    /// `aktionen` sind alle Aktionen, die einem neuen Taster zugewiesen werden können.
+    pub fn neu<'t>(
+        taster: impl Iterator<Item = (&'t Name, &'t Taster)>,
+        aktionen: Vec<Aktion>,
+    ) -> Self {
+        let mut interrupt_pins = anschluss::InterruptPins::new();
+        let taster: BTreeMap<_, _> = taster
+            .inspect(|(_name, taster)| match taster.anschluss.to_save() {
+                InputSave::Pcf8574Port {
+                    bus, a0, a1, a2, variante, interrupt: Some(pin), ..
+                } => {
+                    interrupt_pins.insert(
+                        anschluss::InterruptBaustein::Pcf8574(pcf8574::Adresse {
+                            bus,
+                            a0,
+                            a1,
+                            a2,
+                            variante,
+                        }),
+                        pin,
+                    );
+                }
+                InputSave::Mcp23017Port { bus, a0, a1, a2, port, interrupt: Some(pin), .. } => {
+                    interrupt_pins.insert(
+                        anschluss::InterruptBaustein::Mcp23017(
+                            mcp23017::Adresse { bus, a0, a1, a2 },
+                            mcp23017::Bank::von_port(port),
+                        ),
+                        pin,
+                    );
+                }
+                _ => {}
+            })
+            .map(Self::iter_map)
+            .collect();
+        AuswahlStatus {
+            neu_name: String::new(),
+            neu_anschluss: InputSave::Pin { pin: 0 },
+            neu_name_state: text_input::State::new(),
+            neu_anschluss_state: anschluss::Status::neu_input(interrupt_pins),
+            neu_trigger: Trigger::FallingEdge,
+            neu_aktion: aktionen.first().cloned(),
+            aktionen,
+            aktion_state: pick_list::State::default(),
+            neu_fahrstraße: String::new(),
+            neu_fahrstraße_state: text_input::State::new(),
+            fahrstraße_fehler: None,
+            neu_button_state: button::State::new(),
+            taster,
+            scrollable_state: scrollable::State::new(),
+        }
+    }
+
+    fn iter_map<'t>((name, taster): (&'t Name, &'t Taster)) -> (Name, (String, button::State)) {
+        (
+            name.clone(),
+            (
+                format!("{} ({}): {}", taster.anschluss, taster.trigger, taster.aktion),
+                button::State::new(),
+            ),
+        )
+    }
+
+    /// Entferne den Taster mit übergebenen Namen.
+    pub fn entferne(&mut self, name: &Name) {
+        self.taster.remove(name);
+    }
+
+    /// Füge einen neuen Taster hinzu.
+    /// Falls der Name bereits existiert wird der bisherige ersetzt.
+    pub fn hinzufügen(&mut self, name: &Name, taster: &Taster) {
+        let (key, value) = Self::iter_map((name, taster));
+        self.taster.insert(key, value);
+    }
+
+    /// Aktualisiere die Hinweise auf bereits reservierte Anschlüsse.
+    pub fn setze_reservierungen(&mut self, reservierungen: &Reservierungen) {
+        self.neu_anschluss_state.setze_reservierungen(reservierungen)
+    }
+}
+
+#[derive(Debug, Clone)]
+enum InterneAuswahlNachricht {
+    Schließe,
+    Hinzufügen,
+    Lösche(Name),
+    Name(String),
+    Anschluss(InputSave),
+    Trigger(Trigger),
+    Aktion(Aktion),
+    Fahrstraße(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum AuswahlNachricht {
+    Schließe,
+    Hinzufügen(Name, TasterSave),
+    Lösche(Name),
+}
+
+pub struct Auswahl<'a, R: Renderer + card::Renderer> {
+    card: Card<'a, InterneAuswahlNachricht, R>,
+    neu_name: &'a mut String,
+    neu_anschluss: &'a mut InputSave,
+    neu_trigger: &'a mut Trigger,
+    neu_aktion: &'a mut Option<Aktion>,
+    neu_fahrstraße: &'a mut String,
+    fahrstraße_fehler: &'a mut Option<String>,
+}
+
+impl<'a, R> Auswahl<'a, R>
+where
+    R: 'a
+        + Renderer
+        + text::Renderer
+        + text_input::Renderer
+        + radio::Renderer
+        + column::Renderer
+        + row::Renderer
+        + container::Renderer
+        + button::Renderer
+        + scrollable::Renderer
+        + pick_list::Renderer
+        + number_input::Renderer
+        + tabs::Renderer
+        + card::Renderer,
+    <R as tab_bar::Renderer>::Style: From<TabBar>,
+{
+    pub fn neu(
+        AuswahlStatus {
+            neu_name,
+            neu_anschluss,
+            neu_name_state,
+            neu_anschluss_state,
+            neu_trigger,
+            neu_aktion,
+            aktionen,
+            aktion_state,
+            neu_fahrstraße,
+            neu_fahrstraße_state,
+            fahrstraße_fehler,
+            neu_button_state,
+            taster,
+            scrollable_state,
+        }: &'a mut AuswahlStatus,
+    ) -> Self {
+        let trigger_radios = [Trigger::FallingEdge, Trigger::RisingEdge, Trigger::Both]
+            .iter()
+            .fold(Column::new(), |column, trigger| {
+                column.push(Radio::new(
+                    *trigger,
+                    trigger.to_string(),
+                    Some(*neu_trigger),
+                    InterneAuswahlNachricht::Trigger,
+                ))
+            });
+        let card = Card::new(Text::new("Stellpult").width(Length::Fill), {
+            let mut scrollable = Scrollable::new(scrollable_state)
+                .push(
+                    Column::new()
+                        .push(
+                            Row::new()
+                                .push(
+                                    TextInput::new(
+                                        neu_name_state,
+                                        "<Name>",
+                                        neu_name,
+                                        InterneAuswahlNachricht::Name,
+                                    )
+                                    .width(Length::Units(200)),
+                                )
+                                .push(
+                                    Element::from(anschluss::Auswahl::neu_input(
+                                        neu_anschluss_state,
+                                    ))
+                                    .map(InterneAuswahlNachricht::Anschluss),
+                                )
+                                .push(trigger_radios),
+                        )
+                        .push(
+                            Row::new()
+                                .push(PickList::new(
+                                    aktion_state,
+                                    &aktionen[..],
+                                    neu_aktion.clone(),
+                                    InterneAuswahlNachricht::Aktion,
+                                ))
+                                .push(
+                                    TextInput::new(
+                                        neu_fahrstraße_state,
+                                        "<Fahrstraße: Weiche=Richtung, …>",
+                                        neu_fahrstraße,
+                                        InterneAuswahlNachricht::Fahrstraße,
+                                    )
+                                    .width(Length::Units(300)),
+                                ),
+                        )
+                        .push(Text::new(fahrstraße_fehler.as_deref().unwrap_or("")))
+                        .push(
+                            Button::new(neu_button_state, Text::new("Hinzufügen"))
+                                .on_press(InterneAuswahlNachricht::Hinzufügen),
+                        ),
+                )
+                .width(Length::Shrink);
+            for (name, (beschreibung, delete_state)) in taster {
+                scrollable = scrollable.push(
+                    Row::new().push(Text::new(&format!("{}: {}", name.0, beschreibung))).push(
+                        Button::new(delete_state, Text::new("X"))
+                            .on_press(InterneAuswahlNachricht::Lösche(name.clone())),
+                    ),
+                );
+            }
+            scrollable
+        })
+        .on_close(InterneAuswahlNachricht::Schließe)
+        .width(Length::Shrink);
+        Auswahl {
+            card,
+            neu_name,
+            neu_anschluss,
+            neu_trigger,
+            neu_aktion,
+            neu_fahrstraße,
+            fahrstraße_fehler,
+        }
+    }
+}
+
+impl<'a, R: 'a + Renderer + card::Renderer> Widget<AuswahlNachricht, R> for Auswahl<'a, R> {
+    reexport_no_event_methods! {Card<'a, InterneAuswahlNachricht, R>, card, InterneAuswahlNachricht, R}
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &R,
+        clipboard: &mut dyn Clipboard,
+        messages: &mut Vec<AuswahlNachricht>,
+    ) -> event::Status {
+        let mut card_messages = Vec::new();
+        let mut status = self.card.on_event(
+            event,
+            layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            &mut card_messages,
+        );
+        for message in card_messages {
+            match message {
+                InterneAuswahlNachricht::Schließe => messages.push(AuswahlNachricht::Schließe),
+                InterneAuswahlNachricht::Hinzufügen => {
+                    // Eine eingegebene Fahrstraße hat Vorrang vor der ausgewählten Aktion.
+                    let aktion = if self.neu_fahrstraße.trim().is_empty() {
+                        self.neu_aktion.clone()
+                    } else {
+                        match lese_fahrstraße(self.neu_fahrstraße.as_str()) {
+                            Ok(weichen) => Some(Aktion::FahrstraßeStellen(weichen)),
+                            Err(fehler) => {
+                                *self.fahrstraße_fehler = Some(fehler);
+                                None
+                            }
+                        }
+                    };
+                    if let Some(aktion) = aktion {
+                        *self.fahrstraße_fehler = None;
+                        messages.push(AuswahlNachricht::Hinzufügen(
+                            Name(self.neu_name.clone()),
+                            Taster {
+                                anschluss: self.neu_anschluss.clone(),
+                                trigger: *self.neu_trigger,
+                                aktion,
+                            },
+                        ))
+                    }
+                }
+                InterneAuswahlNachricht::Lösche(name) => {
+                    messages.push(AuswahlNachricht::Lösche(name))
+                }
+                InterneAuswahlNachricht::Name(name) => *self.neu_name = name,
+                InterneAuswahlNachricht::Anschluss(anschluss) => *self.neu_anschluss = anschluss,
+                InterneAuswahlNachricht::Trigger(trigger) => *self.neu_trigger = trigger,
+                InterneAuswahlNachricht::Aktion(aktion) => *self.neu_aktion = Some(aktion),
+                InterneAuswahlNachricht::Fahrstraße(fahrstraße) => {
+                    *self.neu_fahrstraße = fahrstraße
+                }
+            }
+            status = event::Status::Captured;
+        }
+        status
+    }
+}
+
+impl<'a, R: 'a + Renderer + card::Renderer> From<Auswahl<'a, R>>
+    for Element<'a, AuswahlNachricht, R>
+{
+    fn from(auswahl: Auswahl<'a, R>) -> Self {
+        Element::new(auswahl)
+    }
+}
@@ -12,7 +12,7 @@ use iced_native::{
 use super::{
     anschluss, farbwahl::Farbwahl, macros::reexport_no_event_methods, style::tab_bar::TabBar,
 };
-use crate::anschluss::{polarity::Polarität, OutputSave};
+use crate::anschluss::{anschlüsse::Reservierungen, polarity::Polarität, OutputSave};
 use crate::farbe::Farbe;
 pub use crate::steuerung::streckenabschnitt::{Map, Name, Streckenabschnitt};
 
@@ -170,6 +170,11 @@ impl AuswahlStatus {
         self.streckenabschnitte.insert(key, value);
     }
 
+    /// Aktualisiere die Hinweise auf bereits reservierte Anschlüsse.
+    pub fn setze_reservierungen(&mut self, reservierungen: &Reservierungen) {
+        self.neu_anschluss_state.setze_reservierungen(reservierungen)
+    }
+
     pub fn streckenabschnitt(&self) -> (Name, Farbe, OutputSave) {
         (Name(self.neu_name.clone()), self.neu_farbe, self.neu_anschluss_state.output_anschluss())
     }
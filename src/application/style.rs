@@ -2,6 +2,7 @@
 
 pub mod background;
 pub mod border;
+pub mod button;
 pub mod rule;
 pub mod scrollable;
 pub mod tab_bar;
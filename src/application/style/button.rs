@@ -0,0 +1,31 @@
+//! Style Strukturen für einen iced::Button
+
+/// Auffälliger Knopf zum Auslösen des Nothalts.
+pub struct Nothalt;
+impl iced::button::StyleSheet for Nothalt {
+    fn active(&self) -> iced::button::Style {
+        iced::button::Style {
+            background: Some(iced::Background::Color(iced::Color::from_rgb(0.8, 0., 0.))),
+            border_radius: 5.,
+            border_width: 2.,
+            border_color: iced::Color::BLACK,
+            text_color: iced::Color::WHITE,
+            ..Default::default()
+        }
+    }
+}
+
+/// Knopf zum Aufheben eines aktiven Nothalts.
+pub struct NothaltAufheben;
+impl iced::button::StyleSheet for NothaltAufheben {
+    fn active(&self) -> iced::button::Style {
+        iced::button::Style {
+            background: Some(iced::Background::Color(iced::Color::from_rgb(1., 0.8, 0.))),
+            border_radius: 5.,
+            border_width: 2.,
+            border_color: iced::Color::BLACK,
+            text_color: iced::Color::BLACK,
+            ..Default::default()
+        }
+    }
+}
@@ -27,7 +27,11 @@ impl<'t> Frame<'t> {
     }
 
     /// Draws the stroke of the given Path on the Frame with the provided style.
-    pub fn stroke(&mut self, Pfad { pfad, transformationen }: &Pfad, stroke: impl Into<Stroke>) {
+    pub fn stroke(
+        &mut self,
+        Pfad { pfad, transformationen, .. }: &Pfad,
+        stroke: impl Into<Stroke>,
+    ) {
         self.with_save(|frame| {
             for transformation in transformationen {
                 frame.transformation(transformation)
@@ -37,7 +41,7 @@ impl<'t> Frame<'t> {
     }
 
     /// Draws the given Path on the Frame by filling it with the provided style.
-    pub fn fill(&mut self, Pfad { pfad, transformationen }: &Pfad, fill: impl Into<Fill>) {
+    pub fn fill(&mut self, Pfad { pfad, transformationen, .. }: &Pfad, fill: impl Into<Fill>) {
         self.with_save(|frame| {
             for transformation in transformationen {
                 frame.transformation(transformation)
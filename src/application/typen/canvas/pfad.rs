@@ -1,6 +1,6 @@
 //! Pfad auf einem Canvas und assoziierte Typen
 
-use std::marker::PhantomData;
+use std::{fmt::Write, marker::PhantomData};
 
 use zugkontrolle_derive::chain;
 
@@ -16,6 +16,8 @@ use crate::application::typen::{
 pub struct Pfad {
     pub(crate) pfad: iced::canvas::Path,
     pub(crate) transformationen: Vec<Transformation>,
+    /// Pfad-Daten im SVG-Format (Attribut `d`).
+    pub(crate) svg: String,
 }
 
 impl Pfad {
@@ -29,6 +31,28 @@ impl Pfad {
             .close_chain()
             .baue_unter_transformationen(transformationen)
     }
+
+    /// Erzeuge ein SVG-`path`-Element mit den zusätzlichen /attributen/.
+    pub fn svg(&self, attribute: &str) -> String {
+        let mut transform = String::new();
+        for transformation in &self.transformationen {
+            let _ = match transformation {
+                Transformation::Translation(Vektor { x, y }) => {
+                    write!(transform, "translate({} {}) ", x.0, y.0)
+                },
+                Transformation::Rotation(winkel) => {
+                    write!(transform, "rotate({}) ", winkel.0.to_degrees())
+                },
+                Transformation::Skalieren(skalar) => write!(transform, "scale({}) ", skalar.0),
+            };
+        }
+        format!(
+            "<path d=\"{}\" transform=\"{}\" {}/>",
+            self.svg.trim_end(),
+            transform.trim_end(),
+            attribute
+        )
+    }
 }
 
 /// Unterstützte Transformationen.
@@ -121,13 +145,18 @@ where
 /// Alle Methoden verwenden die hier definierten Typen.
 pub struct Erbauer<V, B> {
     builder: iced::canvas::path::Builder,
+    svg: String,
     phantom_data: PhantomData<*const (V, B)>,
 }
 
 impl Erbauer<Vektor, Bogen> {
     /// Erstelle einen neuen Erbauer.
     pub fn neu() -> Self {
-        Erbauer { builder: iced::canvas::path::Builder::new(), phantom_data: PhantomData }
+        Erbauer {
+            builder: iced::canvas::path::Builder::new(),
+            svg: String::new(),
+            phantom_data: PhantomData,
+        }
     }
 
     /// Finalisiere the Pfad und erzeuge den unveränderlichen Pfad.
@@ -138,7 +167,7 @@ impl Erbauer<Vektor, Bogen> {
     /// Finalisiere the Pfad und erzeuge den unveränderlichen Pfad
     /// nach Anwendung der übergebenen Transformationen.
     pub fn baue_unter_transformationen(self, transformationen: Vec<Transformation>) -> Pfad {
-        Pfad { pfad: self.builder.build(), transformationen }
+        Pfad { pfad: self.builder.build(), transformationen, svg: self.svg }
     }
 }
 
@@ -147,6 +176,7 @@ impl<V: Into<Vektor>, B: Into<Bogen>> Erbauer<V, B> {
     #[chain]
     pub fn move_to(&mut self, punkt: V) {
         let Vektor { x, y } = punkt.into();
+        let _ = write!(self.svg, "M {} {} ", x.0, y.0);
         self.builder.move_to(iced::Point { x: x.0, y: y.0 })
     }
 
@@ -154,6 +184,7 @@ impl<V: Into<Vektor>, B: Into<Bogen>> Erbauer<V, B> {
     #[chain]
     pub fn line_to(&mut self, ziel: V) {
         let Vektor { x, y } = ziel.into();
+        let _ = write!(self.svg, "L {} {} ", x.0, y.0);
         self.builder.line_to(iced::Point { x: x.0, y: y.0 })
     }
 
@@ -163,6 +194,25 @@ impl<V: Into<Vektor>, B: Into<Bogen>> Erbauer<V, B> {
     #[chain]
     pub fn arc(&mut self, bogen: B) {
         let Bogen { zentrum: Vektor { x, y }, radius, anfang, ende } = bogen.into();
+        // SVG-Bögen werden über Anfangs- und Endpunkt definiert,
+        // durch Aufteilen in zwei Hälften sind auch Vollkreise möglich.
+        let punkt = |winkel: f32| (x.0 + radius.0 * winkel.cos(), y.0 + radius.0 * winkel.sin());
+        let (anfang_x, anfang_y) = punkt(anfang.0);
+        let (mitte_x, mitte_y) = punkt(0.5 * (anfang.0 + ende.0));
+        let (ende_x, ende_y) = punkt(ende.0);
+        let richtung = if ende.0 > anfang.0 { 1 } else { 0 };
+        let _ = write!(
+            self.svg,
+            "M {} {} A {r} {r} 0 0 {s} {} {} A {r} {r} 0 0 {s} {} {} ",
+            anfang_x,
+            anfang_y,
+            mitte_x,
+            mitte_y,
+            ende_x,
+            ende_y,
+            r = radius.0,
+            s = richtung
+        );
         self.builder.arc(iced::canvas::path::Arc {
             center: iced::Point { x: x.0, y: y.0 },
             radius: radius.0,
@@ -193,6 +243,7 @@ impl<V: Into<Vektor>, B: Into<Bogen>> Erbauer<V, B> {
     /// Zeichne eine Linie vom aktuellen Punkt zum start des aktuellen Unterpfades.
     #[chain]
     pub fn close(&mut self) {
+        self.svg.push_str("Z ");
         self.builder.close()
     }
 
@@ -210,10 +261,12 @@ impl<V: Into<Vektor>, B: Into<Bogen>> Erbauer<V, B> {
         &mut self,
         action: impl for<'s> FnOnce(&'s mut Erbauer<Invertiert<V, X>, Invertiert<B, X>>),
     ) {
+        let svg = &mut self.svg;
         take_mut::take(&mut self.builder, |builder| {
             let mut inverted_builder: Erbauer<Invertiert<V, X>, Invertiert<B, X>> =
-                Erbauer { builder, phantom_data: PhantomData };
+                Erbauer { builder, svg: std::mem::take(svg), phantom_data: PhantomData };
             action(&mut inverted_builder);
+            *svg = inverted_builder.svg;
             inverted_builder.builder
         })
     }
@@ -224,10 +277,12 @@ impl<V: Into<Vektor>, B: Into<Bogen>> Erbauer<V, B> {
         &mut self,
         action: impl for<'s> FnOnce(&'s mut Erbauer<Invertiert<V, Y>, Invertiert<B, Y>>),
     ) {
+        let svg = &mut self.svg;
         take_mut::take(&mut self.builder, |builder| {
             let mut inverted_builder: Erbauer<Invertiert<V, Y>, Invertiert<B, Y>> =
-                Erbauer { builder, phantom_data: PhantomData };
+                Erbauer { builder, svg: std::mem::take(svg), phantom_data: PhantomData };
             action(&mut inverted_builder);
+            *svg = inverted_builder.svg;
             inverted_builder.builder
         })
     }
@@ -0,0 +1,86 @@
+//! Steuerung im Browser, die Seite verwendet die WebSocket-Schnittstelle aus [netzwerk](super::netzwerk).
+//!
+//! Der HTTP-Server liefert nur die (statische) Seite aus, Gleisplan und Zustand werden
+//! anschließend über die WebSocket-Verbindung abgefragt.
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use log::{debug, warn};
+
+use super::netzwerk::{mit_standard_host, warne_falls_erreichbar};
+
+/// Maximale Wartezeit auf eine Anfrage.
+const LESE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Inhalt der ausgelieferten Seite, `{netzwerk_port}` wird vor dem Senden ersetzt.
+const SEITE: &str = include_str!("web/seite.html");
+
+/// Starte einen HTTP-Server an `adresse`, der die Steuerungs-Seite ausliefert.
+///
+/// Die Seite verbindet sich mit dem WebSocket-Server an Port von `netzwerk` des gleichen Rechners.
+/// Besteht `adresse` nur aus einem Port wird wie bei der Netzwerk-Steuerung an `127.0.0.1` gebunden.
+pub fn starte(adresse: &str, netzwerk: SocketAddr) -> io::Result<()> {
+    let listener = TcpListener::bind(mit_standard_host(adresse))?;
+    let adresse = listener.local_addr()?;
+    warne_falls_erreichbar("Die Browser-Steuerung", adresse);
+    if !adresse.ip().is_loopback() && netzwerk.ip().is_loopback() {
+        warn!(
+            "Die Browser-Steuerung an {} kann von anderen Rechnern aus die Netzwerk-Steuerung an {} \
+            nicht erreichen!",
+            adresse, netzwerk
+        )
+    }
+    let seite = SEITE.replace("{netzwerk_port}", &netzwerk.port().to_string());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    if let Err(fehler) = beantworte(stream, &seite) {
+                        warn!("Fehler bei einer HTTP-Anfrage: {}", fehler)
+                    }
+                },
+                Err(fehler) => warn!("Verbindung nicht angenommen: {}", fehler),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Lese eine HTTP-Anfrage und sende die Seite oder einen Fehler-Status.
+fn beantworte(stream: TcpStream, seite: &str) -> io::Result<()> {
+    stream.set_read_timeout(Some(LESE_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut anfrage_zeile = String::new();
+    reader.read_line(&mut anfrage_zeile)?;
+    // restliche Header werden ignoriert
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear()
+    }
+    debug!("HTTP-Anfrage: {}", anfrage_zeile.trim_end());
+    let mut teile = anfrage_zeile.split_whitespace();
+    let (status, content_type, inhalt) = match (teile.next(), teile.next()) {
+        (Some("GET"), Some("/")) | (Some("GET"), Some("/index.html")) => {
+            ("200 OK", "text/html; charset=utf-8", seite)
+        },
+        (Some("GET"), Some(_pfad)) => {
+            ("404 Not Found", "text/plain; charset=utf-8", "Nicht gefunden")
+        },
+        _ => ("405 Method Not Allowed", "text/plain; charset=utf-8", "Nicht unterstützt"),
+    };
+    let mut stream = &stream;
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        inhalt.len(),
+        inhalt
+    )?;
+    stream.flush()
+}
@@ -9,7 +9,7 @@ use iced_native::{
 };
 
 use crate::{
-    anschluss::OutputSave,
+    anschluss::{anschlüsse::Reservierungen, OutputSave},
     application::{anschluss, macros::reexport_no_event_methods, style::tab_bar::TabBar},
     lookup::Lookup,
     steuerung::weiche::{Name, Weiche},
@@ -46,6 +46,18 @@ where
     }
 }
 
+impl<AnschlüsseSave, AnschlüsseAuswahlStatus> Status<AnschlüsseSave, AnschlüsseAuswahlStatus> {
+    /// Aktualisiere die Hinweise auf bereits reservierte Anschlüsse.
+    pub fn setze_reservierungen<Richtung>(&mut self, reservierungen: &Reservierungen)
+    where
+        AnschlüsseAuswahlStatus: Lookup<Richtung, anschluss::Status<anschluss::Output>>,
+    {
+        for (_richtung, anschluss_status) in self.anschlüsse_state.mut_refs() {
+            anschluss_status.setze_reservierungen(reservierungen)
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 enum InterneNachricht<Richtung> {
     Name(String),
@@ -0,0 +1,218 @@
+//! Eingabe einer Zuordnung bisheriger zu neuen Anschlüssen.
+
+use iced_aw::native::{card, Card};
+use iced_native::{
+    button, column, container, event, row, scrollable, text, text_input, Button, Clipboard, Column,
+    Element, Event, Layout, Length, Point, Renderer, Row, Scrollable, Text, TextInput, Widget,
+};
+
+use super::macros::reexport_no_event_methods;
+use crate::anschluss::zuordnung::Zuordnung;
+
+#[derive(Debug, Default)]
+struct EintragState {
+    quelle_state: text_input::State,
+    ziel_state: text_input::State,
+    entfernen_button_state: button::State,
+}
+
+#[derive(Debug)]
+pub struct Status {
+    einträge: Vec<(String, String)>,
+    eintrag_states: Vec<EintragState>,
+    fehler: Option<String>,
+    hinzufügen_button_state: button::State,
+    anwenden_button_state: button::State,
+    scrollable_state: scrollable::State,
+}
+
+impl Status {
+    /// Beginne mit den Einträgen von `zuordnung`, bzw. einem leeren Eintrag.
+    pub fn neu(zuordnung: &Zuordnung) -> Self {
+        let mut einträge: Vec<_> = zuordnung
+            .einträge()
+            .iter()
+            .map(|(quelle, ziel)| (quelle.to_string(), ziel.to_string()))
+            .collect();
+        if einträge.is_empty() {
+            einträge.push((String::new(), String::new()))
+        }
+        Status {
+            einträge,
+            eintrag_states: Vec::new(),
+            fehler: None,
+            hinzufügen_button_state: button::State::new(),
+            anwenden_button_state: button::State::new(),
+            scrollable_state: scrollable::State::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum InterneNachricht {
+    Schließe,
+    Quelle(usize, String),
+    Ziel(usize, String),
+    Hinzufügen,
+    Entfernen(usize),
+    Anwenden,
+}
+
+#[derive(Debug, Clone)]
+pub enum Nachricht {
+    Schließe,
+    Anwenden(Zuordnung),
+}
+
+pub struct Auswahl<'a, R: Renderer + card::Renderer> {
+    card: Card<'a, InterneNachricht, R>,
+    einträge: &'a mut Vec<(String, String)>,
+    fehler: &'a mut Option<String>,
+}
+
+impl<'a, R> Auswahl<'a, R>
+where
+    R: 'a
+        + Renderer
+        + text::Renderer
+        + text_input::Renderer
+        + column::Renderer
+        + row::Renderer
+        + container::Renderer
+        + button::Renderer
+        + scrollable::Renderer
+        + card::Renderer,
+{
+    pub fn neu(
+        Status {
+            einträge,
+            eintrag_states,
+            fehler,
+            hinzufügen_button_state,
+            anwenden_button_state,
+            scrollable_state,
+        }: &'a mut Status,
+    ) -> Self {
+        eintrag_states.resize_with(einträge.len(), EintragState::default);
+        let mut column = Column::new()
+            .push(Text::new("Bisheriger Anschluss = neuer Anschluss, z.B. pin:17 = pcf8574:0x21:3"))
+            .spacing(5);
+        for (index, ((quelle, ziel), eintrag_state)) in
+            einträge.iter().zip(eintrag_states.iter_mut()).enumerate()
+        {
+            let EintragState { quelle_state, ziel_state, entfernen_button_state } = eintrag_state;
+            column = column.push(
+                Row::new()
+                    .push(
+                        TextInput::new(quelle_state, "<bisher>", quelle, move |quelle| {
+                            InterneNachricht::Quelle(index, quelle)
+                        })
+                        .width(Length::Units(200)),
+                    )
+                    .push(Text::new("="))
+                    .push(
+                        TextInput::new(ziel_state, "<neu>", ziel, move |ziel| {
+                            InterneNachricht::Ziel(index, ziel)
+                        })
+                        .width(Length::Units(200)),
+                    )
+                    .push(
+                        Button::new(entfernen_button_state, Text::new("X"))
+                            .on_press(InterneNachricht::Entfernen(index)),
+                    )
+                    .spacing(5),
+            );
+        }
+        column = column.push(
+            Row::new()
+                .push(
+                    Button::new(hinzufügen_button_state, Text::new("Hinzufügen"))
+                        .on_press(InterneNachricht::Hinzufügen),
+                )
+                .push(
+                    Button::new(anwenden_button_state, Text::new("Anwenden"))
+                        .on_press(InterneNachricht::Anwenden),
+                )
+                .spacing(5),
+        );
+        if let Some(text) = fehler.as_ref() {
+            column = column.push(Text::new(text.clone()))
+        }
+        let card = Card::new(
+            Text::new("Anschlüsse zuordnen").width(Length::Fill),
+            Scrollable::new(scrollable_state).push(column).width(Length::Shrink),
+        )
+        .on_close(InterneNachricht::Schließe)
+        .width(Length::Shrink);
+        Auswahl { card, einträge, fehler }
+    }
+}
+
+impl<'a, R: 'a + Renderer + card::Renderer> Widget<Nachricht, R> for Auswahl<'a, R> {
+    reexport_no_event_methods! {Card<'a, InterneNachricht, R>, card, InterneNachricht, R}
+
+    fn on_event(
+        &mut self,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &R,
+        clipboard: &mut dyn Clipboard,
+        messages: &mut Vec<Nachricht>,
+    ) -> event::Status {
+        let mut card_messages = Vec::new();
+        let mut status = self.card.on_event(
+            event,
+            layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            &mut card_messages,
+        );
+        for message in card_messages {
+            match message {
+                InterneNachricht::Schließe => messages.push(Nachricht::Schließe),
+                InterneNachricht::Quelle(index, quelle) => {
+                    if let Some((bisher, _ziel)) = self.einträge.get_mut(index) {
+                        *bisher = quelle
+                    }
+                },
+                InterneNachricht::Ziel(index, ziel) => {
+                    if let Some((_quelle, bisher)) = self.einträge.get_mut(index) {
+                        *bisher = ziel
+                    }
+                },
+                InterneNachricht::Hinzufügen => self.einträge.push((String::new(), String::new())),
+                InterneNachricht::Entfernen(index) => {
+                    if index < self.einträge.len() {
+                        self.einträge.remove(index);
+                    }
+                },
+                InterneNachricht::Anwenden => {
+                    let text = self
+                        .einträge
+                        .iter()
+                        .filter(|(quelle, ziel)| !quelle.is_empty() || !ziel.is_empty())
+                        .map(|(quelle, ziel)| format!("{} = {}", quelle, ziel))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    match text.parse() {
+                        Ok(zuordnung) => {
+                            *self.fehler = None;
+                            messages.push(Nachricht::Anwenden(zuordnung))
+                        },
+                        Err(fehler) => *self.fehler = Some(fehler),
+                    }
+                },
+            }
+            status = event::Status::Captured;
+        }
+        status
+    }
+}
+
+impl<'a, R: 'a + Renderer + card::Renderer> From<Auswahl<'a, R>> for Element<'a, Nachricht, R> {
+    fn from(auswahl: Auswahl<'a, R>) -> Self {
+        Element::new(auswahl)
+    }
+}
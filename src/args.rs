@@ -4,7 +4,7 @@ use std::str::FromStr;
 
 use argh::{EarlyExit, FromArgs, TopLevelCommand};
 use version::version;
-use zugkontrolle::application::gleis::gleise::Modus;
+use zugkontrolle::{anschluss::anschlüsse::Backend, application::gleis::gleise::Modus};
 
 #[derive(Debug)]
 struct Wrapper(pub Args);
@@ -40,10 +40,95 @@ pub struct Args {
     /// dateiname
     pub pfad: Option<String>,
 
+    #[argh(option)]
+    /// hardware-Profil mit den Anschlüssen zu den Namen im Gleisplan
+    pub profil: Option<String>,
+
     #[argh(option, short = 'm')]
     /// modus bei Programstart
     pub modus: Option<Modus>,
 
+    #[argh(option)]
+    /// gpio-pin eines Nothalt-Tasters (gegen Masse geschaltet)
+    pub nothalt_pin: Option<u8>,
+
+    #[argh(option)]
+    /// datei mit einer Zuordnung neuer Anschlüsse (Zeilen "<bisher> = <neu>"),
+    /// wird ohne Anzeige auf die Datei unter pfad angewendet
+    pub zuordnung: Option<String>,
+
+    #[argh(option)]
+    /// speicherort für das Ergebnis einer Zuordnung oder Zusammenführung,
+    /// bzw. des Profils beim Konvertieren
+    pub ausgabe: Option<String>,
+
+    #[argh(option)]
+    /// schreibe die Verkabelung des Zustands unter pfad in eine Datei
+    /// (Format abhängig von der Endung: .md, .csv, .tex)
+    pub verkabelung: Option<String>,
+
+    #[argh(switch)]
+    /// zeige den Zustand unter pfad als Text an, inklusive aller Anschlüsse
+    /// und der Anzahl der Gleise je Beschreibung
+    pub inhalt: bool,
+
+    #[argh(switch)]
+    /// prüfe den Zustand unter pfad auf mehrfach verwendete Anschlüsse,
+    /// ohne sie zu reservieren
+    pub konflikte: bool,
+
+    #[argh(option)]
+    /// speichere den Zustand unter pfad als vollständigen Zustand in eine neue Datei,
+    /// mit ausgabe getrennt in Gleisplan und Profil
+    pub konvertieren: Option<String>,
+
+    #[argh(option)]
+    /// zeige die Unterschiede zwischen dem Zustand unter pfad und der neueren datei an
+    pub vergleich: Option<String>,
+
+    #[argh(option)]
+    /// führe die Änderungen des Zustands unter pfad und der datei zusammen,
+    /// benötigt basis und ausgabe
+    pub vereinige: Option<String>,
+
+    #[argh(option)]
+    /// gemeinsamer Ursprung der zusammengeführten Zustände
+    pub basis: Option<String>,
+
+    #[argh(option)]
+    /// backend für Gpio und I2C (hardware, cdev[:gpiochip], remote:host[:port], stub),
+    /// ohne Angabe abhängig von /dev/gpiomem und /dev/i2c-*
+    pub backend: Option<Backend>,
+
+    #[argh(switch)]
+    /// lade den Zustand unter pfad und steuere ihn ohne Anzeige,
+    /// beenden mit SIGTERM
+    pub headless: bool,
+
+    #[argh(option)]
+    /// starte nur einen Server für die Anschlüsse an adresse, ohne Anzeige (z.B. 0.0.0.0:7353)
+    pub remote_server: Option<String>,
+
+    #[argh(option)]
+    /// adresse für die Steuerung über WebSocket (nur Port: 127.0.0.1) (z.B. 8765)
+    pub netzwerk: Option<String>,
+
+    #[argh(option)]
+    /// adresse für die Steuerung im Browser (nur Port: 127.0.0.1), benötigt --netzwerk (z.B. 8080)
+    pub web: Option<String>,
+
+    #[argh(option)]
+    /// adresse eines MQTT-Brokers (host[:port])
+    pub mqtt: Option<String>,
+
+    #[argh(option, default = "String::from(\"zugkontrolle\")")]
+    /// basis aller MQTT-Topics (standard: zugkontrolle)
+    pub mqtt_basis: String,
+
+    #[argh(option)]
+    /// name eines gespeicherten Skriptes, das nach dem Laden gestartet wird (mehrfach möglich)
+    pub skript: Vec<String>,
+
     #[argh(switch)]
     /// zeige zusätzliche Informationen in der Konsole an
     pub verbose: bool,
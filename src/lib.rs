@@ -5,6 +5,7 @@ pub mod application;
 pub mod farbe;
 pub mod lookup;
 pub mod non_empty;
+pub mod speicherformat;
 pub mod steuerung;
 pub mod zugtyp;
 
@@ -3,8 +3,12 @@
 use iced::{Application, Settings};
 use simple_logger::SimpleLogger;
 use zugkontrolle::{
-    anschluss::anschlüsse::{self, Anschlüsse},
-    application::icon::icon,
+    anschluss::{
+        anschlüsse::{self, Anschlüsse},
+        remote, verkabelung,
+        zuordnung::Zuordnung,
+    },
+    application::{gleis::gleise, headless, icon::icon, mqtt},
     Lego, Märklin, Zugkontrolle,
 };
 
@@ -12,7 +16,32 @@ pub mod args;
 use self::args::Args;
 
 fn main() -> Result<(), Error> {
-    let Args { zugtyp, pfad, modus, verbose, .. } = Args::from_env();
+    let Args {
+        zugtyp,
+        pfad,
+        profil,
+        modus,
+        nothalt_pin,
+        zuordnung,
+        ausgabe,
+        verkabelung,
+        inhalt,
+        konflikte,
+        konvertieren,
+        vergleich,
+        vereinige,
+        basis,
+        backend,
+        headless,
+        remote_server,
+        netzwerk,
+        web,
+        mqtt,
+        mqtt_basis,
+        skript,
+        verbose,
+        ..
+    } = Args::from_env();
 
     let log_level = if verbose { log::LevelFilter::Debug } else { log::LevelFilter::Warn };
     SimpleLogger::new()
@@ -21,14 +50,70 @@ fn main() -> Result<(), Error> {
         .init()
         .expect("failed to initialize error logging");
 
-    Anschlüsse::neu().map_err(Error::from).and_then(|anschlüsse| {
+    if let Some(verkabelung_pfad) = verkabelung {
+        return verkabelung_schreiben(zugtyp, pfad, profil, verkabelung_pfad);
+    }
+
+    if inhalt {
+        return inhalt_anzeigen(zugtyp, pfad, profil);
+    }
+
+    if konflikte {
+        return konflikte_prüfen(zugtyp, pfad, profil);
+    }
+
+    if let Some(ziel) = konvertieren {
+        return konvertieren_nach(zugtyp, pfad, profil, ziel, ausgabe);
+    }
+
+    if let Some(neu) = vergleich {
+        return vergleichen(zugtyp, pfad, profil, neu);
+    }
+
+    if let Some(andere) = vereinige {
+        return vereinigen(zugtyp, pfad, andere, basis, ausgabe);
+    }
+
+    if headless && pfad.is_none() {
+        return Err(Error::Headless(headless::Error::KeinPfad));
+    }
+
+    if let Some(backend) = backend {
+        Anschlüsse::wähle_backend(backend)?;
+    }
+
+    Anschlüsse::neu().map_err(Error::from).and_then(|mut anschlüsse| {
+        if let Some(zuordnung_pfad) = zuordnung {
+            return zuordnung_anwenden(zugtyp, &mut anschlüsse, pfad, zuordnung_pfad, ausgabe);
+        }
+        if let Some(adresse) = remote_server {
+            return remote::Server::starte(&mut anschlüsse, adresse).map_err(Error::from);
+        }
+        let flags = (
+            anschlüsse,
+            pfad,
+            profil,
+            modus,
+            nothalt_pin,
+            netzwerk,
+            web,
+            mqtt.map(|adresse| mqtt::Einstellungen { adresse, basis: mqtt_basis }),
+            skript,
+        );
+        if headless {
+            return match zugtyp {
+                args::Zugtyp::Märklin => headless::starte::<Märklin>(flags),
+                args::Zugtyp::Lego => headless::starte::<Lego>(flags),
+            }
+            .map_err(Error::from);
+        }
         let settings = Settings {
             window: iced::window::Settings {
                 size: (1024, 768),
                 icon: Some(icon()),
                 ..Default::default()
             },
-            ..Settings::with_flags((anschlüsse, pfad, modus))
+            ..Settings::with_flags(flags)
         };
         match zugtyp {
             args::Zugtyp::Märklin => Zugkontrolle::<Märklin>::run(settings),
@@ -38,10 +123,243 @@ fn main() -> Result<(), Error> {
     })
 }
 
+/// Wende die Zuordnung aus `zuordnung_pfad` auf die Datei `pfad` an, ohne die Anzeige zu starten.
+fn zuordnung_anwenden(
+    zugtyp: args::Zugtyp,
+    anschlüsse: &mut Anschlüsse,
+    pfad: Option<String>,
+    zuordnung_pfad: String,
+    ausgabe: Option<String>,
+) -> Result<(), Error> {
+    let (pfad, ausgabe) = match (pfad, ausgabe) {
+        (Some(pfad), Some(ausgabe)) => (pfad, ausgabe),
+        _ => return Err(Error::Zuordnung("--zuordnung benötigt --pfad und --ausgabe".to_string())),
+    };
+    let zuordnung: Zuordnung =
+        std::fs::read_to_string(&zuordnung_pfad)?.parse().map_err(Error::Zuordnung)?;
+    let ergebnis = match zugtyp {
+        args::Zugtyp::Märklin => {
+            gleise::Gleise::<Märklin>::zuordnung_anwenden(anschlüsse, &pfad, &ausgabe, &zuordnung)
+        }
+        args::Zugtyp::Lego => {
+            gleise::Gleise::<Lego>::zuordnung_anwenden(anschlüsse, &pfad, &ausgabe, &zuordnung)
+        }
+    };
+    match ergebnis {
+        Ok(geändert) => {
+            println!("{} Anschlüsse geändert, gespeichert in {}.", geändert, ausgabe);
+            Ok(())
+        }
+        Err(gleise::Error::Konflikte(konflikte)) => {
+            for konflikt in konflikte.iter() {
+                eprintln!("{}", konflikt)
+            }
+            Err(Error::Zuordnung(format!(
+                "{} Konflikte, {} nicht gespeichert.",
+                konflikte.len(),
+                ausgabe
+            )))
+        }
+        Err(fehler) => Err(fehler.into()),
+    }
+}
+
+/// Schreibe die Verkabelung des Zustands in `pfad` nach `verkabelung_pfad`.
+///
+/// Dafür werden keine Anschlüsse reserviert, es ist also keine Hardware notwendig.
+fn verkabelung_schreiben(
+    zugtyp: args::Zugtyp,
+    pfad: Option<String>,
+    profil: Option<String>,
+    verkabelung_pfad: String,
+) -> Result<(), Error> {
+    let pfad = pfad.ok_or(Error::Verkabelung("--verkabelung benötigt --pfad".to_string()))?;
+    let format = verkabelung::Format::für_pfad(&verkabelung_pfad).ok_or_else(|| {
+        Error::Verkabelung(format!("Unbekanntes Format (.md, .csv, .tex): {}", verkabelung_pfad))
+    })?;
+    let (verkabelung, ungebunden) = match zugtyp {
+        args::Zugtyp::Märklin => gleise::Gleise::<Märklin>::verkabelung(&pfad, profil.as_ref())?,
+        args::Zugtyp::Lego => gleise::Gleise::<Lego>::verkabelung(&pfad, profil.as_ref())?,
+    };
+    for besitzer in ungebunden {
+        eprintln!("{}: nicht im Profil", besitzer)
+    }
+    for kennung in verkabelung.mehrfach_verwendet() {
+        eprintln!("{} wird mehrfach verwendet", kennung)
+    }
+    std::fs::write(&verkabelung_pfad, verkabelung.formatiert(format, &pfad))?;
+    println!(
+        "{} Anschlüsse dokumentiert in {}.",
+        verkabelung.verbindungen().len(),
+        verkabelung_pfad
+    );
+    Ok(())
+}
+
+/// Zeige den Inhalt des Zustands in `pfad` an, ohne Anschlüsse zu reservieren.
+fn inhalt_anzeigen(
+    zugtyp: args::Zugtyp,
+    pfad: Option<String>,
+    profil: Option<String>,
+) -> Result<(), Error> {
+    let pfad = pfad.ok_or(Error::Datei("--inhalt benötigt --pfad".to_string()))?;
+    let (inhalt, ungebunden) = match zugtyp {
+        args::Zugtyp::Märklin => gleise::Gleise::<Märklin>::inhalt(&pfad, profil.as_ref())?,
+        args::Zugtyp::Lego => gleise::Gleise::<Lego>::inhalt(&pfad, profil.as_ref())?,
+    };
+    for besitzer in ungebunden {
+        eprintln!("{}: nicht im Profil", besitzer)
+    }
+    print!("{}", inhalt);
+    Ok(())
+}
+
+/// Prüfe den Zustand in `pfad` auf mehrfach verwendete Anschlüsse und I2C-Adressen.
+///
+/// Dafür werden keine Anschlüsse reserviert, es ist also keine Hardware notwendig.
+fn konflikte_prüfen(
+    zugtyp: args::Zugtyp,
+    pfad: Option<String>,
+    profil: Option<String>,
+) -> Result<(), Error> {
+    let pfad = pfad.ok_or(Error::Datei("--konflikte benötigt --pfad".to_string()))?;
+    let (verkabelung, ungebunden) = match zugtyp {
+        args::Zugtyp::Märklin => gleise::Gleise::<Märklin>::verkabelung(&pfad, profil.as_ref())?,
+        args::Zugtyp::Lego => gleise::Gleise::<Lego>::verkabelung(&pfad, profil.as_ref())?,
+    };
+    for besitzer in ungebunden {
+        eprintln!("{}: nicht im Profil", besitzer)
+    }
+    let mut konflikte = 0;
+    for kennung in verkabelung.mehrfach_verwendet() {
+        let objekte: Vec<_> = verkabelung
+            .verbindungen()
+            .iter()
+            .filter(|verbindung| verbindung.kennung == kennung)
+            .map(|verbindung| format!("{} {}", verbindung.objekt, verbindung.funktion))
+            .collect();
+        eprintln!("{} wird mehrfach verwendet: {}", kennung, objekte.join(", "));
+        konflikte += 1;
+    }
+    for adresse in verkabelung.adressen_mehrfach_verwendet() {
+        eprintln!("I2C-Adresse {} wird von verschiedenen Bausteinen verwendet", adresse);
+        konflikte += 1;
+    }
+    if konflikte == 0 {
+        println!("Keine Konflikte in {}.", pfad);
+        Ok(())
+    } else {
+        Err(Error::Datei(format!("{} Konflikte in {}.", konflikte, pfad)))
+    }
+}
+
+/// Speichere den Zustand in `pfad` unter `ziel`, mit `profil_ziel` getrennt in Gleisplan und Profil.
+fn konvertieren_nach(
+    zugtyp: args::Zugtyp,
+    pfad: Option<String>,
+    profil: Option<String>,
+    ziel: String,
+    profil_ziel: Option<String>,
+) -> Result<(), Error> {
+    let pfad = pfad.ok_or(Error::Datei("--konvertieren benötigt --pfad".to_string()))?;
+    let ungebunden = match zugtyp {
+        args::Zugtyp::Märklin => gleise::Gleise::<Märklin>::konvertieren(
+            &pfad,
+            profil.as_ref(),
+            &ziel,
+            profil_ziel.as_ref(),
+        )?,
+        args::Zugtyp::Lego => gleise::Gleise::<Lego>::konvertieren(
+            &pfad,
+            profil.as_ref(),
+            &ziel,
+            profil_ziel.as_ref(),
+        )?,
+    };
+    for besitzer in ungebunden {
+        eprintln!("{}: nicht im Profil", besitzer)
+    }
+    match profil_ziel {
+        Some(profil_ziel) => {
+            println!("Gleisplan in {}, Profil in {} gespeichert.", ziel, profil_ziel)
+        },
+        None => println!("Zustand in {} gespeichert.", ziel),
+    }
+    Ok(())
+}
+
+/// Zeige die Unterschiede zwischen den Zuständen in `pfad` und `neu` an, eine Zeile pro Änderung.
+fn vergleichen(
+    zugtyp: args::Zugtyp,
+    pfad: Option<String>,
+    profil: Option<String>,
+    neu: String,
+) -> Result<(), Error> {
+    let pfad = pfad.ok_or(Error::Datei("--vergleich benötigt --pfad".to_string()))?;
+    let unterschiede = match zugtyp {
+        args::Zugtyp::Märklin => {
+            gleise::Gleise::<Märklin>::vergleiche(&pfad, &neu, profil.as_ref())?
+        },
+        args::Zugtyp::Lego => gleise::Gleise::<Lego>::vergleiche(&pfad, &neu, profil.as_ref())?,
+    };
+    if unterschiede.is_empty() {
+        println!("Keine Unterschiede zwischen {} und {}.", pfad, neu);
+    }
+    for unterschied in unterschiede {
+        println!("{}", unterschied)
+    }
+    Ok(())
+}
+
+/// Führe die Änderungen der Zustände in `pfad` und `andere` gegenüber `basis` zusammen.
+///
+/// Bei Konflikten wird keine Datei geschrieben.
+fn vereinigen(
+    zugtyp: args::Zugtyp,
+    pfad: Option<String>,
+    andere: String,
+    basis: Option<String>,
+    ziel: Option<String>,
+) -> Result<(), Error> {
+    let pfad = pfad.ok_or(Error::Datei("--vereinige benötigt --pfad".to_string()))?;
+    let basis = basis.ok_or(Error::Datei("--vereinige benötigt --basis".to_string()))?;
+    let ziel = ziel.ok_or(Error::Datei("--vereinige benötigt --ausgabe".to_string()))?;
+    let ergebnis = match zugtyp {
+        args::Zugtyp::Märklin => {
+            gleise::Gleise::<Märklin>::vereinige(&basis, &pfad, &andere, &ziel)
+        },
+        args::Zugtyp::Lego => gleise::Gleise::<Lego>::vereinige(&basis, &pfad, &andere, &ziel),
+    };
+    match ergebnis {
+        Ok(()) => {
+            println!("Zusammengeführter Zustand in {} gespeichert.", ziel);
+            Ok(())
+        },
+        Err(gleise::Error::Vereinigen(konflikte)) => {
+            for konflikt in konflikte.iter() {
+                eprintln!("{}", konflikt)
+            }
+            Err(Error::Datei(format!(
+                "{} Konflikte beim Zusammenführen von {} und {}.",
+                konflikte.len(),
+                pfad,
+                andere
+            )))
+        },
+        Err(fehler) => Err(fehler.into()),
+    }
+}
+
 #[derive(Debug)]
 enum Error {
     Iced(iced::Error),
     Anschlüsse(anschlüsse::Error),
+    IO(std::io::Error),
+    Gleise(gleise::Error),
+    Zuordnung(String),
+    Verkabelung(String),
+    Datei(String),
+    Headless(headless::Error),
 }
 impl From<iced::Error> for Error {
     fn from(error: iced::Error) -> Self {
@@ -53,3 +371,18 @@ impl From<anschlüsse::Error> for Error {
         Error::Anschlüsse(error)
     }
 }
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::IO(error)
+    }
+}
+impl From<gleise::Error> for Error {
+    fn from(error: gleise::Error) -> Self {
+        Error::Gleise(error)
+    }
+}
+impl From<headless::Error> for Error {
+    fn from(error: headless::Error) -> Self {
+        Error::Headless(error)
+    }
+}
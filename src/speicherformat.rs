@@ -0,0 +1,210 @@
+//! Versionierung des Speicherformats.
+//!
+//! Gespeicherte Zustände werden mit bincode serialisiert, Felder werden dabei nur über ihre
+//! Position erkannt. Jede Änderung am Aufbau bekommt daher eine neue [Version],
+//! die zusammen mit einer Kennung vor dem eigentlichen Inhalt gespeichert wird.
+//! Beim Lesen älterer Versionen werden später hinzugefügte Felder über [neu_seit]
+//! durch einen Standardwert ersetzt.
+
+use std::{cell::Cell, io::Write};
+
+use bincode::Options;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+
+/// Kennung am Anfang eines versionierten Speicherstands.
+const KENNUNG: &str = "Zugkontrolle Speicherstand";
+
+/// Aufbau eines Speicherstands, neue Versionen werden am Ende hinzugefügt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Version {
+    /// Format von Version 2.0.1, gespeichert ohne Kennung.
+    Basis,
+    /// Pwm-Geschwindigkeiten speichern ihr PwmProfil.
+    PwmProfil,
+    /// Taster des Stellpults.
+    Stellpult,
+    /// Drehregler an einem Adc.
+    Drehregler,
+    /// Pwm-Anschlüsse können ein Kanal eines Pca9685 sein.
+    Pca9685,
+    /// I2C-Bausteine speichern ihren Bus.
+    I2cBus,
+    /// Skripte.
+    Skripte,
+    /// Regeln.
+    Regeln,
+    /// Pca9685-Kanäle speichern die Frequenz ihres Pca9685.
+    Pca9685Frequenz,
+}
+
+impl Version {
+    /// Version, mit der gespeichert wird.
+    pub const AKTUELL: Version = Version::Pca9685Frequenz;
+}
+
+thread_local! {
+    /// Version des Speicherstands, der im aktuellen Thread gelesen wird.
+    static LESE_VERSION: Cell<Version> = const { Cell::new(Version::AKTUELL) };
+}
+
+/// Version des Speicherstands, der gerade gelesen wird.
+///
+/// Außerhalb von [Speicherstand::lese] ist das immer [Version::AKTUELL].
+pub fn lese_version() -> Version {
+    LESE_VERSION.with(Cell::get)
+}
+
+fn mit_version<T>(version: Version, lese: impl FnOnce() -> T) -> T {
+    let bisher = LESE_VERSION.with(|lese_version| lese_version.replace(version));
+    let ergebnis = lese();
+    LESE_VERSION.with(|lese_version| lese_version.set(bisher));
+    ergebnis
+}
+
+/// Lese ein Feld, das mit `version` zum Speicherformat hinzugefügt wurde.
+///
+/// Ältere Speicherstände enthalten das Feld nicht, stattdessen wird `standard` verwendet.
+pub fn neu_seit<'de, D, T>(
+    version: Version,
+    deserializer: D,
+    standard: impl FnOnce() -> T,
+) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    if lese_version() < version {
+        Ok(standard())
+    } else {
+        T::deserialize(deserializer)
+    }
+}
+
+fn optionen() -> impl Options {
+    bincode::options().with_fixint_encoding()
+}
+
+/// Speichere `wert` mit Kennung und aktueller Version.
+pub fn schreibe<T: Serialize>(writer: impl Write, wert: &T) -> Result<(), bincode::Error> {
+    optionen().serialize_into(writer, &(KENNUNG, Version::AKTUELL, wert))
+}
+
+/// Ein Speicherstand, dessen Inhalt noch nicht gelesen wurde.
+#[derive(Debug)]
+pub struct Speicherstand<'t> {
+    version: Version,
+    inhalt: &'t [u8],
+}
+
+impl<'t> Speicherstand<'t> {
+    /// Trenne Kennung und Version vom Inhalt.
+    ///
+    /// Speicherstände ohne Kennung wurden von Version 2.0.1 (oder älter) gespeichert
+    /// und verwenden [Version::Basis].
+    pub fn neu(bytes: &'t [u8]) -> Result<Self, bincode::Error> {
+        let mut inhalt = bytes;
+        match bincode::deserialize_from::<_, String>(&mut inhalt) {
+            Ok(kennung) if kennung == KENNUNG => {
+                let version = bincode::deserialize_from(&mut inhalt)?;
+                Ok(Speicherstand { version, inhalt })
+            },
+            _ => Ok(Speicherstand { version: Version::Basis, inhalt: bytes }),
+        }
+    }
+
+    /// Der Name am Anfang des Inhalts, alle Speicherformate beginnen damit.
+    pub fn name(&self) -> Result<String, bincode::Error> {
+        bincode::deserialize(self.inhalt)
+    }
+
+    /// Lese den Inhalt gemäß seiner Version.
+    pub fn lese<T: DeserializeOwned>(&self) -> Result<T, bincode::Error> {
+        mit_version(self.version, || optionen().deserialize(self.inhalt))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::anschluss::{i2c::Bus, pcf8574::Variante, pwm, Level, OutputSave, Polarität};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Zustand {
+        name: String,
+        werte: Vec<String>,
+    }
+
+    fn zustand() -> Zustand {
+        Zustand { name: "Test".to_string(), werte: vec!["Wert".to_string()] }
+    }
+
+    /// Ein Zustand, dessen Feld `neu` erst mit [Version::PwmProfil] hinzugefügt wurde.
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Erweitert {
+        name: String,
+        #[serde(deserialize_with = "neu_seit_pwm_profil")]
+        neu: Vec<String>,
+    }
+
+    fn neu_seit_pwm_profil<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<String>, D::Error> {
+        neu_seit(Version::PwmProfil, deserializer, Vec::new)
+    }
+
+    #[test]
+    fn versioniert() {
+        let mut bytes = Vec::new();
+        schreibe(&mut bytes, &zustand()).expect("Schreiben fehlgeschlagen");
+        let speicherstand = Speicherstand::neu(&bytes).expect("Ungültiger Speicherstand");
+        assert_eq!(speicherstand.version, Version::AKTUELL);
+        assert_eq!(speicherstand.name().expect("Kein Name"), "Test");
+        assert_eq!(speicherstand.lese::<Zustand>().expect("Lesen fehlgeschlagen"), zustand());
+    }
+
+    #[test]
+    fn ohne_kennung() {
+        let bytes = bincode::serialize(&zustand()).expect("Schreiben fehlgeschlagen");
+        let speicherstand = Speicherstand::neu(&bytes).expect("Ungültiger Speicherstand");
+        assert_eq!(speicherstand.version, Version::Basis);
+        assert_eq!(speicherstand.lese::<Zustand>().expect("Lesen fehlgeschlagen"), zustand());
+    }
+
+    #[test]
+    fn ohne_kennung_vor_feld() {
+        let bytes = bincode::serialize(&"Test").expect("Schreiben fehlgeschlagen");
+        let speicherstand = Speicherstand::neu(&bytes).expect("Ungültiger Speicherstand");
+        let erwartet = Erweitert { name: "Test".to_string(), neu: Vec::new() };
+        assert_eq!(speicherstand.lese::<Erweitert>().expect("Lesen fehlgeschlagen"), erwartet);
+        assert_eq!(lese_version(), Version::AKTUELL);
+    }
+
+    #[test]
+    fn ohne_kennung_nur_basis() {
+        // ohne Kennung gibt es keine neueren Felder, übrige Bytes sind ein Fehler
+        let erweitert = Erweitert { name: "Test".to_string(), neu: vec!["Neu".to_string()] };
+        let bytes = bincode::serialize(&erweitert).expect("Schreiben fehlgeschlagen");
+        let speicherstand = Speicherstand::neu(&bytes).expect("Ungültiger Speicherstand");
+        let _ = speicherstand.lese::<Erweitert>().expect_err("Neueres Feld ohne Kennung gelesen");
+    }
+
+    #[test]
+    fn anschlüsse_ohne_kennung() {
+        // Pcf8574Port (Variante 1) ohne Bus, gefolgt von einem Pwm-Pin ohne Pca9685-Variante
+        let bytes = bincode::serialize(&(
+            1u32,
+            (Level::High, Level::Low, Level::Low, Variante::A, 3u8, Polarität::Normal),
+            18u8,
+        ))
+        .expect("Schreiben fehlgeschlagen");
+        let speicherstand = Speicherstand::neu(&bytes).expect("Ungültiger Speicherstand");
+        let (output, pwm): (OutputSave, pwm::Save) =
+            speicherstand.lese().expect("Lesen fehlgeschlagen");
+        assert!(
+            matches!(output, OutputSave::Pcf8574Port { bus: Bus::STANDARD, port: 3, .. }),
+            "{:?}",
+            output
+        );
+        assert!(matches!(pwm, pwm::Save::Pin(18)), "{:?}", pwm);
+    }
+}
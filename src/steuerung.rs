@@ -14,3 +14,13 @@ pub use kontakt::Kontakt;
 
 pub mod plan;
 pub use plan::Plan;
+
+pub mod stellpult;
+pub use stellpult::Taster;
+
+pub mod drehregler;
+pub use drehregler::Drehregler;
+
+pub mod skript;
+
+pub mod regel;
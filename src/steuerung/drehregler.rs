@@ -0,0 +1,174 @@
+//! Drehregler (Potentiometer an einem Adc) zum Einstellen einer Geschwindigkeit.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, sleep, JoinHandle},
+    time::Duration,
+};
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::anschluss::{adc, Anschlüsse, Error, Reserviere, ToSave};
+use crate::steuerung::geschwindigkeit;
+
+#[cfg(test)]
+mod test;
+
+/// Zeit zwischen zwei Messungen.
+const ABFRAGE_INTERVALL: Duration = Duration::from_millis(50);
+/// Gewicht eines neuen Messwerts bei der Glättung (exponentieller gleitender Mittelwert).
+const GLÄTTUNG: f64 = 0.3;
+/// Minimale Änderung der Position, bevor ein neuer Wert ausgegeben wird.
+const HYSTERESE: i16 = 2;
+
+/// Glättet Messwerte und unterdrückt kleine Schwankungen (Hysterese).
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    geglättet: Option<f64>,
+    ausgegeben: Option<u8>,
+}
+
+impl Filter {
+    pub fn neu() -> Self {
+        Filter::default()
+    }
+
+    /// Verarbeite einen neuen Messwert (normiert auf [0, 1]).
+    ///
+    /// Gibt die neue Position (0 - `u8::MAX`) zurück, falls sie sich ausreichend geändert hat.
+    /// Endanschläge werden immer ausgegeben, damit der Drehregler ganz auf- und zudrehen kann.
+    pub fn messwert(&mut self, wert: f64) -> Option<u8> {
+        let wert = wert.max(0.).min(1.);
+        let geglättet = match self.geglättet {
+            Some(bisher) => bisher + GLÄTTUNG * (wert - bisher),
+            None => wert,
+        };
+        self.geglättet = Some(geglättet);
+        let position = (geglättet * f64::from(u8::MAX)).round() as u8;
+        let ausgeben = match self.ausgegeben {
+            None => true,
+            Some(bisher) if bisher == position => false,
+            Some(_bisher) if position == 0 || position == u8::MAX => true,
+            Some(bisher) => (i16::from(position) - i16::from(bisher)).abs() >= HYSTERESE,
+        };
+        if ausgeben {
+            self.ausgegeben = Some(position);
+            Some(position)
+        } else {
+            None
+        }
+    }
+}
+
+/// Eine laufende Abfrage eines Drehreglers. Wird beim drop beendet.
+#[derive(Debug)]
+struct Abfrage {
+    aktiv: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Abfrage {
+    fn drop(&mut self) {
+        self.aktiv.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            if let Err(err) = thread.join() {
+                error!("Abfrage eines Drehreglers ist abgestürzt: {:?}", err)
+            }
+        }
+    }
+}
+
+/// Ein Drehregler, der die Geschwindigkeit mit gleichem Namen einstellt.
+#[derive(Debug)]
+pub struct Drehregler {
+    anschluss: Arc<Mutex<adc::Kanal>>,
+    abfrage: Option<Abfrage>,
+}
+
+impl Drehregler {
+    pub fn neu(anschluss: adc::Kanal) -> Self {
+        Drehregler { anschluss: Arc::new(Mutex::new(anschluss)), abfrage: None }
+    }
+
+    /// Frage den Drehregler regelmäßig in einem eigenen Thread ab.
+    ///
+    /// `callback` wird mit der neuen Position (0 - `u8::MAX`) aufgerufen,
+    /// sobald sie sich nach Filterung geändert hat.
+    /// Eine bereits laufende Abfrage wird beendet.
+    pub fn starte_abfrage(&mut self, mut callback: impl FnMut(u8) + Send + 'static) {
+        self.stoppe_abfrage();
+        let aktiv = Arc::new(AtomicBool::new(true));
+        let anschluss = self.anschluss.clone();
+        let aktiv_clone = aktiv.clone();
+        let thread = thread::spawn(move || {
+            let mut filter = Filter::neu();
+            let mut letzter_fehler = false;
+            while aktiv_clone.load(Ordering::Relaxed) {
+                let messwert = match anschluss.lock() {
+                    Ok(kanal) => kanal.lese(),
+                    Err(poison_error) => poison_error.into_inner().lese(),
+                };
+                match messwert {
+                    Ok(wert) => {
+                        letzter_fehler = false;
+                        if let Some(position) = filter.messwert(wert) {
+                            callback(position)
+                        }
+                    }
+                    Err(err) => {
+                        // nur den ersten Fehler einer Serie melden
+                        if !letzter_fehler {
+                            error!("Fehler beim Lesen eines Drehreglers: {:?}", err)
+                        }
+                        letzter_fehler = true;
+                    }
+                }
+                sleep(ABFRAGE_INTERVALL)
+            }
+        });
+        self.abfrage = Some(Abfrage { aktiv, thread: Some(thread) });
+    }
+
+    /// Beende eine laufende Abfrage.
+    pub fn stoppe_abfrage(&mut self) {
+        self.abfrage = None;
+    }
+
+    /// Adc-Kanal des Drehreglers (z.B. zum Setzen simulierter Werte).
+    pub fn anschluss(&self) -> &Arc<Mutex<adc::Kanal>> {
+        &self.anschluss
+    }
+}
+
+/// Serealisierbare Informationen eines Drehreglers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DrehreglerSave {
+    pub anschluss: adc::Save,
+}
+
+impl ToSave for Drehregler {
+    type Save = DrehreglerSave;
+
+    fn to_save(&self) -> DrehreglerSave {
+        let anschluss = match self.anschluss.lock() {
+            Ok(kanal) => kanal.to_save(),
+            Err(poison_error) => poison_error.into_inner().to_save(),
+        };
+        DrehreglerSave { anschluss }
+    }
+}
+
+impl Reserviere<Drehregler> for DrehreglerSave {
+    fn reserviere(self, anschlüsse: &mut Anschlüsse) -> Result<Drehregler, Error> {
+        Ok(Drehregler::neu(self.anschluss.reserviere(anschlüsse)?))
+    }
+}
+
+/// Drehregler, zugeordnet zum Namen der eingestellten Geschwindigkeit.
+pub type Map = BTreeMap<geschwindigkeit::Name, Drehregler>;
+pub type MapSave = BTreeMap<geschwindigkeit::Name, DrehreglerSave>;
@@ -0,0 +1,91 @@
+//! unittests für den Filter und die Reservierung eines Drehreglers
+
+use super::{DrehreglerSave, Filter};
+use crate::anschluss::{
+    adc,
+    anschlüsse::{self, test_sperre, Besitzer, SyncError, Verfügbarkeit},
+    i2c, Anschlüsse, Error, Reserviere, ToSave,
+};
+
+#[test]
+fn erster_messwert_wird_ausgegeben() {
+    let mut filter = Filter::neu();
+    assert_eq!(filter.messwert(0.5), Some(128));
+}
+
+#[test]
+fn hysterese_unterdrückt_rauschen() {
+    let mut filter = Filter::neu();
+    assert_eq!(filter.messwert(0.5), Some(128));
+    for wert in [0.502, 0.498, 0.503, 0.497].iter() {
+        assert_eq!(filter.messwert(*wert), None);
+    }
+}
+
+#[test]
+fn glättung_nähert_sich_dem_messwert() {
+    let mut filter = Filter::neu();
+    assert_eq!(filter.messwert(0.), Some(0));
+    let mut letzte_position = 0;
+    for _ in 0..50 {
+        if let Some(position) = filter.messwert(1.) {
+            assert!(position > letzte_position);
+            letzte_position = position;
+        }
+    }
+    assert_eq!(letzte_position, u8::MAX);
+}
+
+#[test]
+fn werte_außerhalb_werden_begrenzt() {
+    let mut filter = Filter::neu();
+    assert_eq!(filter.messwert(1.5), Some(u8::MAX));
+    let mut filter = Filter::neu();
+    assert_eq!(filter.messwert(-0.5), Some(0));
+}
+
+#[test]
+fn adc_kanal_reservieren() {
+    let _sperre = test_sperre();
+    let mut anschlüsse = Anschlüsse::neu().expect("Aufruf von neu.");
+    let ads1115 =
+        adc::Adresse { bus: i2c::Bus::STANDARD, variante: adc::Variante::Ads1115, offset: 0 };
+    let pcf8591 = adc::Adresse { variante: adc::Variante::Pcf8591, ..ads1115 };
+    let besitzer = Besitzer::Drehregler("Lok".to_string());
+    let save = DrehreglerSave { anschluss: adc::Save { adresse: ads1115, kanal: 2 } };
+    let drehregler = anschlüsse
+        .mit_besitzer(besitzer.clone(), |anschlüsse| save.clone().reserviere(anschlüsse))
+        .expect("Aufruf von Ads1115 Kanal 2.");
+    assert_eq!(drehregler.to_save(), save);
+    assert!(
+        matches!(
+            save.clone().reserviere(&mut anschlüsse),
+            Err(Error::Anschlüsse(anschlüsse::Error::Sync(SyncError::InVerwendung)))
+        ),
+        "2. Aufruf von Ads1115 Kanal 2."
+    );
+    // Pcf8591 mit Offset 0 verwendet die gleiche I2C-Adresse
+    anschlüsse.reserviere_adc_kanal(pcf8591, 0).expect_err("Aufruf von Pcf8591 bei Ads1115.");
+    anschlüsse.reserviere_adc_kanal(ads1115, 4).expect_err("Aufruf von Ads1115 mit Kanal 4.");
+
+    let reservierungen = anschlüsse.reservierungen().expect("Reservierungen.");
+    let adresse = i2c::Adresse::from(ads1115);
+    let art = i2c::Art::Adc(adc::Variante::Ads1115);
+    assert_eq!(reservierungen.i2c_port(adresse, art, 2), Verfügbarkeit::Reserviert(Some(besitzer)));
+    assert_eq!(reservierungen.i2c_port(adresse, art, 0), Verfügbarkeit::Frei);
+    assert_eq!(
+        reservierungen.i2c_port(adresse, i2c::Art::Adc(adc::Variante::Pcf8591), 0),
+        Verfügbarkeit::AndererBaustein(art)
+    );
+
+    // ohne Hardware-Backend wird der simulierte Wert gelesen
+    let kanal = drehregler.anschluss().lock().expect("Zugriff auf den Kanal.");
+    kanal.setze_simulierten_wert(u16::MAX).expect("Setzen des simulierten Werts.");
+    assert_eq!(kanal.lese().expect("Lesen des Kanals."), 1.);
+    drop(kanal);
+
+    drop(drehregler);
+    let reservierungen = anschlüsse.reservierungen().expect("Reservierungen nach drop.");
+    assert_eq!(reservierungen.i2c_port(adresse, art, 2), Verfügbarkeit::Frei);
+    anschlüsse.reserviere_adc_kanal(pcf8591, 0).expect("Aufruf von Pcf8591 nach drop.");
+}
@@ -9,18 +9,58 @@ use std::{
 };
 
 use log::error;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::anschluss::{
-    self, pwm, Anschlüsse, Fließend, OutputAnschluss, OutputSave, Polarität, Reserviere, ToSave,
+    self, pwm,
+    verkabelung::{Beschreibung, Verbindung, Verkabeln},
+    zuordnung::{self, Zuordnen, Zuordnung},
+    Anschlüsse, Fließend, OutputAnschluss, OutputSave, Polarität, Reserviere, ToSave,
 };
 use crate::non_empty::{MaybeEmpty, NonEmpty};
+use crate::speicherformat::{neu_seit, Version};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Geschwindigkeit<Leiter> {
     pub leiter: Leiter,
 }
 
+/// Gemeinsame Funktionalität aller Arten der Stromzufuhr.
+pub trait Leiter {
+    /// 0 deaktiviert die Stromzufuhr.
+    /// Werte über dem Maximalwert werden wie der Maximalwert behandelt.
+    ///
+    /// Benötigt das Anfahren einen [Anfahrimpuls], wird nur dieser gestartet und seine Dauer
+    /// zurückgegeben. Danach muss `geschwindigkeit` erneut mit dem gleichen Wert aufgerufen werden.
+    fn geschwindigkeit(&mut self, wert: u8) -> Result<Option<Duration>, Error>;
+
+    /// Anhalten und Umdrehen der Fahrtrichtung.
+    fn umdrehen(&mut self) -> Result<(), Error>;
+
+    /// Größter unterscheidbarer Wert für `geschwindigkeit`.
+    fn maximal_wert(&self) -> u8;
+}
+
+impl<L: Leiter> Geschwindigkeit<L> {
+    /// 0 deaktiviert die Stromzufuhr.
+    /// Werte über dem Maximalwert werden wie der Maximalwert behandelt.
+    ///
+    /// Die Dauer eines gestarteten Anfahrimpulses wird zurückgegeben, siehe [Leiter::geschwindigkeit].
+    pub fn geschwindigkeit(&mut self, wert: u8) -> Result<Option<Duration>, Error> {
+        self.leiter.geschwindigkeit(wert)
+    }
+
+    /// Anhalten und Umdrehen der Fahrtrichtung.
+    pub fn umdrehen(&mut self) -> Result<(), Error> {
+        self.leiter.umdrehen()
+    }
+
+    /// Größter unterscheidbarer Wert für `geschwindigkeit`.
+    pub fn maximal_wert(&self) -> u8 {
+        self.leiter.maximal_wert()
+    }
+}
+
 impl<T: ToSave> ToSave for Geschwindigkeit<T> {
     type Save = Geschwindigkeit<T::Save>;
 
@@ -38,21 +78,163 @@ impl<T: Reserviere<R>, R> Reserviere<Geschwindigkeit<R>> for Geschwindigkeit<T>
     }
 }
 
+impl<T: Zuordnen> Zuordnen for Geschwindigkeit<T> {
+    fn zuordnen(&mut self, zuordnung: &Zuordnung) -> Result<usize, zuordnung::Error> {
+        self.leiter.zuordnen(zuordnung)
+    }
+}
+
+impl<T: Verkabeln> Verkabeln for Geschwindigkeit<T> {
+    fn verkabeln(&self, beschreibung: &Beschreibung, verbindungen: &mut Vec<Verbindung>) {
+        self.leiter.verkabeln(beschreibung, verbindungen)
+    }
+}
+
+/// Einstellungen zur Ansteuerung einer Geschwindigkeit über ein Pwm-Signal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PwmProfil {
+    /// Frequenz des Pwm-Signals (in Herz).
+    pub frequenz: f64,
+    /// Duty-cycle (\[0,1\]) für die kleinste Geschwindigkeit ungleich 0.
+    pub min_duty_cycle: f64,
+    /// Duty-cycle (\[0,1\]) für die maximale Geschwindigkeit.
+    pub max_duty_cycle: f64,
+    /// Kurzer Puls beim Anfahren aus dem Stillstand.
+    pub anfahrimpuls: Option<Anfahrimpuls>,
+    /// Stützstellen (\[0,1\]) einer nicht-linearen Geschwindigkeitskurve.
+    ///
+    /// Die Stützstellen werden gleichmäßig zwischen kleinster und größter Geschwindigkeit verteilt,
+    /// dazwischen wird linear interpoliert.
+    /// Bei weniger als zwei Stützstellen wird eine lineare Kurve verwendet.
+    pub kurve: Vec<f64>,
+}
+
+/// Puls mit erhöhtem Duty-cycle, um einen Zug aus dem Stillstand anfahren zu lassen.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Anfahrimpuls {
+    pub duty_cycle: f64,
+    /// Längere Impulse werden auf [MAX_ANFAHRIMPULS_DAUER] gekürzt.
+    pub dauer: Duration,
+}
+
+/// Während des Anfahrimpulses fährt ein Zug schneller als eingestellt,
+/// daher ist seine Dauer begrenzt.
+pub const MAX_ANFAHRIMPULS_DAUER: Duration = Duration::from_millis(200);
+
+impl PwmProfil {
+    /// Standard-Profil für Mittelleiter.
+    ///
+    /// Der maximale Duty-cycle ist begrenzt, um keine Überspannung (zum Umdrehen) zu erzeugen.
+    pub fn mittelleiter() -> Self {
+        PwmProfil {
+            frequenz: PWM_FREQUENZ,
+            min_duty_cycle: 0.,
+            max_duty_cycle: FRAC_FAHRSPANNUNG_ÜBERSPANNUNG,
+            anfahrimpuls: None,
+            kurve: Vec::new(),
+        }
+    }
+
+    /// Standard-Profil für Zweileiter.
+    pub fn zweileiter() -> Self {
+        PwmProfil {
+            frequenz: PWM_FREQUENZ,
+            min_duty_cycle: 0.,
+            max_duty_cycle: 1.,
+            anfahrimpuls: None,
+            kurve: Vec::new(),
+        }
+    }
+
+    /// Lese das Profil einer Mittelleiter-Geschwindigkeit,
+    /// ältere Speicherstände verwenden das Standard-Profil.
+    fn lese_mittelleiter<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        neu_seit(Version::PwmProfil, deserializer, PwmProfil::mittelleiter)
+    }
+
+    /// Lese das Profil einer Zweileiter-Geschwindigkeit,
+    /// ältere Speicherstände verwenden das Standard-Profil.
+    fn lese_zweileiter<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        neu_seit(Version::PwmProfil, deserializer, PwmProfil::zweileiter)
+    }
+
+    /// Duty-cycle für den gewünschten Geschwindigkeits-Wert.
+    ///
+    /// 0 ergibt immer einen Duty-cycle von 0.
+    pub fn duty_cycle(&self, wert: u8) -> f64 {
+        if wert == 0 {
+            return 0.;
+        }
+        let x = (wert - 1) as f64 / (u8::MAX - 1) as f64;
+        let y = match self.kurve.len() {
+            0 | 1 => x,
+            länge => {
+                let intervalle = (länge - 1) as f64;
+                let position = x * intervalle;
+                let index = (position.floor() as usize).min(länge - 2);
+                let anteil = position - index as f64;
+                self.kurve[index] + anteil * (self.kurve[index + 1] - self.kurve[index])
+            }
+        };
+        let duty_cycle =
+            self.min_duty_cycle + y.max(0.).min(1.) * (self.max_duty_cycle - self.min_duty_cycle);
+        duty_cycle.max(0.).min(1.)
+    }
+
+    /// Duty-cycle und Dauer des Anfahrimpulses, um aus dem Stillstand mit `wert` anzufahren.
+    ///
+    /// Ein Anfahrimpuls wird nur benötigt, wenn sein Duty-cycle größer ist als der für `wert`.
+    pub fn anfahrimpuls(&self, wert: u8) -> Option<(f64, Duration)> {
+        let Anfahrimpuls { duty_cycle: impuls_duty_cycle, dauer } = self.anfahrimpuls.as_ref()?;
+        let duty_cycle = self.duty_cycle(wert);
+        if duty_cycle > 0. && duty_cycle < *impuls_duty_cycle {
+            Some((impuls_duty_cycle.min(1.), (*dauer).min(MAX_ANFAHRIMPULS_DAUER)))
+        } else {
+            None
+        }
+    }
+}
+
+/// Stelle den Duty-cycle für `wert` ein.
+///
+/// Beim Anfahren aus dem Stillstand wird ggf. nur der Anfahrimpuls eingestellt
+/// und seine Dauer zurückgegeben.
 fn geschwindigkeit_pwm(
     pin: &mut pwm::Pin,
     wert: u8,
-    faktor: f64,
+    profil: &PwmProfil,
     polarity: Polarität,
-) -> Result<(), pwm::Error> {
-    debug_assert!(0. < faktor && faktor <= 1., "Faktor muss zwischen 0 und 1 liegen: {}", faktor);
+) -> Result<Option<Duration>, pwm::Error> {
+    let frequenz = profil.frequenz;
+    if let Some((impuls_duty_cycle, dauer)) = profil.anfahrimpuls(wert) {
+        let stillstand = match pin.is_enabled()? {
+            Some(pwm::Config { time: pwm::Time::Frequency { duty_cycle, .. }, .. }) => {
+                *duty_cycle == 0.
+            }
+            Some(pwm::Config { time: pwm::Time::Period { pulse_width, .. }, .. }) => {
+                pulse_width.as_nanos() == 0
+            }
+            None => true,
+        };
+        if stillstand {
+            pin.enable_with_config(pwm::Config {
+                polarity,
+                time: pwm::Time::Frequency { frequency: frequenz, duty_cycle: impuls_duty_cycle },
+            })?;
+            return Ok(Some(dauer));
+        }
+    }
     pin.enable_with_config(pwm::Config {
         polarity,
-        time: pwm::Time::Frequency {
-            frequency: PWM_FREQUENZ,
-            duty_cycle: faktor * wert as f64 / u8::MAX as f64,
-        },
-    })
+        time: pwm::Time::Frequency { frequency: frequenz, duty_cycle: profil.duty_cycle(wert) },
+    })?;
+    Ok(None)
 }
+
+fn maximal_wert_ks(geschwindigkeit: &NonEmpty<OutputAnschluss>) -> u8 {
+    geschwindigkeit.len().min(u8::MAX.into()) as u8
+}
+
 fn geschwindigkeit_ks(
     geschwindigkeit: &mut NonEmpty<OutputAnschluss>,
     letzter_wert: &mut usize,
@@ -88,6 +270,8 @@ pub enum Mittelleiter<Pwm = pwm::Pin, Anschluss = OutputAnschluss> {
     Pwm {
         pin: Pwm,
         polarität: Polarität,
+        #[serde(deserialize_with = "PwmProfil::lese_mittelleiter")]
+        profil: PwmProfil,
     },
     KonstanteSpannung {
         geschwindigkeit: NonEmpty<Anschluss>,
@@ -99,8 +283,8 @@ pub enum Mittelleiter<Pwm = pwm::Pin, Anschluss = OutputAnschluss> {
 impl Display for Mittelleiter {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            Mittelleiter::Pwm { pin, polarität } => {
-                write!(f, "Pwm({}, {})", pin.pin(), polarität)
+            Mittelleiter::Pwm { pin, polarität, profil: _ } => {
+                write!(f, "Pwm({}, {})", pin, polarität)
             }
             Mittelleiter::KonstanteSpannung { geschwindigkeit, letzter_wert: _, umdrehen } => {
                 write!(f, "KonstanteSpannung(")?;
@@ -124,9 +308,11 @@ impl ToSave for Mittelleiter {
 
     fn to_save(&self) -> MittelleiterSave {
         match self {
-            Mittelleiter::Pwm { pin, polarität } => {
-                Mittelleiter::Pwm { pin: pin.to_save(), polarität: *polarität }
-            }
+            Mittelleiter::Pwm { pin, polarität, profil } => Mittelleiter::Pwm {
+                pin: pin.to_save(),
+                polarität: *polarität,
+                profil: profil.clone(),
+            },
             Mittelleiter::KonstanteSpannung { geschwindigkeit, letzter_wert, umdrehen } => {
                 Mittelleiter::KonstanteSpannung {
                     geschwindigkeit: geschwindigkeit
@@ -144,8 +330,9 @@ impl ToSave for Mittelleiter {
 impl Reserviere<Mittelleiter> for MittelleiterSave {
     fn reserviere(self, anschlüsse: &mut Anschlüsse) -> Result<Mittelleiter, anschluss::Error> {
         Ok(match self {
-            Mittelleiter::Pwm { pin, polarität } => {
-                Mittelleiter::Pwm { pin: pin.reserviere(anschlüsse)?, polarität }
+            Mittelleiter::Pwm { pin, polarität, profil } => {
+                let pin = pin.mit_frequenz(profil.frequenz)?;
+                Mittelleiter::Pwm { pin: pin.reserviere(anschlüsse)?, polarität, profil }
             }
             Mittelleiter::KonstanteSpannung { geschwindigkeit, letzter_wert: _, umdrehen } => {
                 Mittelleiter::KonstanteSpannung {
@@ -161,39 +348,61 @@ impl Reserviere<Mittelleiter> for MittelleiterSave {
         })
     }
 }
+impl<P: Zuordnen, A: Zuordnen> Zuordnen for Mittelleiter<P, A> {
+    fn zuordnen(&mut self, zuordnung: &Zuordnung) -> Result<usize, zuordnung::Error> {
+        Ok(match self {
+            Mittelleiter::Pwm { pin, .. } => pin.zuordnen(zuordnung)?,
+            Mittelleiter::KonstanteSpannung { geschwindigkeit, umdrehen, .. } => {
+                geschwindigkeit.zuordnen(zuordnung)? + umdrehen.zuordnen(zuordnung)?
+            }
+        })
+    }
+}
+impl<P: Verkabeln, A: Verkabeln> Verkabeln for Mittelleiter<P, A> {
+    fn verkabeln(&self, beschreibung: &Beschreibung, verbindungen: &mut Vec<Verbindung>) {
+        match self {
+            Mittelleiter::Pwm { pin, polarität, .. } => pin.verkabeln(
+                &beschreibung.funktion("Geschwindigkeit").polarität(*polarität),
+                verbindungen,
+            ),
+            Mittelleiter::KonstanteSpannung { geschwindigkeit, umdrehen, .. } => {
+                geschwindigkeit.verkabeln(&beschreibung.funktion("Geschwindigkeit"), verbindungen);
+                umdrehen.verkabeln(&beschreibung.funktion("Umdrehen"), verbindungen)
+            }
+        }
+    }
+}
 
 // TODO als Zugtyp-Eigenschaft?
 const STOPPZEIT: Duration = Duration::from_millis(500);
 const PWM_FREQUENZ: f64 = 50.;
-// TODO Zugtyp-Eigenschaft, wenn Mittelleiter gewählt
-// oder allgemein (max_duty_cycle)?
 const FRAC_FAHRSPANNUNG_ÜBERSPANNUNG: f64 = 16. / 25.;
 const UMDREHENZEIT: Duration = Duration::from_millis(500);
 
-impl Geschwindigkeit<Mittelleiter> {
+impl Leiter for Mittelleiter {
     /// 0 deaktiviert die Stromzufuhr.
     /// Werte über dem Maximalwert werden wie der Maximalwert behandelt.
-    /// Pwm: 0-u8::MAX
+    /// Pwm: 0-u8::MAX, Duty-cycle gemäß PwmProfil
     /// Konstante Spannung: 0-#Anschlüsse (geordnete Liste)
-    pub fn geschwindigkeit(&mut self, wert: u8) -> Result<(), Error> {
-        match &mut self.leiter {
-            Mittelleiter::Pwm { pin, polarität } => {
-                Ok(geschwindigkeit_pwm(pin, wert, FRAC_FAHRSPANNUNG_ÜBERSPANNUNG, *polarität)?)
+    fn geschwindigkeit(&mut self, wert: u8) -> Result<Option<Duration>, Error> {
+        match self {
+            Mittelleiter::Pwm { pin, polarität, profil } => {
+                Ok(geschwindigkeit_pwm(pin, wert, profil, *polarität)?)
             }
             Mittelleiter::KonstanteSpannung { geschwindigkeit, letzter_wert, .. } => {
-                geschwindigkeit_ks(geschwindigkeit, letzter_wert, wert)
+                geschwindigkeit_ks(geschwindigkeit, letzter_wert, wert).map(|()| None)
             }
         }
     }
 
-    pub fn umdrehen(&mut self) -> Result<(), Error> {
+    fn umdrehen(&mut self) -> Result<(), Error> {
         self.geschwindigkeit(0)?;
         sleep(STOPPZEIT);
-        Ok(match &mut self.leiter {
-            Mittelleiter::Pwm { pin, polarität } => {
+        Ok(match self {
+            Mittelleiter::Pwm { pin, polarität, profil } => {
                 pin.enable_with_config(pwm::Config {
                     polarity: *polarität,
-                    time: pwm::Time::Frequency { frequency: PWM_FREQUENZ, duty_cycle: 1. },
+                    time: pwm::Time::Frequency { frequency: profil.frequenz, duty_cycle: 1. },
                 })?;
                 sleep(UMDREHENZEIT);
                 pin.disable()?
@@ -205,6 +414,15 @@ impl Geschwindigkeit<Mittelleiter> {
             }
         })
     }
+
+    fn maximal_wert(&self) -> u8 {
+        match self {
+            Mittelleiter::Pwm { .. } => u8::MAX,
+            Mittelleiter::KonstanteSpannung { geschwindigkeit, .. } => {
+                maximal_wert_ks(geschwindigkeit)
+            }
+        }
+    }
 }
 
 pub type ZweileiterSave = Zweileiter<pwm::Save, OutputSave>;
@@ -213,6 +431,8 @@ pub enum Zweileiter<Pwm = pwm::Pin, Anschluss = OutputAnschluss> {
     Pwm {
         geschwindigkeit: Pwm,
         polarität: Polarität,
+        #[serde(deserialize_with = "PwmProfil::lese_zweileiter")]
+        profil: PwmProfil,
         fahrtrichtung: Anschluss,
     },
     KonstanteSpannung {
@@ -225,8 +445,8 @@ pub enum Zweileiter<Pwm = pwm::Pin, Anschluss = OutputAnschluss> {
 impl Display for Zweileiter {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            Zweileiter::Pwm { geschwindigkeit, polarität, fahrtrichtung } => {
-                write!(f, "Pwm({}, {}-{})", geschwindigkeit.pin(), polarität, fahrtrichtung)
+            Zweileiter::Pwm { geschwindigkeit, polarität, profil: _, fahrtrichtung } => {
+                write!(f, "Pwm({}, {}-{})", geschwindigkeit, polarität, fahrtrichtung)
             }
             Zweileiter::KonstanteSpannung { geschwindigkeit, letzter_wert: _, fahrtrichtung } => {
                 write!(f, "KonstanteSpannung(")?;
@@ -245,36 +465,48 @@ impl Display for Zweileiter {
     }
 }
 
-impl Geschwindigkeit<Zweileiter> {
-    pub fn geschwindigkeit(&mut self, wert: u8) -> Result<(), Error> {
-        match &mut self.leiter {
-            Zweileiter::Pwm { geschwindigkeit, polarität, .. } => {
-                Ok(geschwindigkeit_pwm(geschwindigkeit, wert, 1., *polarität)?)
+impl Leiter for Zweileiter {
+    fn geschwindigkeit(&mut self, wert: u8) -> Result<Option<Duration>, Error> {
+        match self {
+            Zweileiter::Pwm { geschwindigkeit, polarität, profil, .. } => {
+                Ok(geschwindigkeit_pwm(geschwindigkeit, wert, profil, *polarität)?)
             }
             Zweileiter::KonstanteSpannung { geschwindigkeit, letzter_wert, .. } => {
-                geschwindigkeit_ks(geschwindigkeit, letzter_wert, wert)
+                geschwindigkeit_ks(geschwindigkeit, letzter_wert, wert).map(|()| None)
             }
         }
     }
 
-    pub fn fahrtrichtung(&mut self, neue_fahrtrichtung: Fahrtrichtung) -> Result<(), Error> {
+    fn umdrehen(&mut self) -> Result<(), Error> {
         self.geschwindigkeit(0)?;
         sleep(STOPPZEIT);
-        let fahrtrichtung = match &mut self.leiter {
+        Ok(self.fahrtrichtung_anschluss().umstellen()?)
+    }
+
+    fn maximal_wert(&self) -> u8 {
+        match self {
+            Zweileiter::Pwm { .. } => u8::MAX,
+            Zweileiter::KonstanteSpannung { geschwindigkeit, .. } => {
+                maximal_wert_ks(geschwindigkeit)
+            }
+        }
+    }
+}
+
+impl Zweileiter {
+    fn fahrtrichtung_anschluss(&mut self) -> &mut OutputAnschluss {
+        match self {
             Zweileiter::Pwm { fahrtrichtung, .. } => fahrtrichtung,
             Zweileiter::KonstanteSpannung { fahrtrichtung, .. } => fahrtrichtung,
-        };
-        Ok(fahrtrichtung.einstellen(neue_fahrtrichtung.into())?)
+        }
     }
+}
 
-    pub fn umdrehen(&mut self) -> Result<(), Error> {
+impl Geschwindigkeit<Zweileiter> {
+    pub fn fahrtrichtung(&mut self, neue_fahrtrichtung: Fahrtrichtung) -> Result<(), Error> {
         self.geschwindigkeit(0)?;
         sleep(STOPPZEIT);
-        let fahrtrichtung = match &mut self.leiter {
-            Zweileiter::Pwm { fahrtrichtung, .. } => fahrtrichtung,
-            Zweileiter::KonstanteSpannung { fahrtrichtung, .. } => fahrtrichtung,
-        };
-        Ok(fahrtrichtung.umstellen()?)
+        Ok(self.leiter.fahrtrichtung_anschluss().einstellen(neue_fahrtrichtung.into())?)
     }
 }
 
@@ -283,11 +515,14 @@ impl ToSave for Zweileiter {
 
     fn to_save(&self) -> ZweileiterSave {
         match self {
-            Zweileiter::Pwm { geschwindigkeit, polarität, fahrtrichtung } => Zweileiter::Pwm {
-                geschwindigkeit: geschwindigkeit.to_save(),
-                polarität: *polarität,
-                fahrtrichtung: fahrtrichtung.to_save(),
-            },
+            Zweileiter::Pwm { geschwindigkeit, polarität, profil, fahrtrichtung } => {
+                Zweileiter::Pwm {
+                    geschwindigkeit: geschwindigkeit.to_save(),
+                    polarität: *polarität,
+                    profil: profil.clone(),
+                    fahrtrichtung: fahrtrichtung.to_save(),
+                }
+            }
             Zweileiter::KonstanteSpannung { geschwindigkeit, letzter_wert, fahrtrichtung } => {
                 Zweileiter::KonstanteSpannung {
                     geschwindigkeit: geschwindigkeit
@@ -305,11 +540,15 @@ impl ToSave for Zweileiter {
 impl Reserviere<Zweileiter> for ZweileiterSave {
     fn reserviere(self, anschlüsse: &mut Anschlüsse) -> Result<Zweileiter, anschluss::Error> {
         Ok(match self {
-            Zweileiter::Pwm { geschwindigkeit, polarität, fahrtrichtung } => Zweileiter::Pwm {
-                geschwindigkeit: geschwindigkeit.reserviere(anschlüsse)?,
-                polarität,
-                fahrtrichtung: fahrtrichtung.reserviere(anschlüsse)?,
-            },
+            Zweileiter::Pwm { geschwindigkeit, polarität, profil, fahrtrichtung } => {
+                let geschwindigkeit = geschwindigkeit.mit_frequenz(profil.frequenz)?;
+                Zweileiter::Pwm {
+                    geschwindigkeit: geschwindigkeit.reserviere(anschlüsse)?,
+                    polarität,
+                    profil,
+                    fahrtrichtung: fahrtrichtung.reserviere(anschlüsse)?,
+                }
+            }
             Zweileiter::KonstanteSpannung { geschwindigkeit, letzter_wert: _, fahrtrichtung } => {
                 Zweileiter::KonstanteSpannung {
                     geschwindigkeit: geschwindigkeit
@@ -324,6 +563,35 @@ impl Reserviere<Zweileiter> for ZweileiterSave {
         })
     }
 }
+impl<P: Zuordnen, A: Zuordnen> Zuordnen for Zweileiter<P, A> {
+    fn zuordnen(&mut self, zuordnung: &Zuordnung) -> Result<usize, zuordnung::Error> {
+        Ok(match self {
+            Zweileiter::Pwm { geschwindigkeit, fahrtrichtung, .. } => {
+                geschwindigkeit.zuordnen(zuordnung)? + fahrtrichtung.zuordnen(zuordnung)?
+            }
+            Zweileiter::KonstanteSpannung { geschwindigkeit, fahrtrichtung, .. } => {
+                geschwindigkeit.zuordnen(zuordnung)? + fahrtrichtung.zuordnen(zuordnung)?
+            }
+        })
+    }
+}
+impl<P: Verkabeln, A: Verkabeln> Verkabeln for Zweileiter<P, A> {
+    fn verkabeln(&self, beschreibung: &Beschreibung, verbindungen: &mut Vec<Verbindung>) {
+        match self {
+            Zweileiter::Pwm { geschwindigkeit, polarität, fahrtrichtung, .. } => {
+                geschwindigkeit.verkabeln(
+                    &beschreibung.funktion("Geschwindigkeit").polarität(*polarität),
+                    verbindungen,
+                );
+                fahrtrichtung.verkabeln(&beschreibung.funktion("Fahrtrichtung"), verbindungen)
+            }
+            Zweileiter::KonstanteSpannung { geschwindigkeit, fahrtrichtung, .. } => {
+                geschwindigkeit.verkabeln(&beschreibung.funktion("Geschwindigkeit"), verbindungen);
+                fahrtrichtung.verkabeln(&beschreibung.funktion("Fahrtrichtung"), verbindungen)
+            }
+        }
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Fahrtrichtung {
@@ -372,3 +640,84 @@ impl From<pwm::Error> for Error {
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Name(pub String);
 pub type Map<Leiter> = BTreeMap<Name, Geschwindigkeit<Leiter>>;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn profil(kurve: Vec<f64>, anfahrimpuls: Option<Anfahrimpuls>) -> PwmProfil {
+        PwmProfil {
+            frequenz: PWM_FREQUENZ,
+            min_duty_cycle: 0.2,
+            max_duty_cycle: 0.8,
+            anfahrimpuls,
+            kurve,
+        }
+    }
+
+    fn assert_ungefähr(erwartet: f64, wert: f64) {
+        assert!((erwartet - wert).abs() < 1e-9, "erwartet {erwartet}, erhalten {wert}");
+    }
+
+    #[test]
+    fn duty_cycle_endpunkte() {
+        for kurve in [Vec::new(), vec![0.5], vec![0., 0.8, 1.], vec![0.1, 0.3, 0.9]] {
+            let profil = profil(kurve.clone(), None);
+            assert_eq!(profil.duty_cycle(0), 0., "0 ist immer Stillstand: {kurve:?}");
+            let erste = kurve.first().copied().filter(|_| kurve.len() > 1).unwrap_or(0.);
+            let letzte = kurve.last().copied().filter(|_| kurve.len() > 1).unwrap_or(1.);
+            assert_ungefähr(0.2 + erste * 0.6, profil.duty_cycle(1));
+            assert_ungefähr(0.2 + letzte * 0.6, profil.duty_cycle(u8::MAX));
+        }
+    }
+
+    #[test]
+    fn duty_cycle_interpolation() {
+        let linear = profil(Vec::new(), None);
+        // 1 + 254 / 2
+        assert_ungefähr(0.5, linear.duty_cycle(128));
+
+        let kurve = profil(vec![0., 0.8, 1.], None);
+        // genau auf der mittleren Stützstelle
+        assert_ungefähr(0.2 + 0.8 * 0.6, kurve.duty_cycle(128));
+        // zwischen den Stützstellen wird linear interpoliert
+        let x = 63. / 254.;
+        assert_ungefähr(0.2 + 2. * x * 0.8 * 0.6, kurve.duty_cycle(64));
+        let x = 191. / 254.;
+        assert_ungefähr(0.2 + (0.8 + (2. * x - 1.) * 0.2) * 0.6, kurve.duty_cycle(192));
+        // monoton für eine monotone Kurve
+        assert!((1..u8::MAX).all(|wert| kurve.duty_cycle(wert) <= kurve.duty_cycle(wert + 1)));
+    }
+
+    #[test]
+    fn duty_cycle_begrenzt() {
+        let kurve = profil(vec![-1., 2.], None);
+        assert_ungefähr(0.2, kurve.duty_cycle(1));
+        assert_ungefähr(0.8, kurve.duty_cycle(u8::MAX));
+        let ungültig =
+            PwmProfil { min_duty_cycle: -0.5, max_duty_cycle: 1.5, ..profil(Vec::new(), None) };
+        assert_eq!(ungültig.duty_cycle(1), 0.);
+        assert_eq!(ungültig.duty_cycle(u8::MAX), 1.);
+    }
+
+    #[test]
+    fn anfahrimpuls() {
+        let ohne = profil(Vec::new(), None);
+        assert_eq!(ohne.anfahrimpuls(1), None);
+
+        let lang = Anfahrimpuls { duty_cycle: 0.5, dauer: Duration::from_secs(1) };
+        let mit = profil(Vec::new(), Some(lang));
+        assert_eq!(mit.anfahrimpuls(0), None, "Kein Impuls beim Anhalten.");
+        assert_eq!(
+            mit.anfahrimpuls(1),
+            Some((0.5, MAX_ANFAHRIMPULS_DAUER)),
+            "Dauer wird auf 200ms gekürzt."
+        );
+        assert_eq!(mit.anfahrimpuls(u8::MAX), None, "Kein Impuls über dem Impuls-Duty-cycle.");
+        assert_eq!(mit.anfahrimpuls(128), None, "Kein Impuls bei gleichem Duty-cycle.");
+
+        let kurz = Anfahrimpuls { duty_cycle: 1.5, dauer: Duration::from_millis(50) };
+        let mit = profil(Vec::new(), Some(kurz));
+        assert_eq!(mit.anfahrimpuls(u8::MAX), Some((1., Duration::from_millis(50))));
+    }
+}
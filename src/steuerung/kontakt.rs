@@ -3,6 +3,9 @@
 use serde::{Deserialize, Serialize};
 
 use crate::anschluss::{
+    anschlüsse::Besitzer,
+    verkabelung::{Beschreibung, Verbindung, Verkabeln},
+    zuordnung::{self, Zuordnen, Zuordnung},
     Anschlüsse, Error, InputAnschluss, InputSave, Level, Reserviere, ToSave, Trigger,
 };
 
@@ -48,10 +51,23 @@ impl ToSave for Kontakt<InputAnschluss> {
 
 impl Reserviere<Kontakt<InputAnschluss>> for Kontakt<InputSave> {
     fn reserviere(self, anschlüsse: &mut Anschlüsse) -> Result<Kontakt<InputAnschluss>, Error> {
+        let besitzer = Besitzer::Kontakt(self.name.0.clone());
         Ok(Kontakt {
             name: self.name,
-            anschluss: self.anschluss.reserviere(anschlüsse)?,
+            anschluss: self.anschluss.reserviere_für(anschlüsse, besitzer)?,
             trigger: self.trigger,
         })
     }
 }
+
+impl<Anschluss: Zuordnen> Zuordnen for Kontakt<Anschluss> {
+    fn zuordnen(&mut self, zuordnung: &Zuordnung) -> Result<usize, zuordnung::Error> {
+        self.anschluss.zuordnen(zuordnung)
+    }
+}
+
+impl<Anschluss: Verkabeln> Verkabeln for Kontakt<Anschluss> {
+    fn verkabeln(&self, beschreibung: &Beschreibung, verbindungen: &mut Vec<Verbindung>) {
+        self.anschluss.verkabeln(&beschreibung.trigger(self.trigger), verbindungen)
+    }
+}
@@ -0,0 +1,540 @@
+//! Regeln, die bei einem Ereignis auf der Anlage Aktionen auslösen.
+//!
+//! Eine Regel besteht aus einem [Auslöser], optionalen [Bedingung]en und [Schritt]en,
+//! deren Aktionen sofort oder nach einer Verzögerung ausgeführt werden.
+//! Kontakte melden sich über ihren Interrupt (`set_async_interrupt`),
+//! Änderungen an Weichen und Streckenabschnitten erkennt die Anwendung am Zustand.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::{Local, Timelike};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    anschluss::{Level, Trigger},
+    steuerung::{geschwindigkeit, kontakt, streckenabschnitt, weiche},
+};
+
+/// Maximale Wartezeit eines [Zeitgebers], bevor geprüft wird, ob er beendet wurde.
+const PRÜF_INTERVALL: Duration = Duration::from_millis(50);
+/// Kleinster erlaubter Abstand eines [Auslöser::Takt].
+pub const MINIMALER_TAKT: Duration = Duration::from_secs(1);
+
+/// Name einer Regel.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Name(pub String);
+pub type Map = BTreeMap<Name, Regel>;
+
+/// Eine Regel: wenn `auslöser` und alle `bedingungen` erfüllt sind, führe `schritte` aus.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Regel {
+    pub auslöser: Auslöser,
+    pub bedingungen: Vec<Bedingung>,
+    pub schritte: Vec<Schritt>,
+}
+
+impl Regel {
+    /// Sind alle Bedingungen bei den aktuellen Richtungen der `weichen` zur Uhrzeit `jetzt` erfüllt?
+    pub fn bedingungen_erfüllt(
+        &self,
+        weichen: &BTreeMap<weiche::Name, String>,
+        jetzt: Uhrzeit,
+    ) -> bool {
+        self.bedingungen.iter().all(|bedingung| bedingung.erfüllt(weichen, jetzt))
+    }
+}
+
+impl Display for Regel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "wenn {}", self.auslöser)?;
+        for (i, bedingung) in self.bedingungen.iter().enumerate() {
+            write!(f, " {} {}", if i == 0 { "falls" } else { "und" }, bedingung)?;
+        }
+        for (i, schritt) in self.schritte.iter().enumerate() {
+            write!(f, "{} {}", if i == 0 { ":" } else { "," }, schritt)?;
+        }
+        Ok(())
+    }
+}
+
+/// Ereignis auf der Anlage, auf das eine Regel reagieren kann.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ereignis {
+    Kontakt { name: kontakt::Name, level: Level },
+    Streckenabschnitt { name: streckenabschnitt::Name, fließend: bool },
+    Weiche { name: weiche::Name, richtung: String },
+}
+
+/// Auslöser einer Regel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Auslöser {
+    /// Ein Kontakt meldet eine Flanke, bei [Trigger::Both] jede Flanke.
+    Kontakt { name: kontakt::Name, trigger: Trigger },
+    /// Der Strom eines Streckenabschnitts wurde geschaltet, ohne `fließend` bei jeder Änderung.
+    Streckenabschnitt { name: streckenabschnitt::Name, fließend: Option<bool> },
+    /// Eine Weiche wurde gestellt, ohne `richtung` bei jeder Änderung.
+    Weiche { name: weiche::Name, richtung: Option<String> },
+    /// Wiederholt im Abstand `intervall`, mindestens [MINIMALER_TAKT].
+    Takt { intervall: Duration },
+}
+
+impl Auslöser {
+    /// Wird der Auslöser durch `ereignis` erfüllt?
+    pub fn passt(&self, ereignis: &Ereignis) -> bool {
+        match (self, ereignis) {
+            (Auslöser::Kontakt { name, trigger }, Ereignis::Kontakt { name: kontakt, level }) => {
+                name == kontakt
+                    && match (trigger, level) {
+                        (Trigger::Both, _)
+                        | (Trigger::RisingEdge, Level::High)
+                        | (Trigger::FallingEdge, Level::Low) => true,
+                        _ => false,
+                    }
+            },
+            (
+                Auslöser::Streckenabschnitt { name, fließend },
+                Ereignis::Streckenabschnitt { name: streckenabschnitt, fließend: aktuell },
+            ) => {
+                name == streckenabschnitt && fließend.map_or(true, |fließend| fließend == *aktuell)
+            },
+            (
+                Auslöser::Weiche { name, richtung },
+                Ereignis::Weiche { name: weiche, richtung: aktuell },
+            ) => name == weiche && richtung.as_ref().map_or(true, |richtung| richtung == aktuell),
+            _ => false,
+        }
+    }
+}
+
+impl Display for Auslöser {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Auslöser::Kontakt { name, trigger } => {
+                let flanke = match trigger {
+                    Trigger::RisingEdge => "steigend",
+                    Trigger::FallingEdge => "fallend",
+                    Trigger::Both => "beide",
+                    Trigger::Disabled => "nie",
+                };
+                write!(f, "Kontakt {} {}", name.0, flanke)
+            },
+            Auslöser::Streckenabschnitt { name, fließend } => {
+                write!(
+                    f,
+                    "Streckenabschnitt {} {}",
+                    name.0,
+                    strom_beschreibung(*fließend, "geändert")
+                )
+            },
+            Auslöser::Weiche { name, richtung } => {
+                write!(f, "Weiche {} {}", name.0, richtung.as_deref().unwrap_or("geändert"))
+            },
+            Auslöser::Takt { intervall } => write!(f, "alle {}s", intervall.as_secs_f64()),
+        }
+    }
+}
+
+/// Zusätzliche Bedingung einer Regel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Bedingung {
+    /// Die Weiche steht in der angegebenen Richtung.
+    Weiche { name: weiche::Name, richtung: String },
+    /// Die Uhrzeit liegt zwischen `von` (inklusive) und `bis` (exklusive),
+    /// für `von` nach `bis` über Mitternacht. Für `von` gleich `bis` ist sie immer erfüllt.
+    Zeitfenster { von: Uhrzeit, bis: Uhrzeit },
+}
+
+impl Bedingung {
+    /// Ist die Bedingung bei den aktuellen Richtungen der `weichen` zur Uhrzeit `jetzt` erfüllt?
+    pub fn erfüllt(&self, weichen: &BTreeMap<weiche::Name, String>, jetzt: Uhrzeit) -> bool {
+        match self {
+            Bedingung::Weiche { name, richtung } => weichen.get(name) == Some(richtung),
+            Bedingung::Zeitfenster { von, bis } if von < bis => von <= &jetzt && &jetzt < bis,
+            // über Mitternacht, bei gleichen Uhrzeiten der ganze Tag
+            Bedingung::Zeitfenster { von, bis } => von <= &jetzt || &jetzt < bis,
+        }
+    }
+}
+
+impl Display for Bedingung {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Bedingung::Weiche { name, richtung } => write!(f, "Weiche {} {}", name.0, richtung),
+            Bedingung::Zeitfenster { von, bis } => write!(f, "{}-{}", von, bis),
+        }
+    }
+}
+
+/// Aktion einer Regel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Aktion {
+    /// Stelle die Weiche in die angegebene Richtung.
+    Weiche { name: weiche::Name, richtung: String },
+    /// Stelle die Geschwindigkeit auf `wert`.
+    Geschwindigkeit { name: geschwindigkeit::Name, wert: u8 },
+    /// Schalte den Strom eines Streckenabschnitts, ohne `fließend` wird umgeschaltet.
+    Streckenabschnitt { name: streckenabschnitt::Name, fließend: Option<bool> },
+}
+
+impl Display for Aktion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Aktion::Weiche { name, richtung } => write!(f, "Weiche {} {}", name.0, richtung),
+            Aktion::Geschwindigkeit { name, wert } => {
+                write!(f, "Geschwindigkeit {} {}", name.0, wert)
+            },
+            Aktion::Streckenabschnitt { name, fließend } => write!(
+                f,
+                "Streckenabschnitt {} {}",
+                name.0,
+                strom_beschreibung(*fließend, "umschalten")
+            ),
+        }
+    }
+}
+
+/// Eine Aktion, die `verzögerung` nach dem Auslösen der Regel ausgeführt wird.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schritt {
+    pub aktion: Aktion,
+    pub verzögerung: Duration,
+}
+
+impl Display for Schritt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if !self.verzögerung.is_zero() {
+            write!(f, "nach {}s ", self.verzögerung.as_secs_f64())?;
+        }
+        write!(f, "{}", self.aktion)
+    }
+}
+
+fn strom_beschreibung(fließend: Option<bool>, ohne: &str) -> &str {
+    match fließend {
+        Some(true) => "an",
+        Some(false) => "aus",
+        None => ohne,
+    }
+}
+
+/// Uhrzeit auf Minuten genau, z.B. für ein [Bedingung::Zeitfenster].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Uhrzeit {
+    pub stunde: u8,
+    pub minute: u8,
+}
+
+impl Uhrzeit {
+    /// Die aktuelle lokale Uhrzeit.
+    pub fn jetzt() -> Self {
+        let jetzt = Local::now();
+        Uhrzeit { stunde: jetzt.hour() as u8, minute: jetzt.minute() as u8 }
+    }
+}
+
+impl Display for Uhrzeit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.stunde, self.minute)
+    }
+}
+
+impl FromStr for Uhrzeit {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ungültig = || Error::Ungültig { eingabe: s.to_string(), erwartet: "Uhrzeit (HH:MM)" };
+        let (stunde, minute) = s.trim().split_once(':').ok_or_else(ungültig)?;
+        let stunde = stunde.parse().map_err(|_| ungültig())?;
+        let minute = minute.parse().map_err(|_| ungültig())?;
+        if stunde < 24 && minute < 60 {
+            Ok(Uhrzeit { stunde, minute })
+        } else {
+            Err(ungültig())
+        }
+    }
+}
+
+/// Lese einen nicht-leeren Namen.
+pub fn lese_name(eingabe: &str) -> Result<String, Error> {
+    let name = eingabe.trim();
+    if name.is_empty() {
+        Err(Error::KeinName)
+    } else {
+        Ok(name.to_string())
+    }
+}
+
+/// Lese die Flanke eines Kontakts ("steigend", "fallend" oder "beide", leer für beide).
+pub fn lese_flanke(eingabe: &str) -> Result<Trigger, Error> {
+    match eingabe.trim() {
+        "steigend" => Ok(Trigger::RisingEdge),
+        "fallend" => Ok(Trigger::FallingEdge),
+        "" | "beide" => Ok(Trigger::Both),
+        _ => Err(Error::Ungültig {
+            eingabe: eingabe.to_string(),
+            erwartet: "\"steigend\", \"fallend\" oder \"beide\"",
+        }),
+    }
+}
+
+/// Lese den Strom eines Streckenabschnitts ("an" oder "aus", leer für umschalten/jede Änderung).
+pub fn lese_strom(eingabe: &str) -> Result<Option<bool>, Error> {
+    match eingabe.trim() {
+        "an" => Ok(Some(true)),
+        "aus" => Ok(Some(false)),
+        "" | "umschalten" | "geändert" => Ok(None),
+        _ => Err(Error::Ungültig {
+            eingabe: eingabe.to_string(),
+            erwartet: "\"an\", \"aus\" oder nichts",
+        }),
+    }
+}
+
+/// Lese einen Wert einer Geschwindigkeit (0-255).
+pub fn lese_wert(eingabe: &str) -> Result<u8, Error> {
+    eingabe.trim().parse().map_err(|_| Error::Ungültig {
+        eingabe: eingabe.to_string(),
+        erwartet: "Zahl von 0 bis 255",
+    })
+}
+
+/// Lese eine Dauer in Sekunden, Komma oder Punkt als Dezimaltrennzeichen, leer für 0.
+pub fn lese_sekunden(eingabe: &str) -> Result<Duration, Error> {
+    let eingabe_trim = eingabe.trim();
+    if eingabe_trim.is_empty() {
+        return Ok(Duration::from_secs(0));
+    }
+    match eingabe_trim.replace(',', ".").parse::<f64>() {
+        Ok(sekunden) if sekunden.is_finite() && sekunden >= 0. => {
+            Ok(Duration::from_secs_f64(sekunden))
+        },
+        _ => Err(Error::Ungültig { eingabe: eingabe.to_string(), erwartet: "Sekunden" }),
+    }
+}
+
+/// Lese den Abstand eines [Auslöser::Takt] in Sekunden, mindestens [MINIMALER_TAKT].
+pub fn lese_takt(eingabe: &str) -> Result<Duration, Error> {
+    let intervall = lese_sekunden(eingabe)?;
+    if intervall < MINIMALER_TAKT {
+        Err(Error::TaktZuKurz)
+    } else {
+        Ok(intervall)
+    }
+}
+
+/// Lese ein Zeitfenster (z.B. "08:00-20:00").
+pub fn lese_zeitfenster(eingabe: &str) -> Result<Bedingung, Error> {
+    let (von, bis) = eingabe.split_once('-').ok_or_else(|| Error::Ungültig {
+        eingabe: eingabe.to_string(),
+        erwartet: "Zeitfenster (HH:MM-HH:MM)",
+    })?;
+    Ok(Bedingung::Zeitfenster { von: von.parse()?, bis: bis.parse()? })
+}
+
+/// Ruft in einem eigenen Thread einen Callback nach einer Wartezeit auf, bis er gedroppt wird.
+#[derive(Debug)]
+pub struct Zeitgeber {
+    aktiv: Arc<AtomicBool>,
+}
+
+impl Zeitgeber {
+    /// Rufe `callback` einmal nach `wartezeit` auf.
+    pub fn einmal(wartezeit: Duration, callback: impl FnOnce() + Send + 'static) -> Self {
+        let aktiv = Arc::new(AtomicBool::new(true));
+        let aktiv_clone = aktiv.clone();
+        let _ = thread::spawn(move || {
+            if warte(&aktiv_clone, wartezeit) {
+                callback();
+                aktiv_clone.store(false, Ordering::Relaxed)
+            }
+        });
+        Zeitgeber { aktiv }
+    }
+
+    /// Rufe `callback` wiederholt im Abstand `intervall` auf.
+    pub fn takt(intervall: Duration, mut callback: impl FnMut() + Send + 'static) -> Self {
+        let aktiv = Arc::new(AtomicBool::new(true));
+        let aktiv_clone = aktiv.clone();
+        let _ = thread::spawn(move || {
+            while warte(&aktiv_clone, intervall) {
+                callback()
+            }
+        });
+        Zeitgeber { aktiv }
+    }
+
+    /// Steht noch ein Aufruf des Callbacks aus?
+    pub fn aktiv(&self) -> bool {
+        self.aktiv.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for Zeitgeber {
+    fn drop(&mut self) {
+        // der Thread beendet sich spätestens nach PRÜF_INTERVALL
+        self.aktiv.store(false, Ordering::Relaxed)
+    }
+}
+
+/// Warte `dauer`, gibt zurück ob `aktiv` währenddessen gesetzt blieb.
+fn warte(aktiv: &AtomicBool, dauer: Duration) -> bool {
+    let ende = Instant::now() + dauer;
+    loop {
+        if !aktiv.load(Ordering::Relaxed) {
+            return false;
+        }
+        let jetzt = Instant::now();
+        if jetzt >= ende {
+            return true;
+        }
+        thread::sleep((ende - jetzt).min(PRÜF_INTERVALL))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Die Eingabe konnte nicht gelesen werden.
+    Ungültig {
+        eingabe: String,
+        erwartet: &'static str,
+    },
+    KeinName,
+    KeineAktion,
+    TaktZuKurz,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Ungültig { eingabe, erwartet } => {
+                write!(f, "Ungültige Eingabe \"{}\", erwartet: {}", eingabe, erwartet)
+            },
+            Error::KeinName => write!(f, "Kein Name angegeben!"),
+            Error::KeineAktion => write!(f, "Die Regel hat keine Aktion!"),
+            Error::TaktZuKurz => {
+                write!(f, "Der Takt muss mindestens {}s sein!", MINIMALER_TAKT.as_secs_f64())
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn uhrzeit(stunde: u8, minute: u8) -> Uhrzeit {
+        Uhrzeit { stunde, minute }
+    }
+
+    fn zeitfenster_erfüllt(von: Uhrzeit, bis: Uhrzeit, jetzt: Uhrzeit) -> bool {
+        Bedingung::Zeitfenster { von, bis }.erfüllt(&BTreeMap::new(), jetzt)
+    }
+
+    #[test]
+    fn zeitfenster() {
+        let acht = uhrzeit(8, 0);
+        let zwanzig = uhrzeit(20, 0);
+        assert!(zeitfenster_erfüllt(acht, zwanzig, acht), "Anfang inklusive.");
+        assert!(zeitfenster_erfüllt(acht, zwanzig, uhrzeit(19, 59)));
+        assert!(!zeitfenster_erfüllt(acht, zwanzig, zwanzig), "Ende exklusive.");
+        assert!(!zeitfenster_erfüllt(acht, zwanzig, uhrzeit(7, 59)));
+        // über Mitternacht
+        let zweiundzwanzig = uhrzeit(22, 0);
+        let sechs = uhrzeit(6, 0);
+        assert!(zeitfenster_erfüllt(zweiundzwanzig, sechs, zweiundzwanzig));
+        assert!(zeitfenster_erfüllt(zweiundzwanzig, sechs, uhrzeit(23, 59)));
+        assert!(zeitfenster_erfüllt(zweiundzwanzig, sechs, uhrzeit(0, 0)));
+        assert!(zeitfenster_erfüllt(zweiundzwanzig, sechs, uhrzeit(5, 59)));
+        assert!(!zeitfenster_erfüllt(zweiundzwanzig, sechs, sechs));
+        assert!(!zeitfenster_erfüllt(zweiundzwanzig, sechs, uhrzeit(12, 0)));
+        // gleicher Anfang und Ende: der ganze Tag
+        for jetzt in [uhrzeit(0, 0), uhrzeit(7, 59), acht, uhrzeit(23, 59)] {
+            assert!(zeitfenster_erfüllt(acht, acht, jetzt), "{}", jetzt);
+        }
+    }
+
+    #[test]
+    fn uhrzeit_lesen() {
+        assert_eq!("00:00".parse(), Ok(uhrzeit(0, 0)));
+        assert_eq!(" 23:59 ".parse(), Ok(uhrzeit(23, 59)));
+        assert_eq!("8:05".parse(), Ok(uhrzeit(8, 5)));
+        for ungültig in ["24:00", "12:60", "-1:00", "12", "12:", "zwölf:00", ""] {
+            assert!(ungültig.parse::<Uhrzeit>().is_err(), "{} gelesen.", ungültig);
+        }
+        assert_eq!(
+            lese_zeitfenster("22:00-06:00"),
+            Ok(Bedingung::Zeitfenster { von: uhrzeit(22, 0), bis: uhrzeit(6, 0) })
+        );
+        assert!(lese_zeitfenster("22:00").is_err());
+    }
+
+    #[test]
+    fn auslöser_kontakt() {
+        let name = kontakt::Name("K".to_string());
+        let ereignis = |level| Ereignis::Kontakt { name: name.clone(), level };
+        let passt = |trigger, level| {
+            Auslöser::Kontakt { name: name.clone(), trigger }.passt(&ereignis(level))
+        };
+        assert!(passt(Trigger::RisingEdge, Level::High));
+        assert!(!passt(Trigger::RisingEdge, Level::Low));
+        assert!(!passt(Trigger::FallingEdge, Level::High));
+        assert!(passt(Trigger::FallingEdge, Level::Low));
+        assert!(passt(Trigger::Both, Level::High));
+        assert!(passt(Trigger::Both, Level::Low));
+        assert!(!passt(Trigger::Disabled, Level::High));
+        assert!(!passt(Trigger::Disabled, Level::Low));
+        let anderer =
+            Auslöser::Kontakt { name: kontakt::Name("L".to_string()), trigger: Trigger::Both };
+        assert!(!anderer.passt(&ereignis(Level::High)), "Anderer Kontakt.");
+        let weiche_gestellt = Ereignis::Weiche {
+            name: weiche::Name("K".to_string()),
+            richtung: "Gerade".to_string(),
+        };
+        assert!(!Auslöser::Kontakt { name, trigger: Trigger::Both }.passt(&weiche_gestellt));
+    }
+
+    #[test]
+    fn auslöser_streckenabschnitt_weiche() {
+        let streckenabschnitt = streckenabschnitt::Name("S".to_string());
+        let an = Ereignis::Streckenabschnitt { name: streckenabschnitt.clone(), fließend: true };
+        let auslöser =
+            |fließend| Auslöser::Streckenabschnitt { name: streckenabschnitt.clone(), fließend };
+        assert!(auslöser(None).passt(&an));
+        assert!(auslöser(Some(true)).passt(&an));
+        assert!(!auslöser(Some(false)).passt(&an));
+
+        let weiche = weiche::Name("W".to_string());
+        let kurve = Ereignis::Weiche { name: weiche.clone(), richtung: "Kurve".to_string() };
+        let auslöser = |richtung: Option<&str>| Auslöser::Weiche {
+            name: weiche.clone(),
+            richtung: richtung.map(str::to_string),
+        };
+        assert!(auslöser(None).passt(&kurve));
+        assert!(auslöser(Some("Kurve")).passt(&kurve));
+        assert!(!auslöser(Some("Gerade")).passt(&kurve));
+        assert!(!Auslöser::Takt { intervall: MINIMALER_TAKT }.passt(&kurve));
+    }
+
+    #[test]
+    fn sekunden_und_takt() {
+        assert_eq!(lese_sekunden(""), Ok(Duration::from_secs(0)));
+        assert_eq!(lese_sekunden("1,5"), Ok(Duration::from_millis(1500)));
+        assert_eq!(lese_sekunden(" 2.25 "), Ok(Duration::from_millis(2250)));
+        assert!(lese_sekunden("-1").is_err());
+        assert!(lese_sekunden("1,5,0").is_err());
+        assert_eq!(lese_takt("1"), Ok(MINIMALER_TAKT));
+        assert_eq!(lese_takt("2,5"), Ok(Duration::from_millis(2500)));
+        assert_eq!(lese_takt("0,5"), Err(Error::TaktZuKurz));
+        assert_eq!(lese_takt(""), Err(Error::TaktZuKurz));
+    }
+}
@@ -0,0 +1,341 @@
+//! Skripte zur Automatisierung, geschrieben in [Rhai](https://rhai.rs).
+//!
+//! Jedes Skript läuft in einem eigenen Thread und kann jederzeit abgebrochen werden.
+//! Aktionen auf der Anlage werden nicht direkt ausgeführt, sondern als [Aktion] übergeben,
+//! damit sie genauso wie Eingaben der Anzeige behandelt werden (z.B. während eines Nothalts).
+//!
+//! Verfügbare Funktionen:
+//! - `weiche("W1").schalten(Gerade)`, Richtungen: `Gerade`, `Kurve`, `Links`, `Rechts`
+//! - `geschwindigkeit("Oval").setze(120)`, `geschwindigkeit("Oval").umdrehen()`
+//! - `streckenabschnitt("S1").strom(true)`
+//! - `warte_auf_kontakt("K3")`, mit maximaler Wartezeit in Sekunden:
+//!   `warte_auf_kontakt("K3", 10)` (gibt zurück, ob der Kontakt ausgelöst wurde)
+//! - `warte(2)` bzw. `warte(0.5)`, Wartezeit in Sekunden
+//! - `print("Text")` schreibt eine Meldung ins Protokoll
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::{debug, info};
+use rhai::{
+    module_resolvers::DummyModuleResolver, Dynamic, Engine, EvalAltResult, Module, ParseError,
+    Position, FLOAT, INT,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::steuerung::{geschwindigkeit, kontakt, streckenabschnitt, weiche};
+
+/// Maximale Wartezeit, bevor geprüft wird, ob das Skript abgebrochen wurde.
+const PRÜF_INTERVALL: Duration = Duration::from_millis(50);
+/// Richtungen einer Weiche, als Konstanten im Skript verfügbar.
+const RICHTUNGEN: [&str; 4] = ["Gerade", "Kurve", "Links", "Rechts"];
+
+/// Name eines Skriptes.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Name(pub String);
+
+/// Quelltext aller Skripte, zugeordnet über ihren Namen.
+pub type Map = BTreeMap<Name, String>;
+
+/// Aktion auf der Anlage, ausgelöst durch ein Skript.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Aktion {
+    Weiche { name: weiche::Name, richtung: String },
+    Geschwindigkeit { name: geschwindigkeit::Name, wert: u8 },
+    Umdrehen { name: geschwindigkeit::Name },
+    Streckenabschnitt { name: streckenabschnitt::Name, fließend: bool },
+}
+
+/// Grund für das Ende eines Skriptes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ende {
+    Fertig,
+    Abgebrochen,
+    Fehler(String),
+}
+
+impl Display for Ende {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Ende::Fertig => write!(f, "fertig"),
+            Ende::Abgebrochen => write!(f, "abgebrochen"),
+            Ende::Fehler(fehler) => write!(f, "Fehler: {}", fehler),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Syntax(ParseError),
+}
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        Error::Syntax(error)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax(fehler) => write!(f, "Syntaxfehler: {}", fehler),
+        }
+    }
+}
+
+/// Eingeschränkte Engine ohne Zugriff auf Dateien oder `eval`.
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+    engine.set_max_call_levels(64);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(10_000);
+    engine.set_max_array_size(10_000);
+    engine.set_max_map_size(10_000);
+    engine
+}
+
+/// Prüfe den Quelltext eines Skriptes auf Syntaxfehler.
+pub fn prüfe(quelltext: &str) -> Result<(), Error> {
+    engine().compile(quelltext)?;
+    Ok(())
+}
+
+fn abgebrochen() -> Box<EvalAltResult> {
+    Box::new(EvalAltResult::ErrorTerminated(Dynamic::UNIT, Position::NONE))
+}
+
+/// Warte bis `ende`, solange das Skript nicht abgebrochen wird.
+fn warte_bis(ende: Instant, abbrechen: &AtomicBool) -> Result<(), Box<EvalAltResult>> {
+    loop {
+        if abbrechen.load(Ordering::Relaxed) {
+            return Err(abgebrochen());
+        }
+        let jetzt = Instant::now();
+        if jetzt >= ende {
+            return Ok(());
+        }
+        thread::sleep((ende - jetzt).min(PRÜF_INTERVALL))
+    }
+}
+
+/// Warte, bis der Kontakt `name` ausgelöst wird, höchstens aber bis `ende`.
+///
+/// Vorher ausgelöste Kontakte werden ignoriert.
+fn warte_auf_kontakt(
+    kontakte: &mpsc::Receiver<kontakt::Name>,
+    name: &str,
+    ende: Option<Instant>,
+    abbrechen: &AtomicBool,
+) -> Result<bool, Box<EvalAltResult>> {
+    for _kontakt in kontakte.try_iter() {}
+    loop {
+        if abbrechen.load(Ordering::Relaxed) {
+            return Err(abgebrochen());
+        }
+        let wartezeit = match ende {
+            Some(ende) => {
+                let jetzt = Instant::now();
+                if jetzt >= ende {
+                    return Ok(false);
+                }
+                (ende - jetzt).min(PRÜF_INTERVALL)
+            },
+            None => PRÜF_INTERVALL,
+        };
+        match kontakte.recv_timeout(wartezeit) {
+            Ok(kontakt) if kontakt.0 == name => return Ok(true),
+            Ok(_) | Err(RecvTimeoutError::Timeout) => {},
+            // Anwendung wurde beendet
+            Err(RecvTimeoutError::Disconnected) => return Err(abgebrochen()),
+        }
+    }
+}
+
+fn sekunden(wert: FLOAT) -> Result<Duration, Box<EvalAltResult>> {
+    if wert.is_finite() && wert >= 0. {
+        Ok(Duration::from_secs_f64(wert))
+    } else {
+        Err(format!("Ungültige Wartezeit: {}", wert).into())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct WeicheSkript(weiche::Name);
+
+#[derive(Debug, Clone)]
+struct GeschwindigkeitSkript(geschwindigkeit::Name);
+
+#[derive(Debug, Clone)]
+struct StreckenabschnittSkript(streckenabschnitt::Name);
+
+/// Engine mit allen Funktionen für die Steuerung der Anlage.
+fn engine_mit_anlage(
+    name: &Name,
+    ausführen: Rc<dyn Fn(Aktion) -> Result<(), String>>,
+    kontakte: mpsc::Receiver<kontakt::Name>,
+    abbrechen: Arc<AtomicBool>,
+) -> Engine {
+    let mut engine = engine();
+    // als Modul, damit sie auch in Funktionen verfügbar sind
+    let mut konstanten = Module::new();
+    for richtung in RICHTUNGEN.iter() {
+        konstanten.set_var(*richtung, richtung.to_string());
+    }
+    engine.register_global_module(konstanten.into());
+    let abbrechen_clone = abbrechen.clone();
+    engine.on_progress(move |_operationen| {
+        if abbrechen_clone.load(Ordering::Relaxed) {
+            Some(Dynamic::UNIT)
+        } else {
+            None
+        }
+    });
+    let skript_name = name.0.clone();
+    engine.on_print(move |text| info!("Skript {}: {}", skript_name, text));
+    let skript_name = name.0.clone();
+    engine.on_debug(move |text, _quelle, position| {
+        debug!("Skript {} ({}): {}", skript_name, position, text)
+    });
+
+    let ausführen_clone = ausführen.clone();
+    let aktion = move |aktion| ausführen_clone(aktion).map_err(Box::<EvalAltResult>::from);
+    let aktion = Rc::new(aktion);
+
+    engine.register_type_with_name::<WeicheSkript>("Weiche");
+    engine.register_fn("weiche", |name: &str| WeicheSkript(weiche::Name(name.to_string())));
+    let aktion_clone = aktion.clone();
+    engine.register_fn("schalten", move |weiche: &mut WeicheSkript, richtung: &str| {
+        aktion_clone(Aktion::Weiche { name: weiche.0.clone(), richtung: richtung.to_string() })
+    });
+
+    engine.register_type_with_name::<GeschwindigkeitSkript>("Geschwindigkeit");
+    engine.register_fn("geschwindigkeit", |name: &str| {
+        GeschwindigkeitSkript(geschwindigkeit::Name(name.to_string()))
+    });
+    let aktion_clone = aktion.clone();
+    engine.register_fn("setze", move |geschwindigkeit: &mut GeschwindigkeitSkript, wert: INT| {
+        if wert < 0 || wert > INT::from(u8::MAX) {
+            return Err(format!("Ungültige Geschwindigkeit: {}", wert).into());
+        }
+        aktion_clone(Aktion::Geschwindigkeit { name: geschwindigkeit.0.clone(), wert: wert as u8 })
+    });
+    let aktion_clone = aktion.clone();
+    engine.register_fn("umdrehen", move |geschwindigkeit: &mut GeschwindigkeitSkript| {
+        aktion_clone(Aktion::Umdrehen { name: geschwindigkeit.0.clone() })
+    });
+
+    engine.register_type_with_name::<StreckenabschnittSkript>("Streckenabschnitt");
+    engine.register_fn("streckenabschnitt", |name: &str| {
+        StreckenabschnittSkript(streckenabschnitt::Name(name.to_string()))
+    });
+    engine.register_fn(
+        "strom",
+        move |streckenabschnitt: &mut StreckenabschnittSkript, fließend: bool| {
+            aktion(Aktion::Streckenabschnitt { name: streckenabschnitt.0.clone(), fließend })
+        },
+    );
+
+    let abbrechen_clone = abbrechen.clone();
+    engine.register_fn("warte", move |wert: INT| {
+        warte_bis(Instant::now() + sekunden(wert as FLOAT)?, &abbrechen_clone)
+    });
+    let abbrechen_clone = abbrechen.clone();
+    engine.register_fn("warte", move |wert: FLOAT| {
+        warte_bis(Instant::now() + sekunden(wert)?, &abbrechen_clone)
+    });
+
+    let kontakte = Rc::new(kontakte);
+    let kontakte_clone = kontakte.clone();
+    let abbrechen_clone = abbrechen.clone();
+    engine.register_fn("warte_auf_kontakt", move |name: &str| {
+        warte_auf_kontakt(&kontakte_clone, name, None, &abbrechen_clone).map(|_ausgelöst| ())
+    });
+    let kontakte_clone = kontakte.clone();
+    let abbrechen_clone = abbrechen.clone();
+    engine.register_fn("warte_auf_kontakt", move |name: &str, wert: INT| {
+        let ende = Instant::now() + sekunden(wert as FLOAT)?;
+        warte_auf_kontakt(&kontakte_clone, name, Some(ende), &abbrechen_clone)
+    });
+    engine.register_fn("warte_auf_kontakt", move |name: &str, wert: FLOAT| {
+        let ende = Instant::now() + sekunden(wert)?;
+        warte_auf_kontakt(&kontakte, name, Some(ende), &abbrechen)
+    });
+    engine
+}
+
+/// Ein laufendes Skript. Wird beim drop abgebrochen.
+#[derive(Debug)]
+pub struct Ausführung {
+    abbrechen: Arc<AtomicBool>,
+    aktiv: Arc<AtomicBool>,
+    kontakte: mpsc::Sender<kontakt::Name>,
+}
+
+impl Drop for Ausführung {
+    fn drop(&mut self) {
+        // kein join, das Skript wartet evtl. auf die Bearbeitung einer Aktion im aktuellen Thread
+        self.abbrechen()
+    }
+}
+
+impl Ausführung {
+    /// Starte das Skript `name` in einem eigenen Thread.
+    ///
+    /// Aktionen werden über `ausführen` ausgelöst, der Aufruf blockiert bis zum Ergebnis.
+    /// Nach Ende des Skriptes wird `beendet` mit dem Grund aufgerufen.
+    /// Syntaxfehler werden direkt zurückgegeben, das Skript wird dann nicht gestartet.
+    pub fn starte(
+        name: Name,
+        quelltext: String,
+        ausführen: impl Fn(Aktion) -> Result<(), String> + Send + 'static,
+        beendet: impl FnOnce(Ende) + Send + 'static,
+    ) -> Result<Self, Error> {
+        prüfe(&quelltext)?;
+        let abbrechen = Arc::new(AtomicBool::new(false));
+        let aktiv = Arc::new(AtomicBool::new(true));
+        let (sender, empfänger) = mpsc::channel();
+        let abbrechen_clone = abbrechen.clone();
+        let aktiv_clone = aktiv.clone();
+        thread::spawn(move || {
+            let engine =
+                engine_mit_anlage(&name, Rc::new(ausführen), empfänger, abbrechen_clone.clone());
+            let ende = match engine.run(&quelltext) {
+                Ok(()) => Ende::Fertig,
+                // Fehler beim Abbrechen sind evtl. in einem Funktionsaufruf verpackt
+                Err(_fehler) if abbrechen_clone.load(Ordering::Relaxed) => Ende::Abgebrochen,
+                Err(fehler) => Ende::Fehler(fehler.to_string()),
+            };
+            debug!("Skript {} beendet: {}", name.0, ende);
+            aktiv_clone.store(false, Ordering::Relaxed);
+            beendet(ende)
+        });
+        Ok(Ausführung { abbrechen, aktiv, kontakte: sender })
+    }
+
+    /// Melde einen ausgelösten Kontakt, z.B. für `warte_auf_kontakt`.
+    pub fn kontakt(&self, name: &kontakt::Name) {
+        // ein beendetes Skript wartet nicht mehr auf Kontakte
+        let _ = self.kontakte.send(name.clone());
+    }
+
+    /// Breche das Skript beim nächsten Befehl oder während einer Wartezeit ab.
+    pub fn abbrechen(&self) {
+        self.abbrechen.store(true, Ordering::Relaxed)
+    }
+
+    /// Läuft das Skript noch?
+    pub fn aktiv(&self) -> bool {
+        self.aktiv.load(Ordering::Relaxed)
+    }
+}
@@ -0,0 +1,137 @@
+//! Ein Stellpult mit Tastern, die Aktionen auf der Anlage auslösen.
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::anschluss::{
+    verkabelung::{Beschreibung, Verbindung, Verkabeln},
+    zuordnung::{self, Zuordnen, Zuordnung},
+    Anschlüsse, Error, InputAnschluss, InputSave, Level, Reserviere, ToSave, Trigger,
+};
+use crate::steuerung::{geschwindigkeit, streckenabschnitt, weiche};
+
+/// Aktion, die durch einen Taster ausgelöst wird.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Aktion {
+    WeicheUmschalten(weiche::Name),
+    StreckenabschnittUmschalten(streckenabschnitt::Name),
+    GeschwindigkeitUmdrehen(geschwindigkeit::Name),
+    /// Stelle alle Weichen einer Fahrstraße in die jeweilige Richtung.
+    FahrstraßeStellen(Vec<(weiche::Name, String)>),
+}
+
+impl Display for Aktion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Aktion::WeicheUmschalten(name) => write!(f, "Weiche {} umschalten", name.0),
+            Aktion::StreckenabschnittUmschalten(name) => {
+                write!(f, "Streckenabschnitt {} umschalten", name.0)
+            }
+            Aktion::GeschwindigkeitUmdrehen(name) => {
+                write!(f, "Geschwindigkeit {} umdrehen", name.0)
+            }
+            Aktion::FahrstraßeStellen(weichen) => {
+                write!(f, "Fahrstraße")?;
+                for (i, (name, richtung)) in weichen.iter().enumerate() {
+                    let trennzeichen = if i == 0 { " " } else { ", " };
+                    write!(f, "{}{}={}", trennzeichen, name.0, richtung)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Lese eine Fahrstraße im Format "Weiche=Richtung, Weiche=Richtung, …".
+pub fn lese_fahrstraße(eingabe: &str) -> Result<Vec<(weiche::Name, String)>, String> {
+    eingabe
+        .split(',')
+        .map(|teil| match teil.split_once('=') {
+            Some((name, richtung)) if !name.trim().is_empty() && !richtung.trim().is_empty() => {
+                Ok((weiche::Name(name.trim().to_string()), richtung.trim().to_string()))
+            }
+            _ => Err(format!("Ungültige Weiche \"{}\", erwartet \"Weiche=Richtung\"!", teil)),
+        })
+        .collect()
+}
+
+/// Mindestabstand zwischen zwei ausgelösten Aktionen eines Tasters (Entprellen).
+const ENTPRELLZEIT: Duration = Duration::from_millis(250);
+
+pub type TasterSave = Taster<InputSave>;
+/// Ein Taster des Stellpults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Taster<Anschluss = InputAnschluss> {
+    pub anschluss: Anschluss,
+    pub trigger: Trigger,
+    pub aktion: Aktion,
+}
+
+impl Taster<InputAnschluss> {
+    /// Rufe `callback` auf, sobald der Taster gedrückt wird.
+    ///
+    /// Wiederholte Auslöser innerhalb kurzer Zeit (Prellen) werden ignoriert.
+    pub fn set_async_interrupt(
+        &mut self,
+        mut callback: impl FnMut(&Aktion) + Send + 'static,
+    ) -> Result<(), Error> {
+        let aktion = self.aktion.clone();
+        let mut letzter_auslöser: Option<Instant> = None;
+        self.anschluss.set_async_interrupt(self.trigger, move |_level: Level| {
+            let jetzt = Instant::now();
+            if letzter_auslöser.map_or(true, |zeitpunkt| jetzt - zeitpunkt >= ENTPRELLZEIT) {
+                letzter_auslöser = Some(jetzt);
+                callback(&aktion)
+            }
+        })
+    }
+
+    pub fn clear_async_interrupt(&mut self) -> Result<(), Error> {
+        self.anschluss.clear_async_interrupt()
+    }
+}
+
+impl ToSave for Taster<InputAnschluss> {
+    type Save = TasterSave;
+
+    fn to_save(&self) -> TasterSave {
+        Taster {
+            anschluss: self.anschluss.to_save(),
+            trigger: self.trigger,
+            aktion: self.aktion.clone(),
+        }
+    }
+}
+
+impl Reserviere<Taster<InputAnschluss>> for TasterSave {
+    fn reserviere(self, anschlüsse: &mut Anschlüsse) -> Result<Taster<InputAnschluss>, Error> {
+        Ok(Taster {
+            anschluss: self.anschluss.reserviere(anschlüsse)?,
+            trigger: self.trigger,
+            aktion: self.aktion,
+        })
+    }
+}
+
+impl<Anschluss: Zuordnen> Zuordnen for Taster<Anschluss> {
+    fn zuordnen(&mut self, zuordnung: &Zuordnung) -> Result<usize, zuordnung::Error> {
+        self.anschluss.zuordnen(zuordnung)
+    }
+}
+
+impl<Anschluss: Verkabeln> Verkabeln for Taster<Anschluss> {
+    fn verkabeln(&self, beschreibung: &Beschreibung, verbindungen: &mut Vec<Verbindung>) {
+        let beschreibung = beschreibung.funktion(self.aktion.to_string()).trigger(self.trigger);
+        self.anschluss.verkabeln(&beschreibung, verbindungen)
+    }
+}
+
+/// Name eines Tasters.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Name(pub String);
+pub type Map<Anschluss = InputAnschluss> = BTreeMap<Name, Taster<Anschluss>>;
@@ -4,7 +4,11 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-use crate::anschluss::{Error, Fließend, OutputAnschluss, OutputSave};
+use crate::anschluss::{
+    verkabelung::{Beschreibung, Verbindung, Verkabeln},
+    zuordnung::{self, Zuordnen, Zuordnung},
+    Error, Fließend, OutputAnschluss, OutputSave,
+};
 use crate::farbe::Farbe;
 
 pub type StreckenabschnittSave = Streckenabschnitt<OutputSave>;
@@ -23,6 +27,22 @@ impl Streckenabschnitt<OutputAnschluss> {
     pub fn strom_umschalten(&mut self) -> Result<(), Error> {
         self.anschluss.umstellen()
     }
+
+    pub fn ist_fließend(&mut self) -> Result<bool, Error> {
+        self.anschluss.ist_fließend()
+    }
+}
+
+impl<Anschluss: Zuordnen> Zuordnen for Streckenabschnitt<Anschluss> {
+    fn zuordnen(&mut self, zuordnung: &Zuordnung) -> Result<usize, zuordnung::Error> {
+        self.anschluss.zuordnen(zuordnung)
+    }
+}
+
+impl<Anschluss: Verkabeln> Verkabeln for Streckenabschnitt<Anschluss> {
+    fn verkabeln(&self, beschreibung: &Beschreibung, verbindungen: &mut Vec<Verbindung>) {
+        self.anschluss.verkabeln(beschreibung, verbindungen)
+    }
 }
 
 /// Name eines Streckenabschnittes.
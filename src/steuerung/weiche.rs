@@ -4,7 +4,12 @@ use std::{thread::sleep, time::Duration};
 
 use serde::{Deserialize, Serialize};
 
-use crate::anschluss::{Anschlüsse, Error, Fließend, OutputAnschluss, Reserviere, ToSave};
+use crate::anschluss::{
+    anschlüsse::Besitzer,
+    verkabelung::{Beschreibung, Verbindung, Verkabeln},
+    zuordnung::{self, Zuordnen, Zuordnung},
+    Anschlüsse, Error, Fließend, OutputAnschluss, Reserviere, ToSave,
+};
 use crate::lookup::Lookup;
 
 /// Name einer Weiche.
@@ -54,14 +59,25 @@ where
 }
 impl<Richtung: Clone, T: Reserviere<R>, R> Reserviere<Weiche<Richtung, R>> for Weiche<Richtung, T> {
     fn reserviere(self, anschlüsse: &mut Anschlüsse) -> Result<Weiche<Richtung, R>, Error> {
+        let besitzer = Besitzer::Weiche(self.name.0.clone());
         Ok(Weiche {
             name: self.name,
             aktuelle_richtung: self.aktuelle_richtung.clone(),
             letzte_richtung: self.letzte_richtung.clone(),
-            anschlüsse: self.anschlüsse.reserviere(anschlüsse)?,
+            anschlüsse: self.anschlüsse.reserviere_für(anschlüsse, besitzer)?,
         })
     }
 }
+impl<Richtung, T: Zuordnen> Zuordnen for Weiche<Richtung, T> {
+    fn zuordnen(&mut self, zuordnung: &Zuordnung) -> Result<usize, zuordnung::Error> {
+        self.anschlüsse.zuordnen(zuordnung)
+    }
+}
+impl<Richtung, T: Verkabeln> Verkabeln for Weiche<Richtung, T> {
+    fn verkabeln(&self, beschreibung: &Beschreibung, verbindungen: &mut Vec<Verbindung>) {
+        self.anschlüsse.verkabeln(beschreibung, verbindungen)
+    }
+}
 
 // TODO als Teil des Zugtyp-Traits?
 const SCHALTZEIT: Duration = Duration::from_millis(500);
@@ -33,7 +33,7 @@ pub fn create_richtung(args: Vec<syn::NestedMeta>, item: syn::ItemEnum) -> Token
             )
             .collect();
         let default_variant = enum_variants[0];
-        let enum_variants_str = enum_variants.iter().map(ToString::to_string);
+        let enum_variants_str: Vec<_> = enum_variants.iter().map(ToString::to_string).collect();
         let struct_fields: Vec<syn::Ident> = enum_variants
                     .iter()
                     // TODO fix upstream?
@@ -51,6 +51,10 @@ pub fn create_richtung(args: Vec<syn::NestedMeta>, item: syn::ItemEnum) -> Token
             #vis enum Richtung {
                 #(#enum_variants),*
             }
+            impl Richtung {
+                /// Alle Richtungen in Reihenfolge der Definition.
+                pub const ALLE: &[Richtung] = &[#(Richtung::#enum_variants),*];
+            }
             impl Default for Richtung {
                 fn default() -> Self {
                     Richtung::#default_variant
@@ -83,6 +87,25 @@ pub fn create_richtung(args: Vec<syn::NestedMeta>, item: syn::ItemEnum) -> Token
                     })
                 }
             }
+            impl #base_ident::anschluss::zuordnung::Zuordnen for RichtungAnschlüsseSave {
+                fn zuordnen(
+                    &mut self,
+                    zuordnung: &#base_ident::anschluss::zuordnung::Zuordnung,
+                ) -> Result<usize, #base_ident::anschluss::zuordnung::Error> {
+                    let RichtungAnschlüsseSave {  #(#struct_fields),* } = self;
+                    Ok(0 #(+ #struct_fields.zuordnen(zuordnung)?)*)
+                }
+            }
+            impl #base_ident::anschluss::verkabelung::Verkabeln for RichtungAnschlüsseSave {
+                fn verkabeln(
+                    &self,
+                    beschreibung: &#base_ident::anschluss::verkabelung::Beschreibung,
+                    verbindungen: &mut Vec<#base_ident::anschluss::verkabelung::Verbindung>,
+                ) {
+                    let RichtungAnschlüsseSave {  #(#struct_fields),* } = self;
+                    #(#struct_fields.verkabeln(&beschreibung.funktion(#enum_variants_str), verbindungen);)*
+                }
+            }
             impl Default for RichtungAnschlüsseSave {
                 fn default() -> Self {
                     RichtungAnschlüsseSave {